@@ -46,6 +46,27 @@ pub trait LspBackend: Send {
         include_declaration: bool,
     ) -> miette::Result<()>;
 
+    /// Request the symbols of a document
+    fn request_document_symbols(&mut self, file_path: &Path) -> miette::Result<()>;
+
+    /// Request the code actions available at a position
+    fn request_code_actions(
+        &mut self,
+        file_path: &Path,
+        line: u32,
+        character: u32,
+    ) -> miette::Result<()>;
+
+    /// Run a command offered by a code action
+    fn execute_command(
+        &mut self,
+        command: &str,
+        arguments: Vec<serde_json::Value>,
+    ) -> miette::Result<()>;
+
+    /// Request the edits that format a document
+    fn request_formatting(&mut self, file_path: &Path) -> miette::Result<()>;
+
     /// Shutdown the LSP backend
     fn shutdown(&mut self) -> miette::Result<()>;
 
@@ -1,16 +1,20 @@
 use lsp_types::{
-    ClientCapabilities, CompletionClientCapabilities, CompletionContext, CompletionParams,
+    ClientCapabilities, CodeActionClientCapabilities, CodeActionContext, CodeActionOrCommand,
+    CodeActionParams, CompletionClientCapabilities, CompletionContext, CompletionParams,
     CompletionResponse, CompletionTriggerKind, DidChangeTextDocumentParams,
-    DidOpenTextDocumentParams, GotoCapability, GotoDefinitionParams, GotoDefinitionResponse,
-    InitializeParams, InitializeResult, InitializedParams, Location, Position,
-    PublishDiagnosticsClientCapabilities, PublishDiagnosticsParams, ReferenceContext,
-    ReferenceParams, SemanticTokens, SemanticTokensClientCapabilities, SemanticTokensParams,
-    TextDocumentClientCapabilities, TextDocumentContentChangeEvent, TextDocumentIdentifier,
-    TextDocumentItem, TextDocumentPositionParams, Uri, VersionedTextDocumentIdentifier,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentSymbolClientCapabilities,
+    DocumentSymbolParams, DocumentSymbolResponse, ExecuteCommandParams, FormattingOptions,
+    GotoCapability, GotoDefinitionParams, GotoDefinitionResponse, InitializeParams,
+    InitializeResult, InitializedParams, Location, Position, PublishDiagnosticsClientCapabilities,
+    PublishDiagnosticsParams, ReferenceContext, ReferenceParams, SemanticTokens,
+    SemanticTokensClientCapabilities, SemanticTokensParams, TextDocumentClientCapabilities,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, TextEdit, Uri, VersionedTextDocumentIdentifier,
     WorkDoneProgressParams, WorkspaceFolder,
     notification::{Notification, PublishDiagnostics},
     request::{
-        Completion, GotoDefinition, Initialize, References, Request, SemanticTokensFullRequest,
+        CodeActionRequest, Completion, DocumentSymbolRequest, ExecuteCommand, Formatting,
+        GotoDefinition, Initialize, References, Request, SemanticTokensFullRequest,
     },
 };
 use serde::{Deserialize, Serialize};
@@ -79,6 +83,12 @@ pub enum LspEvent {
     Definition(GotoDefinitionResponse),
     /// References received from the server
     References(Vec<Location>),
+    /// Document symbols received from the server
+    DocumentSymbols(DocumentSymbolResponse),
+    /// Code actions received from the server
+    CodeActions(Vec<CodeActionOrCommand>),
+    /// Edits formatting a document received from the server
+    Formatting(Vec<TextEdit>),
     /// Server initialized successfully with completion trigger characters
     Initialized(Vec<String>), // trigger_characters
     /// Error occurred
@@ -212,6 +222,19 @@ impl LspClient {
                 references: Some(lsp_types::DynamicRegistrationClientCapabilities {
                     dynamic_registration: Some(false),
                 }),
+                document_symbol: Some(DocumentSymbolClientCapabilities {
+                    dynamic_registration: Some(false),
+                    symbol_kind: None,
+                    hierarchical_document_symbol_support: Some(true),
+                    tag_support: None,
+                }),
+                code_action: Some(CodeActionClientCapabilities {
+                    dynamic_registration: Some(false),
+                    ..Default::default()
+                }),
+                formatting: Some(lsp_types::DynamicRegistrationClientCapabilities {
+                    dynamic_registration: Some(false),
+                }),
                 publish_diagnostics: Some(PublishDiagnosticsClientCapabilities {
                     related_information: Some(true),
                     tag_support: None,
@@ -396,6 +419,78 @@ impl LspClient {
         Ok(())
     }
 
+    /// Request the symbols of a document
+    pub fn request_document_symbols(&mut self, file_path: &Path) -> miette::Result<()> {
+        let uri = Uri::from_str(&format!("file:///{}", file_path.display()))
+            .map_err(|e| miette::miette!("Failed to convert file path to URI: {}", e))?;
+
+        let params = DocumentSymbolParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: Default::default(),
+        };
+
+        self.send_request::<DocumentSymbolRequest>(params)?;
+        Ok(())
+    }
+
+    /// Request the code actions available at a position
+    pub fn request_code_actions(
+        &mut self,
+        file_path: &Path,
+        line: u32,
+        character: u32,
+    ) -> miette::Result<()> {
+        let uri = Uri::from_str(&format!("file:///{}", file_path.display()))
+            .map_err(|e| miette::miette!("Failed to convert file path to URI: {}", e))?;
+
+        let position = Position { line, character };
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier { uri },
+            range: lsp_types::Range {
+                start: position,
+                end: position,
+            },
+            context: CodeActionContext::default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: Default::default(),
+        };
+
+        self.send_request::<CodeActionRequest>(params)?;
+        Ok(())
+    }
+
+    /// Run a command offered by a code action
+    pub fn execute_command(&mut self, command: &str, arguments: Vec<Value>) -> miette::Result<()> {
+        let params = ExecuteCommandParams {
+            command: command.to_string(),
+            arguments,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        self.send_request::<ExecuteCommand>(params)?;
+        Ok(())
+    }
+
+    /// Request the edits that format a document
+    pub fn request_formatting(&mut self, file_path: &Path) -> miette::Result<()> {
+        let uri = Uri::from_str(&format!("file:///{}", file_path.display()))
+            .map_err(|e| miette::miette!("Failed to convert file path to URI: {}", e))?;
+
+        let params = DocumentFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            options: FormattingOptions {
+                tab_size: 4,
+                insert_spaces: true,
+                ..Default::default()
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        self.send_request::<Formatting>(params)?;
+        Ok(())
+    }
+
     /// Send a request to the LSP server
     fn send_request<R: Request>(&mut self, params: R::Params) -> miette::Result<()>
     where
@@ -555,6 +650,31 @@ impl LspClient {
                                     let _ = event_tx.send(LspEvent::References(locations));
                                 }
                             }
+                            "textDocument/documentSymbol" => {
+                                if let Ok(symbols) =
+                                    serde_json::from_value::<Option<DocumentSymbolResponse>>(result)
+                                    && let Some(symbols) = symbols
+                                {
+                                    let _ = event_tx.send(LspEvent::DocumentSymbols(symbols));
+                                }
+                            }
+                            "textDocument/codeAction" => {
+                                if let Ok(actions) = serde_json::from_value::<
+                                    Option<Vec<CodeActionOrCommand>>,
+                                >(result)
+                                {
+                                    let _ = event_tx
+                                        .send(LspEvent::CodeActions(actions.unwrap_or_default()));
+                                }
+                            }
+                            "textDocument/formatting" => {
+                                if let Ok(edits) =
+                                    serde_json::from_value::<Option<Vec<TextEdit>>>(result)
+                                {
+                                    let _ = event_tx
+                                        .send(LspEvent::Formatting(edits.unwrap_or_default()));
+                                }
+                            }
                             "textDocument/completion" => {
                                 if let Ok(completion) =
                                     serde_json::from_value::<CompletionResponse>(result)
@@ -655,6 +775,27 @@ impl crate::backend::LspBackend for LspClient {
         self.request_references(file_path, line, character, include_declaration)
     }
 
+    fn request_document_symbols(&mut self, file_path: &std::path::Path) -> miette::Result<()> {
+        self.request_document_symbols(file_path)
+    }
+
+    fn request_code_actions(
+        &mut self,
+        file_path: &std::path::Path,
+        line: u32,
+        character: u32,
+    ) -> miette::Result<()> {
+        self.request_code_actions(file_path, line, character)
+    }
+
+    fn execute_command(&mut self, command: &str, arguments: Vec<Value>) -> miette::Result<()> {
+        self.execute_command(command, arguments)
+    }
+
+    fn request_formatting(&mut self, file_path: &std::path::Path) -> miette::Result<()> {
+        self.request_formatting(file_path)
+    }
+
     fn shutdown(&mut self) -> miette::Result<()> {
         self.shutdown()
     }
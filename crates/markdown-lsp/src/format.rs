@@ -0,0 +1,688 @@
+use std::ops::Range;
+
+use markdown::mdast::{AlignKind, Node};
+use markdown::{Constructs, ParseOptions};
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+/// Marker of bullet list items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BulletMarker {
+    /// `- item`
+    #[default]
+    Dash,
+    /// `* item`
+    Asterisk,
+    /// `+ item`
+    Plus,
+}
+
+impl BulletMarker {
+    pub fn as_char(self) -> char {
+        match self {
+            Self::Dash => '-',
+            Self::Asterisk => '*',
+            Self::Plus => '+',
+        }
+    }
+}
+
+/// Delimiter of emphasis (`*a*`) or strong emphasis (`**a**`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmphasisMarker {
+    #[default]
+    Asterisk,
+    Underscore,
+}
+
+impl EmphasisMarker {
+    fn as_char(self) -> char {
+        match self {
+            Self::Asterisk => '*',
+            Self::Underscore => '_',
+        }
+    }
+}
+
+/// Numbers of ordered list items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListNumbering {
+    /// Count up from the first item: `1.`, `2.`, `3.`
+    #[default]
+    Ordered,
+    /// Repeat the first item's number: `1.`, `1.`, `1.`
+    One,
+}
+
+/// Syntax of headings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadingStyle {
+    /// `# Title` at every level
+    #[default]
+    Atx,
+    /// Titles underlined with `===` or `---` for levels 1 and 2, ATX below
+    Setext,
+}
+
+/// Fence of code blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeFence {
+    /// ```` ``` ````
+    #[default]
+    Backtick,
+    /// `~~~`
+    Tilde,
+}
+
+impl CodeFence {
+    fn as_char(self) -> char {
+        match self {
+            Self::Backtick => '`',
+            Self::Tilde => '~',
+        }
+    }
+}
+
+/// Style a Markdown document is formatted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatOptions {
+    pub bullet: BulletMarker,
+    pub emphasis: EmphasisMarker,
+    pub strong: EmphasisMarker,
+    pub numbering: ListNumbering,
+    pub heading: HeadingStyle,
+    pub code_fence: CodeFence,
+    /// Pad table cells so the pipes line up
+    pub align_tables: bool,
+    /// Separate top-level blocks by exactly one blank line
+    pub blank_lines: bool,
+    /// End the document with a single newline
+    pub final_newline: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            bullet: BulletMarker::default(),
+            emphasis: EmphasisMarker::default(),
+            strong: EmphasisMarker::default(),
+            numbering: ListNumbering::default(),
+            heading: HeadingStyle::default(),
+            code_fence: CodeFence::default(),
+            align_tables: true,
+            blank_lines: true,
+            final_newline: true,
+        }
+    }
+}
+
+/// One change to the document, made of replacements of byte ranges that are
+/// kept or dropped together
+#[derive(Debug, Clone)]
+struct Edit(Vec<(Range<usize>, String)>);
+
+/// Collects the edits of a pass, leaving out those that change nothing
+struct Edits<'a> {
+    text: &'a str,
+    edits: Vec<Edit>,
+}
+
+impl<'a> Edits<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            edits: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, replacements: Vec<(Range<usize>, String)>) {
+        let replacements: Vec<_> = replacements
+            .into_iter()
+            .filter(|(range, new_text)| self.text.get(range.clone()) != Some(new_text.as_str()))
+            .collect();
+        if !replacements.is_empty() {
+            self.edits.push(Edit(replacements));
+        }
+    }
+
+    fn replace(&mut self, range: Range<usize>, new_text: impl Into<String>) {
+        self.push(vec![(range, new_text.into())]);
+    }
+}
+
+/// Pass computing the edits towards one part of the style
+type Pass = fn(&str, &Node, &FormatOptions, &str) -> Vec<Edit>;
+
+/// Rewrite a Markdown document in the given style
+///
+/// Every edit is checked to leave the rendered HTML as it was, and edits that
+/// would change it are left out, so formatting never alters the content.
+pub fn format(content: &str, options: &FormatOptions) -> String {
+    const PASSES: [Pass; 7] = [
+        blank_line_edits,
+        heading_edits,
+        code_fence_edits,
+        table_edits,
+        list_marker_edits,
+        emphasis_edits,
+        final_newline_edits,
+    ];
+
+    let expected = rendered(content);
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    PASSES.iter().fold(content.to_string(), |text, pass| {
+        let Ok(root) = markdown::to_mdast(&text, &parse_options()) else {
+            return text;
+        };
+        let edits = pass(&text, &root, options, newline);
+        let mut accepted = Vec::new();
+        accept_edits(&text, &edits, &expected, &mut accepted);
+        apply(&text, &accepted)
+    })
+}
+
+/// Parser settings matching how mq-markdown reads documents
+fn parse_options() -> ParseOptions {
+    ParseOptions {
+        constructs: Constructs {
+            frontmatter: true,
+            math_flow: true,
+            math_text: true,
+            ..Constructs::gfm()
+        },
+        gfm_strikethrough_single_tilde: true,
+        math_text_single_dollar: true,
+        ..ParseOptions::gfm()
+    }
+}
+
+/// HTML of a document, ignoring the trailing line ending that only reflects
+/// whether the source ends with one
+fn rendered(text: &str) -> String {
+    mq_markdown::to_html(text).trim_end().to_string()
+}
+
+/// Apply edits in document order, skipping any that overlap an earlier one
+fn apply(text: &str, edits: &[Edit]) -> String {
+    let mut replacements: Vec<&(Range<usize>, String)> =
+        edits.iter().flat_map(|edit| &edit.0).collect();
+    replacements.sort_by_key(|(range, _)| range.start);
+
+    let mut result = String::with_capacity(text.len());
+    let mut offset = 0;
+    for (range, new_text) in replacements {
+        if range.start < offset {
+            continue;
+        }
+        result.push_str(&text[offset..range.start]);
+        result.push_str(new_text);
+        offset = range.end;
+    }
+    result.push_str(&text[offset..]);
+    result
+}
+
+/// Add the edits that keep the rendered HTML to `accepted`
+///
+/// When applying all of them changes the HTML, they are split in halves
+/// until the edits responsible are found.
+fn accept_edits(text: &str, edits: &[Edit], expected: &str, accepted: &mut Vec<Edit>) {
+    if edits.is_empty() {
+        return;
+    }
+    let candidate: Vec<Edit> = accepted.iter().chain(edits).cloned().collect();
+    if rendered(&apply(text, &candidate)) == expected {
+        *accepted = candidate;
+    } else if edits.len() > 1 {
+        let (first, second) = edits.split_at(edits.len() / 2);
+        accept_edits(text, first, expected, accepted);
+        accept_edits(text, second, expected, accepted);
+    }
+}
+
+/// Call `f` for a node and each of its descendants
+fn visit<'a>(node: &'a Node, f: &mut impl FnMut(&'a Node)) {
+    f(node);
+    for child in node.children().into_iter().flatten() {
+        visit(child, f);
+    }
+}
+
+/// Byte range of a node in the document
+fn span(node: &Node) -> Option<Range<usize>> {
+    node.position()
+        .map(|position| position.start.offset..position.end.offset)
+}
+
+/// Byte range from the first to the last child of a node
+fn children_span(node: &Node) -> Option<Range<usize>> {
+    let children = node.children()?;
+    let start = span(children.first()?)?.start;
+    let end = span(children.last()?)?.end;
+    Some(start..end)
+}
+
+/// Put one blank line between top-level blocks and none before the first
+fn blank_line_edits(text: &str, root: &Node, options: &FormatOptions, newline: &str) -> Vec<Edit> {
+    let mut edits = Edits::new(text);
+    if !options.blank_lines {
+        return edits.edits;
+    }
+
+    let blocks: Vec<Range<usize>> = root
+        .children()
+        .into_iter()
+        .flatten()
+        .filter_map(span)
+        .collect();
+    if let Some(first) = blocks.first()
+        && text[..first.start].trim().is_empty()
+        && let Some(last_newline) = text[..first.start].rfind('\n')
+    {
+        edits.replace(0..last_newline + 1, "");
+    }
+
+    for pair in blocks.windows(2) {
+        let gap = &text[pair[0].end..pair[1].start];
+        // Keep the indentation of the next block, which may matter to it
+        if gap.trim().is_empty()
+            && let Some(last_newline) = gap.rfind('\n')
+        {
+            edits.replace(
+                pair[0].end..pair[0].end + last_newline + 1,
+                newline.repeat(2),
+            );
+        }
+    }
+    edits.edits
+}
+
+/// Write headings in the configured style, without closing `#`s
+fn heading_edits(text: &str, root: &Node, options: &FormatOptions, newline: &str) -> Vec<Edit> {
+    let mut edits = Edits::new(text);
+    visit(root, &mut |node| {
+        let Node::Heading(heading) = node else {
+            return;
+        };
+        let (Some(range), Some(position)) = (span(node), node.position()) else {
+            return;
+        };
+        let hashes = "#".repeat(heading.depth as usize);
+        let Some(content) = children_span(node) else {
+            // An empty heading can only be written with `#`s
+            edits.replace(range, hashes);
+            return;
+        };
+        let title = &text[content.clone()];
+        if title.contains('\n') {
+            // Titles over several lines only exist as setext headings
+            return;
+        }
+
+        if options.heading == HeadingStyle::Setext
+            && heading.depth <= 2
+            && position.start.column == 1
+        {
+            let underline = if heading.depth == 1 { "=" } else { "-" };
+            edits.replace(
+                range,
+                format!(
+                    "{}{}{}",
+                    title,
+                    newline,
+                    underline.repeat(title.width().max(3))
+                ),
+            );
+        } else {
+            edits.push(vec![
+                (range.start..content.start, format!("{} ", hashes)),
+                (content.end..range.end, String::new()),
+            ]);
+        }
+    });
+    edits.edits
+}
+
+/// Fence code blocks with the configured character
+fn code_fence_edits(text: &str, root: &Node, options: &FormatOptions, _newline: &str) -> Vec<Edit> {
+    let mut edits = Edits::new(text);
+    let target = options.code_fence.as_char();
+    visit(root, &mut |node| {
+        let Node::Code(code) = node else {
+            return;
+        };
+        let Some(range) = span(node) else {
+            return;
+        };
+        let source = &text[range.clone()];
+        let fenced = source.trim_start_matches(' ');
+        let indent = source.len() - fenced.len();
+        // Indented code blocks have no fence
+        let Some(fence) = fenced.chars().next().filter(|c| matches!(c, '`' | '~')) else {
+            return;
+        };
+        let open_len = fenced.len() - fenced.trim_start_matches(fence).len();
+        let info = fenced[open_len..].lines().next().unwrap_or_default();
+        if open_len < 3 || (target == '`' && info.contains('`')) {
+            return;
+        }
+
+        // Longer than any run of the fence character in the code
+        let longest_run = code
+            .value
+            .split(|c| c != target)
+            .map(str::len)
+            .max()
+            .unwrap_or(0);
+        let new_fence = target.to_string().repeat((longest_run + 1).max(3));
+        let open = range.start + indent..range.start + indent + open_len;
+
+        // Blocks running to the end of the document or container are not closed
+        let close = source.rfind('\n').and_then(|last_newline| {
+            let last_line = source[last_newline + 1..].trim_end();
+            let run = last_line.len() - last_line.trim_end_matches(fence).len();
+            let prefix = &last_line[..last_line.len() - run];
+            (run >= open_len && prefix.chars().all(|c| c == ' ' || c == '>')).then(|| {
+                let start = range.start + last_newline + 1 + prefix.len();
+                start..start + run
+            })
+        });
+        let mut replacements = vec![(open, new_fence.clone())];
+        replacements.extend(close.map(|close| (close, new_fence)));
+        edits.push(replacements);
+    });
+    edits.edits
+}
+
+/// Pad table cells so the pipes of every row line up
+fn table_edits(text: &str, root: &Node, options: &FormatOptions, newline: &str) -> Vec<Edit> {
+    let mut edits = Edits::new(text);
+    if !options.align_tables {
+        return edits.edits;
+    }
+    visit(root, &mut |node| {
+        let Node::Table(table) = node else {
+            return;
+        };
+        // Tables inside quotes or lists keep their prefixes as written
+        let (Some(range), Some(position)) = (span(node), node.position()) else {
+            return;
+        };
+        if position.start.column != 1 {
+            return;
+        }
+        if let Some(lines) = format_table(text, range.clone(), &table.children, &table.align) {
+            edits.replace(range, lines.join(newline));
+        }
+    });
+    edits.edits
+}
+
+/// Lines of a table with aligned pipes, or None when the rows don't map to
+/// lines one to one or text outside the cells would be lost
+fn format_table(
+    text: &str,
+    range: Range<usize>,
+    rows: &[Node],
+    align: &[AlignKind],
+) -> Option<Vec<String>> {
+    let mut line_ranges = Vec::new();
+    let mut offset = range.start;
+    for line in text[range.clone()].split('\n') {
+        line_ranges.push(offset..offset + line.len());
+        offset += line.len() + 1;
+    }
+    if line_ranges.len() != rows.len() + 1 {
+        return None;
+    }
+    let delimiter = &text[line_ranges[1].clone()];
+    if !delimiter
+        .chars()
+        .all(|c| matches!(c, '|' | '-' | ':') || c.is_whitespace())
+    {
+        return None;
+    }
+
+    let mut cells: Vec<Vec<&str>> = Vec::new();
+    let row_lines = [&line_ranges[0]].into_iter().chain(&line_ranges[2..]);
+    for (row, line) in rows.iter().zip(row_lines) {
+        let contents: Vec<Range<usize>> = row
+            .children()?
+            .iter()
+            .map(|cell| children_span(cell).unwrap_or(0..0))
+            .collect();
+        // Everything else on the line must be pipes and spaces
+        let mut outside = String::new();
+        let mut offset = line.start;
+        for content in contents.iter().filter(|content| !content.is_empty()) {
+            outside.push_str(text.get(offset..content.start)?);
+            offset = content.end;
+        }
+        outside.push_str(text.get(offset..line.end)?);
+        if contents.len() > align.len() || !outside.chars().all(|c| c == '|' || c.is_whitespace()) {
+            return None;
+        }
+        cells.push(
+            contents
+                .into_iter()
+                .map(|content| text[content].trim())
+                .collect(),
+        );
+    }
+
+    let widths: Vec<usize> = (0..align.len())
+        .map(|column| {
+            cells
+                .iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.width())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+    let format_row = |row: &[&str]| {
+        let cells: Vec<String> = widths
+            .iter()
+            .zip(align)
+            .enumerate()
+            .map(|(column, (width, align))| {
+                let cell = row.get(column).copied().unwrap_or_default();
+                let space = width.saturating_sub(cell.width());
+                let (left, right) = match align {
+                    AlignKind::Right => (space, 0),
+                    AlignKind::Center => (space / 2, space - space / 2),
+                    AlignKind::Left | AlignKind::None => (0, space),
+                };
+                format!("{}{}{}", " ".repeat(left), cell, " ".repeat(right))
+            })
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+    let separator: Vec<String> = widths
+        .iter()
+        .zip(align)
+        .map(|(width, align)| match align {
+            AlignKind::Left => format!(":{}", "-".repeat(width - 1)),
+            AlignKind::Center => format!(":{}:", "-".repeat(width - 2)),
+            AlignKind::Right => format!("{}:", "-".repeat(width - 1)),
+            AlignKind::None => "-".repeat(*width),
+        })
+        .collect();
+
+    let mut lines = vec![
+        format_row(&cells[0]),
+        format!("| {} |", separator.join(" | ")),
+    ];
+    lines.extend(cells[1..].iter().map(|row| format_row(row)));
+    Some(lines)
+}
+
+/// Use the configured bullet and number the items of ordered lists
+fn list_marker_edits(
+    text: &str,
+    root: &Node,
+    options: &FormatOptions,
+    _newline: &str,
+) -> Vec<Edit> {
+    let mut edits = Edits::new(text);
+    visit(root, &mut |node| {
+        let Node::List(list) = node else {
+            return;
+        };
+        let first = list.start.unwrap_or(1);
+        for (index, item) in list.children.iter().enumerate() {
+            let Some(range) = span(item) else {
+                continue;
+            };
+            let marker = range.start + (text[range.clone()].len() - text[range].trim_start().len());
+            if list.ordered {
+                let digits = text[marker..]
+                    .bytes()
+                    .take_while(u8::is_ascii_digit)
+                    .count();
+                let number = match options.numbering {
+                    ListNumbering::Ordered => first + index as u32,
+                    ListNumbering::One => first,
+                };
+                if digits > 0 {
+                    edits.replace(marker..marker + digits, number.to_string());
+                }
+            } else if text[marker..].starts_with(['-', '*', '+']) {
+                edits.replace(marker..marker + 1, options.bullet.as_char().to_string());
+            }
+        }
+    });
+    edits.edits
+}
+
+/// Delimit emphasis and strong emphasis with the configured characters
+fn emphasis_edits(text: &str, root: &Node, options: &FormatOptions, _newline: &str) -> Vec<Edit> {
+    let mut edits = Edits::new(text);
+    visit(root, &mut |node| {
+        let (marker, size) = match node {
+            Node::Emphasis(_) => (options.emphasis, 1),
+            Node::Strong(_) => (options.strong, 2),
+            _ => return,
+        };
+        let Some(range) = span(node) else {
+            return;
+        };
+        if range.len() < size * 2 {
+            return;
+        }
+        let open = range.start..range.start + size;
+        let close = range.end - size..range.end;
+        let (Some(open_text), Some(close_text)) = (text.get(open.clone()), text.get(close.clone()))
+        else {
+            return;
+        };
+        let is_delimiter = |s: &str| s.chars().all(|c| c == '*') || s.chars().all(|c| c == '_');
+        if open_text == close_text && is_delimiter(open_text) {
+            let delimiter = marker.as_char().to_string().repeat(size);
+            edits.push(vec![(open, delimiter.clone()), (close, delimiter)]);
+        }
+    });
+    edits.edits
+}
+
+/// End the document with exactly one newline, dropping trailing blank lines
+///
+/// Blocks such as footnote definitions span the blank lines after them, so
+/// the end of the text is used rather than the end of the last block.
+fn final_newline_edits(
+    text: &str,
+    _root: &Node,
+    options: &FormatOptions,
+    newline: &str,
+) -> Vec<Edit> {
+    let mut edits = Edits::new(text);
+    let end = text.trim_end().len();
+    if options.final_newline && end > 0 {
+        edits.replace(end..text.len(), newline);
+    }
+    edits.edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_options() {
+        let content =
+            "Title\n=====\n\n* a *b* __c__\n* d\n\n3. x\n3. y\n\n```\nfn main() {}\n```\n";
+        assert_eq!(
+            format(content, &FormatOptions::default()),
+            "# Title\n\n- a *b* **c**\n- d\n\n3. x\n4. y\n\n```\nfn main() {}\n```\n"
+        );
+
+        let options = FormatOptions {
+            bullet: BulletMarker::Plus,
+            emphasis: EmphasisMarker::Underscore,
+            strong: EmphasisMarker::Underscore,
+            numbering: ListNumbering::One,
+            heading: HeadingStyle::Setext,
+            code_fence: CodeFence::Tilde,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format(content, &options),
+            "Title\n=====\n\n+ a _b_ __c__\n+ d\n\n3. x\n3. y\n\n~~~\nfn main() {}\n~~~\n"
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_rendering() {
+        // Switching to underscores inside a word would end the emphasis
+        let options = FormatOptions {
+            emphasis: EmphasisMarker::Underscore,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format("snake*case*name and *word*\n", &options),
+            "snake*case*name and _word_\n"
+        );
+
+        // Fences grow past runs of the fence character in the code
+        let options = FormatOptions {
+            code_fence: CodeFence::Tilde,
+            ..FormatOptions::default()
+        };
+        assert_eq!(format("```\n~~~\n```\n", &options), "~~~~\n~~~\n~~~~\n");
+    }
+
+    #[test]
+    fn test_format_crlf() {
+        assert_eq!(
+            format("* a\r\n* b\r\n# H\r\n\r\n\r\n", &FormatOptions::default()),
+            "- a\r\n- b\r\n\r\n# H\r\n"
+        );
+    }
+
+    #[test]
+    fn test_format_toggles() {
+        let content = "# A\n\n\n| a | b |\n|-|-:|\n| long cell | c |";
+        let options = FormatOptions {
+            align_tables: false,
+            blank_lines: false,
+            final_newline: false,
+            ..FormatOptions::default()
+        };
+        assert_eq!(format(content, &options), content);
+        assert_eq!(
+            format(content, &FormatOptions::default()),
+            "# A\n\n| a         |   b |\n| --------- | --: |\n| long cell |   c |\n"
+        );
+    }
+}
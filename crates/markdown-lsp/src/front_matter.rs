@@ -0,0 +1,629 @@
+use serde::{Deserialize, Serialize};
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// Syntax of a front matter block, chosen by its delimiter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    /// YAML between `---` lines
+    Yaml,
+    /// TOML between `+++` lines
+    Toml,
+}
+
+impl FrontMatterFormat {
+    /// Format opened by a document's first line
+    pub fn from_delimiter(line: &str) -> Option<Self> {
+        match line.trim_end() {
+            "---" => Some(Self::Yaml),
+            "+++" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    pub fn delimiter(&self) -> &'static str {
+        match self {
+            Self::Yaml => "---",
+            Self::Toml => "+++",
+        }
+    }
+
+    /// Character separating a key from its value
+    pub fn separator(&self) -> char {
+        match self {
+            Self::Yaml => ':',
+            Self::Toml => '=',
+        }
+    }
+}
+
+/// Value of a front matter field
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    /// TOML date or date-time literal
+    Date(String),
+    List(Vec<FieldValue>),
+    /// Nested table or mapping, which is not checked further
+    Map,
+}
+
+impl FieldValue {
+    fn from_yaml(value: Yaml) -> Self {
+        match value {
+            Yaml::Boolean(b) => Self::Boolean(b),
+            Yaml::Integer(n) => Self::Number(n as f64),
+            Yaml::Real(n) => Self::Number(n.parse().unwrap_or_default()),
+            Yaml::String(s) => Self::String(s),
+            Yaml::Array(items) => Self::List(items.into_iter().map(Self::from_yaml).collect()),
+            Yaml::Hash(_) => Self::Map,
+            Yaml::Null | Yaml::Alias(_) | Yaml::BadValue => Self::Null,
+        }
+    }
+
+    fn from_toml(value: toml::Value) -> Self {
+        match value {
+            toml::Value::Boolean(b) => Self::Boolean(b),
+            toml::Value::Integer(n) => Self::Number(n as f64),
+            toml::Value::Float(n) => Self::Number(n),
+            toml::Value::String(s) => Self::String(s),
+            toml::Value::Datetime(date) => Self::Date(date.to_string()),
+            toml::Value::Array(items) => {
+                Self::List(items.into_iter().map(Self::from_toml).collect())
+            }
+            toml::Value::Table(_) => Self::Map,
+        }
+    }
+
+    /// Scalar values as text, with lists flattened
+    pub fn texts(&self) -> Vec<String> {
+        match self {
+            Self::Null | Self::Map => vec![],
+            Self::Boolean(b) => vec![b.to_string()],
+            Self::Number(n) => vec![n.to_string()],
+            Self::String(s) | Self::Date(s) => vec![s.clone()],
+            Self::List(items) => items.iter().flat_map(Self::texts).collect(),
+        }
+    }
+
+    /// Name of the value's type for messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean(_) => "boolean",
+            Self::Number(_) => "number",
+            Self::String(_) => "string",
+            Self::Date(_) => "date",
+            Self::List(_) => "list",
+            Self::Map => "table",
+        }
+    }
+}
+
+/// Top-level key of a front matter block
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatterField {
+    pub key: String,
+    pub value: FieldValue,
+    /// Document line the key is on
+    pub line: usize,
+}
+
+/// Front matter block at the top of a Markdown document
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatter {
+    pub format: FrontMatterFormat,
+    /// Line of the closing delimiter; the content is on the lines between
+    pub end_line: usize,
+    /// Top-level fields in document order, empty when the block does not parse
+    pub fields: Vec<FrontMatterField>,
+    /// Parse error message and the document line it points at
+    pub error: Option<(usize, String)>,
+}
+
+impl FrontMatter {
+    /// Find and parse the front matter block opening on the first line
+    ///
+    /// Returns None when there is no block or it is never closed.
+    pub fn parse<S: AsRef<str>>(lines: &[S]) -> Option<Self> {
+        let format = FrontMatterFormat::from_delimiter(lines.first()?.as_ref())?;
+        let end_line = lines
+            .iter()
+            .skip(1)
+            .position(|line| line.as_ref().trim_end() == format.delimiter())?
+            + 1;
+        let content = lines[1..end_line]
+            .iter()
+            .map(|line| line.as_ref())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut front_matter = Self {
+            format,
+            end_line,
+            fields: Vec::new(),
+            error: None,
+        };
+        let values = match format {
+            FrontMatterFormat::Yaml => Self::parse_yaml(&content),
+            FrontMatterFormat::Toml => Self::parse_toml(&content),
+        };
+        match values {
+            Ok(values) => {
+                let key_lines = top_level_keys(lines, &front_matter);
+                front_matter.fields = values
+                    .into_iter()
+                    .map(|(key, value)| FrontMatterField {
+                        line: key_lines
+                            .iter()
+                            .find(|(_, k)| *k == key)
+                            .map_or(0, |(line, _)| *line),
+                        key,
+                        value,
+                    })
+                    .collect();
+                front_matter.fields.sort_by_key(|field| field.line);
+            }
+            Err((line, message)) => {
+                // Errors point into the content, which starts on the second line
+                front_matter.error = Some(((line + 1).min(end_line), message));
+            }
+        }
+        Some(front_matter)
+    }
+
+    fn parse_yaml(content: &str) -> Result<Vec<(String, FieldValue)>, (usize, String)> {
+        let value = YamlLoader::load_from_str(content)
+            .map_err(|e| (e.marker().line().saturating_sub(1), e.info().to_string()))?
+            .into_iter()
+            .next()
+            .unwrap_or(Yaml::Null);
+        match value {
+            Yaml::Null => Ok(vec![]),
+            Yaml::Hash(mapping) => Ok(mapping
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let key = match key {
+                        Yaml::String(key) | Yaml::Real(key) => key,
+                        Yaml::Integer(n) => n.to_string(),
+                        Yaml::Boolean(b) => b.to_string(),
+                        _ => return None,
+                    };
+                    Some((key, FieldValue::from_yaml(value)))
+                })
+                .collect()),
+            value => Err((
+                0,
+                format!(
+                    "expected key-value pairs, found a {}",
+                    FieldValue::from_yaml(value).type_name()
+                ),
+            )),
+        }
+    }
+
+    fn parse_toml(content: &str) -> Result<Vec<(String, FieldValue)>, (usize, String)> {
+        let table: toml::Table = content.parse().map_err(|e: toml::de::Error| {
+            let line = e
+                .span()
+                .map_or(0, |span| content[..span.start].matches('\n').count());
+            (line, e.message().to_string())
+        })?;
+        Ok(table
+            .into_iter()
+            .map(|(key, value)| (key, FieldValue::from_toml(value)))
+            .collect())
+    }
+
+    /// Field with a key
+    pub fn get(&self, key: &str) -> Option<&FrontMatterField> {
+        self.fields.iter().find(|field| field.key == key)
+    }
+
+    /// Whether a document line is between the delimiters
+    pub fn contains_line(&self, line: usize) -> bool {
+        line > 0 && line < self.end_line
+    }
+
+    /// Check the fields against a schema
+    ///
+    /// Returns the document line and message of each problem.
+    pub fn validate(&self, schema: &FrontMatterSchema) -> Vec<(usize, String)> {
+        if self.error.is_some() {
+            return vec![];
+        }
+
+        let mut issues = Vec::new();
+        for field_schema in &schema.fields {
+            let field = self
+                .get(&field_schema.name)
+                .filter(|field| field.value != FieldValue::Null);
+            let Some(field) = field else {
+                if field_schema.required {
+                    issues.push((
+                        0,
+                        format!(
+                            "Missing required front matter field '{}'",
+                            field_schema.name
+                        ),
+                    ));
+                }
+                continue;
+            };
+
+            if !field_schema.field_type.accepts(&field.value) {
+                issues.push((
+                    field.line,
+                    format!(
+                        "Front matter field '{}' should be a {}, found a {}",
+                        field.key,
+                        field_schema.field_type.name(),
+                        field.value.type_name()
+                    ),
+                ));
+                continue;
+            }
+            if !field_schema.values.is_empty() {
+                for value in field.value.texts() {
+                    if !field_schema.values.contains(&value) {
+                        issues.push((
+                            field.line,
+                            format!(
+                                "'{}' is not an allowed value for '{}' (expected one of: {})",
+                                value,
+                                field.key,
+                                field_schema.values.join(", ")
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        issues
+    }
+}
+
+/// Lines and names of the top-level keys in a front matter block
+///
+/// Nested keys, list items and comments are skipped. A TOML `[table]` header
+/// counts as the key of the table, and the keys below it are skipped.
+pub fn top_level_keys<S: AsRef<str>>(
+    lines: &[S],
+    front_matter: &FrontMatter,
+) -> Vec<(usize, String)> {
+    let mut keys: Vec<(usize, String)> = Vec::new();
+    let mut in_table = false;
+    for (line, text) in lines.iter().enumerate().take(front_matter.end_line).skip(1) {
+        let text = text.as_ref();
+        if front_matter.format == FrontMatterFormat::Toml && text.trim_start().starts_with('[') {
+            in_table = true;
+            let header = text.trim().trim_start_matches('[').split([']', '.']).next();
+            if let Some(key) = header.map(|key| key.trim().trim_matches(['"', '\'']))
+                && !keys.iter().any(|(_, k)| k == key)
+            {
+                keys.push((line, key.to_string()));
+            }
+            continue;
+        }
+        if in_table {
+            continue;
+        }
+        if let Some(key) = line_key(text, front_matter.format) {
+            keys.push((line, key));
+        }
+    }
+    keys
+}
+
+/// Top-level key defined on a line, if any
+pub fn line_key(text: &str, format: FrontMatterFormat) -> Option<String> {
+    if text.starts_with(char::is_whitespace) || text.starts_with(['-', '#']) {
+        return None;
+    }
+    let (key, _) = text.split_once(format.separator())?;
+    let key = key.trim().trim_matches(['"', '\'']);
+    (!key.is_empty()).then(|| key.to_string())
+}
+
+/// Write a string as a value, quoting it when the format requires
+///
+/// YAML strings stay bare unless they would read as another type or contain
+/// syntax; TOML strings are always quoted.
+pub fn quote_string(text: &str, format: FrontMatterFormat) -> String {
+    let needs_quotes = match format {
+        FrontMatterFormat::Toml => true,
+        FrontMatterFormat::Yaml => {
+            text.is_empty()
+                || text != text.trim()
+                || text.starts_with([
+                    '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'',
+                    '"', '%', '@', '`',
+                ])
+                || text.contains(": ")
+                || text.contains(" #")
+                || text.ends_with(':')
+                || !matches!(
+                    YamlLoader::load_from_str(text).as_deref(),
+                    Ok([Yaml::String(_)])
+                )
+        }
+    };
+    if needs_quotes {
+        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Whether text starts with a `YYYY-MM-DD` date
+pub fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    if bytes.len() < 10
+        || !(bytes[4] == b'-' && bytes[7] == b'-')
+        || !(bytes.len() == 10 || matches!(bytes[10], b'T' | b't' | b' '))
+    {
+        return false;
+    }
+    let number = |range: std::ops::Range<usize>| {
+        text.get(range.clone())
+            .filter(|s| s.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|s| s.parse::<u32>().ok())
+    };
+    matches!(
+        (number(0..4), number(5..7), number(8..10)),
+        (Some(_), Some(1..=12), Some(1..=31))
+    )
+}
+
+/// Type a front matter field must have
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    #[default]
+    String,
+    Number,
+    Boolean,
+    /// `YYYY-MM-DD`, optionally followed by a time
+    Date,
+    /// List of strings, such as tags
+    List,
+}
+
+impl FieldType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Date => "date",
+            Self::List => "list",
+        }
+    }
+
+    /// Whether a value has this type
+    pub fn accepts(&self, value: &FieldValue) -> bool {
+        match (self, value) {
+            (Self::String, FieldValue::String(_)) => true,
+            (Self::Number, FieldValue::Number(_)) => true,
+            (Self::Boolean, FieldValue::Boolean(_)) => true,
+            (Self::Date, FieldValue::Date(_)) => true,
+            (Self::Date, FieldValue::String(s)) => is_date(s),
+            (Self::List, FieldValue::List(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Expected front matter field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub field_type: FieldType,
+    /// Report documents without this field
+    #[serde(default)]
+    pub required: bool,
+    /// Shown next to the key in completions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Allowed values; any value is accepted when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+impl FieldSchema {
+    pub fn new(name: impl Into<String>, field_type: FieldType) -> Self {
+        Self {
+            name: name.into(),
+            field_type,
+            required: false,
+            description: None,
+            values: Vec::new(),
+        }
+    }
+}
+
+/// Front matter fields used for completion, validation and the edit form
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrontMatterSchema {
+    #[serde(default = "FrontMatterSchema::default_fields")]
+    pub fields: Vec<FieldSchema>,
+}
+
+impl FrontMatterSchema {
+    fn default_fields() -> Vec<FieldSchema> {
+        vec![
+            FieldSchema::new("title", FieldType::String),
+            FieldSchema::new("date", FieldType::Date),
+            FieldSchema::new("tags", FieldType::List),
+        ]
+    }
+
+    /// Schema of a field by name
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+impl Default for FrontMatterSchema {
+    fn default() -> Self {
+        Self {
+            fields: Self::default_fields(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_yaml_front_matter() {
+        let lines = [
+            "---",
+            "title: Notes",
+            "tags:",
+            "  - rust",
+            "  - editor",
+            "draft: true",
+            "---",
+            "# Notes",
+        ];
+        let front_matter = FrontMatter::parse(&lines).unwrap();
+        assert_eq!(front_matter.format, FrontMatterFormat::Yaml);
+        assert_eq!(front_matter.end_line, 6);
+        assert_eq!(front_matter.error, None);
+        let keys: Vec<(&str, usize)> = front_matter
+            .fields
+            .iter()
+            .map(|field| (field.key.as_str(), field.line))
+            .collect();
+        assert_eq!(keys, vec![("title", 1), ("tags", 2), ("draft", 5)]);
+        assert_eq!(
+            front_matter.get("tags").unwrap().value.texts(),
+            vec!["rust", "editor"]
+        );
+        assert!(front_matter.contains_line(3));
+        assert!(!front_matter.contains_line(6));
+
+        let broken = FrontMatter::parse(&["---", "title: Notes", "tags: [a, b", "---"]).unwrap();
+        assert!(broken.fields.is_empty());
+        assert!(
+            broken
+                .error
+                .is_some_and(|(line, _)| (2..=3).contains(&line))
+        );
+
+        assert_eq!(FrontMatter::parse(&["---", "title: Notes"]), None);
+        assert_eq!(FrontMatter::parse(&["# Notes", "---"]), None);
+    }
+
+    #[test]
+    fn test_parse_toml_front_matter() {
+        let lines = [
+            "+++",
+            "title = \"Notes\"",
+            "date = 2024-05-01",
+            "tags = [\"rust\"]",
+            "[extra]",
+            "title = \"nested\"",
+            "+++",
+        ];
+        let front_matter = FrontMatter::parse(&lines).unwrap();
+        assert_eq!(front_matter.format, FrontMatterFormat::Toml);
+        let keys: Vec<(&str, usize)> = front_matter
+            .fields
+            .iter()
+            .map(|field| (field.key.as_str(), field.line))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![("title", 1), ("date", 2), ("tags", 3), ("extra", 4)]
+        );
+        assert_eq!(
+            front_matter.get("date").unwrap().value,
+            FieldValue::Date("2024-05-01".to_string())
+        );
+
+        let broken = FrontMatter::parse(&["+++", "title = \"Notes\"", "tags = [", "+++"]).unwrap();
+        assert_eq!(broken.error.as_ref().map(|(line, _)| *line), Some(2));
+    }
+
+    #[test]
+    fn test_validate_front_matter() {
+        let mut schema = FrontMatterSchema::default();
+        schema.fields[0].required = true;
+        let mut status = FieldSchema::new("status", FieldType::String);
+        status.values = vec!["draft".to_string(), "published".to_string()];
+        schema.fields.push(status);
+
+        let lines = [
+            "---",
+            "date: 2024-13-01",
+            "tags: rust",
+            "status: archived",
+            "---",
+        ];
+        let issues = FrontMatter::parse(&lines).unwrap().validate(&schema);
+        assert_eq!(
+            issues,
+            vec![
+                (0, "Missing required front matter field 'title'".to_string()),
+                (
+                    1,
+                    "Front matter field 'date' should be a date, found a string".to_string()
+                ),
+                (
+                    2,
+                    "Front matter field 'tags' should be a list, found a string".to_string()
+                ),
+                (
+                    3,
+                    "'archived' is not an allowed value for 'status' (expected one of: draft, published)"
+                        .to_string()
+                ),
+            ]
+        );
+
+        let lines = [
+            "---",
+            "title: Notes",
+            "date: 2024-05-01",
+            "tags: [rust]",
+            "---",
+        ];
+        assert!(
+            FrontMatter::parse(&lines)
+                .unwrap()
+                .validate(&schema)
+                .is_empty()
+        );
+
+        assert!(is_date("2024-05-01T10:00:00Z"));
+        assert!(!is_date("2024-5-1"));
+        assert_eq!(quote_string("rust", FrontMatterFormat::Yaml), "rust");
+        assert_eq!(quote_string("true", FrontMatterFormat::Yaml), "\"true\"");
+        assert_eq!(quote_string("a: b", FrontMatterFormat::Yaml), "\"a: b\"");
+        assert_eq!(
+            quote_string("say \"hi\"", FrontMatterFormat::Toml),
+            "\"say \\\"hi\\\"\""
+        );
+        assert!(!is_date("2024-5-1"));
+    }
+
+    #[test]
+    fn test_schema_from_toml() {
+        let schema: FrontMatterSchema = toml::from_str(
+            "[[fields]]\nname = \"layout\"\nrequired = true\nvalues = [\"post\", \"page\"]\n",
+        )
+        .unwrap();
+        assert_eq!(schema.fields.len(), 1);
+        assert_eq!(schema.fields[0].field_type, FieldType::String);
+        assert!(schema.fields[0].required);
+
+        let schema: FrontMatterSchema = toml::from_str("").unwrap();
+        assert_eq!(schema, FrontMatterSchema::default());
+    }
+}
@@ -1,8 +1,14 @@
 pub mod backend;
 pub mod client;
 pub mod diagnostics;
+pub mod format;
+pub mod front_matter;
+pub mod link_index;
 pub mod manager;
 pub mod markdown_lsp;
+pub mod spell;
+pub mod toc;
+pub mod wiki_link;
 
 pub use backend::LspBackend;
 pub use client::{LspClient, LspEvent};
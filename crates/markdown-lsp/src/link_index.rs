@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use lsp_types::{Position, Range};
+use mq_markdown::{Markdown, Node};
+
+use crate::markdown_lsp::MarkdownLsp;
+use crate::wiki_link;
+
+/// Link from a Markdown file to a file or heading in the workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkReference {
+    /// File containing the link
+    pub source: PathBuf,
+    /// Range of the link in the source file, with columns counted in characters
+    pub range: Range,
+    /// Linked file
+    pub target: PathBuf,
+    /// Linked heading anchor, if any
+    pub anchor: Option<String>,
+    /// Trimmed text of the line the link is on
+    pub context: String,
+}
+
+/// Links between the Markdown files of a workspace, used to answer backlinks
+/// and cross-file references
+#[derive(Debug, Clone)]
+pub struct LinkIndex {
+    root: PathBuf,
+    /// Whether `[[Page]]` links are indexed along with Markdown links
+    wiki_links: bool,
+    /// Markdown files in the workspace
+    pages: Vec<PathBuf>,
+    /// Outgoing links by source file
+    links: HashMap<PathBuf, Vec<LinkReference>>,
+}
+
+impl LinkIndex {
+    /// Create an empty index for a workspace root
+    pub fn new(root: &Path, wiki_links: bool) -> Self {
+        Self {
+            root: normalize_path(root),
+            wiki_links,
+            pages: Vec::new(),
+            links: HashMap::new(),
+        }
+    }
+
+    /// Create an index and scan every Markdown file under the root
+    pub fn build(root: &Path, wiki_links: bool) -> Self {
+        let mut index = Self::new(root, wiki_links);
+        index.rebuild();
+        index
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Markdown files in the workspace, sorted
+    pub fn pages(&self) -> &[PathBuf] {
+        &self.pages
+    }
+
+    /// Rescan the workspace for Markdown files without reading them
+    pub fn refresh_pages(&mut self) {
+        self.pages = wiki_link::workspace_pages(&self.root);
+    }
+
+    /// Rescan the workspace and reindex every Markdown file
+    pub fn rebuild(&mut self) {
+        self.refresh_pages();
+        self.links.clear();
+        for page in self.pages.clone() {
+            if let Ok(content) = std::fs::read_to_string(&page) {
+                self.update_file(&page, &content);
+            }
+        }
+    }
+
+    /// Reindex the links of one file from its current content
+    pub fn update_file(&mut self, path: &Path, content: &str) {
+        let path = normalize_path(path);
+        if let Err(pos) = self.pages.binary_search(&path)
+            && path.starts_with(&self.root)
+        {
+            self.pages.insert(pos, path.clone());
+        }
+
+        let links = self.extract_links(&path, content);
+        self.links.insert(path, links);
+    }
+
+    /// Links pointing to a file, or to one of its headings when `anchor` is given
+    ///
+    /// Sorted by source file and line.
+    pub fn references_to(&self, target: &Path, anchor: Option<&str>) -> Vec<&LinkReference> {
+        let target = normalize_path(target);
+        let mut references: Vec<&LinkReference> = self
+            .links
+            .values()
+            .flatten()
+            .filter(|link| link.target == target)
+            .filter(|link| {
+                anchor.is_none_or(|anchor| {
+                    link.anchor
+                        .as_deref()
+                        .is_some_and(|a| a.eq_ignore_ascii_case(anchor))
+                })
+            })
+            .collect();
+        references.sort_by(|a, b| {
+            (&a.source, a.range.start.line, a.range.start.character).cmp(&(
+                &b.source,
+                b.range.start.line,
+                b.range.start.character,
+            ))
+        });
+        references
+    }
+
+    /// Links to a file from other files
+    pub fn backlinks(&self, target: &Path) -> Vec<&LinkReference> {
+        let target = normalize_path(target);
+        self.references_to(&target, None)
+            .into_iter()
+            .filter(|link| link.source != target)
+            .collect()
+    }
+
+    fn extract_links(&self, source: &Path, content: &str) -> Vec<LinkReference> {
+        let lines: Vec<&str> = content.lines().collect();
+        let context = |line: usize| lines.get(line).map_or("", |l| l.trim()).to_string();
+        let mut references = Vec::new();
+
+        if let Ok(markdown) = Markdown::from_markdown_str(content) {
+            let mut links = Vec::new();
+            collect_links(&markdown, &markdown.nodes, &mut links);
+            for (url, range) in links {
+                if let Some((target, anchor)) = resolve_url(source, &url) {
+                    // Markdown positions count bytes
+                    let position = |pos: Position| {
+                        Position::new(
+                            pos.line,
+                            char_column(&lines, pos.line as usize, pos.character as usize),
+                        )
+                    };
+                    references.push(LinkReference {
+                        source: source.to_path_buf(),
+                        context: context(range.start.line as usize),
+                        range: Range {
+                            start: position(range.start),
+                            end: position(range.end),
+                        },
+                        target,
+                        anchor,
+                    });
+                }
+            }
+        }
+
+        if self.wiki_links {
+            for (line, link) in wiki_link::document_wiki_links(content) {
+                let target = if link.page.is_empty() {
+                    Some(source)
+                } else {
+                    wiki_link::resolve_page(&self.pages, &self.root, &link.page)
+                };
+                let Some(target) = target else {
+                    continue;
+                };
+                let column = |offset: usize| char_column(&lines, line, offset);
+                references.push(LinkReference {
+                    source: source.to_path_buf(),
+                    range: Range {
+                        start: Position::new(line as u32, column(link.range.start)),
+                        end: Position::new(line as u32, column(link.range.end)),
+                    },
+                    target: target.to_path_buf(),
+                    anchor: link.anchor(),
+                    context: context(line),
+                });
+            }
+        }
+
+        references
+    }
+}
+
+/// Character column of a byte offset into one of `lines`
+fn char_column(lines: &[&str], line: usize, byte: usize) -> u32 {
+    lines.get(line).map_or(0, |text| match text.get(..byte) {
+        Some(prefix) => prefix.chars().count(),
+        None => text.chars().count(),
+    }) as u32
+}
+
+/// URLs and ranges of the links in a node list, with reference links resolved
+fn collect_links(markdown: &Markdown, nodes: &[Node], links: &mut Vec<(String, Range)>) {
+    for node in nodes {
+        let url = match node {
+            Node::Link(link) => Some(link.url.as_str().to_string()),
+            Node::LinkRef(link) => markdown.nodes.iter().find_map(|node| match node {
+                Node::Definition(def) if def.ident.eq_ignore_ascii_case(&link.ident) => {
+                    Some(def.url.as_str().to_string())
+                }
+                _ => None,
+            }),
+            _ => {
+                collect_links(markdown, &node.children(), links);
+                None
+            }
+        };
+        if let Some(url) = url
+            && let Some(position) = node.position()
+        {
+            links.push((url, MarkdownLsp::position_to_lsp_range(&position)));
+        }
+    }
+}
+
+/// Target file and anchor of a link URL, or None for external URLs
+pub fn resolve_url(source: &Path, url: &str) -> Option<(PathBuf, Option<String>)> {
+    let url = url.trim();
+    if url.is_empty() || has_scheme(url) {
+        return None;
+    }
+
+    let (path, anchor) = match url.split_once('#') {
+        Some((path, anchor)) => (path, Some(percent_decode(anchor))),
+        None => (url, None),
+    };
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    let target = if path.is_empty() {
+        source.to_path_buf()
+    } else {
+        let dir = source.parent().unwrap_or(Path::new(""));
+        normalize_path(&dir.join(percent_decode(path)))
+    };
+    Some((target, anchor.filter(|anchor| !anchor.is_empty())))
+}
+
+/// Whether a URL starts with a scheme such as `https:` (single letters are drive names)
+pub fn has_scheme(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Decode `%XX` escapes, leaving malformed ones as they are
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = s.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Make a path absolute and remove `.` and `..` components without touching the filesystem
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_url() {
+        let source = Path::new("/notes/a.md");
+        assert_eq!(
+            resolve_url(source, "../guide/Setup%20Guide.md#install"),
+            Some((
+                PathBuf::from("/guide/Setup Guide.md"),
+                Some("install".to_string())
+            ))
+        );
+        assert_eq!(
+            resolve_url(source, "#intro"),
+            Some((PathBuf::from("/notes/a.md"), Some("intro".to_string())))
+        );
+        assert_eq!(resolve_url(source, "https://mqlang.org"), None);
+        assert!(has_scheme("mailto:me@example.com"));
+        assert!(!has_scheme("C:/notes/a.md"));
+    }
+
+    #[test]
+    fn test_link_index_backlinks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("notes")).unwrap();
+        std::fs::write(
+            dir.join("index.md"),
+            "# Index\n\nSee [guide](notes/guide.md#setup).\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("notes/guide.md"),
+            "# Guide\n\n## Setup\n\n[Back](../index.md) and [[guide#Setup]]\n\n[ref][idx]\n\n[idx]: ../index.md\n",
+        )
+        .unwrap();
+
+        let mut index = LinkIndex::build(dir, true);
+        let guide = dir.join("notes/guide.md");
+        let backlinks = index.backlinks(&guide);
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].source, dir.join("index.md"));
+        assert_eq!(backlinks[0].range.start.line, 2);
+        assert_eq!(backlinks[0].context, "See [guide](notes/guide.md#setup).");
+
+        // Wiki links to the page itself count as references, not backlinks
+        assert_eq!(index.references_to(&guide, Some("setup")).len(), 2);
+        assert_eq!(index.backlinks(&dir.join("index.md")).len(), 2);
+
+        index.update_file(&dir.join("index.md"), "# Index\n");
+        assert!(index.backlinks(&guide).is_empty());
+
+        let without_wiki = LinkIndex::build(dir, false);
+        assert_eq!(without_wiki.references_to(&guide, Some("setup")).len(), 1);
+    }
+
+    #[test]
+    fn test_link_columns_count_characters() {
+        let index = LinkIndex::new(Path::new("/notes"), true);
+        let source = Path::new("/notes/a.md");
+        let links = index.extract_links(source, "Café [b](b.md) — [[#intro]]\n");
+        let columns: Vec<(u32, u32)> = links
+            .iter()
+            .map(|link| (link.range.start.character, link.range.end.character))
+            .collect();
+        assert_eq!(columns, vec![(5, 14), (17, 27)]);
+    }
+}
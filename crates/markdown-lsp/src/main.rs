@@ -3,15 +3,22 @@ use std::sync::mpsc;
 
 use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
 use lsp_types::{
-    CompletionOptions, InitializeParams, ServerCapabilities, TextDocumentSyncCapability,
-    TextDocumentSyncKind,
+    CodeActionProviderCapability, CompletionOptions, ExecuteCommandOptions, InitializeParams,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
     notification::{DidChangeTextDocument, DidOpenTextDocument, Notification as _},
-    request::{Completion, GotoDefinition, References, Request as _},
+    request::{
+        CodeActionRequest, Completion, DocumentSymbolRequest, ExecuteCommand, Formatting,
+        GotoDefinition, References, Request as _,
+    },
 };
 
 use markdown_lsp::backend::LspBackend;
 use markdown_lsp::client::LspEvent;
+use markdown_lsp::format::FormatOptions;
+use markdown_lsp::front_matter::FrontMatterSchema;
 use markdown_lsp::markdown_lsp::MarkdownLsp;
+use markdown_lsp::spell::{self, SpellConfig};
+use markdown_lsp::toc::TocOptions;
 
 fn main() -> miette::Result<()> {
     eprintln!("markdown-lsp: starting...");
@@ -32,6 +39,13 @@ fn main() -> miette::Result<()> {
         }),
         definition_provider: Some(lsp_types::OneOf::Left(true)),
         references_provider: Some(lsp_types::OneOf::Left(true)),
+        document_symbol_provider: Some(lsp_types::OneOf::Left(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: vec![spell::ADD_TO_DICTIONARY_COMMAND.to_string()],
+            ..Default::default()
+        }),
+        document_formatting_provider: Some(lsp_types::OneOf::Left(true)),
         ..Default::default()
     })
     .unwrap();
@@ -55,18 +69,68 @@ fn main() -> miette::Result<()> {
         .map(|uri| uri_to_path(&uri))
         .unwrap_or_else(|| PathBuf::from("."));
 
+    // Clients may pass the front matter schema as `{"frontMatterSchema": {"fields": [...]}}`
+    let front_matter_schema = init_params
+        .initialization_options
+        .as_ref()
+        .and_then(|options| options.get("frontMatterSchema").cloned())
+        .and_then(|schema| serde_json::from_value::<FrontMatterSchema>(schema).ok())
+        .unwrap_or_default();
+
+    // and spell checking settings as `{"spellCheck": {"language": "en_US", "dictionaries": [...]}}`
+    let spell_config = init_params
+        .initialization_options
+        .as_ref()
+        .and_then(|options| options.get("spellCheck").cloned())
+        .and_then(|config| serde_json::from_value::<SpellConfig>(config).ok())
+        .unwrap_or_default();
+
+    // and the formatting style as `{"format": {"bullet": "dash", "heading": "atx", ...}}`
+    let format_options = init_params
+        .initialization_options
+        .as_ref()
+        .and_then(|options| options.get("format").cloned())
+        .and_then(|options| serde_json::from_value::<FormatOptions>(options).ok())
+        .unwrap_or_default();
+
+    // and the table of contents depth range as `{"toc": {"min_depth": 2, "max_depth": 4, "bullet": "dash"}}`
+    let toc_options = init_params
+        .initialization_options
+        .as_ref()
+        .and_then(|options| options.get("toc").cloned())
+        .and_then(|options| serde_json::from_value::<TocOptions>(options).ok())
+        .unwrap_or_default();
+
     eprintln!("markdown-lsp: initialized with root {:?}", root_path);
 
-    main_loop(&connection, root_path)?;
+    main_loop(
+        &connection,
+        root_path,
+        front_matter_schema,
+        spell_config,
+        format_options,
+        toc_options,
+    )?;
 
     io_threads.join().map_err(|e| miette::miette!("{e:?}"))?;
     eprintln!("markdown-lsp: shutdown complete");
     Ok(())
 }
 
-fn main_loop(connection: &Connection, root_path: PathBuf) -> miette::Result<()> {
+fn main_loop(
+    connection: &Connection,
+    root_path: PathBuf,
+    front_matter_schema: FrontMatterSchema,
+    spell_config: SpellConfig,
+    format_options: FormatOptions,
+    toc_options: TocOptions,
+) -> miette::Result<()> {
     let (event_tx, event_rx) = mpsc::channel();
-    let mut lsp = MarkdownLsp::new(root_path, event_tx);
+    let mut lsp = MarkdownLsp::new(root_path, event_tx)
+        .with_front_matter_schema(front_matter_schema)
+        .with_spell_config(spell_config)
+        .with_format_options(format_options)
+        .with_toc_options(toc_options);
 
     for msg in &connection.receiver {
         match msg {
@@ -151,6 +215,61 @@ fn handle_request(
 
             send_response(connection, id, result);
         }
+        DocumentSymbolRequest::METHOD => {
+            let (id, params) = extract_request::<DocumentSymbolRequest>(req)?;
+            let file_path = uri_to_path(&params.text_document.uri);
+
+            lsp.request_document_symbols(&file_path).ok();
+
+            let result = match event_rx.try_recv() {
+                Ok(LspEvent::DocumentSymbols(resp)) => serde_json::to_value(resp).ok(),
+                _ => Some(serde_json::Value::Null),
+            };
+
+            send_response(connection, id, result);
+        }
+        CodeActionRequest::METHOD => {
+            let (id, params) = extract_request::<CodeActionRequest>(req)?;
+            let file_path = uri_to_path(&params.text_document.uri);
+
+            lsp.request_code_actions(
+                &file_path,
+                params.range.start.line,
+                params.range.start.character,
+            )
+            .ok();
+
+            let result = match event_rx.try_recv() {
+                Ok(LspEvent::CodeActions(resp)) => serde_json::to_value(resp).ok(),
+                _ => Some(serde_json::Value::Null),
+            };
+
+            send_response(connection, id, result);
+        }
+        Formatting::METHOD => {
+            let (id, params) = extract_request::<Formatting>(req)?;
+            let file_path = uri_to_path(&params.text_document.uri);
+
+            lsp.request_formatting(&file_path).ok();
+
+            let result = match event_rx.try_recv() {
+                Ok(LspEvent::Formatting(edits)) => serde_json::to_value(edits).ok(),
+                _ => Some(serde_json::Value::Null),
+            };
+
+            send_response(connection, id, result);
+        }
+        ExecuteCommand::METHOD => {
+            let (id, params) = extract_request::<ExecuteCommand>(req)?;
+
+            if let Err(e) = lsp.execute_command(&params.command, params.arguments) {
+                eprintln!("markdown-lsp: {}", e);
+            }
+
+            send_response(connection, id, Some(serde_json::Value::Null));
+            // Adding a word to the dictionary checks the document again
+            drain_diagnostics(event_rx, connection);
+        }
         _ => {
             eprintln!("markdown-lsp: unhandled request: {}", req.method);
         }
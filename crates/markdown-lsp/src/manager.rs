@@ -1,6 +1,10 @@
 use crate::backend::LspBackend;
 use crate::client::{LspClient, LspEvent};
+use crate::format::FormatOptions;
+use crate::front_matter::FrontMatterSchema;
 use crate::markdown_lsp::MarkdownLsp;
+use crate::spell::SpellConfig;
+use crate::toc::TocOptions;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
@@ -54,6 +58,14 @@ pub struct LspManager {
     server_configs: HashMap<String, LspServerConfig>,
     /// Workspace root path
     root_path: PathBuf,
+    /// Front matter schema for the embedded Markdown backend
+    front_matter_schema: FrontMatterSchema,
+    /// Spell checking settings for the embedded Markdown backend
+    spell_config: SpellConfig,
+    /// Formatting style for the embedded Markdown backend
+    format_options: FormatOptions,
+    /// Table of contents options for the embedded Markdown backend
+    toc_options: TocOptions,
 }
 
 impl LspManager {
@@ -65,9 +77,42 @@ impl LspManager {
             trigger_characters: HashMap::new(),
             server_configs,
             root_path,
+            front_matter_schema: FrontMatterSchema::default(),
+            spell_config: SpellConfig::default(),
+            format_options: FormatOptions::default(),
+            toc_options: TocOptions::default(),
         }
     }
 
+    /// Set the front matter schema used by the embedded Markdown backend
+    pub fn with_front_matter_schema(mut self, schema: FrontMatterSchema) -> Self {
+        self.front_matter_schema = schema;
+        self
+    }
+
+    /// Set the spell checking settings used by the embedded Markdown backend
+    pub fn with_spell_config(mut self, config: SpellConfig) -> Self {
+        self.spell_config = config;
+        self
+    }
+
+    /// Set the formatting style used by the embedded Markdown backend
+    pub fn with_format_options(mut self, options: FormatOptions) -> Self {
+        self.format_options = options;
+        self
+    }
+
+    /// Set the table of contents options used by the embedded Markdown backend
+    pub fn with_toc_options(mut self, options: TocOptions) -> Self {
+        self.toc_options = options;
+        self
+    }
+
+    /// Workspace root path
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
     /// Get or create an LSP backend for the given language
     pub fn get_or_create_backend(
         &mut self,
@@ -94,7 +139,13 @@ impl LspManager {
         let backend: Box<dyn LspBackend> = if server_config.embedded {
             // Create embedded backend
             match language_id {
-                "markdown" => Box::new(MarkdownLsp::new(self.root_path.clone(), event_tx)),
+                "markdown" => Box::new(
+                    MarkdownLsp::new(self.root_path.clone(), event_tx)
+                        .with_front_matter_schema(self.front_matter_schema.clone())
+                        .with_spell_config(self.spell_config.clone())
+                        .with_format_options(self.format_options)
+                        .with_toc_options(self.toc_options),
+                ),
                 _ => {
                     return Err(miette::miette!(
                         "No embedded LSP implementation for language: {}",
@@ -233,6 +284,53 @@ impl LspManager {
         Ok(())
     }
 
+    /// Request the symbols of a document
+    pub fn request_document_symbols(
+        &mut self,
+        language_id: &str,
+        file_path: &Path,
+    ) -> miette::Result<()> {
+        let backend = self.get_or_create_backend(language_id)?;
+        backend.request_document_symbols(file_path)?;
+        Ok(())
+    }
+
+    /// Request the code actions available at a position
+    pub fn request_code_actions(
+        &mut self,
+        language_id: &str,
+        file_path: &Path,
+        line: u32,
+        character: u32,
+    ) -> miette::Result<()> {
+        let backend = self.get_or_create_backend(language_id)?;
+        backend.request_code_actions(file_path, line, character)?;
+        Ok(())
+    }
+
+    /// Run a command offered by a code action
+    pub fn execute_command(
+        &mut self,
+        language_id: &str,
+        command: &str,
+        arguments: Vec<serde_json::Value>,
+    ) -> miette::Result<()> {
+        let backend = self.get_or_create_backend(language_id)?;
+        backend.execute_command(command, arguments)?;
+        Ok(())
+    }
+
+    /// Request the edits that format a document
+    pub fn request_formatting(
+        &mut self,
+        language_id: &str,
+        file_path: &Path,
+    ) -> miette::Result<()> {
+        let backend = self.get_or_create_backend(language_id)?;
+        backend.request_formatting(file_path)?;
+        Ok(())
+    }
+
     /// Poll for events from all LSP backends
     pub fn poll_events(&mut self) -> Vec<(String, LspEvent)> {
         let mut events = Vec::new();
@@ -1,6 +1,8 @@
 use lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionResponse, Diagnostic, DiagnosticSeverity,
-    GotoDefinitionResponse, InsertTextFormat, Location, Position, Range, Uri as LspUri,
+    CodeAction, CodeActionKind, CodeActionOrCommand, Command, CompletionItem, CompletionItemKind,
+    CompletionResponse, Diagnostic, DiagnosticSeverity, DocumentSymbol, DocumentSymbolResponse,
+    GotoDefinitionResponse, InsertTextFormat, Location, Position, Range, TextEdit, Uri as LspUri,
+    WorkspaceEdit,
 };
 use mq_markdown::{Heading, Link, Markdown, Node, Position as MdPosition};
 use std::collections::HashMap;
@@ -9,6 +11,18 @@ use std::sync::mpsc;
 
 use crate::backend::LspBackend;
 use crate::client::LspEvent;
+use crate::format::{self, FormatOptions};
+use crate::front_matter::{self, FieldType, FrontMatter, FrontMatterField, FrontMatterSchema};
+use crate::link_index::{self, LinkIndex};
+use crate::spell::{self, SpellChecker, SpellConfig};
+use crate::toc::{self, TocOptions};
+use crate::wiki_link::{self, WikiLink};
+
+/// Source of spelling diagnostics
+const SPELL_SOURCE: &str = "spell";
+
+/// Most corrections offered for an unknown word
+const MAX_SPELLING_SUGGESTIONS: usize = 5;
 
 /// Document state for the embedded Markdown LSP
 struct MarkdownDocument {
@@ -19,8 +33,17 @@ struct MarkdownDocument {
 /// Embedded Markdown Language Server
 pub struct MarkdownLsp {
     documents: HashMap<String, MarkdownDocument>,
-    #[allow(dead_code)]
-    root_path: PathBuf,
+    /// Links between the workspace's Markdown files, which also lists the
+    /// pages wiki links can point to
+    link_index: LinkIndex,
+    /// Expected front matter fields
+    front_matter_schema: FrontMatterSchema,
+    /// Front matter fields of the workspace's files, for value completions
+    front_matter_values: HashMap<PathBuf, Vec<FrontMatterField>>,
+    spell_checker: SpellChecker,
+    format_options: FormatOptions,
+    /// Depth range and marker the table of contents is checked against
+    toc_options: TocOptions,
     event_tx: mpsc::Sender<LspEvent>,
 }
 
@@ -29,11 +52,124 @@ impl MarkdownLsp {
     pub fn new(root_path: PathBuf, event_tx: mpsc::Sender<LspEvent>) -> Self {
         Self {
             documents: HashMap::new(),
-            root_path,
+            link_index: LinkIndex::new(&root_path, true),
+            front_matter_schema: FrontMatterSchema::default(),
+            front_matter_values: HashMap::new(),
+            spell_checker: SpellChecker::new(SpellConfig::default()),
+            format_options: FormatOptions::default(),
+            toc_options: TocOptions::default(),
             event_tx,
         }
     }
 
+    /// Set the schema front matter is completed and validated against
+    pub fn with_front_matter_schema(mut self, schema: FrontMatterSchema) -> Self {
+        self.front_matter_schema = schema;
+        self
+    }
+
+    /// Set the dictionaries and language used for spell checking
+    pub fn with_spell_config(mut self, config: SpellConfig) -> Self {
+        self.spell_checker = SpellChecker::new(config);
+        self
+    }
+
+    /// Set the style documents are formatted to
+    pub fn with_format_options(mut self, options: FormatOptions) -> Self {
+        self.format_options = options;
+        self
+    }
+
+    /// Set the options the table of contents is expected to be generated with
+    pub fn with_toc_options(mut self, options: TocOptions) -> Self {
+        self.toc_options = options;
+        self
+    }
+
+    /// Remember the front matter fields of a file for value completions
+    fn update_front_matter_values(&mut self, path: PathBuf, content: &str) {
+        let path = link_index::normalize_path(&path);
+        let lines: Vec<&str> = content.lines().collect();
+        match FrontMatter::parse(&lines) {
+            Some(front_matter) if front_matter.error.is_none() => {
+                self.front_matter_values.insert(path, front_matter.fields);
+            }
+            // Keep the last good values while a block is being edited
+            Some(_) => {}
+            None => {
+                self.front_matter_values.remove(&path);
+            }
+        }
+    }
+
+    /// Rescan the workspace for pages wiki links can point to
+    fn refresh_pages(&mut self) {
+        self.link_index.refresh_pages();
+    }
+
+    /// Convert a URI string back to a file path
+    fn uri_to_path(uri: &str) -> PathBuf {
+        PathBuf::from(uri.strip_prefix("file://").unwrap_or(uri))
+    }
+
+    /// File a wiki link page refers to (the document itself for an empty page)
+    fn resolve_wiki_page(&self, uri: &str, page: &str) -> Option<PathBuf> {
+        if page.is_empty() {
+            return Some(Self::uri_to_path(uri));
+        }
+        wiki_link::resolve_page(self.link_index.pages(), self.link_index.root(), page)
+            .map(Path::to_path_buf)
+    }
+
+    /// URI of a file for a Location, with spaces escaped as page names often contain them
+    fn location_uri(path: &Path) -> Option<LspUri> {
+        Self::path_to_uri(path).replace(' ', "%20").parse().ok()
+    }
+
+    /// Headings of a page with their range, from the open document or the file on disk
+    fn page_headings(&self, path: &Path) -> Vec<(String, Range)> {
+        let parsed;
+        let ast = match self
+            .documents
+            .get(&Self::path_to_uri(path))
+            .and_then(|doc| doc.ast.as_ref())
+        {
+            Some(ast) => ast,
+            None => {
+                parsed = std::fs::read_to_string(path)
+                    .ok()
+                    .and_then(|content| Markdown::from_markdown_str(&content).ok());
+                match &parsed {
+                    Some(ast) => ast,
+                    None => return vec![],
+                }
+            }
+        };
+
+        self.collect_headings(ast)
+            .into_iter()
+            .filter_map(|(node, heading)| {
+                let text = heading.values.iter().map(|n| n.value()).collect::<String>();
+                Some((text, Self::position_to_lsp_range(&node.position()?)))
+            })
+            .collect()
+    }
+
+    /// Range of a byte range in a line, in characters
+    fn line_range(line_content: &str, line: usize, bytes: &std::ops::Range<usize>) -> Range {
+        let column = |offset: usize| line_content[..offset].chars().count() as u32;
+        Range {
+            start: Position {
+                line: line as u32,
+                character: column(bytes.start),
+            },
+            end: Position {
+                line: line as u32,
+                character: column(bytes.end),
+            },
+        }
+    }
+
     /// Convert a file path to a URI string
     fn path_to_uri(path: &Path) -> String {
         format!("file://{}", path.display())
@@ -42,10 +178,16 @@ impl MarkdownLsp {
     /// Parse a markdown document and store it
     fn parse_document(&mut self, uri: &str, content: &str) {
         let ast = Markdown::from_markdown_str(content).ok();
-
-        // Generate diagnostics for broken links
+        self.link_index
+            .update_file(&Self::uri_to_path(uri), content);
+        self.update_front_matter_values(Self::uri_to_path(uri), content);
+        let lines: Vec<&str> = content.lines().collect();
+        let language = self.spell_checker.document_language(&lines);
+        self.spell_checker.load(&language);
+
+        // Generate diagnostics for broken links, stale tables of contents and spelling
         if let Some(ref markdown) = ast {
-            self.generate_diagnostics(uri, markdown);
+            self.generate_diagnostics(uri, content, markdown);
         }
 
         self.documents.insert(
@@ -111,7 +253,7 @@ impl MarkdownLsp {
     }
 
     /// Convert markdown position to LSP range
-    fn position_to_lsp_range(pos: &MdPosition) -> Range {
+    pub(crate) fn position_to_lsp_range(pos: &MdPosition) -> Range {
         Range {
             start: Position {
                 line: (pos.start.line - 1) as u32,
@@ -124,8 +266,8 @@ impl MarkdownLsp {
         }
     }
 
-    /// Generate diagnostics for broken links
-    fn generate_diagnostics(&self, uri: &str, ast: &Markdown) {
+    /// Generate diagnostics for broken links and a stale table of contents
+    fn generate_diagnostics(&self, uri: &str, content: &str, ast: &Markdown) {
         let mut diagnostics = Vec::new();
 
         // Collect all heading slugs
@@ -153,6 +295,11 @@ impl MarkdownLsp {
             }
         }
 
+        diagnostics.extend(self.wiki_link_diagnostics(uri, content, &heading_slugs));
+        diagnostics.extend(self.front_matter_diagnostics(content));
+        diagnostics.extend(self.stale_toc_diagnostic(content, ast));
+        diagnostics.extend(self.spelling_diagnostics(content, ast));
+
         // Always send diagnostics event, even if empty (to clear previous diagnostics)
         if let Ok(lsp_uri) = uri.parse::<LspUri>() {
             let _ =
@@ -165,6 +312,267 @@ impl MarkdownLsp {
         }
     }
 
+    /// Report front matter that does not parse or does not match the schema
+    fn front_matter_diagnostics(&self, content: &str) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = content.lines().collect();
+        let Some(front_matter) = FrontMatter::parse(&lines) else {
+            return vec![];
+        };
+        let line_range = |line: usize| {
+            let text = lines.get(line).copied().unwrap_or_default();
+            Self::line_range(text, line, &(0..text.len()))
+        };
+
+        if let Some((line, message)) = &front_matter.error {
+            return vec![Diagnostic {
+                range: line_range(*line),
+                severity: Some(DiagnosticSeverity::ERROR),
+                message: format!("Invalid front matter: {}", message),
+                ..Default::default()
+            }];
+        }
+        front_matter
+            .validate(&self.front_matter_schema)
+            .into_iter()
+            .map(|(line, message)| Diagnostic {
+                range: line_range(line),
+                severity: Some(DiagnosticSeverity::WARNING),
+                message,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Point out unknown words in the document's prose
+    fn spelling_diagnostics(&self, content: &str, ast: &Markdown) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = content.lines().collect();
+        let language = self.spell_checker.document_language(&lines);
+        self.spell_checker
+            .misspellings(&language, content, ast)
+            .into_iter()
+            .map(|misspelling| Diagnostic {
+                range: Self::line_range(
+                    lines[misspelling.line],
+                    misspelling.line,
+                    &misspelling.range,
+                ),
+                severity: Some(DiagnosticSeverity::INFORMATION),
+                source: Some(SPELL_SOURCE.to_string()),
+                message: format!("Unknown word: '{}'", misspelling.word),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Corrections and an add-to-dictionary action for the unknown word at a position
+    pub fn get_code_actions(
+        &self,
+        uri: &str,
+        line: u32,
+        character: u32,
+    ) -> Vec<CodeActionOrCommand> {
+        let Some(document) = self.documents.get(uri) else {
+            return vec![];
+        };
+        let Some(ast) = &document.ast else {
+            return vec![];
+        };
+        let Ok(lsp_uri) = uri.parse::<LspUri>() else {
+            return vec![];
+        };
+        let lines: Vec<&str> = document.content.lines().collect();
+        let language = self.spell_checker.document_language(&lines);
+
+        let Some((misspelling, range)) = self
+            .spell_checker
+            .misspellings(&language, &document.content, ast)
+            .into_iter()
+            .filter(|misspelling| misspelling.line == line as usize)
+            .map(|misspelling| {
+                let range = Self::line_range(
+                    lines[misspelling.line],
+                    misspelling.line,
+                    &misspelling.range,
+                );
+                (misspelling, range)
+            })
+            .find(|(_, range)| {
+                range.start.character <= character && character <= range.end.character
+            })
+        else {
+            return vec![];
+        };
+
+        let mut actions: Vec<CodeActionOrCommand> = self
+            .spell_checker
+            .suggest(&language, &misspelling.word, MAX_SPELLING_SUGGESTIONS)
+            .into_iter()
+            .map(|suggestion| {
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Change to '{}'", suggestion),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(
+                            lsp_uri.clone(),
+                            vec![TextEdit {
+                                range,
+                                new_text: suggestion,
+                            }],
+                        )])),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Add '{}' to dictionary", misspelling.word),
+            kind: Some(CodeActionKind::QUICKFIX),
+            command: Some(Command {
+                title: "Add to dictionary".to_string(),
+                command: spell::ADD_TO_DICTIONARY_COMMAND.to_string(),
+                arguments: Some(vec![
+                    serde_json::Value::String(misspelling.word),
+                    serde_json::Value::String(uri.to_string()),
+                ]),
+            }),
+            ..Default::default()
+        }));
+        actions
+    }
+
+    /// Run a command from a code action
+    ///
+    /// Adding a word to the dictionary checks the document it came from
+    /// again, named by the command's second argument.
+    pub fn run_command(
+        &mut self,
+        command: &str,
+        arguments: &[serde_json::Value],
+    ) -> miette::Result<()> {
+        if command != spell::ADD_TO_DICTIONARY_COMMAND {
+            return Err(miette::miette!("Unknown command: {}", command));
+        }
+        let Some(word) = arguments.first().and_then(serde_json::Value::as_str) else {
+            return Err(miette::miette!("Missing word to add to the dictionary"));
+        };
+        self.spell_checker.add_to_personal_dictionary(word)?;
+
+        if let Some(uri) = arguments.get(1).and_then(serde_json::Value::as_str)
+            && let Some(document) = self.documents.get(uri)
+            && let Some(ast) = &document.ast
+        {
+            self.generate_diagnostics(uri, &document.content, ast);
+        }
+        Ok(())
+    }
+
+    /// Edit replacing the whole document with its formatted version, or none
+    /// when it is already formatted
+    pub fn get_formatting(&self, uri: &str) -> Vec<TextEdit> {
+        let Some(document) = self.documents.get(uri) else {
+            return vec![];
+        };
+        let formatted = format::format(&document.content, &self.format_options);
+        if formatted == document.content {
+            return vec![];
+        }
+
+        let last_line = document.content.rsplit('\n').next().unwrap_or_default();
+        let end = Position {
+            line: document.content.matches('\n').count() as u32,
+            character: last_line.chars().count() as u32,
+        };
+        vec![TextEdit {
+            range: Range {
+                start: Position::default(),
+                end,
+            },
+            new_text: formatted,
+        }]
+    }
+
+    /// Warn about wiki links to missing pages or headings
+    fn wiki_link_diagnostics(
+        &self,
+        uri: &str,
+        content: &str,
+        heading_slugs: &[String],
+    ) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = content.lines().collect();
+        wiki_link::document_wiki_links(content)
+            .into_iter()
+            .filter_map(|(line, link)| {
+                let message = self.unresolved_wiki_link(uri, &link, heading_slugs)?;
+                Some(Diagnostic {
+                    range: Self::line_range(lines[line], line, &link.range),
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    message,
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Describe why a wiki link does not resolve, or None if it does
+    fn unresolved_wiki_link(
+        &self,
+        uri: &str,
+        link: &WikiLink,
+        heading_slugs: &[String],
+    ) -> Option<String> {
+        if link.page.is_empty() {
+            let anchor = link.anchor()?;
+            return (!heading_slugs.contains(&anchor))
+                .then(|| format!("Unresolved wiki link: heading '{}' not found", anchor));
+        }
+
+        let Some(path) = self.resolve_wiki_page(uri, &link.page) else {
+            return Some(format!(
+                "Unresolved wiki link: page '{}' not found",
+                link.page
+            ));
+        };
+        let anchor = link.anchor()?;
+        let found = self
+            .page_headings(&path)
+            .iter()
+            .any(|(text, _)| Self::make_heading_slug(text) == anchor);
+        (!found).then(|| {
+            format!(
+                "Unresolved wiki link: heading '{}' not found in '{}'",
+                anchor, link.page
+            )
+        })
+    }
+
+    /// Warn when the table of contents between the toc markers no longer matches the headings
+    fn stale_toc_diagnostic(&self, content: &str, ast: &Markdown) -> Option<Diagnostic> {
+        let lines: Vec<&str> = content.lines().collect();
+        let (start, end) = toc::find_toc(&lines)?;
+        let entries = toc::existing_entries(&lines, start, end);
+        let expected = toc::toc_entries(ast, self.toc_options);
+        if entries == expected {
+            return None;
+        }
+
+        Some(Diagnostic {
+            range: Range {
+                start: Position {
+                    line: start as u32,
+                    character: 0,
+                },
+                end: Position {
+                    line: start as u32,
+                    character: lines[start].chars().count() as u32,
+                },
+            },
+            severity: Some(DiagnosticSeverity::WARNING),
+            message: "Table of contents is out of date".to_string(),
+            ..Default::default()
+        })
+    }
+
     /// Extract anchor link from node if it's an anchor link
     fn extract_anchor_link(node: &Node) -> Option<(String, &MdPosition)> {
         match node {
@@ -205,6 +613,16 @@ impl MarkdownLsp {
         let char_pos = (character as usize).min(line_content.len());
         let prefix = &line_content[..char_pos];
 
+        // Schema keys and known values inside the front matter
+        if let Some(items) = self.front_matter_completions(uri, &lines, line as usize, prefix) {
+            return items;
+        }
+
+        // Page and heading completions inside an unclosed [[
+        if let Some(items) = self.wiki_link_completions(uri, prefix) {
+            return items;
+        }
+
         let mut items = vec![];
 
         // Heading completions after #
@@ -371,8 +789,182 @@ impl MarkdownLsp {
         items
     }
 
+    /// Complete schema keys at the start of a front matter line, and values
+    /// after a key from the schema and the rest of the workspace
+    ///
+    /// Returns None when the line is not inside front matter.
+    fn front_matter_completions(
+        &self,
+        uri: &str,
+        lines: &[&str],
+        line: usize,
+        prefix: &str,
+    ) -> Option<Vec<CompletionItem>> {
+        let front_matter = FrontMatter::parse(lines)?;
+        if !front_matter.contains_line(line) {
+            return None;
+        }
+        let format = front_matter.format;
+        let keys = front_matter::top_level_keys(lines, &front_matter);
+
+        // List items and continuation lines belong to the key above them
+        let key = if prefix.starts_with(char::is_whitespace) || prefix.starts_with('-') {
+            keys.iter()
+                .rev()
+                .find(|(key_line, _)| *key_line < line)
+                .map(|(_, key)| key.clone())
+        } else {
+            front_matter::line_key(prefix, format)
+        };
+
+        let Some(key) = key else {
+            let items = self
+                .front_matter_schema
+                .fields
+                .iter()
+                .filter(|field| !keys.iter().any(|(l, key)| *l != line && *key == field.name))
+                .enumerate()
+                .map(|(i, field)| CompletionItem {
+                    label: field.name.clone(),
+                    kind: Some(CompletionItemKind::PROPERTY),
+                    detail: Some(
+                        field
+                            .description
+                            .clone()
+                            .unwrap_or_else(|| field.field_type.name().to_string()),
+                    ),
+                    insert_text: Some(match format {
+                        front_matter::FrontMatterFormat::Yaml => format!("{}: ", field.name),
+                        front_matter::FrontMatterFormat::Toml => format!("{} = ", field.name),
+                    }),
+                    sort_text: Some(format!("{:04}", i)),
+                    ..Default::default()
+                })
+                .collect();
+            return Some(items);
+        };
+
+        let schema = self.front_matter_schema.field(&key);
+        let field_type = schema.map(|field| field.field_type);
+        let mut candidates: Vec<(String, usize)> = schema
+            .map(|field| field.values.iter().map(|v| (v.clone(), 0)).collect())
+            .unwrap_or_default();
+        if field_type == Some(FieldType::Boolean) {
+            candidates.extend([("true".to_string(), 0), ("false".to_string(), 0)]);
+        }
+        for fields in self.front_matter_values.values() {
+            let Some(field) = fields.iter().find(|field| field.key == key) else {
+                continue;
+            };
+            let mut texts = field.value.texts();
+            texts.dedup();
+            for text in texts {
+                match candidates.iter_mut().find(|(value, _)| *value == text) {
+                    Some((_, count)) => *count += 1,
+                    None => candidates.push((text, 1)),
+                }
+            }
+        }
+
+        // Skip list entries the document already has, as of its last good parse
+        // while the block does not parse
+        let path = link_index::normalize_path(&Self::uri_to_path(uri));
+        let current = match &front_matter.error {
+            None => front_matter.fields.iter().find(|field| field.key == key),
+            Some(_) => self
+                .front_matter_values
+                .get(&path)
+                .and_then(|fields| fields.iter().find(|field| field.key == key)),
+        };
+        let current = current
+            .filter(|field| matches!(field.value, front_matter::FieldValue::List(_)))
+            .map(|field| field.value.texts())
+            .unwrap_or_default();
+        candidates.retain(|(value, _)| !current.contains(value));
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        // Bare strings need quotes in TOML unless one is already open
+        let word_start = prefix.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_');
+        let quote = format == front_matter::FrontMatterFormat::Toml
+            && !word_start.ends_with('"')
+            && !matches!(
+                field_type,
+                Some(FieldType::Number | FieldType::Boolean | FieldType::Date)
+            );
+
+        let items = candidates
+            .into_iter()
+            .enumerate()
+            .map(|(i, (value, count))| CompletionItem {
+                label: value.clone(),
+                kind: Some(CompletionItemKind::VALUE),
+                detail: Some(match count {
+                    0 => "allowed value".to_string(),
+                    1 => "used in 1 file".to_string(),
+                    n => format!("used in {} files", n),
+                }),
+                insert_text: Some(if quote {
+                    front_matter::quote_string(&value, format)
+                } else {
+                    value
+                }),
+                sort_text: Some(format!("{:04}", i)),
+                ..Default::default()
+            })
+            .collect();
+        Some(items)
+    }
+
+    /// Complete page names after `[[`, and headings after `[[Page#`
+    ///
+    /// Returns None when the prefix is not inside a wiki link.
+    fn wiki_link_completions(&self, uri: &str, prefix: &str) -> Option<Vec<CompletionItem>> {
+        let start = prefix.rfind("[[")?;
+        let partial = &prefix[start + 2..];
+        if partial.contains([']', '|']) {
+            return None;
+        }
+
+        if let Some((page, _)) = partial.split_once('#') {
+            let Some(path) = self.resolve_wiki_page(uri, page) else {
+                return Some(vec![]);
+            };
+            let items = self
+                .page_headings(&path)
+                .into_iter()
+                .enumerate()
+                .map(|(i, (text, _))| CompletionItem {
+                    label: text.clone(),
+                    detail: Some(format!("#{}", Self::make_heading_slug(&text))),
+                    kind: Some(CompletionItemKind::REFERENCE),
+                    insert_text: Some(text),
+                    sort_text: Some(format!("{:04}", i)),
+                    ..Default::default()
+                })
+                .collect();
+            return Some(items);
+        }
+
+        let items = self
+            .link_index
+            .pages()
+            .iter()
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_string_lossy().into_owned();
+                Some(CompletionItem {
+                    label: name.clone(),
+                    detail: Some(wiki_link::page_name(self.link_index.root(), path)),
+                    kind: Some(CompletionItemKind::FILE),
+                    insert_text: Some(name),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        Some(items)
+    }
+
     /// Create a URL-safe heading slug
-    fn make_heading_slug(text: &str) -> String {
+    pub fn make_heading_slug(text: &str) -> String {
         text.to_lowercase()
             .chars()
             .map(|c| if c.is_alphanumeric() { c } else { '-' })
@@ -391,6 +983,18 @@ impl MarkdownLsp {
         character: u32,
     ) -> Option<GotoDefinitionResponse> {
         let doc = self.documents.get(uri)?;
+
+        // Wiki links are found by scanning the line, as their AST positions span the paragraph
+        if let Some(line_content) = doc.content.lines().nth(line as usize) {
+            let offset = line_content
+                .char_indices()
+                .nth(character as usize)
+                .map_or(line_content.len(), |(i, _)| i);
+            if let Some(link) = wiki_link::wiki_link_at(line_content, offset) {
+                return self.wiki_link_definition(uri, &link);
+            }
+        }
+
         let ast = doc.ast.as_ref()?;
 
         // Find node at cursor position
@@ -441,6 +1045,24 @@ impl MarkdownLsp {
         None
     }
 
+    /// Locate the page, or the heading in it, a wiki link points to
+    fn wiki_link_definition(&self, uri: &str, link: &WikiLink) -> Option<GotoDefinitionResponse> {
+        let path = self.resolve_wiki_page(uri, &link.page)?;
+        let range = match link.anchor() {
+            Some(anchor) => self
+                .page_headings(&path)
+                .into_iter()
+                .find(|(text, _)| Self::make_heading_slug(text) == anchor)
+                .map(|(_, range)| range)?,
+            None => Range::default(),
+        };
+
+        Some(GotoDefinitionResponse::Scalar(Location {
+            uri: Self::location_uri(&path)?,
+            range,
+        }))
+    }
+
     /// Get references for headings and links
     fn get_references(
         &self,
@@ -458,9 +1080,8 @@ impl MarkdownLsp {
         let Some(node) = self.find_node_at_position(ast, line, character) else {
             return vec![];
         };
-        let lsp_uri: LspUri = match uri.parse() {
-            Ok(u) => u,
-            Err(_) => return vec![],
+        let Some(lsp_uri) = Self::location_uri(&Self::uri_to_path(uri)) else {
+            return vec![];
         };
 
         let mut locations = Vec::new();
@@ -489,6 +1110,16 @@ impl MarkdownLsp {
                         });
                     }
                 }
+
+                // And the links to it from other files
+                let path = Self::uri_to_path(uri);
+                locations.extend(
+                    self.link_index
+                        .references_to(&path, Some(&slug))
+                        .into_iter()
+                        .filter(|link| link.source != path)
+                        .filter_map(Self::reference_location),
+                );
             }
             Node::Link(link) => {
                 let url_str = link.url.as_str();
@@ -522,6 +1153,27 @@ impl MarkdownLsp {
                             });
                         }
                     }
+                } else if let Some((target, anchor)) =
+                    link_index::resolve_url(&Self::uri_to_path(uri), url_str)
+                {
+                    // Link to another file: its target and every link to the same target
+                    if include_declaration && let Some(uri) = Self::location_uri(&target) {
+                        let range = anchor.as_deref().map_or(Some(Range::default()), |anchor| {
+                            self.page_headings(&target)
+                                .into_iter()
+                                .find(|(text, _)| Self::make_heading_slug(text) == anchor)
+                                .map(|(_, range)| range)
+                        });
+                        if let Some(range) = range {
+                            locations.push(Location { uri, range });
+                        }
+                    }
+                    locations.extend(
+                        self.link_index
+                            .references_to(&target, anchor.as_deref())
+                            .into_iter()
+                            .filter_map(Self::reference_location),
+                    );
                 }
             }
             _ => {}
@@ -529,10 +1181,81 @@ impl MarkdownLsp {
 
         locations
     }
+
+    /// Location of an indexed link
+    fn reference_location(link: &link_index::LinkReference) -> Option<Location> {
+        Some(Location {
+            uri: Self::location_uri(&link.source)?,
+            range: link.range,
+        })
+    }
+
+    /// Get the heading tree of a document as nested symbols
+    fn get_document_symbols(&self, uri: &str) -> Vec<DocumentSymbol> {
+        let Some(ast) = self.documents.get(uri).and_then(|doc| doc.ast.as_ref()) else {
+            return vec![];
+        };
+
+        let mut roots = Vec::new();
+        let mut stack: Vec<(u8, DocumentSymbol)> = Vec::new();
+
+        for (node, heading) in self.collect_headings(ast) {
+            let Some(pos) = node.position() else {
+                continue;
+            };
+            let range = Self::position_to_lsp_range(&pos);
+
+            while stack
+                .last()
+                .is_some_and(|(depth, _)| *depth >= heading.depth)
+            {
+                Self::attach_symbol(&mut stack, &mut roots);
+            }
+
+            #[allow(deprecated)]
+            stack.push((
+                heading.depth,
+                DocumentSymbol {
+                    name: heading.values.iter().map(|n| n.value()).collect(),
+                    detail: None,
+                    kind: lsp_types::SymbolKind::STRING,
+                    tags: None,
+                    deprecated: None,
+                    range,
+                    selection_range: range,
+                    children: None,
+                },
+            ));
+        }
+
+        while !stack.is_empty() {
+            Self::attach_symbol(&mut stack, &mut roots);
+        }
+
+        roots
+    }
+
+    /// Pop the innermost open heading and attach it to its parent
+    fn attach_symbol(stack: &mut Vec<(u8, DocumentSymbol)>, roots: &mut Vec<DocumentSymbol>) {
+        let Some((_, symbol)) = stack.pop() else {
+            return;
+        };
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.get_or_insert_with(Vec::new).push(symbol),
+            None => roots.push(symbol),
+        }
+    }
 }
 
 impl LspBackend for MarkdownLsp {
     fn initialize(&mut self) -> miette::Result<()> {
+        self.link_index.rebuild();
+        for page in self.link_index.pages().to_vec() {
+            if let Ok(content) = std::fs::read_to_string(&page) {
+                self.update_front_matter_values(page, &content);
+            }
+        }
+
         // Send initialized event with trigger characters
         let _ = self.event_tx.send(LspEvent::Initialized(vec![
             "#".to_string(),
@@ -549,6 +1272,8 @@ impl LspBackend for MarkdownLsp {
     }
 
     fn did_open(&mut self, file_path: &Path, content: &str) -> miette::Result<()> {
+        // Opening a file may be the first sight of a new page
+        self.refresh_pages();
         let uri = Self::path_to_uri(file_path);
         self.parse_document(&uri, content);
         Ok(())
@@ -608,6 +1333,44 @@ impl LspBackend for MarkdownLsp {
         Ok(())
     }
 
+    fn request_document_symbols(&mut self, file_path: &Path) -> miette::Result<()> {
+        let uri = Self::path_to_uri(file_path);
+        let symbols = self.get_document_symbols(&uri);
+        let _ = self
+            .event_tx
+            .send(LspEvent::DocumentSymbols(DocumentSymbolResponse::Nested(
+                symbols,
+            )));
+        Ok(())
+    }
+
+    fn request_code_actions(
+        &mut self,
+        file_path: &Path,
+        line: u32,
+        character: u32,
+    ) -> miette::Result<()> {
+        let uri = Self::path_to_uri(file_path);
+        let actions = self.get_code_actions(&uri, line, character);
+        let _ = self.event_tx.send(LspEvent::CodeActions(actions));
+        Ok(())
+    }
+
+    fn execute_command(
+        &mut self,
+        command: &str,
+        arguments: Vec<serde_json::Value>,
+    ) -> miette::Result<()> {
+        self.run_command(command, &arguments)
+    }
+
+    fn request_formatting(&mut self, file_path: &Path) -> miette::Result<()> {
+        let uri = Self::path_to_uri(file_path);
+        let edits = self.get_formatting(&uri);
+        let _ = self.event_tx.send(LspEvent::Formatting(edits));
+        Ok(())
+    }
+
     fn shutdown(&mut self) -> miette::Result<()> {
         self.documents.clear();
         Ok(())
@@ -624,7 +1387,11 @@ mod tests {
 
     fn create_test_lsp() -> (MarkdownLsp, mpsc::Receiver<LspEvent>) {
         let (tx, rx) = mpsc::channel();
-        let lsp = MarkdownLsp::new(PathBuf::from("/tmp"), tx);
+        // Keep dictionaries installed on the machine out of the tests
+        let lsp = MarkdownLsp::new(PathBuf::from("/tmp"), tx).with_spell_config(SpellConfig {
+            enabled: false,
+            ..SpellConfig::default()
+        });
         (lsp, rx)
     }
 
@@ -750,4 +1517,300 @@ mod tests {
             assert_eq!(loc.range.start.line, 0);
         }
     }
+
+    #[test]
+    fn test_document_symbols_nested() {
+        let (mut lsp, _rx) = create_test_lsp();
+        let content = "# One\n\n## Two\n\n### Three\n\n## Four\n\n# Five";
+        lsp.parse_document("file:///test.md", content);
+
+        let symbols = lsp.get_document_symbols("file:///test.md");
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].name, "One");
+        assert_eq!(symbols[1].name, "Five");
+
+        let children = symbols[0].children.as_ref().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].name, "Two");
+        assert_eq!(children[1].name, "Four");
+        assert_eq!(
+            children[0].children.as_ref().unwrap()[0].range.start.line,
+            4
+        );
+    }
+
+    #[test]
+    fn test_formatting() {
+        let (mut lsp, _rx) = create_test_lsp();
+        lsp.parse_document("file:///test.md", "Title\n===\n* a\n* b");
+
+        let edits = lsp.get_formatting("file:///test.md");
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range.start, Position::new(0, 0));
+        assert_eq!(edits[0].range.end, Position::new(3, 3));
+        assert_eq!(edits[0].new_text, "# Title\n\n- a\n- b\n");
+
+        lsp.parse_document("file:///test.md", "# Title\n");
+        assert!(lsp.get_formatting("file:///test.md").is_empty());
+    }
+
+    #[test]
+    fn test_stale_toc_diagnostic() {
+        let (mut lsp, rx) = create_test_lsp();
+        let content = "<!-- toc -->\n- [Intro](#intro)\n<!-- tocstop -->\n\n## Intro\n\n## Usage\n";
+        lsp.parse_document("file:///test.md", content);
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        assert_eq!(params.diagnostics.len(), 1);
+        assert_eq!(params.diagnostics[0].range.start.line, 0);
+
+        let content = "<!-- toc -->\n\n- [Intro](#intro)\n- [Usage](#usage)\n\n<!-- tocstop -->\n\n## Intro\n\n## Usage\n";
+        lsp.parse_document("file:///test.md", content);
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        assert!(params.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_stale_toc_diagnostic_uses_options() {
+        let (lsp, rx) = create_test_lsp();
+        let mut lsp = lsp.with_toc_options(TocOptions {
+            min_depth: 2,
+            max_depth: 2,
+            ..TocOptions::default()
+        });
+        // Subheadings outside the configured range are not expected in the list
+        let content =
+            "<!-- toc -->\n- [Intro](#intro)\n<!-- tocstop -->\n\n## Intro\n\n### Setup\n";
+        lsp.parse_document("file:///test.md", content);
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        assert!(params.diagnostics.is_empty());
+
+        // but a new heading within it is
+        let content = "<!-- toc -->\n- [Intro](#intro)\n<!-- tocstop -->\n\n## Intro\n\n### Setup\n\n## Usage\n";
+        lsp.parse_document("file:///test.md", content);
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        assert_eq!(params.diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_toc_with_formatting() {
+        // Saving refreshes the table of contents, then formats the document
+        let (lsp, rx) = create_test_lsp();
+        let doc = "<!-- toc -->\n<!-- tocstop -->\n\n## Intro\n\n### Setup\n";
+        let ast = Markdown::from_markdown_str(doc).unwrap();
+        let options = TocOptions {
+            bullet: format::BulletMarker::Asterisk,
+            ..TocOptions::default()
+        };
+        let mut lines = toc::toc_block(&ast, options);
+        lines.extend(doc.lines().skip(2).map(str::to_string));
+        let content = lines.join("\n") + "\n";
+
+        let formatted = format::format(
+            &content,
+            &FormatOptions {
+                bullet: format::BulletMarker::Asterisk,
+                ..FormatOptions::default()
+            },
+        );
+        assert_eq!(formatted, content);
+
+        let mut lsp = lsp.with_toc_options(options);
+        lsp.parse_document("file:///test.md", &formatted);
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        assert!(params.diagnostics.is_empty());
+    }
+
+    fn create_wiki_workspace() -> (MarkdownLsp, mpsc::Receiver<LspEvent>, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("notes")).unwrap();
+        std::fs::write(
+            root.join("notes/Setup Guide.md"),
+            "# Setup Guide\n\n## Install Steps\n",
+        )
+        .unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut lsp = MarkdownLsp::new(root.to_path_buf(), tx);
+        lsp.refresh_pages();
+        (lsp, rx, dir)
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let (mut lsp, rx, dir) = create_wiki_workspace();
+        let root = dir.path();
+        let uri = format!("file://{}", root.join("index.md").display());
+        let content = "# Index\n\n[[Setup Guide#Install Steps|install]] [[Missing]] [[setup guide#Nope]] [[#Index]]\n[[Setup Guide#";
+        lsp.parse_document(&uri, content);
+
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        let messages: Vec<&str> = params
+            .diagnostics
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unresolved wiki link: page 'Missing' not found",
+                "Unresolved wiki link: heading 'nope' not found in 'setup guide'",
+            ]
+        );
+        assert_eq!(params.diagnostics[0].range.start.character, 38);
+
+        let Some(GotoDefinitionResponse::Scalar(location)) = lsp.get_definition(&uri, 2, 5) else {
+            panic!("expected definition");
+        };
+        assert!(
+            location.uri.as_str().ends_with("Setup%20Guide.md")
+                || location.uri.as_str().ends_with("Setup Guide.md")
+        );
+        assert_eq!(location.range.start.line, 2);
+        assert!(lsp.get_definition(&uri, 2, 40).is_none());
+
+        let pages = lsp.get_completions(&uri, 2, 2);
+        let labels: Vec<&str> = pages.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["index", "Setup Guide"]);
+        let headings = lsp.get_completions(&uri, 3, 14);
+        let labels: Vec<&str> = headings.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["Setup Guide", "Install Steps"]);
+
+        // References to a heading include links from other files
+        let guide = root.join("notes/Setup Guide.md");
+        let guide_uri = format!("file://{}", guide.display());
+        lsp.parse_document(&guide_uri, "# Setup Guide\n\n## Install Steps\n");
+        let refs = lsp.get_references(&guide_uri, 2, 4, false);
+        assert_eq!(refs.len(), 1);
+        assert!(refs[0].uri.as_str().ends_with("index.md"));
+        assert_eq!(refs[0].range.start.line, 2);
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let (lsp, rx) = create_test_lsp();
+        let mut schema = FrontMatterSchema::default();
+        schema.fields[0].required = true;
+        let mut lsp = lsp.with_front_matter_schema(schema);
+
+        lsp.parse_document(
+            "file:///tmp/fm-a.md",
+            "---\ntitle: A\ntags: [rust, editor]\n---\n",
+        );
+        lsp.parse_document("file:///tmp/fm-b.md", "+++\ntags = [\"rust\"]\n+++\n");
+        let _ = rx.try_iter().count();
+
+        let uri = "file:///tmp/fm-c.md";
+        lsp.parse_document(uri, "---\ndate: May\ntags:\n  - editor\n---\n# C\n");
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        let messages: Vec<(u32, &str)> = params
+            .diagnostics
+            .iter()
+            .map(|d| (d.range.start.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (0, "Missing required front matter field 'title'"),
+                (
+                    1,
+                    "Front matter field 'date' should be a date, found a string"
+                ),
+            ]
+        );
+
+        // Keys not yet present, then tags from the workspace by use
+        lsp.parse_document(
+            uri,
+            "---\ndate: May\ntags:\n  - editor\n  - \nt\n---\n# C\n",
+        );
+        let keys = lsp.get_completions(uri, 5, 1);
+        let labels: Vec<&str> = keys.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["title"]);
+        assert_eq!(keys[0].insert_text.as_deref(), Some("title: "));
+        let values = lsp.get_completions(uri, 4, 4);
+        let labels: Vec<&str> = values.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["rust"]);
+        assert_eq!(values[0].detail.as_deref(), Some("used in 2 files"));
+        assert!(
+            lsp.get_completions(uri, 7, 3)
+                .iter()
+                .all(|item| item.kind != Some(CompletionItemKind::VALUE))
+        );
+
+        lsp.parse_document(uri, "+++\ntags = [\n+++\n");
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        assert_eq!(params.diagnostics.len(), 1);
+        assert!(
+            params.diagnostics[0]
+                .message
+                .starts_with("Invalid front matter: ")
+        );
+        assert_eq!(
+            params.diagnostics[0].severity,
+            Some(DiagnosticSeverity::ERROR)
+        );
+        let values = lsp.get_completions(uri, 1, 8);
+        assert_eq!(values[0].insert_text.as_deref(), Some("\"rust\""));
+    }
+
+    #[test]
+    fn test_spelling() {
+        let (tx, rx) = mpsc::channel();
+        let mut lsp = MarkdownLsp::new(PathBuf::from("/tmp"), tx);
+        lsp.spell_checker.insert_dictionary(
+            "en_US",
+            spell::Dictionary::parse("TRY esiatnrl\n", "4\nthe\nword\nwords\nfox\n"),
+        );
+
+        let uri = "file:///tmp/spell.md";
+        lsp.parse_document(uri, "# The wrod\n\n`wrod` fox\n");
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        assert_eq!(params.diagnostics.len(), 1);
+        let diagnostic = &params.diagnostics[0];
+        assert_eq!(diagnostic.message, "Unknown word: 'wrod'");
+        assert_eq!(diagnostic.source.as_deref(), Some("spell"));
+        assert_eq!(
+            (diagnostic.range.start, diagnostic.range.end),
+            (Position::new(0, 6), Position::new(0, 10))
+        );
+
+        let titles: Vec<String> = lsp
+            .get_code_actions(uri, 0, 8)
+            .into_iter()
+            .map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => action.title,
+                CodeActionOrCommand::Command(command) => command.title,
+            })
+            .collect();
+        assert_eq!(titles, vec!["Change to 'word'", "Add 'wrod' to dictionary"]);
+        assert!(lsp.get_code_actions(uri, 0, 2).is_empty());
+
+        lsp.run_command(
+            spell::ADD_TO_DICTIONARY_COMMAND,
+            &[serde_json::json!("wrod"), serde_json::json!(uri)],
+        )
+        .unwrap();
+        let Ok(LspEvent::Diagnostics(params)) = rx.try_recv() else {
+            panic!("expected diagnostics");
+        };
+        assert!(params.diagnostics.is_empty());
+    }
 }
@@ -0,0 +1,452 @@
+mod dictionary;
+
+pub use dictionary::Dictionary;
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use miette::IntoDiagnostic;
+use mq_markdown::{Markdown, Node};
+use serde::{Deserialize, Serialize};
+
+use crate::front_matter::{FieldValue, FrontMatter};
+use crate::wiki_link;
+
+/// Command adding the word in its first argument to the personal dictionary
+pub const ADD_TO_DICTIONARY_COMMAND: &str = "markdown-lsp.addToDictionary";
+
+/// Directories searched for Hunspell dictionaries after the configured ones
+const SYSTEM_DICTIONARY_DIRS: &[&str] = &[
+    "/usr/share/hunspell",
+    "/usr/share/myspell",
+    "/usr/share/myspell/dicts",
+    "/Library/Spelling",
+];
+
+/// Front matter keys selecting the language of a document
+const LANGUAGE_KEYS: &[&str] = &["lang", "language"];
+
+/// Spell checking settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpellConfig {
+    #[serde(default = "SpellConfig::default_enabled")]
+    pub enabled: bool,
+    /// Dictionary used when the front matter has no `lang`, such as `en_US`
+    #[serde(default = "SpellConfig::default_language")]
+    pub language: String,
+    /// Directories with `<language>.aff` and `<language>.dic` files
+    #[serde(default)]
+    pub dictionaries: Vec<PathBuf>,
+    /// File with one accepted word per line
+    #[serde(default)]
+    pub personal_dictionary: Option<PathBuf>,
+}
+
+impl SpellConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_language() -> String {
+        "en_US".to_string()
+    }
+
+    /// Configured directories followed by the system ones
+    fn dictionary_dirs(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.dictionaries
+            .iter()
+            .cloned()
+            .chain(SYSTEM_DICTIONARY_DIRS.iter().map(PathBuf::from))
+    }
+}
+
+impl Default for SpellConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            language: Self::default_language(),
+            dictionaries: Vec::new(),
+            personal_dictionary: None,
+        }
+    }
+}
+
+/// Unknown word in the prose of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Misspelling {
+    /// Document line (0-based)
+    pub line: usize,
+    /// Byte range of the word in its line
+    pub range: Range<usize>,
+    pub word: String,
+}
+
+/// Spell checker with dictionaries loaded on first use per language
+#[derive(Debug)]
+pub struct SpellChecker {
+    config: SpellConfig,
+    /// Dictionaries by language, None when none was found
+    dictionaries: HashMap<String, Option<Dictionary>>,
+    /// Words accepted in every language
+    personal: HashSet<String>,
+}
+
+impl SpellChecker {
+    pub fn new(config: SpellConfig) -> Self {
+        let personal = config
+            .personal_dictionary
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| {
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|word| !word.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            config,
+            dictionaries: HashMap::new(),
+            personal,
+        }
+    }
+
+    /// Language of a document from its front matter, or the configured one
+    ///
+    /// Tags such as `en-GB` are written the Hunspell way, `en_GB`.
+    pub fn document_language<S: AsRef<str>>(&self, lines: &[S]) -> String {
+        FrontMatter::parse(lines)
+            .and_then(|front_matter| {
+                LANGUAGE_KEYS
+                    .iter()
+                    .find_map(|key| match &front_matter.get(key)?.value {
+                        FieldValue::String(language) => Some(language.trim().to_string()),
+                        _ => None,
+                    })
+            })
+            .filter(|language| !language.is_empty())
+            .unwrap_or_else(|| self.config.language.clone())
+            .replace('-', "_")
+    }
+
+    /// Load the dictionary of a language unless it was already looked for
+    pub fn load(&mut self, language: &str) {
+        if !self.config.enabled || self.dictionaries.contains_key(language) {
+            return;
+        }
+
+        let dictionary = self.config.dictionary_dirs().find_map(|dir| {
+            let aff = dir.join(format!("{}.aff", language));
+            let dic = dir.join(format!("{}.dic", language));
+            if !aff.is_file() || !dic.is_file() {
+                return None;
+            }
+            Dictionary::load(&aff, &dic)
+                .inspect_err(|e| eprintln!("Failed to load dictionary {}: {}", dic.display(), e))
+                .ok()
+        });
+        self.dictionaries.insert(language.to_string(), dictionary);
+    }
+
+    /// Use a dictionary for a language instead of searching the directories
+    pub fn insert_dictionary(&mut self, language: &str, dictionary: Dictionary) {
+        self.dictionaries
+            .insert(language.to_string(), Some(dictionary));
+    }
+
+    fn dictionary(&self, language: &str) -> Option<&Dictionary> {
+        if !self.config.enabled {
+            return None;
+        }
+        self.dictionaries.get(language)?.as_ref()
+    }
+
+    /// Whether a word is known, or cannot be checked for lack of a dictionary
+    pub fn check(&self, language: &str, word: &str) -> bool {
+        self.personal.contains(word)
+            || self.personal.contains(&word.to_lowercase())
+            || self
+                .dictionary(language)
+                .is_none_or(|dictionary| dictionary.check(word))
+    }
+
+    /// Corrections for a word, best first
+    pub fn suggest(&self, language: &str, word: &str, limit: usize) -> Vec<String> {
+        self.dictionary(language)
+            .map(|dictionary| dictionary.suggest(word, limit))
+            .unwrap_or_default()
+    }
+
+    /// Unknown words in the prose of a document
+    ///
+    /// Code, HTML, math, front matter, image descriptions, autolinks and wiki
+    /// link targets are never checked.
+    pub fn misspellings(
+        &self,
+        language: &str,
+        content: &str,
+        markdown: &Markdown,
+    ) -> Vec<Misspelling> {
+        if self.dictionary(language).is_none() {
+            return Vec::new();
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut segments = Vec::new();
+        for node in &markdown.nodes {
+            prose_segments(node, &lines, &mut segments);
+        }
+        let wiki_links = wiki_link::document_wiki_links(content);
+
+        let mut misspellings = Vec::new();
+        for (line, segment) in segments {
+            for word in words(&lines[line][segment.clone()]) {
+                let range = segment.start + word.start..segment.start + word.end;
+                let in_wiki_link = wiki_links.iter().any(|(link_line, link)| {
+                    *link_line == line
+                        && link.range.start < range.end
+                        && range.start < link.range.end
+                });
+                let word = &lines[line][range.clone()];
+                if !in_wiki_link && !self.check(language, word) {
+                    misspellings.push(Misspelling {
+                        line,
+                        range,
+                        word: word.to_string(),
+                    });
+                }
+            }
+        }
+        misspellings
+    }
+
+    /// Accept a word from now on and append it to the personal dictionary
+    ///
+    /// Without a personal dictionary file the word is only kept in memory.
+    pub fn add_to_personal_dictionary(&mut self, word: &str) -> miette::Result<()> {
+        if !self.personal.insert(word.to_string()) {
+            return Ok(());
+        }
+        let Some(path) = &self.config.personal_dictionary else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).into_diagnostic()?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .into_diagnostic()?;
+        writeln!(file, "{}", word).into_diagnostic()
+    }
+}
+
+/// Collect the line and byte range of each piece of checkable text under a node
+fn prose_segments(node: &Node, lines: &[&str], segments: &mut Vec<(usize, Range<usize>)>) {
+    match node {
+        Node::Text(_) => {
+            let Some(position) = node.position() else {
+                return;
+            };
+            let start = position.start.line.saturating_sub(1);
+            let end = position.end.line.saturating_sub(1);
+            for line in start..=end {
+                let Some(content) = lines.get(line) else {
+                    break;
+                };
+                let from = if line == start {
+                    position.start.column.saturating_sub(1)
+                } else {
+                    0
+                };
+                let to = if line == end {
+                    position.end.column.saturating_sub(1).min(content.len())
+                } else {
+                    content.len()
+                };
+                if from < to && content.is_char_boundary(from) && content.is_char_boundary(to) {
+                    segments.push((line, from..to));
+                }
+            }
+        }
+        // Autolinks show their URL as the link text
+        Node::Link(link)
+            if link.values.iter().map(Node::value).collect::<String>() == link.url.as_str() => {}
+        Node::Code(_)
+        | Node::CodeInline(_)
+        | Node::Yaml(_)
+        | Node::Toml(_)
+        | Node::Html(_)
+        | Node::Math(_)
+        | Node::MathInline(_)
+        | Node::Image(_)
+        | Node::ImageRef(_)
+        | Node::Definition(_)
+        | Node::MdxFlowExpression(_)
+        | Node::MdxJsxFlowElement(_)
+        | Node::MdxJsxTextElement(_)
+        | Node::MdxTextExpression(_)
+        | Node::MdxJsEsm(_) => {}
+        _ => {
+            for child in node.children() {
+                prose_segments(&child, lines, segments);
+            }
+        }
+    }
+}
+
+/// Byte ranges of the words worth checking in a piece of prose
+///
+/// Words are runs of letters, with apostrophes allowed between letters.
+/// URLs, paths and e-mail addresses are skipped whole, and so are words with
+/// digits, capitals past the first letter (acronyms and identifiers), CJK
+/// characters, single letters and HTML entities.
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut offset = 0;
+
+    for chunk in text.split_inclusive(char::is_whitespace) {
+        let chunk_start = offset;
+        offset += chunk.len();
+        let chunk = chunk.trim_end();
+        if chunk.contains("://") || chunk.starts_with("www.") || chunk.contains(['@', '/']) {
+            continue;
+        }
+
+        let chars: Vec<(usize, char)> = chunk.char_indices().collect();
+        let mut start = None;
+        for (idx, &(pos, c)) in chars.iter().enumerate() {
+            let in_word = c.is_alphanumeric()
+                || (matches!(c, '\'' | '\u{2019}')
+                    && start.is_some()
+                    && chars
+                        .get(idx + 1)
+                        .is_some_and(|(_, next)| next.is_alphabetic()));
+            match (in_word, start) {
+                (true, None) => start = Some(pos),
+                (false, Some(word_start)) => {
+                    if is_checkable(chunk, word_start..pos) {
+                        words.push(chunk_start + word_start..chunk_start + pos);
+                    }
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(word_start) = start
+            && is_checkable(chunk, word_start..chunk.len())
+        {
+            words.push(chunk_start + word_start..chunk_start + chunk.len());
+        }
+    }
+    words
+}
+
+fn is_checkable(chunk: &str, range: Range<usize>) -> bool {
+    let word = &chunk[range.clone()];
+    let is_entity = chunk[..range.start].ends_with('&') && chunk[range.end..].starts_with(';');
+    word.chars().count() >= 2
+        && !is_entity
+        && !word.chars().any(|c| c.is_numeric() || is_cjk(c))
+        && !word.chars().skip(1).any(char::is_uppercase)
+}
+
+/// Whether a character is written without spaces between words
+pub fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30ff}'   // Hiragana and Katakana
+            | '\u{3400}'..='\u{4dbf}' // CJK Extension A
+            | '\u{4e00}'..='\u{9fff}' // CJK Unified Ideographs
+            | '\u{f900}'..='\u{faff}' // CJK Compatibility Ideographs
+            | '\u{ac00}'..='\u{d7af}' // Hangul syllables
+            | '\u{20000}'..='\u{2fa1f}' // CJK Extensions B-F
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8\nTRY esiantrolcdugmphbyfvkwzxjq\n\nSFX S Y 1\nSFX S 0 s .\n";
+    const DIC: &str = "7\nthe\nword/S\nquick\nfox\nlink\nsome\nand\n";
+
+    fn checker() -> SpellChecker {
+        let mut checker = SpellChecker::new(SpellConfig::default());
+        checker.insert_dictionary("en_US", Dictionary::parse(AFF, DIC));
+        checker
+    }
+
+    fn misspelled(checker: &SpellChecker, content: &str) -> Vec<String> {
+        let markdown: Markdown = content.parse().unwrap();
+        let language = checker.document_language(&content.lines().collect::<Vec<_>>());
+        checker
+            .misspellings(&language, content, &markdown)
+            .into_iter()
+            .map(|misspelling| misspelling.word)
+            .collect()
+    }
+
+    #[test]
+    fn test_words() {
+        let text = "Teh don't x2 HTML &amp; see https://a.com mail@b.org and/or 日本語 a Word";
+        let found: Vec<&str> = words(text).into_iter().map(|range| &text[range]).collect();
+        assert_eq!(found, vec!["Teh", "don't", "see", "Word"]);
+    }
+
+    #[test]
+    fn test_misspellings() {
+        let checker = checker();
+        let content = "---\ntitel: teh\n---\n# The quik fox\n\nSome `codde` and [linkk](http://a.com) <http://wrng.com> [[Pagee]].\n\n```\nbadd\n```\n";
+        assert_eq!(misspelled(&checker, content), vec!["quik", "linkk"]);
+
+        let markdown: Markdown = content.parse().unwrap();
+        let first = &checker.misspellings("en_US", content, &markdown)[0];
+        assert_eq!((first.line, first.range.clone()), (3, 6..10));
+    }
+
+    #[test]
+    fn test_document_language() {
+        let checker = checker();
+        assert_eq!(checker.document_language(&["# Title"]), "en_US");
+        assert_eq!(
+            checker.document_language(&["---", "lang: en-GB", "---"]),
+            "en_GB"
+        );
+        assert_eq!(
+            checker.document_language(&["+++", "language = \"de_DE\"", "+++"]),
+            "de_DE"
+        );
+
+        // Documents in a language without a dictionary are not checked
+        assert!(misspelled(&checker, "---\nlang: fr\n---\nBonjour\n").is_empty());
+    }
+
+    #[test]
+    fn test_personal_dictionary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("personal.dic");
+        let config = SpellConfig {
+            personal_dictionary: Some(path.clone()),
+            ..SpellConfig::default()
+        };
+
+        let mut checker = SpellChecker::new(config.clone());
+        checker.insert_dictionary("en_US", Dictionary::parse(AFF, DIC));
+        assert!(!checker.check("en_US", "mq"));
+        checker.add_to_personal_dictionary("mq").unwrap();
+        assert!(checker.check("en_US", "mq"));
+
+        // Words are read back from the file
+        let checker = SpellChecker::new(config);
+        assert!(checker.personal.contains("mq"));
+    }
+}
@@ -0,0 +1,691 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Affix or word flag, from one or two characters or a number depending on `FLAG`
+type Flag = u32;
+
+/// How flags are written in the affix and dictionary files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagType {
+    /// One character per flag (the default, also `FLAG UTF-8`)
+    Char,
+    /// Two characters per flag (`FLAG long`)
+    Long,
+    /// Comma-separated numbers (`FLAG num`)
+    Numeric,
+}
+
+impl FlagType {
+    fn parse(self, flags: &str) -> Vec<Flag> {
+        match self {
+            Self::Char => flags.chars().map(|c| c as Flag).collect(),
+            Self::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|pair| pair.iter().fold(0, |flag, &c| (flag << 16) | c as Flag))
+                .collect(),
+            Self::Numeric => flags
+                .split(',')
+                .filter_map(|n| n.trim().parse().ok())
+                .collect(),
+        }
+    }
+}
+
+/// Fewest characters of a compound part when `COMPOUNDMIN` is not set
+const DEFAULT_COMPOUND_MIN: usize = 3;
+
+/// Position of a word inside a compound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompoundPart {
+    Begin,
+    Middle,
+    End,
+}
+
+/// Whether `flags` contain an optional flag
+fn has_flag(flags: &[Flag], flag: Option<Flag>) -> bool {
+    flag.is_some_and(|f| flags.contains(&f))
+}
+
+/// One position of an affix condition, such as `[^aeiou]` in `[^aeiou]y`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CharClass {
+    Any,
+    Char(char),
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Char(expected) => c == *expected,
+            Self::Set { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+
+    fn parse_condition(condition: &str) -> Vec<Self> {
+        if condition == "." {
+            return Vec::new();
+        }
+        let mut classes = Vec::new();
+        let mut chars = condition.chars();
+        while let Some(c) = chars.next() {
+            classes.push(match c {
+                '.' => Self::Any,
+                '[' => {
+                    let mut set: Vec<char> = chars.by_ref().take_while(|&c| c != ']').collect();
+                    let negated = set.first() == Some(&'^');
+                    if negated {
+                        set.remove(0);
+                    }
+                    Self::Set {
+                        chars: set,
+                        negated,
+                    }
+                }
+                c => Self::Char(c),
+            });
+        }
+        classes
+    }
+}
+
+/// Prefix or suffix rule from a `PFX` or `SFX` line
+#[derive(Debug, Clone)]
+struct Affix {
+    flag: Flag,
+    /// Whether the rule combines with affixes of the other kind
+    cross_product: bool,
+    /// Characters removed from the stem before adding the affix
+    strip: String,
+    add: String,
+    /// Characters the stem must start (prefix) or end (suffix) with
+    condition: Vec<CharClass>,
+    /// Flags of the affixed word, allowing it to take another affix
+    continuation: Vec<Flag>,
+}
+
+impl Affix {
+    /// Stem of a word formed with this prefix
+    fn remove_prefix(&self, word: &str) -> Option<String> {
+        let rest = word.strip_prefix(self.add.as_str())?;
+        if rest.is_empty() {
+            return None;
+        }
+        let stem = format!("{}{}", self.strip, rest);
+        let mut chars = stem.chars();
+        self.condition
+            .iter()
+            .all(|class| chars.next().is_some_and(|c| class.matches(c)))
+            .then_some(stem)
+    }
+
+    /// Stem of a word formed with this suffix
+    fn remove_suffix(&self, word: &str) -> Option<String> {
+        let rest = word.strip_suffix(self.add.as_str())?;
+        if rest.is_empty() {
+            return None;
+        }
+        let stem = format!("{}{}", rest, self.strip);
+        let mut chars = stem.chars().rev();
+        self.condition
+            .iter()
+            .rev()
+            .all(|class| chars.next().is_some_and(|c| class.matches(c)))
+            .then_some(stem)
+    }
+}
+
+/// Hunspell dictionary, read from a `.aff` and a `.dic` file
+///
+/// Supports prefixes and suffixes (with cross products and one level of
+/// continuation), flag aliases, the `TRY`, `REP`, `FORBIDDENWORD`,
+/// `NOSUGGEST`, `NEEDAFFIX` and `ONLYINCOMPOUND` options, and compounds
+/// built with `COMPOUNDFLAG`, `COMPOUNDBEGIN`, `COMPOUNDMIDDLE`,
+/// `COMPOUNDEND`, `COMPOUNDMIN` and `COMPOUNDWORDMAX`. `COMPOUNDRULE`
+/// patterns and the `CHECKCOMPOUND*` restrictions are not supported.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    /// Words and the flags of all their entries
+    words: HashMap<String, Vec<Flag>>,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+    /// Characters tried when suggesting, most frequent first
+    try_chars: Vec<char>,
+    /// Common misspellings and their corrections
+    replacements: Vec<(String, String)>,
+    forbidden: Option<Flag>,
+    no_suggest: Option<Flag>,
+    need_affix: Option<Flag>,
+    only_in_compound: Option<Flag>,
+    /// Flag of words that can be any part of a compound
+    compound_flag: Option<Flag>,
+    /// Flags of words that can only begin, continue or end a compound
+    compound_begin: Option<Flag>,
+    compound_middle: Option<Flag>,
+    compound_end: Option<Flag>,
+    /// Fewest characters of each part of a compound
+    compound_min: Option<usize>,
+    /// Most words in a compound
+    compound_word_max: Option<usize>,
+}
+
+impl Dictionary {
+    /// Load a dictionary from its affix and word files
+    pub fn load(aff_path: &Path, dic_path: &Path) -> miette::Result<Self> {
+        let read = |path: &Path| {
+            std::fs::read(path)
+                .map_err(|e| miette::miette!("Failed to read {}: {}", path.display(), e))
+        };
+        let (aff, dic) = (read(aff_path)?, read(dic_path)?);
+
+        // The affix file names the encoding of both files
+        let latin1 = String::from_utf8_lossy(&aff).lines().any(|line| {
+            line.strip_prefix("SET")
+                .is_some_and(|set| matches!(set.trim(), "ISO8859-1" | "ISO-8859-1"))
+        });
+        let decode = |bytes: &[u8]| {
+            if latin1 {
+                bytes.iter().map(|&b| b as char).collect()
+            } else {
+                String::from_utf8_lossy(bytes).into_owned()
+            }
+        };
+        Ok(Self::parse(&decode(&aff), &decode(&dic)))
+    }
+
+    /// Build a dictionary from the contents of its affix and word files
+    pub fn parse(aff: &str, dic: &str) -> Self {
+        let mut dictionary = Self::default();
+        let mut flag_type = FlagType::Char;
+        let mut aliases: Vec<Vec<Flag>> = Vec::new();
+        // The first `AF` line is the number of aliases
+        let mut alias_count_read = false;
+        let mut cross_products: HashMap<Flag, bool> = HashMap::new();
+        let option_flag = |fields: &[&str], flag_type: FlagType| {
+            fields
+                .get(1)
+                .and_then(|flag| flag_type.parse(flag).first().copied())
+        };
+
+        for line in aff.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", kind, ..] => {
+                    flag_type = match *kind {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Numeric,
+                        _ => FlagType::Char,
+                    }
+                }
+                ["AF", flags, ..] => {
+                    if alias_count_read {
+                        aliases.push(flag_type.parse(flags));
+                    }
+                    alias_count_read = true;
+                }
+                ["TRY", chars, ..] => dictionary.try_chars = chars.chars().collect(),
+                ["REP", from, to, ..] => dictionary
+                    .replacements
+                    .push((from.to_string(), to.replace('_', " "))),
+                ["FORBIDDENWORD", ..] => dictionary.forbidden = option_flag(&fields, flag_type),
+                ["NOSUGGEST", ..] => dictionary.no_suggest = option_flag(&fields, flag_type),
+                ["NEEDAFFIX", ..] => dictionary.need_affix = option_flag(&fields, flag_type),
+                ["ONLYINCOMPOUND", ..] => {
+                    dictionary.only_in_compound = option_flag(&fields, flag_type)
+                }
+                ["COMPOUNDFLAG", ..] => dictionary.compound_flag = option_flag(&fields, flag_type),
+                ["COMPOUNDBEGIN", ..] => {
+                    dictionary.compound_begin = option_flag(&fields, flag_type)
+                }
+                ["COMPOUNDMIDDLE", ..] => {
+                    dictionary.compound_middle = option_flag(&fields, flag_type)
+                }
+                ["COMPOUNDEND" | "COMPOUNDLAST", ..] => {
+                    dictionary.compound_end = option_flag(&fields, flag_type)
+                }
+                ["COMPOUNDMIN", min, ..] => dictionary.compound_min = min.parse().ok(),
+                ["COMPOUNDWORDMAX", max, ..] => dictionary.compound_word_max = max.parse().ok(),
+                ["PFX" | "SFX", flag, cross @ ("Y" | "N"), count]
+                    if count.parse::<usize>().is_ok() =>
+                {
+                    if let Some(&flag) = flag_type.parse(flag).first() {
+                        cross_products.insert(flag, *cross == "Y");
+                    }
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] => {
+                    let Some(&flag) = flag_type.parse(flag).first() else {
+                        continue;
+                    };
+                    let (add, continuation) = match add.split_once('/') {
+                        Some((add, flags)) => (add, Self::parse_flags(flags, flag_type, &aliases)),
+                        None => (*add, Vec::new()),
+                    };
+                    let zero = |s: &str| {
+                        if s == "0" {
+                            String::new()
+                        } else {
+                            s.to_string()
+                        }
+                    };
+                    let affix = Affix {
+                        flag,
+                        cross_product: cross_products.get(&flag).copied().unwrap_or(false),
+                        strip: zero(strip),
+                        add: zero(add),
+                        condition: CharClass::parse_condition(rest.first().unwrap_or(&".")),
+                        continuation,
+                    };
+                    if *kind == "PFX" {
+                        dictionary.prefixes.push(affix);
+                    } else {
+                        dictionary.suffixes.push(affix);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // The first line of the word file is the word count
+        for line in dic.lines().skip(1) {
+            // Morphological fields follow the word after whitespace
+            let Some(entry) = line.split_whitespace().next() else {
+                continue;
+            };
+            let slash = entry
+                .char_indices()
+                .find(|&(idx, c)| c == '/' && !entry[..idx].ends_with('\\'))
+                .map(|(idx, _)| idx);
+            let (word, flags) = match slash {
+                Some(idx) => (
+                    &entry[..idx],
+                    Self::parse_flags(&entry[idx + 1..], flag_type, &aliases),
+                ),
+                None => (entry, Vec::new()),
+            };
+            dictionary
+                .words
+                .entry(word.replace("\\/", "/"))
+                .or_default()
+                .extend(flags);
+        }
+        dictionary
+    }
+
+    /// Flags of a dictionary entry or affix, resolving `AF` aliases
+    fn parse_flags(flags: &str, flag_type: FlagType, aliases: &[Vec<Flag>]) -> Vec<Flag> {
+        if aliases.is_empty() {
+            return flag_type.parse(flags);
+        }
+        flags
+            .parse::<usize>()
+            .ok()
+            .and_then(|idx| aliases.get(idx.checked_sub(1)?))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Add a word without flags, e.g. from a personal dictionary
+    pub fn add_word(&mut self, word: &str) {
+        self.words.entry(word.to_string()).or_default();
+    }
+
+    /// Whether a word is spelled correctly
+    ///
+    /// Capitalized and all-uppercase words are also accepted in lowercase,
+    /// and all-uppercase words capitalized.
+    pub fn check(&self, word: &str) -> bool {
+        self.find(word).is_some()
+    }
+
+    /// Flags of the dictionary entry a word comes from, trying other cases
+    fn find(&self, word: &str) -> Option<&[Flag]> {
+        let word = word.replace('’', "'");
+        if let Some(flags) = self.find_exact(&word) {
+            return Some(flags);
+        }
+        let mut chars = word.chars();
+        let first = chars.next()?;
+        let rest: String = chars.collect();
+        let is_upper = |s: &str| s.chars().all(|c| !c.is_lowercase());
+        if !first.is_uppercase() || !(rest == rest.to_lowercase() || is_upper(&rest)) {
+            return None;
+        }
+        let lower = word.to_lowercase();
+        self.find_exact(&lower).or_else(|| {
+            let capitalized: String = first
+                .to_uppercase()
+                .chain(rest.to_lowercase().chars())
+                .collect();
+            (is_upper(&rest) && capitalized != word)
+                .then(|| self.find_exact(&capitalized))
+                .flatten()
+        })
+    }
+
+    /// Flags of the dictionary entry a word in exactly this case comes from
+    fn find_exact(&self, word: &str) -> Option<&[Flag]> {
+        let entry = |stem: &str| {
+            self.words
+                .get(stem)
+                .map(Vec::as_slice)
+                .filter(|flags| !has_flag(flags, self.forbidden))
+        };
+
+        if let Some(flags) = self.words.get(word) {
+            if has_flag(flags, self.forbidden) {
+                return None;
+            }
+            if !has_flag(flags, self.need_affix) && !has_flag(flags, self.only_in_compound) {
+                return Some(flags);
+            }
+        }
+
+        for suffix in &self.suffixes {
+            let Some(stem) = suffix.remove_suffix(word) else {
+                continue;
+            };
+            if let Some(flags) = entry(&stem).filter(|flags| flags.contains(&suffix.flag)) {
+                return Some(flags);
+            }
+            // A second suffix allowed by the continuation flags of the first
+            for inner in self
+                .suffixes
+                .iter()
+                .filter(|inner| inner.continuation.contains(&suffix.flag))
+            {
+                if let Some(flags) = inner
+                    .remove_suffix(&stem)
+                    .and_then(|stem| entry(&stem))
+                    .filter(|flags| flags.contains(&inner.flag))
+                {
+                    return Some(flags);
+                }
+            }
+        }
+
+        for prefix in &self.prefixes {
+            let Some(stem) = prefix.remove_prefix(word) else {
+                continue;
+            };
+            if let Some(flags) = entry(&stem).filter(|flags| flags.contains(&prefix.flag)) {
+                return Some(flags);
+            }
+            if !prefix.cross_product {
+                continue;
+            }
+            for suffix in self.suffixes.iter().filter(|suffix| suffix.cross_product) {
+                if let Some(flags) = suffix
+                    .remove_suffix(&stem)
+                    .and_then(|stem| entry(&stem))
+                    .filter(|flags| {
+                        flags.contains(&suffix.flag)
+                            && (flags.contains(&prefix.flag)
+                                || suffix.continuation.contains(&prefix.flag))
+                    })
+                {
+                    return Some(flags);
+                }
+            }
+        }
+        self.find_compound(word, 0)
+    }
+
+    /// Flags of the last word of a compound, after `words` words of it were already split off
+    fn find_compound(&self, word: &str, words: usize) -> Option<&[Flag]> {
+        if self.compound_flag.is_none() && self.compound_begin.is_none() {
+            return None;
+        }
+        let min = self.compound_min.unwrap_or(DEFAULT_COMPOUND_MIN).max(1);
+        let part = if words == 0 {
+            CompoundPart::Begin
+        } else {
+            CompoundPart::Middle
+        };
+
+        for (idx, _) in word.char_indices().skip(min) {
+            let (head, tail) = word.split_at(idx);
+            if tail.chars().count() < min {
+                break;
+            }
+            if self.find_part(head, part).is_none() {
+                continue;
+            }
+            if let Some(flags) = self.find_part(tail, CompoundPart::End) {
+                return Some(flags);
+            }
+            // The tail is itself a compound of at least two more words
+            if self.compound_word_max.is_none_or(|max| words + 3 <= max)
+                && let Some(flags) = self.find_compound(tail, words + 1)
+            {
+                return Some(flags);
+            }
+        }
+        None
+    }
+
+    /// Flags of the dictionary entry a compound part comes from
+    ///
+    /// The first part may take a prefix and the last one a suffix.
+    fn find_part(&self, word: &str, part: CompoundPart) -> Option<&[Flag]> {
+        let part_flag = match part {
+            CompoundPart::Begin => self.compound_begin,
+            CompoundPart::Middle => self.compound_middle,
+            CompoundPart::End => self.compound_end,
+        };
+        let entry = |stem: &str| {
+            self.words.get(stem).map(Vec::as_slice).filter(|flags| {
+                !has_flag(flags, self.forbidden)
+                    && (has_flag(flags, self.compound_flag) || has_flag(flags, part_flag))
+            })
+        };
+
+        entry(word).or_else(|| match part {
+            CompoundPart::Begin => self.prefixes.iter().find_map(|prefix| {
+                prefix
+                    .remove_prefix(word)
+                    .and_then(|stem| entry(&stem))
+                    .filter(|flags| flags.contains(&prefix.flag))
+            }),
+            CompoundPart::End => self.suffixes.iter().find_map(|suffix| {
+                suffix
+                    .remove_suffix(word)
+                    .and_then(|stem| entry(&stem))
+                    .filter(|flags| flags.contains(&suffix.flag))
+            }),
+            CompoundPart::Middle => None,
+        })
+    }
+
+    /// Correctly spelled words close to a misspelled one, best first
+    ///
+    /// Candidates come from the `REP` table and from swapping, replacing,
+    /// removing or inserting one character, or splitting the word in two. A
+    /// capitalized word is also corrected in lowercase and capitalized again.
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let mut candidates = Vec::new();
+        let mut chars = word.chars();
+        if let Some(first) = chars.next()
+            && first.is_uppercase()
+            && chars.as_str() == chars.as_str().to_lowercase()
+        {
+            let capitalize = |candidate: String| {
+                let mut chars = candidate.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            };
+            candidates.extend(self.edits(&word.to_lowercase()).into_iter().map(capitalize));
+        }
+        candidates.extend(self.edits(word));
+
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate != word && seen.insert(candidate.clone()))
+            .filter(|candidate| {
+                candidate.split(' ').all(|part| {
+                    self.find(part)
+                        .is_some_and(|flags| !self.no_suggest.is_some_and(|f| flags.contains(&f)))
+                })
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Words one edit away from `word`, in the order they are suggested
+    fn edits(&self, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let try_chars: Vec<char> = if self.try_chars.is_empty() {
+            ('a'..='z').collect()
+        } else {
+            self.try_chars.clone()
+        };
+        let join = |parts: &[&[char]]| parts.iter().flat_map(|p| p.iter()).collect::<String>();
+
+        let mut edits: Vec<String> = Vec::new();
+        for (from, to) in &self.replacements {
+            for (idx, _) in word.match_indices(from.as_str()) {
+                edits.push(format!(
+                    "{}{}{}",
+                    &word[..idx],
+                    to,
+                    &word[idx + from.len()..]
+                ));
+            }
+        }
+        for idx in 1..chars.len() {
+            let mut swapped = chars.clone();
+            swapped.swap(idx - 1, idx);
+            edits.push(swapped.iter().collect());
+        }
+        for idx in 0..chars.len() {
+            for &c in try_chars.iter().filter(|&&c| c != chars[idx]) {
+                edits.push(join(&[&chars[..idx], &[c], &chars[idx + 1..]]));
+            }
+        }
+        for idx in 0..chars.len() {
+            edits.push(join(&[&chars[..idx], &chars[idx + 1..]]));
+        }
+        for idx in 0..=chars.len() {
+            for &c in &try_chars {
+                edits.push(join(&[&chars[..idx], &[c], &chars[idx..]]));
+            }
+        }
+        for idx in 1..chars.len() {
+            edits.push(join(&[&chars[..idx], &[' '], &chars[idx..]]));
+        }
+        edits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8
+TRY esianrtolcdugmphbyfvkwz'
+
+REP 2
+REP f ph
+REP ph f
+
+FORBIDDENWORD !
+
+PFX U Y 1
+PFX U 0 un .
+
+SFX S Y 3
+SFX S y ies [^aeiou]y
+SFX S 0 s [aeiou]y
+SFX S 0 s [^y]
+
+SFX D Y 2
+SFX D 0 ed [^ey]
+SFX D 0 d e
+
+SFX L N 1
+SFX L 0 ly/S .
+";
+
+    const DIC: &str = "7
+cat/S
+city/S
+lock/UDS
+bake/D
+love/L
+Paris
+phone
+irregardless/!
+";
+
+    #[test]
+    fn test_check() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        for word in [
+            "cat", "cats", "city", "cities", "locked", "unlocked", "unlocks", "baked", "lovely",
+            "lovelies", "Paris", "PARIS", "Cat", "CATS",
+        ] {
+            assert!(dictionary.check(word), "{} should be accepted", word);
+        }
+        for word in ["citys", "unbaked", "paris", "cAt", "irregardless", "bakeed"] {
+            assert!(!dictionary.check(word), "{} should be rejected", word);
+        }
+    }
+
+    #[test]
+    fn test_suggest() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        assert_eq!(dictionary.suggest("fone", 3), vec!["phone"]);
+        assert_eq!(dictionary.suggest("ctiy", 3), vec!["city"]);
+        assert_eq!(dictionary.suggest("Ctiy", 3), vec!["City"]);
+        assert!(dictionary.suggest("catts", 5).contains(&"cats".to_string()));
+        assert_eq!(dictionary.suggest("catcity", 5), vec!["cat city"]);
+        assert!(dictionary.suggest("irregardles", 5).is_empty());
+    }
+
+    #[test]
+    fn test_compounds() {
+        let aff = "COMPOUNDFLAG X\nCOMPOUNDMIN 2\nONLYINCOMPOUND O\nSFX S Y 1\nSFX S 0 s .\n";
+        let dic = "4\nfoot/X\nball/XS\nwork/X\nfs/OX\n";
+        let dictionary = Dictionary::parse(aff, dic);
+        for word in [
+            "football",
+            "footballs",
+            "footballwork",
+            "Football",
+            "footfs",
+        ] {
+            assert!(dictionary.check(word), "{} should be accepted", word);
+        }
+        for word in ["fs", "footbal", "footballsfoot", "footf"] {
+            assert!(!dictionary.check(word), "{} should be rejected", word);
+        }
+
+        let aff = format!("{}COMPOUNDWORDMAX 2\n", aff);
+        let dictionary = Dictionary::parse(&aff, dic);
+        assert!(dictionary.check("football"));
+        assert!(!dictionary.check("footballwork"));
+
+        let aff = "COMPOUNDBEGIN B\nCOMPOUNDEND E\n";
+        let dictionary = Dictionary::parse(aff, "2\nsun/B\nflower/E\n");
+        assert!(dictionary.check("sunflower"));
+        assert!(!dictionary.check("flowersun"));
+        assert!(!dictionary.check("sunsun"));
+    }
+
+    #[test]
+    fn test_flag_types() {
+        let aff = "FLAG long\nSFX Aa Y 1\nSFX Aa 0 s .\n";
+        let dictionary = Dictionary::parse(aff, "1\ndog/AaBb\n");
+        assert!(dictionary.check("dogs"));
+
+        let aff = "FLAG num\nAF 1\nAF 101,7\nSFX 101 Y 1\nSFX 101 0 s .\n";
+        let dictionary = Dictionary::parse(aff, "1\ndog/1\n");
+        assert!(dictionary.check("dogs"));
+    }
+}
@@ -0,0 +1,158 @@
+use mq_markdown::{Markdown, Node};
+use serde::{Deserialize, Serialize};
+
+use crate::format::BulletMarker;
+use crate::markdown_lsp::MarkdownLsp;
+
+/// Comment opening a generated table of contents
+pub const TOC_START: &str = "<!-- toc -->";
+/// Comment closing a generated table of contents
+pub const TOC_END: &str = "<!-- tocstop -->";
+
+/// Heading levels included in a table of contents and how its entries are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TocOptions {
+    pub min_depth: u8,
+    pub max_depth: u8,
+    /// List marker of the entries, the formatter's so formatting leaves them alone
+    pub bullet: BulletMarker,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        Self {
+            min_depth: 2,
+            max_depth: 4,
+            bullet: BulletMarker::default(),
+        }
+    }
+}
+
+/// Depth and text of each heading in the document
+fn headings(markdown: &Markdown) -> impl Iterator<Item = (u8, String)> + '_ {
+    markdown.nodes.iter().filter_map(|node| match node {
+        Node::Heading(heading) => Some((
+            heading.depth,
+            heading.values.iter().map(|n| n.value()).collect(),
+        )),
+        _ => None,
+    })
+}
+
+/// List items of the table of contents, one per heading in the depth range
+///
+/// Items are indented relative to the shallowest included heading.
+pub fn toc_entries(markdown: &Markdown, options: TocOptions) -> Vec<String> {
+    let included: Vec<(u8, String)> = headings(markdown)
+        .filter(|(depth, _)| (options.min_depth..=options.max_depth).contains(depth))
+        .collect();
+    let base = included.iter().map(|(depth, _)| *depth).min().unwrap_or(0);
+
+    included
+        .into_iter()
+        .map(|(depth, text)| {
+            format!(
+                "{}{} [{}](#{})",
+                "  ".repeat((depth - base) as usize),
+                options.bullet.as_char(),
+                text,
+                MarkdownLsp::make_heading_slug(&text)
+            )
+        })
+        .collect()
+}
+
+/// Table of contents wrapped in its marker comments
+pub fn toc_block(markdown: &Markdown, options: TocOptions) -> Vec<String> {
+    let entries = toc_entries(markdown, options);
+    if entries.is_empty() {
+        return vec![TOC_START.to_string(), TOC_END.to_string()];
+    }
+
+    let mut block = vec![TOC_START.to_string(), String::new()];
+    block.extend(entries);
+    block.push(String::new());
+    block.push(TOC_END.to_string());
+    block
+}
+
+/// Lines of the start and end markers of the first table of contents
+///
+/// Markers inside fenced code blocks are examples and don't count.
+pub fn find_toc<S: AsRef<str>>(lines: &[S]) -> Option<(usize, usize)> {
+    let mut in_fence = false;
+    let mut markers = lines.iter().enumerate().filter(|(_, line)| {
+        let trimmed = line.as_ref().trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            return false;
+        }
+        !in_fence
+    });
+    let (start, _) = markers.find(|(_, line)| line.as_ref().trim() == TOC_START)?;
+    let (end, _) = markers.find(|(_, line)| line.as_ref().trim() == TOC_END)?;
+    Some((start, end))
+}
+
+/// Non-blank lines between the markers of a table of contents
+pub fn existing_entries<S: AsRef<str>>(lines: &[S], start: usize, end: usize) -> Vec<&str> {
+    lines[start + 1..end]
+        .iter()
+        .map(|line| line.as_ref().trim_end())
+        .filter(|line| !line.trim().is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "# Title\n\n<!-- toc -->\n<!-- tocstop -->\n\n## Install\n\n### From Source\n\n## Usage Notes\n\n##### Deep\n";
+
+    fn markdown(content: &str) -> Markdown {
+        Markdown::from_markdown_str(content).unwrap()
+    }
+
+    #[test]
+    fn test_toc_entries() {
+        let entries = toc_entries(&markdown(DOC), TocOptions::default());
+        assert_eq!(
+            entries,
+            vec![
+                "- [Install](#install)",
+                "  - [From Source](#from-source)",
+                "- [Usage Notes](#usage-notes)",
+            ]
+        );
+
+        let options = TocOptions {
+            min_depth: 1,
+            max_depth: 2,
+            bullet: BulletMarker::Asterisk,
+        };
+        let entries = toc_entries(&markdown(DOC), options);
+        assert_eq!(entries[0], "* [Title](#title)");
+        assert_eq!(entries[1], "  * [Install](#install)");
+    }
+
+    #[test]
+    fn test_find_toc() {
+        let lines: Vec<&str> = DOC.lines().collect();
+        assert_eq!(find_toc(&lines), Some((2, 3)));
+        assert_eq!(find_toc(&["<!-- toc -->", "- a"]), None);
+        assert_eq!(find_toc(&["text"]), None);
+
+        // Markers shown in a code example are skipped
+        let lines = [
+            "```markdown",
+            "<!-- toc -->",
+            "<!-- tocstop -->",
+            "```",
+            "<!-- toc -->",
+            "<!-- tocstop -->",
+        ];
+        assert_eq!(find_toc(&lines), Some((4, 5)));
+        assert_eq!(find_toc(&lines[..4]), None);
+    }
+}
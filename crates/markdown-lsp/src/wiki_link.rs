@@ -0,0 +1,255 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::markdown_lsp::MarkdownLsp;
+
+/// Directories never searched for pages
+const SKIPPED_DIRS: [&str; 2] = ["node_modules", "target"];
+
+/// `[[Page]]`, `[[Page#Heading]]` or `[[Page#Heading|alias]]` link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLink {
+    /// Page name, empty for a heading in the same document (`[[#Heading]]`)
+    pub page: String,
+    pub heading: Option<String>,
+    pub alias: Option<String>,
+    /// Byte range of the link in its line, brackets included
+    pub range: Range<usize>,
+}
+
+impl WikiLink {
+    /// Parse the text between `[[` and `]]`
+    fn parse(inner: &str, range: Range<usize>) -> Option<Self> {
+        let (target, alias) = match inner.split_once('|') {
+            Some((target, alias)) => (target, Some(alias.trim().to_string())),
+            None => (inner, None),
+        };
+        let (page, heading) = match target.split_once('#') {
+            Some((page, heading)) => (page, Some(heading.trim().to_string())),
+            None => (target, None),
+        };
+        let page = page.trim().to_string();
+        if page.is_empty() && heading.as_deref().is_none_or(str::is_empty) {
+            return None;
+        }
+
+        Some(Self {
+            page,
+            heading,
+            alias,
+            range,
+        })
+    }
+
+    /// Link from a target (`Page#Heading`) and alias parsed elsewhere, without a range
+    pub fn from_target(target: &str, alias: Option<&str>) -> Option<Self> {
+        let inner = match alias {
+            Some(alias) => format!("{}|{}", target, alias),
+            None => target.to_string(),
+        };
+        Self::parse(&inner, 0..0)
+    }
+
+    /// Text shown for the link: the alias, else the target as written
+    pub fn label(&self) -> String {
+        if let Some(alias) = &self.alias {
+            return alias.clone();
+        }
+        match &self.heading {
+            Some(heading) if self.page.is_empty() => heading.clone(),
+            Some(heading) => format!("{} › {}", self.page, heading),
+            None => self.page.clone(),
+        }
+    }
+
+    /// Slug of the linked heading
+    pub fn anchor(&self) -> Option<String> {
+        self.heading.as_deref().map(MarkdownLsp::make_heading_slug)
+    }
+}
+
+/// Find the wiki links in a line, skipping inline code
+pub fn find_wiki_links(line: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+    let mut in_code = false;
+    let mut pos = 0;
+
+    while pos < line.len() {
+        let rest = &line[pos..];
+        if rest.starts_with('`') {
+            in_code = !in_code;
+            pos += 1;
+            continue;
+        }
+        if !in_code
+            && rest.starts_with("[[")
+            && let Some(close) = rest[2..].find("]]")
+        {
+            let inner = &rest[2..2 + close];
+            let end = pos + close + 4;
+            if !inner.contains(['[', ']'])
+                && let Some(link) = WikiLink::parse(inner, pos..end)
+            {
+                links.push(link);
+                pos = end;
+                continue;
+            }
+        }
+        pos += rest.chars().next().map_or(1, char::len_utf8);
+    }
+
+    links
+}
+
+/// Wiki links of a document with their line index, skipping fenced code blocks
+pub fn document_wiki_links(content: &str) -> Vec<(usize, WikiLink)> {
+    let mut in_fence = false;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                return false;
+            }
+            !in_fence
+        })
+        .flat_map(|(idx, line)| {
+            find_wiki_links(line)
+                .into_iter()
+                .map(move |link| (idx, link))
+        })
+        .collect()
+}
+
+/// Wiki link whose range contains a byte offset of the line
+pub fn wiki_link_at(line: &str, offset: usize) -> Option<WikiLink> {
+    find_wiki_links(line)
+        .into_iter()
+        .find(|link| link.range.contains(&offset))
+}
+
+/// Page name of a Markdown file: its path relative to the root, without extension
+pub fn page_name(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .with_extension("")
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Markdown files under a workspace root, skipping hidden and build directories
+pub fn workspace_pages(root: &Path) -> Vec<PathBuf> {
+    let mut pages = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') {
+                continue;
+            }
+            if path.is_dir() {
+                if !SKIPPED_DIRS.contains(&name.as_ref()) {
+                    dirs.push(path);
+                }
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext == "md" || ext == "markdown")
+            {
+                pages.push(path);
+            }
+        }
+    }
+
+    pages.sort();
+    pages
+}
+
+/// Find the file a page name refers to
+///
+/// `[[Name]]` matches a file named `Name.md` anywhere in the workspace, and
+/// `[[dir/Name]]` one in a matching directory. Matching ignores case.
+pub fn resolve_page<'a>(pages: &'a [PathBuf], root: &Path, page: &str) -> Option<&'a Path> {
+    let page = page.trim_end_matches(".md").to_lowercase();
+    pages
+        .iter()
+        .filter(|path| {
+            let name = page_name(root, path).to_lowercase();
+            name == page || name.ends_with(&format!("/{}", page))
+        })
+        // Prefer the shallowest match
+        .min_by_key(|path| path.components().count())
+        .map(|path| path.as_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_wiki_links() {
+        let links = find_wiki_links("see [[Page Name]], [[Page#Setup Steps|setup]] or `[[code]]`");
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].page, "Page Name");
+        assert_eq!(links[0].range, 4..17);
+        assert_eq!(links[0].label(), "Page Name");
+        assert_eq!(links[1].heading.as_deref(), Some("Setup Steps"));
+        assert_eq!(links[1].anchor().as_deref(), Some("setup-steps"));
+        assert_eq!(links[1].label(), "setup");
+
+        let links = find_wiki_links("[[#Intro]] [[]] [[a [b]]");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].page, "");
+        assert_eq!(links[0].label(), "Intro");
+    }
+
+    #[test]
+    fn test_document_wiki_links_skip_fences() {
+        let content = "[[A]]\n```\n[[B]]\n```\ntext [[C]]";
+        let links = document_wiki_links(content);
+        let pages: Vec<(usize, &str)> = links
+            .iter()
+            .map(|(line, link)| (*line, link.page.as_str()))
+            .collect();
+        assert_eq!(pages, vec![(0, "A"), (4, "C")]);
+    }
+
+    #[test]
+    fn test_resolve_page() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("notes/deep")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        for file in [
+            "Home.md",
+            "notes/Page Name.md",
+            "notes/deep/home.md",
+            ".git/x.md",
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        let pages = workspace_pages(dir);
+        assert_eq!(pages.len(), 3);
+        assert_eq!(
+            resolve_page(&pages, dir, "page name"),
+            Some(dir.join("notes/Page Name.md").as_path())
+        );
+        assert_eq!(
+            resolve_page(&pages, dir, "Home"),
+            Some(dir.join("Home.md").as_path())
+        );
+        assert_eq!(
+            resolve_page(&pages, dir, "deep/home"),
+            Some(dir.join("notes/deep/home.md").as_path())
+        );
+        assert_eq!(resolve_page(&pages, dir, "Missing"), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! Golden tests for the Markdown formatter
+//!
+//! Each `tests/fixtures/format/<name>.md` is formatted with the default
+//! options and compared with `<name>.formatted.md`. A fixture may also have
+//! `<name>.<style>.formatted.md` files, formatted with the non-default style
+//! named by `<style>` (see `style_options`).
+
+use std::fs;
+use std::path::Path;
+
+use markdown_lsp::format::{
+    BulletMarker, CodeFence, EmphasisMarker, FormatOptions, HeadingStyle, ListNumbering, format,
+};
+
+/// Options of a `<name>.<style>.formatted.md` fixture
+fn style_options(style: &str) -> FormatOptions {
+    let default = FormatOptions::default();
+    match style {
+        "asterisk" => FormatOptions {
+            bullet: BulletMarker::Asterisk,
+            ..default
+        },
+        "plus" => FormatOptions {
+            bullet: BulletMarker::Plus,
+            ..default
+        },
+        "underscore" => FormatOptions {
+            emphasis: EmphasisMarker::Underscore,
+            strong: EmphasisMarker::Underscore,
+            ..default
+        },
+        "one" => FormatOptions {
+            numbering: ListNumbering::One,
+            ..default
+        },
+        "setext" => FormatOptions {
+            heading: HeadingStyle::Setext,
+            ..default
+        },
+        "tilde" => FormatOptions {
+            code_fence: CodeFence::Tilde,
+            ..default
+        },
+        "plain" => FormatOptions {
+            align_tables: false,
+            blank_lines: false,
+            final_newline: false,
+            ..default
+        },
+        _ => panic!("unknown fixture style '{}'", style),
+    }
+}
+
+#[test]
+fn test_format_golden() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/format");
+    let mut expected_paths: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with(".formatted.md"))
+        .collect();
+    expected_paths.sort();
+    assert!(!expected_paths.is_empty());
+
+    for expected_path in expected_paths {
+        let file_name = expected_path.file_name().unwrap().to_string_lossy();
+        let stem = file_name.trim_end_matches(".formatted.md");
+        let (name, options) = match stem.split_once('.') {
+            Some((name, style)) => (name, style_options(style)),
+            None => (stem, FormatOptions::default()),
+        };
+
+        let input = fs::read_to_string(dir.join(format!("{}.md", name))).unwrap();
+        let expected = fs::read_to_string(&expected_path).unwrap();
+        let formatted = format(&input, &options);
+        let name = expected_path.display();
+
+        assert_eq!(formatted, expected, "{}", name);
+        assert_eq!(
+            format(&formatted, &options),
+            formatted,
+            "{} is not idempotent",
+            name
+        );
+        assert_eq!(
+            mq_markdown::to_html(&formatted).trim_end(),
+            mq_markdown::to_html(&input).trim_end(),
+            "{} renders differently",
+            name
+        );
+    }
+}
@@ -1,15 +1,73 @@
+use std::cell::RefCell;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use lsp_types::CompletionItem;
-use markdown_lsp::{DiagnosticsManager, LspEvent, LspManager};
+use lsp_types::{CodeActionOrCommand, CompletionItem, TextEdit};
+use markdown_lsp::link_index::{self, LinkIndex, LinkReference};
+use markdown_lsp::{DiagnosticsManager, LspEvent, LspManager, format, toc, wiki_link};
 use miette::Result;
 
 use crate::config::Config;
-use crate::document::{CursorMovement, DocumentBuffer, FileType};
+use crate::document::table::{self, Table};
+use crate::document::{
+    Cursor, CursorMovement, DocumentBuffer, DocumentType, FileType, Folds, FrontMatterForm,
+    LinkTarget, Outline, SearchMatch, SearchOptions, SearchPattern, SearchScope, SectionStats,
+    TextStats, link, outline, stats,
+};
+use crate::fold_store::FoldStore;
+use crate::input_history::{HistoryKind, HistoryNavigator, InputHistory, ReverseSearch};
 use crate::navigation::{FileLocation, NavigationHistory};
-use crate::renderer::{CodeRenderer, ImageManager};
-use crate::ui::{FileTree, SearchField, SearchMode};
+use crate::renderer::{CodeRenderer, ImageManager, MarkdownRenderer, TableOptions};
+use crate::ui::{
+    FileTree, LineLayout, SearchField, SearchMode, cursor_line_text, line_rows, screen_rows,
+    wrap_columns,
+};
+
+/// Table edit applied to the cell under the cursor; returns the cell to move to
+type TableEdit = fn(&mut Table, usize) -> Option<usize>;
+
+/// Markdown table under the cursor
+struct TableCursor {
+    /// Buffer lines of the table
+    start: usize,
+    end: usize,
+    table: Table,
+    /// Source row (0 header, 1 separator, 2.. data rows)
+    row: usize,
+    cell: usize,
+}
+
+/// Value computed for a key, reused while the key stays the same
+type Cached<K, T> = RefCell<Option<(K, T)>>;
+
+/// Buffer revision and selected range the selection counts belong to
+type SelectionKey = (u64, Option<(Cursor, Cursor)>);
+
+/// Statistics shown every frame, kept until the buffer or the selection changes
+#[derive(Default)]
+struct StatsCache {
+    /// Counts of the document at a buffer revision
+    document: Cached<u64, Option<TextStats>>,
+    /// Counts of the selected text
+    selection: Cached<SelectionKey, Option<TextStats>>,
+    /// Counts of each section at a buffer revision
+    sections: Cached<u64, Vec<SectionStats>>,
+}
+
+impl StatsCache {
+    /// Value cached in `slot` for `key`, computing and storing it on a miss
+    fn get<K: PartialEq, T: Clone>(slot: &Cached<K, T>, key: K, compute: impl FnOnce() -> T) -> T {
+        if let Some((cached, value)) = slot.borrow().as_ref()
+            && *cached == key
+        {
+            return value.clone();
+        }
+        let value = compute();
+        *slot.borrow_mut() = Some((key, value.clone()));
+        value
+    }
+}
 
 /// Main application state
 pub struct App {
@@ -33,6 +91,8 @@ pub struct App {
     image_manager: ImageManager,
     /// Code renderer for syntax highlighting
     code_renderer: CodeRenderer,
+    /// Markdown renderer, kept so its caches last between frames
+    markdown_renderer: MarkdownRenderer,
     /// LSP manager for language server protocol support
     lsp_manager: Option<LspManager>,
     /// Diagnostics manager for LSP diagnostics
@@ -43,6 +103,18 @@ pub struct App {
     navigation_history: NavigationHistory,
     /// Pending definition request location (for adding to history)
     pending_definition_request: Option<(PathBuf, usize, usize)>,
+    /// Missing wiki link page (name and file) awaiting confirmation to create it
+    pending_new_page: Option<(String, PathBuf)>,
+    /// Front matter form dialog, while open
+    front_matter_form: Option<FrontMatterForm>,
+    /// Where the selection started; the cursor is its other end
+    selection_anchor: Option<Cursor>,
+    /// Whether the statistics dialog is visible
+    show_stats_dialog: bool,
+    /// Selected section in the statistics dialog
+    stats_selected: usize,
+    /// Statistics of the current buffer
+    stats_cache: StatsCache,
     /// Completion items from LSP (original unfiltered list)
     completion_items: Vec<CompletionItem>,
     /// Filtered completion items based on user input
@@ -51,6 +123,10 @@ pub struct App {
     completion_selected: usize,
     /// Whether completion popup is visible
     show_completion: bool,
+    /// Quick fixes at the cursor, shown in a popup while not empty
+    code_actions: Vec<CodeActionOrCommand>,
+    /// Selected quick fix
+    code_action_selected: usize,
     /// Column position where completion started
     completion_start_column: usize,
     /// Whether quit confirmation is pending (user pressed quit with unsaved changes)
@@ -61,6 +137,18 @@ pub struct App {
     show_line_numbers: bool,
     /// Whether current line highlight is visible
     show_current_line_highlight: bool,
+    /// Whether the cursor line reveals only the inline element under the cursor
+    partial_wysiwyg: bool,
+    /// Width of the editor pane in the last frame
+    editor_width: u16,
+    /// Height of the editor pane in the last frame
+    editor_height: u16,
+    /// Whether the document is shown fully rendered with pager navigation
+    preview_mode: bool,
+    /// Whether the document was opened read-only (`--view`), so preview can't be left
+    view_only: bool,
+    /// Whether focus mode hides the chrome around a centered text column
+    focus_mode: bool,
     /// Whether search dialog is visible
     show_search_dialog: bool,
     /// Search query
@@ -69,8 +157,14 @@ pub struct App {
     replace_query: String,
     /// Search mode (Find or Replace)
     search_mode: SearchMode,
-    /// Current search results (line, column)
-    search_results: Vec<(usize, usize)>,
+    /// Current search results
+    search_results: Vec<SearchMatch>,
+    /// Search options (regex, case sensitivity, whole word), kept across dialog invocations
+    search_options: SearchOptions,
+    /// Markdown node kinds the search is restricted to
+    search_scope: SearchScope,
+    /// Error from compiling the search query (e.g. invalid regex)
+    search_error: Option<String>,
     /// Current search result index
     search_index: Option<usize>,
     /// Active search field
@@ -89,6 +183,32 @@ pub struct App {
     mq_query_input: String,
     /// mq query result (output or error message)
     mq_query_result: Option<String>,
+    /// Search, goto-line and mq query input history
+    input_history: InputHistory,
+    /// Up/Down navigation state for the open dialog's history
+    history_navigator: HistoryNavigator,
+    /// Active reverse incremental history search (Ctrl+R)
+    history_search: Option<ReverseSearch>,
+    /// Foldable ranges of the document and the folded ones
+    folds: Folds,
+    /// Folded lines saved per file
+    fold_store: FoldStore,
+    /// Headings or symbols of the document
+    outline: Outline,
+    /// Whether the outline sidebar is visible
+    show_outline: bool,
+    /// Whether the outline sidebar has keyboard focus
+    outline_focused: bool,
+    /// Links between the workspace's Markdown files, built when backlinks are first shown
+    link_index: Option<LinkIndex>,
+    /// Links to the current document from other files
+    backlinks: Vec<LinkReference>,
+    /// Selected entry of the backlinks panel
+    backlinks_selected: usize,
+    /// Whether the backlinks panel is visible
+    show_backlinks: bool,
+    /// Whether the backlinks panel has keyboard focus
+    backlinks_focused: bool,
     /// Whether the app is running in pipe mode (stdin/stdout piped)
     pipe_mode: bool,
 }
@@ -100,15 +220,25 @@ impl App {
         let config = Config::load_or_default();
         let mut image_manager = ImageManager::new();
         image_manager.set_base_path(current_dir.clone());
+        image_manager.set_display(config.images.protocol, config.images.rows);
+        image_manager.set_cache_budget(config.images.cache_size_mb * 1024 * 1024);
 
         // Create LSP manager
-        let lsp_manager = Some(LspManager::new(
-            config.lsp_server_configs(),
-            current_dir.clone(),
-        ));
+        let lsp_manager = Some(
+            LspManager::new(config.lsp_server_configs(), current_dir.clone())
+                .with_front_matter_schema(config.front_matter.clone())
+                .with_spell_config(config.spell_config())
+                .with_format_options(config.editor.format_options())
+                .with_toc_options(config.toc_options()),
+        );
 
         let show_line_numbers = config.editor.show_line_numbers;
         let show_current_line_highlight = config.editor.show_current_line_highlight;
+        let partial_wysiwyg = config.editor.partial_wysiwyg;
+        let search_options = config.search.options();
+        let input_history = InputHistory::from_config(&config.history);
+        let fold_store = FoldStore::from_config(config.editor.persist_folds);
+        let folds = Folds::new();
         let mut code_renderer = CodeRenderer::with_theme(&config.editor.theme);
         code_renderer.set_use_semantic_tokens(config.editor.use_semantic_tokens);
 
@@ -123,25 +253,43 @@ impl App {
             config,
             image_manager,
             code_renderer,
+            markdown_renderer: MarkdownRenderer::new(),
             lsp_manager,
             diagnostics_manager: DiagnosticsManager::new(),
             document_version: 0,
             navigation_history: NavigationHistory::new(),
             pending_definition_request: None,
+            pending_new_page: None,
+            front_matter_form: None,
+            selection_anchor: None,
+            show_stats_dialog: false,
+            stats_selected: 0,
+            stats_cache: StatsCache::default(),
             completion_items: Vec::new(),
             filtered_completion_items: Vec::new(),
             completion_selected: 0,
             show_completion: false,
+            code_actions: Vec::new(),
+            code_action_selected: 0,
             completion_start_column: 0,
             quit_confirm_pending: false,
             show_quit_dialog: false,
             show_line_numbers,
             show_current_line_highlight,
+            partial_wysiwyg,
+            editor_width: 0,
+            editor_height: 0,
+            preview_mode: false,
+            view_only: false,
+            focus_mode: false,
             show_search_dialog: false,
             search_query: String::new(),
             replace_query: String::new(),
             search_mode: SearchMode::Find,
             search_results: Vec::new(),
+            search_options,
+            search_scope: SearchScope::All,
+            search_error: None,
             search_index: None,
             search_active_field: SearchField::Search,
             show_save_as_dialog: false,
@@ -151,6 +299,19 @@ impl App {
             show_mq_query_dialog: false,
             mq_query_input: String::new(),
             mq_query_result: None,
+            input_history,
+            history_navigator: HistoryNavigator::default(),
+            history_search: None,
+            folds,
+            fold_store,
+            outline: Outline::new(),
+            show_outline: false,
+            outline_focused: false,
+            link_index: None,
+            backlinks: Vec::new(),
+            backlinks_selected: 0,
+            show_backlinks: false,
+            backlinks_focused: false,
             pipe_mode: false,
         }
     }
@@ -166,12 +327,17 @@ impl App {
         let config = Config::load_or_default();
         let mut image_manager = ImageManager::new();
         image_manager.set_base_path(path.to_path_buf());
+        image_manager.set_display(config.images.protocol, config.images.rows);
+        image_manager.set_cache_budget(config.images.cache_size_mb * 1024 * 1024);
 
         // Create LSP manager
-        let mut lsp_manager = Some(LspManager::new(
-            config.lsp_server_configs(),
-            current_dir.clone(),
-        ));
+        let mut lsp_manager = Some(
+            LspManager::new(config.lsp_server_configs(), current_dir.clone())
+                .with_front_matter_schema(config.front_matter.clone())
+                .with_spell_config(config.spell_config())
+                .with_format_options(config.editor.format_options())
+                .with_toc_options(config.toc_options()),
+        );
 
         // Notify LSP that a document was opened
         let diagnostics_manager = DiagnosticsManager::new();
@@ -188,6 +354,12 @@ impl App {
 
         let show_line_numbers = config.editor.show_line_numbers;
         let show_current_line_highlight = config.editor.show_current_line_highlight;
+        let partial_wysiwyg = config.editor.partial_wysiwyg;
+        let search_options = config.search.options();
+        let input_history = InputHistory::from_config(&config.history);
+        let fold_store = FoldStore::from_config(config.editor.persist_folds);
+        let mut folds = Folds::with_folded(fold_store.get(path));
+        folds.sync(buffer.lines(), buffer.document_type());
         let mut code_renderer = CodeRenderer::with_theme(&config.editor.theme);
         code_renderer.set_use_semantic_tokens(config.editor.use_semantic_tokens);
 
@@ -202,25 +374,43 @@ impl App {
             config,
             image_manager,
             code_renderer,
+            markdown_renderer: MarkdownRenderer::new(),
             lsp_manager,
             diagnostics_manager,
             document_version: 1,
             navigation_history: NavigationHistory::new(),
             pending_definition_request: None,
+            pending_new_page: None,
+            front_matter_form: None,
+            selection_anchor: None,
+            show_stats_dialog: false,
+            stats_selected: 0,
+            stats_cache: StatsCache::default(),
             completion_items: Vec::new(),
             filtered_completion_items: Vec::new(),
             completion_selected: 0,
             show_completion: false,
+            code_actions: Vec::new(),
+            code_action_selected: 0,
             completion_start_column: 0,
             quit_confirm_pending: false,
             show_quit_dialog: false,
             show_line_numbers,
             show_current_line_highlight,
+            partial_wysiwyg,
+            editor_width: 0,
+            editor_height: 0,
+            preview_mode: false,
+            view_only: false,
+            focus_mode: false,
             show_search_dialog: false,
             search_query: String::new(),
             replace_query: String::new(),
             search_mode: SearchMode::Find,
             search_results: Vec::new(),
+            search_options,
+            search_scope: SearchScope::All,
+            search_error: None,
             search_index: None,
             search_active_field: SearchField::Search,
             show_save_as_dialog: false,
@@ -230,6 +420,19 @@ impl App {
             show_mq_query_dialog: false,
             mq_query_input: String::new(),
             mq_query_result: None,
+            input_history,
+            history_navigator: HistoryNavigator::default(),
+            history_search: None,
+            folds,
+            fold_store,
+            outline: Outline::new(),
+            show_outline: false,
+            outline_focused: false,
+            link_index: None,
+            backlinks: Vec::new(),
+            backlinks_selected: 0,
+            show_backlinks: false,
+            backlinks_focused: false,
             pipe_mode: false,
         })
     }
@@ -241,14 +444,26 @@ impl App {
         let config = Config::load_or_default();
         let mut image_manager = ImageManager::new();
         image_manager.set_base_path(current_dir.clone());
+        image_manager.set_display(config.images.protocol, config.images.rows);
+        image_manager.set_cache_budget(config.images.cache_size_mb * 1024 * 1024);
 
-        let lsp_manager = Some(LspManager::new(
-            config.lsp_server_configs(),
-            current_dir.clone(),
-        ));
+        let lsp_manager = Some(
+            LspManager::new(config.lsp_server_configs(), current_dir.clone())
+                .with_front_matter_schema(config.front_matter.clone())
+                .with_spell_config(config.spell_config())
+                .with_format_options(config.editor.format_options())
+                .with_toc_options(config.toc_options()),
+        );
 
         let show_line_numbers = config.editor.show_line_numbers;
         let show_current_line_highlight = config.editor.show_current_line_highlight;
+        let partial_wysiwyg = config.editor.partial_wysiwyg;
+        let search_options = config.search.options();
+        let input_history = InputHistory::from_config(&config.history);
+        // Piped input has no file to keep folds for
+        let fold_store = FoldStore::new();
+        let mut folds = Folds::new();
+        folds.sync(buffer.lines(), buffer.document_type());
         let mut code_renderer = CodeRenderer::with_theme(&config.editor.theme);
         code_renderer.set_use_semantic_tokens(config.editor.use_semantic_tokens);
 
@@ -263,25 +478,43 @@ impl App {
             config,
             image_manager,
             code_renderer,
+            markdown_renderer: MarkdownRenderer::new(),
             lsp_manager,
             diagnostics_manager: DiagnosticsManager::new(),
             document_version: 0,
             navigation_history: NavigationHistory::new(),
             pending_definition_request: None,
+            pending_new_page: None,
+            front_matter_form: None,
+            selection_anchor: None,
+            show_stats_dialog: false,
+            stats_selected: 0,
+            stats_cache: StatsCache::default(),
             completion_items: Vec::new(),
             filtered_completion_items: Vec::new(),
             completion_selected: 0,
             show_completion: false,
+            code_actions: Vec::new(),
+            code_action_selected: 0,
             completion_start_column: 0,
             quit_confirm_pending: false,
             show_quit_dialog: false,
             show_line_numbers,
             show_current_line_highlight,
+            partial_wysiwyg,
+            editor_width: 0,
+            editor_height: 0,
+            preview_mode: false,
+            view_only: false,
+            focus_mode: false,
             show_search_dialog: false,
             search_query: String::new(),
             replace_query: String::new(),
             search_mode: SearchMode::Find,
             search_results: Vec::new(),
+            search_options,
+            search_scope: SearchScope::All,
+            search_error: None,
             search_index: None,
             search_active_field: SearchField::Search,
             show_save_as_dialog: false,
@@ -291,6 +524,19 @@ impl App {
             show_mq_query_dialog: false,
             mq_query_input: String::new(),
             mq_query_result: None,
+            input_history,
+            history_navigator: HistoryNavigator::default(),
+            history_search: None,
+            folds,
+            fold_store,
+            outline: Outline::new(),
+            show_outline: false,
+            outline_focused: false,
+            link_index: None,
+            backlinks: Vec::new(),
+            backlinks_selected: 0,
+            show_backlinks: false,
+            backlinks_focused: false,
             pipe_mode: false,
         })
     }
@@ -345,6 +591,11 @@ impl App {
         &mut self.code_renderer
     }
 
+    /// Get Markdown renderer
+    pub fn markdown_renderer(&self) -> &MarkdownRenderer {
+        &self.markdown_renderer
+    }
+
     /// Get status message
     pub fn status_message(&self) -> Option<&str> {
         self.status_message.as_deref()
@@ -428,62 +679,987 @@ impl App {
         self.file_tree.as_mut()
     }
 
-    /// Check if file browser is visible
-    pub fn is_file_browser_visible(&self) -> bool {
-        self.show_file_browser
+    /// Check if file browser is visible
+    pub fn is_file_browser_visible(&self) -> bool {
+        self.show_file_browser
+    }
+
+    /// Toggle file browser visibility
+    pub fn toggle_file_browser(&mut self) {
+        self.show_file_browser = !self.show_file_browser;
+        self.focus_mode = false;
+
+        // Initialize file tree if it doesn't exist
+        if self.show_file_browser && self.file_tree.is_none() {
+            self.file_tree = Some(FileTree::new(&self.current_dir));
+        }
+    }
+
+    /// Get the document outline
+    pub fn outline(&self) -> &Outline {
+        &self.outline
+    }
+
+    /// Check if the outline sidebar is visible
+    pub fn is_outline_visible(&self) -> bool {
+        self.show_outline
+    }
+
+    /// Check if the outline sidebar has keyboard focus
+    pub fn is_outline_focused(&self) -> bool {
+        self.outline_focused
+    }
+
+    /// Outline entry of the section containing the cursor
+    pub fn outline_current(&self) -> Option<usize> {
+        self.outline.current(self.buffer.cursor().line)
+    }
+
+    /// Show and focus the outline, or hide it if it already has focus
+    ///
+    /// While the outline is visible without focus, the current section keeps
+    /// following the cursor.
+    pub fn toggle_outline(&mut self) {
+        self.focus_mode = false;
+        if self.show_outline && self.outline_focused {
+            self.show_outline = false;
+            self.outline_focused = false;
+            return;
+        }
+        if !self.show_outline {
+            self.show_outline = true;
+            self.refresh_outline();
+        }
+        self.outline_focused = true;
+        self.backlinks_focused = false;
+        self.outline.select_line(self.buffer.cursor().line);
+    }
+
+    /// Links to the current document from other files
+    pub fn backlinks(&self) -> &[LinkReference] {
+        &self.backlinks
+    }
+
+    /// Selected entry of the backlinks panel
+    pub fn backlinks_selected(&self) -> usize {
+        self.backlinks_selected
+    }
+
+    /// Check if the backlinks panel is visible
+    pub fn is_backlinks_visible(&self) -> bool {
+        self.show_backlinks
+    }
+
+    /// Check if the backlinks panel has keyboard focus
+    pub fn is_backlinks_focused(&self) -> bool {
+        self.backlinks_focused
+    }
+
+    /// Root of the workspace scanned for pages and links
+    pub fn workspace_root(&self) -> &Path {
+        self.lsp_manager
+            .as_ref()
+            .map_or(self.current_dir.as_path(), |lsp| lsp.root_path())
+    }
+
+    /// Show and focus the backlinks panel, or hide it if it already has focus
+    pub fn toggle_backlinks(&mut self) {
+        self.focus_mode = false;
+        if self.show_backlinks && self.backlinks_focused {
+            self.show_backlinks = false;
+            self.backlinks_focused = false;
+            return;
+        }
+        if !self.show_backlinks {
+            self.show_backlinks = true;
+            self.refresh_backlinks();
+        }
+        self.backlinks_focused = true;
+        self.outline_focused = false;
+    }
+
+    /// Recompute the backlinks of the current document, indexing the workspace on first use
+    fn refresh_backlinks(&mut self) {
+        if !self.show_backlinks {
+            return;
+        }
+        if self.link_index.is_none() {
+            self.link_index = Some(LinkIndex::build(
+                self.workspace_root(),
+                self.config.links.wiki_links,
+            ));
+        }
+
+        self.backlinks = match (&self.link_index, self.buffer.file_path()) {
+            (Some(index), Some(path)) => index.backlinks(path).into_iter().cloned().collect(),
+            _ => Vec::new(),
+        };
+        self.backlinks_selected = self
+            .backlinks_selected
+            .min(self.backlinks.len().saturating_sub(1));
+    }
+
+    /// Reindex the links of the saved document and refresh the backlinks
+    fn update_link_index(&mut self) {
+        if let Some(index) = &mut self.link_index
+            && let Some(path) = self.buffer.file_path()
+            && matches!(self.buffer.file_type(), FileType::Markdown)
+        {
+            index.update_file(path, &self.buffer.content());
+        }
+        self.refresh_backlinks();
+    }
+
+    fn handle_backlinks_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Up => self.backlinks_selected = self.backlinks_selected.saturating_sub(1),
+            KeyCode::Down if self.backlinks_selected + 1 < self.backlinks.len() => {
+                self.backlinks_selected += 1
+            }
+            KeyCode::Enter => {
+                // Jump to the referring link and return focus to the editor
+                if let Some(link) = self.backlinks.get(self.backlinks_selected).cloned() {
+                    self.push_navigation_location();
+                    self.jump_to_location(
+                        &link.source,
+                        link.range.start.line as usize,
+                        link.range.start.character as usize,
+                    )?;
+                    self.push_navigation_location();
+                    self.backlinks_focused = false;
+                }
+            }
+            KeyCode::Esc => self.backlinks_focused = false,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Rebuild the outline of a Markdown buffer, or ask the language server for symbols
+    fn refresh_outline(&mut self) {
+        if self.buffer.markdown().is_some() {
+            self.sync_outline();
+            return;
+        }
+
+        let language_id = file_type_to_language_id(self.buffer.file_type());
+        if let Some(ref mut lsp) = self.lsp_manager
+            && let Some(file_path) = self.buffer.file_path()
+            && let Some(lang_id) = &language_id
+            && lsp.is_enabled(lang_id)
+        {
+            let path_buf = file_path.to_path_buf();
+            if let Err(e) = lsp.request_document_symbols(lang_id, &path_buf) {
+                eprintln!("Failed to request document symbols: {}", e);
+            }
+        } else {
+            self.outline.set_entries(Vec::new());
+            self.set_status_message("No outline available for this file type".to_string());
+        }
+    }
+
+    /// Update the outline of a Markdown buffer from its headings
+    ///
+    /// Code files are updated when the language server answers the symbol
+    /// request sent on each change.
+    fn sync_outline(&mut self) {
+        if self.show_outline
+            && let Some(markdown) = self.buffer.markdown()
+        {
+            self.outline
+                .set_entries(outline::markdown_outline(markdown));
+        }
+    }
+
+    /// Check if line numbers are visible (never in focus mode)
+    pub fn show_line_numbers(&self) -> bool {
+        self.show_line_numbers && !self.focus_mode
+    }
+
+    /// Toggle line numbers visibility
+    pub fn toggle_line_numbers(&mut self) {
+        self.show_line_numbers = !self.show_line_numbers;
+    }
+
+    /// Check if current line highlight is visible
+    pub fn show_current_line_highlight(&self) -> bool {
+        self.show_current_line_highlight
+    }
+
+    /// Toggle current line highlight visibility
+    pub fn toggle_current_line_highlight(&mut self) {
+        self.show_current_line_highlight = !self.show_current_line_highlight;
+    }
+
+    /// Check if partial WYSIWYG rendering of the cursor line is enabled
+    pub fn partial_wysiwyg(&self) -> bool {
+        self.partial_wysiwyg
+    }
+
+    /// Record the width of the editor pane, used to lay out tables
+    pub fn set_editor_width(&mut self, width: u16) {
+        self.editor_width = width;
+    }
+
+    /// Record the height of the editor pane, used to page in preview mode
+    pub fn set_editor_height(&mut self, height: u16) {
+        self.editor_height = height;
+    }
+
+    /// Check if the document is shown in read-only preview
+    pub fn is_preview(&self) -> bool {
+        self.preview_mode
+    }
+
+    /// Open the document read-only in preview mode, with no way back to editing
+    pub fn set_view_only(&mut self) {
+        self.view_only = true;
+        self.preview_mode = true;
+    }
+
+    /// Switch between editing and the read-only preview
+    ///
+    /// The preview starts at the current scroll position. Paging moves the
+    /// cursor along with the top line, so editing resumes where the preview
+    /// was scrolled to.
+    pub fn toggle_preview(&mut self) {
+        if self.view_only {
+            return;
+        }
+        self.cancel_completion();
+        self.outline_focused = false;
+        self.backlinks_focused = false;
+        self.preview_mode = !self.preview_mode;
+        if !self.preview_mode {
+            self.adjust_scroll();
+        }
+    }
+
+    /// Check if focus mode is on
+    pub fn is_focus_mode(&self) -> bool {
+        self.focus_mode
+    }
+
+    /// Width of the centered text column in focus mode
+    pub fn focus_width(&self) -> u16 {
+        self.config.focus.width
+    }
+
+    /// Switch focus mode, which hides the gutter, status bar and panels
+    ///
+    /// Showing a panel again leaves focus mode.
+    pub fn toggle_focus_mode(&mut self) {
+        self.focus_mode = !self.focus_mode;
+        if self.focus_mode {
+            self.show_file_browser = false;
+            self.show_outline = false;
+            self.outline_focused = false;
+            self.show_backlinks = false;
+            self.backlinks_focused = false;
+        }
+        self.adjust_scroll();
+    }
+
+    /// Lines of the paragraph under the cursor, or None unless focus mode dims the rest
+    ///
+    /// A paragraph is the run of non-blank lines around the cursor.
+    pub fn focus_paragraph(&self) -> Option<Range<usize>> {
+        if !self.focus_mode || !self.config.focus.dim {
+            return None;
+        }
+        let is_blank = |line: usize| self.buffer.line(line).is_none_or(|l| l.trim().is_empty());
+        let cursor_line = self.buffer.cursor().line;
+        if is_blank(cursor_line) {
+            return Some(cursor_line..cursor_line + 1);
+        }
+        let start = (0..cursor_line)
+            .rev()
+            .find(|&line| is_blank(line))
+            .map_or(0, |line| line + 1);
+        let end = (cursor_line + 1..self.buffer.line_count())
+            .find(|&line| is_blank(line))
+            .unwrap_or(self.buffer.line_count());
+        Some(start..end)
+    }
+
+    /// Selected text from its start up to its end, if anything is selected
+    pub fn selection(&self) -> Option<(Cursor, Cursor)> {
+        let anchor = self.selection_anchor?;
+        let cursor = *self.buffer.cursor();
+        let (start, end) = if (anchor.line, anchor.column) <= (cursor.line, cursor.column) {
+            (anchor, cursor)
+        } else {
+            (cursor, anchor)
+        };
+        ((start.line, start.column) != (end.line, end.column)).then_some((start, end))
+    }
+
+    /// Counts of the document's prose, or None for code files
+    pub fn document_stats(&self) -> Option<TextStats> {
+        StatsCache::get(
+            &self.stats_cache.document,
+            self.buffer.revision(),
+            || match self.buffer.file_type() {
+                FileType::Markdown => self.buffer.markdown().map(stats::markdown_stats),
+                FileType::PlainText => Some(stats::plain_text_stats(self.buffer.lines())),
+                FileType::Code(_) => None,
+            },
+        )
+    }
+
+    /// Counts of the selected text, or None without a selection or in code files
+    ///
+    /// Selected Markdown is parsed on its own, so markup is not counted.
+    pub fn selection_stats(&self) -> Option<TextStats> {
+        let selection = self.selection();
+        StatsCache::get(
+            &self.stats_cache.selection,
+            (self.buffer.revision(), selection),
+            || {
+                let (start, end) = selection?;
+                let text = self.buffer.text_between(start, end);
+                match self.buffer.file_type() {
+                    FileType::Markdown => Some(match text.parse::<mq_markdown::Markdown>() {
+                        Ok(markdown) => stats::markdown_stats(&markdown),
+                        Err(_) => stats::plain_text_stats(&text.lines().collect::<Vec<_>>()),
+                    }),
+                    FileType::PlainText => {
+                        Some(stats::plain_text_stats(&text.lines().collect::<Vec<_>>()))
+                    }
+                    FileType::Code(_) => None,
+                }
+            },
+        )
+    }
+
+    /// Counts of each heading section of a Markdown document
+    pub fn section_stats(&self) -> Vec<SectionStats> {
+        StatsCache::get(&self.stats_cache.sections, self.buffer.revision(), || {
+            self.buffer
+                .markdown()
+                .filter(|_| matches!(self.buffer.file_type(), FileType::Markdown))
+                .map(stats::section_stats)
+                .unwrap_or_default()
+        })
+    }
+
+    /// Check if the statistics dialog is visible
+    pub fn show_stats_dialog(&self) -> bool {
+        self.show_stats_dialog
+    }
+
+    /// Selected section in the statistics dialog
+    pub fn stats_selected(&self) -> usize {
+        self.stats_selected
+    }
+
+    /// Open the statistics dialog, selecting the section under the cursor
+    pub fn open_stats_dialog(&mut self) {
+        if self.document_stats().is_none() {
+            self.set_status_message("No statistics for code files".to_string());
+            return;
+        }
+        let cursor_line = self.buffer.cursor().line;
+        self.stats_selected = self
+            .section_stats()
+            .iter()
+            .rposition(|section| section.line <= cursor_line)
+            .unwrap_or(0);
+        self.show_stats_dialog = true;
+    }
+
+    /// Handle keyboard input when the statistics dialog is visible
+    fn handle_stats_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc => self.show_stats_dialog = false,
+            KeyCode::Up => self.stats_selected = self.stats_selected.saturating_sub(1),
+            KeyCode::Down => {
+                let last = self.section_stats().len().saturating_sub(1);
+                self.stats_selected = (self.stats_selected + 1).min(last);
+            }
+            KeyCode::Enter => {
+                // Jump to the selected section and close the dialog
+                if let Some(line) = self
+                    .section_stats()
+                    .get(self.stats_selected)
+                    .map(|section| section.line)
+                {
+                    self.push_navigation_location();
+                    *self.buffer.cursor_mut() = Cursor::with_position(
+                        line.min(self.buffer.line_count().saturating_sub(1)),
+                        0,
+                    );
+                    self.reveal_cursor();
+                    self.adjust_scroll();
+                    self.push_navigation_location();
+                }
+                self.show_stats_dialog = false;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Screen rows in a preview page
+    fn preview_page(&self) -> usize {
+        match self.editor_height {
+            0 => 20,
+            height => height as usize,
+        }
+    }
+
+    /// Screen rows of each visible line in `start..end` as the preview shows them
+    fn preview_line_rows(&self, start: usize, end: usize) -> Vec<(usize, usize)> {
+        line_rows(
+            &self.buffer,
+            &self.markdown_renderer,
+            &self.image_manager,
+            &self.folds,
+            start..end,
+            self.line_layout(true),
+        )
+    }
+
+    /// First visible line at or before `line`, going back `count` visible lines
+    fn visible_lines_before(&self, line: usize, count: usize) -> usize {
+        let mut line = line;
+        for _ in 0..count {
+            if line == 0 {
+                break;
+            }
+            line = self.folds.prev_visible(line - 1);
+        }
+        line
+    }
+
+    /// Lowest top line that still fills the page, so the end of the document
+    /// stays at the bottom
+    fn preview_max_top(&self) -> usize {
+        let line_count = self.buffer.line_count();
+        let page = self.preview_page();
+        // Every line takes at least one row, so a page of lines back is enough
+        let start = self.visible_lines_before(line_count, page);
+        let mut top = start;
+        let mut rows = 0;
+        for (line, height) in self.preview_line_rows(start, line_count).into_iter().rev() {
+            if rows > 0 && rows + height > page {
+                break;
+            }
+            rows += height;
+            top = line;
+        }
+        top
+    }
+
+    /// Scroll the preview so `line` is at the top, keeping the cursor on it
+    fn preview_scroll_to(&mut self, line: usize) {
+        let top = self.folds.prev_visible(line.min(self.preview_max_top()));
+        self.scroll_offset = top;
+        *self.buffer.cursor_mut() = Cursor::with_position(top, 0);
+    }
+
+    /// Scroll the preview down by up to `count` screen rows
+    ///
+    /// The new top line is the last one that started within those rows, so a
+    /// tall image or table is never skipped before it was on screen; at least
+    /// one line is scrolled.
+    fn preview_scroll_down(&mut self, count: usize) {
+        let top = self.scroll_offset;
+        let end = self
+            .folds
+            .visible_end(top, count + 2, self.buffer.line_count());
+        let mut target = top;
+        let mut rows = 0;
+        for (idx, (line, height)) in self.preview_line_rows(top, end).into_iter().enumerate() {
+            if idx > 1 && rows > count {
+                break;
+            }
+            if idx > 0 {
+                target = line;
+            }
+            rows += height;
+        }
+        self.preview_scroll_to(target);
+    }
+
+    /// Scroll the preview up by up to `count` screen rows, at least one line
+    fn preview_scroll_up(&mut self, count: usize) {
+        let top = self.scroll_offset;
+        let start = self.visible_lines_before(top, count);
+        let mut target = top;
+        let mut rows = 0;
+        for (idx, (line, height)) in self
+            .preview_line_rows(start, top)
+            .into_iter()
+            .rev()
+            .enumerate()
+        {
+            rows += height;
+            if idx > 0 && rows > count {
+                break;
+            }
+            target = line;
+        }
+        self.preview_scroll_to(target);
+    }
+
+    /// Scroll the preview to the next heading below the top line, or the
+    /// previous one above it
+    fn preview_jump_heading(&mut self, forward: bool) {
+        let Some(markdown) = self.buffer.markdown() else {
+            return;
+        };
+        let top = self.scroll_offset;
+        let headings = outline::markdown_outline(markdown);
+        let target = if forward {
+            headings.iter().find(|entry| entry.line > top)
+        } else {
+            headings.iter().rev().find(|entry| entry.line < top)
+        };
+        if let Some(entry) = target {
+            let line = entry.line;
+            self.folds.reveal(line);
+            self.preview_scroll_to(line);
+        }
+    }
+
+    /// Handle key input in preview mode, where the document can't be edited
+    fn handle_preview_key(&mut self, key: KeyEvent) -> Result<()> {
+        let page = self.preview_page();
+        match key.code {
+            KeyCode::Char(' ') | KeyCode::Char('f') | KeyCode::PageDown => {
+                self.preview_scroll_down(page.saturating_sub(1).max(1))
+            }
+            KeyCode::Char('b') | KeyCode::PageUp => {
+                self.preview_scroll_up(page.saturating_sub(1).max(1))
+            }
+            KeyCode::Char('d') => self.preview_scroll_down((page / 2).max(1)),
+            KeyCode::Char('u') => self.preview_scroll_up((page / 2).max(1)),
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => self.preview_scroll_down(1),
+            KeyCode::Char('k') | KeyCode::Up => self.preview_scroll_up(1),
+            KeyCode::Char('g') | KeyCode::Home => self.preview_scroll_to(0),
+            KeyCode::Char('G') | KeyCode::End => self.preview_scroll_to(self.buffer.line_count()),
+            KeyCode::Char(']') => self.preview_jump_heading(true),
+            KeyCode::Char('[') => self.preview_jump_heading(false),
+            KeyCode::Char('/') => self.open_search(),
+            KeyCode::Char('n') => self.search_next(),
+            KeyCode::Char('N') => self.search_prev(),
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if self.view_only {
+                    self.should_quit = true;
+                } else {
+                    self.toggle_preview();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Table rendering options for the editor pane
+    pub fn table_options(&self) -> TableOptions {
+        // The match gutter takes the rightmost column while searching
+        let match_gutter = (self.show_search_dialog
+            && !self.search_results.is_empty()
+            && self.editor_width > 1) as u16;
+        let width = self
+            .editor_width
+            .saturating_sub(match_gutter)
+            .saturating_sub(self.line_number_gutter_width());
+        TableOptions {
+            overflow: self.config.editor.table_overflow,
+            width: width as usize,
+        }
+    }
+
+    /// How the editor lays out lines on screen rows
+    ///
+    /// Lines wrap to the text column in focus mode.
+    pub fn line_layout(&self, preview: bool) -> LineLayout {
+        let table_options = self.table_options();
+        LineLayout {
+            table_options,
+            preview,
+            partial_wysiwyg: self.partial_wysiwyg,
+            wrap: self.focus_mode.then_some(table_options.width),
+        }
+    }
+
+    /// Display width of the cursor line text before the cursor
+    ///
+    /// With partial WYSIWYG the cursor line is partly rendered, so the cursor
+    /// column is mapped through the rendered text instead of the source.
+    pub fn cursor_display_width(&self) -> usize {
+        let cursor = self.buffer.cursor();
+        if self.partial_wysiwyg
+            && matches!(self.buffer.document_type(), DocumentType::Markdown { .. })
+            && let Some(line) =
+                self.markdown_renderer
+                    .render_partial_line(&self.buffer, cursor.line, cursor.column)
+        {
+            return line.cursor_width;
+        }
+        self.buffer
+            .display_width_to_column(cursor.line, cursor.column)
+    }
+
+    /// Row of the cursor within its wrapped line and its display column on that row
+    fn cursor_wrap_position(&self) -> (usize, usize) {
+        let width = self.cursor_display_width();
+        // In preview the cursor stays at the start of its line
+        let Some(wrap) = self.line_layout(false).wrap.filter(|_| !self.preview_mode) else {
+            return (0, width);
+        };
+        let text = cursor_line_text(&self.buffer, &self.markdown_renderer, self.partial_wysiwyg);
+        let starts = wrap_columns(&text, wrap);
+        let row = starts
+            .iter()
+            .rposition(|&start| start <= width)
+            .unwrap_or(0);
+        (row, width - starts[row])
+    }
+
+    /// Screen column of the cursor relative to the start of the line text
+    ///
+    /// Differs from `cursor_display_width` when the cursor line wraps.
+    pub fn cursor_screen_column(&self) -> usize {
+        self.cursor_wrap_position().1
+    }
+
+    /// Screen row of the cursor relative to the top of the editor
+    ///
+    /// Differs from `cursor.line - scroll_offset` when images above the cursor
+    /// take extra rows or lines wrap.
+    pub fn cursor_screen_row(&self) -> usize {
+        let cursor_line = self.buffer.cursor().line;
+        screen_rows(
+            &self.buffer,
+            &self.markdown_renderer,
+            &self.image_manager,
+            &self.folds,
+            self.scroll_offset..cursor_line,
+            self.line_layout(self.preview_mode),
+        ) + self.cursor_wrap_position().0
+    }
+
+    /// Calculate the width of line number gutter (including separator)
+    pub fn line_number_gutter_width(&self) -> u16 {
+        if self.show_line_numbers() {
+            let total_lines = self.buffer.line_count();
+            let digits = if total_lines == 0 {
+                1
+            } else {
+                ((total_lines as f64).log10().floor() as usize) + 1
+            }
+            .max(3);
+            // digits + " │ " (3 characters: space + vertical bar + space)
+            (digits + 3) as u16
+        } else {
+            0
+        }
+    }
+
+    /// Foldable ranges of the document and the folded ones
+    pub fn folds(&self) -> &Folds {
+        &self.folds
+    }
+
+    /// Recompute fold ranges after the buffer changed
+    ///
+    /// Folds hiding the cursor line are opened, so edits, search and goto line
+    /// never leave the cursor on a hidden line.
+    fn sync_folds(&mut self) {
+        self.folds
+            .sync(self.buffer.lines(), self.buffer.document_type());
+        self.reveal_cursor();
+    }
+
+    /// Unfold the ranges hiding the cursor line
+    fn reveal_cursor(&mut self) {
+        if self.folds.reveal(self.buffer.cursor().line) {
+            self.save_folds();
+            self.adjust_scroll();
+        }
+    }
+
+    /// Fold or unfold the range under the cursor
+    pub fn toggle_fold(&mut self) {
+        let cursor_line = self.buffer.cursor().line;
+        let Some(header) = self.folds.toggle(cursor_line, self.buffer.lines()) else {
+            self.set_status_message("Nothing to fold here".to_string());
+            return;
+        };
+        // Folding from inside a range moves the cursor to its header
+        if header != cursor_line {
+            *self.buffer.cursor_mut() = Cursor::with_position(header, 0);
+        }
+        self.save_folds();
+        self.adjust_scroll();
+    }
+
+    /// Unfold every folded range
+    pub fn unfold_all(&mut self) {
+        if self.folds.unfold_all() {
+            self.save_folds();
+        }
+    }
+
+    /// Save the folded lines of the current file
+    fn save_folds(&mut self) {
+        if let Some(path) = self.buffer.file_path()
+            && self.fold_store.set(path, self.folds.folded())
+        {
+            let _ = self.fold_store.save();
+        }
+    }
+
+    /// Move the cursor, stepping over folded lines in the direction of movement
+    fn move_cursor_over_folds(&mut self, movement: CursorMovement) {
+        self.buffer.move_cursor(movement);
+        let line = self.buffer.cursor().line;
+        if !self.folds.is_hidden(line) {
+            return;
+        }
+
+        let forward = matches!(
+            movement,
+            CursorMovement::Down | CursorMovement::Right | CursorMovement::PageDown
+        );
+        let next = self.folds.next_visible(line);
+        // A fold reaching the end of the document keeps the cursor on its header
+        let target = if forward && next < self.buffer.line_count() {
+            next
+        } else {
+            self.folds.prev_visible(line)
+        };
+        let line_len = self.buffer.line(target).map_or(0, |l| l.chars().count());
+        let cursor = self.buffer.cursor_mut();
+        cursor.line = target;
+        match movement {
+            CursorMovement::Left | CursorMovement::Right => {
+                // Land where the line boundary was crossed
+                cursor.column = if target > line { 0 } else { line_len };
+                cursor.update_desired_column();
+            }
+            _ => cursor.column = cursor.desired_column.min(line_len),
+        }
+    }
+
+    /// Insert a table of contents at the cursor, or refresh the existing one
+    pub fn insert_toc(&mut self) {
+        let Some(markdown) = self.buffer.markdown() else {
+            self.set_status_message("Table of contents needs a Markdown file".to_string());
+            return;
+        };
+
+        if toc::find_toc(self.buffer.lines()).is_some() {
+            let message = if self.refresh_toc() {
+                "Table of contents updated"
+            } else {
+                "Table of contents is up to date"
+            };
+            self.set_status_message(message.to_string());
+            return;
+        }
+
+        let mut block = toc::toc_block(markdown, self.config.toc_options());
+        let line = self.buffer.cursor().line;
+        let current = self.buffer.line(line).unwrap_or_default().to_string();
+        // Keep the cursor line below the table unless it is blank
+        if !current.trim().is_empty() {
+            block.push(String::new());
+            block.push(current);
+        }
+        if self.buffer.replace_lines(line, line + 1, &block) {
+            self.adjust_scroll();
+            self.notify_lsp_document_change();
+            self.set_status_message("Inserted table of contents".to_string());
+        }
+    }
+
+    /// Regenerate the table of contents between the toc markers from the headings
+    /// Returns true if it changed
+    fn refresh_toc(&mut self) -> bool {
+        let Some(markdown) = self.buffer.markdown() else {
+            return false;
+        };
+        let Some((start, end)) = toc::find_toc(self.buffer.lines()) else {
+            return false;
+        };
+        let block = toc::toc_block(markdown, self.config.toc_options());
+        if self.buffer.lines()[start..=end] == block[..] {
+            return false;
+        }
+
+        // Lines below the table move with it
+        let cursor = *self.buffer.cursor();
+        self.buffer.replace_lines(start, end + 1, &block);
+        if cursor.line > end {
+            let line = (cursor.line + block.len()).saturating_sub(end + 1 - start);
+            *self.buffer.cursor_mut() = Cursor::with_position(line, cursor.column);
+        }
+        self.sync_folds();
+        self.notify_lsp_document_change();
+        true
+    }
+
+    /// Refresh the table of contents before saving, if enabled
+    fn update_toc_on_save(&mut self) {
+        if self.config.toc.update_on_save {
+            self.refresh_toc();
+        }
+    }
+
+    /// Format the document, with the built-in formatter for Markdown and the
+    /// language server for other files
+    pub fn format_document(&mut self) -> Result<()> {
+        if self.preview_mode {
+            self.set_status_message("Preview is read-only".to_string());
+            return Ok(());
+        }
+        if matches!(self.buffer.file_type(), FileType::Markdown) {
+            let message = if self.format_markdown() {
+                "Formatted document"
+            } else {
+                "Document is already formatted"
+            };
+            self.set_status_message(message.to_string());
+            return Ok(());
+        }
+
+        let language_id = file_type_to_language_id(self.buffer.file_type());
+        let Some(ref mut lsp) = self.lsp_manager else {
+            self.set_status_message("LSP not initialized".to_string());
+            return Ok(());
+        };
+        let Some(file_path) = self.buffer.file_path() else {
+            self.set_status_message("No file open".to_string());
+            return Ok(());
+        };
+        let Some(lang_id) = language_id.filter(|lang_id| lsp.is_enabled(lang_id)) else {
+            self.set_status_message("No formatter for this file type".to_string());
+            return Ok(());
+        };
+
+        let path_buf = file_path.to_path_buf();
+        lsp.request_formatting(&lang_id, &path_buf)
+            .map_err(|e| miette::miette!("Failed to request formatting: {}", e))
     }
 
-    /// Toggle file browser visibility
-    pub fn toggle_file_browser(&mut self) {
-        self.show_file_browser = !self.show_file_browser;
-
-        // Initialize file tree if it doesn't exist
-        if self.show_file_browser && self.file_tree.is_none() {
-            self.file_tree = Some(FileTree::new(&self.current_dir));
+    /// Rewrite the Markdown document in the configured style as one undoable
+    /// edit, returning whether anything changed
+    fn format_markdown(&mut self) -> bool {
+        let content = self.buffer.content();
+        let formatted = format::format(&content, &self.config.editor.format_options());
+        if formatted == content {
+            return false;
         }
+
+        // A final newline becomes an empty last line, which saving writes back
+        let lines: Vec<String> = formatted.split('\n').map(str::to_string).collect();
+        self.buffer
+            .replace_lines(0, self.buffer.line_count(), &lines);
+        self.clamp_cursor();
+        self.sync_folds();
+        self.notify_lsp_document_change();
+        true
     }
 
-    /// Check if line numbers are visible
-    pub fn show_line_numbers(&self) -> bool {
-        self.show_line_numbers
+    /// Format Markdown documents before saving, if enabled
+    fn format_on_save(&mut self) {
+        if self.config.editor.format_on_save
+            && !self.preview_mode
+            && matches!(self.buffer.file_type(), FileType::Markdown)
+        {
+            self.format_markdown();
+        }
     }
 
-    /// Toggle line numbers visibility
-    pub fn toggle_line_numbers(&mut self) {
-        self.show_line_numbers = !self.show_line_numbers;
+    /// Open the front matter form for the current Markdown document
+    pub fn open_front_matter_form(&mut self) {
+        if self.preview_mode {
+            self.set_status_message("Preview is read-only".to_string());
+            return;
+        }
+        if !matches!(self.buffer.file_type(), FileType::Markdown) {
+            self.set_status_message("Front matter needs a Markdown file".to_string());
+            return;
+        }
+        if self.config.front_matter.fields.is_empty() {
+            self.set_status_message("No front matter fields configured".to_string());
+            return;
+        }
+        match FrontMatterForm::new(self.buffer.lines(), &self.config.front_matter) {
+            Ok(form) => self.front_matter_form = Some(form),
+            Err(e) => self.set_status_message(format!("{}", e)),
+        }
     }
 
-    /// Check if current line highlight is visible
-    pub fn show_current_line_highlight(&self) -> bool {
-        self.show_current_line_highlight
+    /// Front matter form, if the dialog is open
+    pub fn front_matter_form(&self) -> Option<&FrontMatterForm> {
+        self.front_matter_form.as_ref()
     }
 
-    /// Toggle current line highlight visibility
-    pub fn toggle_current_line_highlight(&mut self) {
-        self.show_current_line_highlight = !self.show_current_line_highlight;
+    /// Write the form's fields into the front matter block
+    fn apply_front_matter_form(&mut self) {
+        let Some(form) = self.front_matter_form.take() else {
+            return;
+        };
+        let Some((end, mut block)) = form.apply(self.buffer.lines()) else {
+            return;
+        };
+
+        // A new block goes above the first line, which is replaced along with it
+        let replaced = end.max(1);
+        if end == 0 {
+            block.push(self.buffer.line(0).unwrap_or_default().to_string());
+        }
+        let cursor = *self.buffer.cursor();
+        if !self.buffer.replace_lines(0, replaced, &block) {
+            return;
+        }
+        if cursor.line >= end {
+            let line = (cursor.line + block.len()).saturating_sub(replaced);
+            *self.buffer.cursor_mut() = Cursor::with_position(line, cursor.column);
+        }
+        self.adjust_scroll();
+        self.sync_folds();
+        self.notify_lsp_document_change();
+        self.set_status_message("Front matter updated".to_string());
     }
 
-    /// Calculate the width of line number gutter (including separator)
-    pub fn line_number_gutter_width(&self) -> u16 {
-        if self.show_line_numbers {
-            let total_lines = self.buffer.line_count();
-            let digits = if total_lines == 0 {
-                1
-            } else {
-                ((total_lines as f64).log10().floor() as usize) + 1
+    /// Handle key input while the front matter form is open
+    fn handle_front_matter_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(form) = self.front_matter_form.as_mut() else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.front_matter_form = None,
+            KeyCode::Enter => self.apply_front_matter_form(),
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::Backspace => form.delete_char(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                form.insert_char(c)
             }
-            .max(3);
-            // digits + " │ " (3 characters: space + vertical bar + space)
-            (digits + 3) as u16
-        } else {
-            0
+            _ => {}
         }
+        Ok(())
     }
 
     /// Open file from path
     pub fn open_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
         let path = path.as_ref();
-        self.buffer = DocumentBuffer::from_file(path)?;
+        let buffer = DocumentBuffer::from_file(path)?;
+        self.save_folds();
+        self.buffer = buffer;
+        self.selection_anchor = None;
+        self.stats_cache = StatsCache::default();
+        self.markdown_renderer = MarkdownRenderer::new();
+        self.folds = Folds::with_folded(self.fold_store.get(path));
+        self.folds
+            .sync(self.buffer.lines(), self.buffer.document_type());
+        self.outline = Outline::new();
 
         if let Some(parent) = path.parent() {
             self.current_dir = parent.to_path_buf();
@@ -504,6 +1680,12 @@ impl App {
             }
         }
 
+        if self.show_outline {
+            self.refresh_outline();
+        }
+        self.backlinks_selected = 0;
+        self.refresh_backlinks();
+
         Ok(())
     }
 
@@ -519,6 +1701,21 @@ impl App {
         // Process events without holding the lsp_manager borrow
         for (_language_id, event) in events {
             match event {
+                LspEvent::Formatting(edits) => {
+                    if edits.is_empty() {
+                        self.set_status_message("Document is already formatted".to_string());
+                    } else {
+                        self.apply_text_edits(edits);
+                        self.set_status_message("Formatted document".to_string());
+                    }
+                }
+                LspEvent::CodeActions(actions) => {
+                    if actions.is_empty() {
+                        self.set_status_message("No quick fixes at the cursor".to_string());
+                    }
+                    self.code_actions = actions;
+                    self.code_action_selected = 0;
+                }
                 LspEvent::Diagnostics(params) => {
                     // Convert LSP diagnostics to our format and update the manager
                     self.diagnostics_manager.update(params.diagnostics);
@@ -557,14 +1754,23 @@ impl App {
                     }
 
                     // Jump to the definition location
-                    if let Err(e) = self.jump_to_definition(response) {
-                        self.set_status_message(format!("Failed to jump to definition: {}", e));
+                    match self.jump_to_definition(response) {
+                        Ok(()) => self.push_navigation_location(),
+                        Err(e) => {
+                            self.set_status_message(format!("Failed to jump to definition: {}", e))
+                        }
                     }
                 }
                 LspEvent::References(_locations) => {
                     // References response received
                     // TODO: Display references list
                 }
+                LspEvent::DocumentSymbols(response) => {
+                    // Markdown outlines are built from the buffer's own AST
+                    if self.buffer.markdown().is_none() {
+                        self.outline.set_entries(outline::symbol_outline(response));
+                    }
+                }
                 LspEvent::Initialized(trigger_chars) => {
                     // Server initialized - save trigger characters and request semantic tokens
                     if let Some(ref mut lsp) = self.lsp_manager {
@@ -584,6 +1790,11 @@ impl App {
                             if let Err(e) = lsp.request_semantic_tokens(lang_id, &path_buf) {
                                 eprintln!("Failed to request semantic tokens: {}", e);
                             }
+                            if self.show_outline
+                                && let Err(e) = lsp.request_document_symbols(lang_id, &path_buf)
+                            {
+                                eprintln!("Failed to request document symbols: {}", e);
+                            }
                         }
                     }
                 }
@@ -613,11 +1824,32 @@ impl App {
             if let Err(e) = lsp.request_semantic_tokens(lang_id, &path_buf) {
                 eprintln!("Failed to request semantic tokens: {}", e);
             }
+
+            // Keep the outline of code files up to date
+            if self.show_outline
+                && self.buffer.markdown().is_none()
+                && let Err(e) = lsp.request_document_symbols(lang_id, &path_buf)
+            {
+                eprintln!("Failed to request document symbols: {}", e);
+            }
         }
     }
 
     /// Request go to definition at current cursor position
     pub fn request_go_to_definition(&mut self) -> Result<()> {
+        // Following a wiki link to a missing page offers to create it
+        if let Some((page, path)) = self.unresolved_wiki_page() {
+            if path.starts_with(link_index::normalize_path(self.workspace_root())) {
+                self.pending_new_page = Some((page, path));
+            } else {
+                self.set_status_message(format!(
+                    "Page '{}' would be created outside the workspace",
+                    page
+                ));
+            }
+            return Ok(());
+        }
+
         let language_id = file_type_to_language_id(self.buffer.file_type());
         if let Some(ref mut lsp) = self.lsp_manager {
             if let Some(file_path) = self.buffer.file_path() {
@@ -652,6 +1884,213 @@ impl App {
         Ok(())
     }
 
+    /// Wiki link page under the cursor with the file it would be created as,
+    /// if no such page exists in the workspace
+    fn unresolved_wiki_page(&self) -> Option<(String, PathBuf)> {
+        if !matches!(self.buffer.file_type(), FileType::Markdown) {
+            return None;
+        }
+        let cursor = self.buffer.cursor();
+        let line = self.buffer.line(cursor.line)?;
+        let offset = line
+            .char_indices()
+            .nth(cursor.column)
+            .map_or(line.len(), |(i, _)| i);
+        let link = wiki_link::wiki_link_at(line, offset)?;
+        if link.page.is_empty() {
+            return None;
+        }
+
+        let root = self.workspace_root();
+        let pages = wiki_link::workspace_pages(root);
+        if wiki_link::resolve_page(&pages, root, &link.page).is_some() {
+            return None;
+        }
+
+        // Pages are looked up without their extension, so `[[Notes.md]]` is Notes.md
+        let page = link.page.trim_end_matches(".md").to_string();
+        let dir = self
+            .buffer
+            .file_path()
+            .and_then(Path::parent)
+            .unwrap_or(&self.current_dir);
+        let path = link_index::normalize_path(&dir.join(format!("{}.md", page)));
+        Some((page, path))
+    }
+
+    /// Prompt shown while confirming the creation of a missing wiki link page
+    pub fn new_page_prompt(&self) -> Option<String> {
+        self.pending_new_page.as_ref().map(|(page, path)| {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            format!(
+                "Page '{}' does not exist. Create {}? (y/n)",
+                page, file_name
+            )
+        })
+    }
+
+    /// Create the pending wiki link page with a title heading and open it
+    fn create_pending_page(&mut self) -> Result<()> {
+        let Some((page, path)) = self.pending_new_page.take() else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                miette::miette!("Failed to create directory {}: {}", parent.display(), e)
+            })?;
+        }
+        let title = page.rsplit('/').next().unwrap_or(&page);
+        std::fs::write(&path, format!("# {}\n", title))
+            .map_err(|e| miette::miette!("Failed to create {}: {}", path.display(), e))?;
+
+        self.push_navigation_location();
+        self.open_file(&path)?;
+        self.push_navigation_location();
+        Ok(())
+    }
+
+    /// Record the cursor location in the navigation history
+    ///
+    /// Jumps record both their origin and their destination, so that Ctrl+B
+    /// can step back from the destination.
+    fn push_navigation_location(&mut self) {
+        if let Some(file_path) = self.buffer.file_path() {
+            let cursor = self.buffer.cursor();
+            self.navigation_history.push(FileLocation::new(
+                file_path.to_path_buf(),
+                cursor.line,
+                cursor.column,
+            ));
+        }
+    }
+
+    /// Follow the link or image under the cursor in a Markdown document
+    ///
+    /// Markdown and code files open in the editor (at the heading of a
+    /// `#anchor`), anchors jump within the document, and URLs and other files
+    /// are handed to the configured opener. Wiki links go through go-to-definition.
+    pub fn follow_link(&mut self) -> Result<()> {
+        if !matches!(self.buffer.file_type(), FileType::Markdown) {
+            self.set_status_message("Links can only be followed in Markdown files".to_string());
+            return Ok(());
+        }
+
+        let cursor = self.buffer.cursor();
+        let line = self.buffer.line(cursor.line).unwrap_or_default();
+        let column = line
+            .char_indices()
+            .nth(cursor.column)
+            .map_or(line.len(), |(i, _)| i);
+        if wiki_link::wiki_link_at(line, column).is_some() {
+            return self.request_go_to_definition();
+        }
+
+        let Some(url) = self
+            .buffer
+            .markdown()
+            .and_then(|markdown| link::link_url_at(markdown, cursor.line, column))
+        else {
+            self.set_status_message("No link under the cursor".to_string());
+            return Ok(());
+        };
+        let base_dir = self
+            .buffer
+            .file_path()
+            .and_then(Path::parent)
+            .unwrap_or(&self.current_dir)
+            .to_path_buf();
+
+        match LinkTarget::parse(&url, &base_dir) {
+            Some(LinkTarget::External(url)) => self.open_externally(&url),
+            Some(LinkTarget::Anchor(anchor)) => self.jump_to_anchor(&anchor),
+            Some(LinkTarget::File { path, anchor }) => {
+                if !path.exists() {
+                    self.set_status_message(format!("File not found: {}", path.display()));
+                } else if matches!(
+                    FileType::from_path(&path),
+                    FileType::Markdown | FileType::Code(_)
+                ) {
+                    // Find the heading first, so the jump is recorded once
+                    let line = anchor
+                        .as_deref()
+                        .and_then(|anchor| self.file_heading_line(&path, anchor));
+                    self.push_navigation_location();
+                    self.jump_to_location(&path, line.unwrap_or(0), 0)?;
+                    self.push_navigation_location();
+                    if let (Some(anchor), None) = (anchor, line) {
+                        self.set_status_message(format!("Heading not found: #{}", anchor));
+                    }
+                } else {
+                    self.open_externally(&path.to_string_lossy());
+                }
+            }
+            None => self.set_status_message("No link under the cursor".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Move the cursor to the heading an anchor refers to, recording the jump
+    fn jump_to_anchor(&mut self, anchor: &str) {
+        let Some(line) = self
+            .buffer
+            .markdown()
+            .and_then(|markdown| link::heading_line(markdown, anchor))
+        else {
+            self.set_status_message(format!("Heading not found: #{}", anchor));
+            return;
+        };
+
+        self.push_navigation_location();
+        *self.buffer.cursor_mut() = Cursor::with_position(line, 0);
+        self.reveal_cursor();
+        self.adjust_scroll();
+        self.push_navigation_location();
+    }
+
+    /// Line of the heading an anchor refers to in a Markdown file, read from
+    /// disk unless it is the open document
+    fn file_heading_line(&self, path: &Path, anchor: &str) -> Option<usize> {
+        if self.buffer.file_path() == Some(path) {
+            return self
+                .buffer
+                .markdown()
+                .and_then(|markdown| link::heading_line(markdown, anchor));
+        }
+        if !matches!(FileType::from_path(path), FileType::Markdown) {
+            return None;
+        }
+        let markdown = std::fs::read_to_string(path)
+            .ok()?
+            .parse::<mq_markdown::Markdown>()
+            .ok()?;
+        link::heading_line(&markdown, anchor)
+    }
+
+    /// Open a URL or file with the configured opener command
+    fn open_externally(&mut self, target: &str) {
+        let command = self.config.links.opener_command();
+        let Some((program, args)) = command.split_first() else {
+            self.set_status_message("No opener command configured".to_string());
+            return;
+        };
+
+        let result = std::process::Command::new(program)
+            .args(args)
+            .arg(target)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+        match result {
+            Ok(mut child) => {
+                // Reap the opener once it exits so it does not linger as a zombie
+                std::thread::spawn(move || child.wait());
+                self.set_status_message(format!("Opened {}", target));
+            }
+            Err(e) => self.set_status_message(format!("Failed to run {}: {}", program, e)),
+        }
+    }
+
     /// Jump to a definition response location
     fn jump_to_definition(&mut self, response: lsp_types::GotoDefinitionResponse) -> Result<()> {
         use lsp_types::GotoDefinitionResponse;
@@ -670,9 +2109,15 @@ impl App {
         };
 
         if let Some(loc) = location {
-            // Convert URI to file path - strip file:// prefix if present
-            let path_str = loc.uri.path().as_str();
-            let path = PathBuf::from(path_str);
+            // Convert URI to file path, decoding escapes such as %20
+            let path = PathBuf::from(
+                loc.uri
+                    .path()
+                    .as_estr()
+                    .decode()
+                    .into_string_lossy()
+                    .as_ref(),
+            );
 
             // Jump to the location
             self.jump_to_location(
@@ -709,6 +2154,7 @@ impl App {
         let cursor = self.buffer.cursor_mut();
         cursor.line = line;
         cursor.column = column;
+        self.reveal_cursor();
         self.adjust_scroll();
 
         Ok(())
@@ -843,6 +2289,167 @@ impl App {
         self.completion_selected = 0;
     }
 
+    /// Request the quick fixes at the cursor, such as spelling corrections
+    pub fn request_code_actions(&mut self) -> Result<()> {
+        if self.preview_mode {
+            self.set_status_message("Preview is read-only".to_string());
+            return Ok(());
+        }
+        let language_id = file_type_to_language_id(self.buffer.file_type());
+        let Some(ref mut lsp) = self.lsp_manager else {
+            self.set_status_message("LSP not initialized".to_string());
+            return Ok(());
+        };
+        let Some(file_path) = self.buffer.file_path() else {
+            self.set_status_message("No file open".to_string());
+            return Ok(());
+        };
+        let Some(lang_id) = language_id.filter(|lang_id| lsp.is_enabled(lang_id)) else {
+            self.set_status_message("LSP not available for this file type".to_string());
+            return Ok(());
+        };
+
+        let cursor = self.buffer.cursor();
+        let path_buf = file_path.to_path_buf();
+        lsp.request_code_actions(
+            &lang_id,
+            &path_buf,
+            cursor.line as u32,
+            cursor.column as u32,
+        )
+        .map_err(|e| miette::miette!("Failed to request code actions: {}", e))
+    }
+
+    /// Quick fixes in the popup, empty when it is closed
+    pub fn code_actions(&self) -> &[CodeActionOrCommand] {
+        &self.code_actions
+    }
+
+    /// Selected quick fix
+    pub fn code_action_selected(&self) -> usize {
+        self.code_action_selected
+    }
+
+    /// Handle keyboard input while the quick fix popup is open
+    fn handle_code_action_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Up => {
+                self.code_action_selected = self
+                    .code_action_selected
+                    .checked_sub(1)
+                    .unwrap_or(self.code_actions.len() - 1);
+            }
+            KeyCode::Down => {
+                self.code_action_selected =
+                    (self.code_action_selected + 1) % self.code_actions.len();
+            }
+            KeyCode::Enter => {
+                let action = self.code_actions.remove(self.code_action_selected);
+                self.code_actions.clear();
+                return self.apply_code_action(action);
+            }
+            _ => self.code_actions.clear(),
+        }
+        Ok(())
+    }
+
+    /// Apply a quick fix's edits to the buffer and run its command
+    fn apply_code_action(&mut self, action: CodeActionOrCommand) -> Result<()> {
+        if self.preview_mode {
+            self.set_status_message("Preview is read-only".to_string());
+            return Ok(());
+        }
+        let command = match action {
+            CodeActionOrCommand::Command(command) => Some(command),
+            CodeActionOrCommand::CodeAction(action) => {
+                // Edits are for the document the action was requested for
+                let edits: Vec<TextEdit> = action
+                    .edit
+                    .and_then(|edit| edit.changes)
+                    .map(|changes| changes.into_values().flatten().collect())
+                    .unwrap_or_default();
+                if !edits.is_empty() {
+                    self.apply_text_edits(edits);
+                }
+                action.command
+            }
+        };
+
+        if let Some(command) = command
+            && let Some(ref mut lsp) = self.lsp_manager
+            && let Some(lang_id) = file_type_to_language_id(self.buffer.file_type())
+        {
+            lsp.execute_command(
+                &lang_id,
+                &command.command,
+                command.arguments.unwrap_or_default(),
+            )
+            .map_err(|e| miette::miette!("Failed to run {}: {}", command.title, e))?;
+        }
+        Ok(())
+    }
+
+    /// Apply text edits from the LSP, last first so earlier positions stay valid
+    fn apply_text_edits(&mut self, mut edits: Vec<TextEdit>) {
+        edits.sort_by_key(|edit| {
+            std::cmp::Reverse((edit.range.start.line, edit.range.start.character))
+        });
+        for edit in edits {
+            let start = edit.range.start;
+            let end = edit.range.end;
+            let (start_line, end_line) = (start.line as usize, end.line as usize);
+            if start_line == end_line && !edit.new_text.contains('\n') {
+                let Some(old_text) = self.buffer.line(start_line).map(|line| {
+                    line.chars()
+                        .skip(start.character as usize)
+                        .take(end.character.saturating_sub(start.character) as usize)
+                        .collect::<String>()
+                }) else {
+                    continue;
+                };
+                self.buffer.replace_at(
+                    start_line,
+                    start.character as usize,
+                    &old_text,
+                    &edit.new_text,
+                );
+            } else if end_line < self.buffer.line_count() {
+                let prefix: String = self
+                    .buffer
+                    .line(start_line)
+                    .unwrap_or_default()
+                    .chars()
+                    .take(start.character as usize)
+                    .collect();
+                let suffix: String = self
+                    .buffer
+                    .line(end_line)
+                    .unwrap_or_default()
+                    .chars()
+                    .skip(end.character as usize)
+                    .collect();
+                let text = format!("{}{}{}", prefix, edit.new_text, suffix);
+                let lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+                self.buffer.replace_lines(start_line, end_line + 1, &lines);
+            }
+        }
+
+        self.clamp_cursor();
+        self.sync_folds();
+        self.notify_lsp_document_change();
+    }
+
+    /// Keep the cursor inside the buffer when the text around it got shorter
+    fn clamp_cursor(&mut self) {
+        let cursor = *self.buffer.cursor();
+        let line = cursor.line.min(self.buffer.line_count().saturating_sub(1));
+        let length = self
+            .buffer
+            .line(line)
+            .map_or(0, |text| text.chars().count());
+        *self.buffer.cursor_mut() = Cursor::with_position(line, cursor.column.min(length));
+    }
+
     /// Check if quit dialog is visible
     pub fn show_quit_dialog(&self) -> bool {
         self.show_quit_dialog
@@ -885,11 +2492,31 @@ impl App {
         self.search_results.len()
     }
 
+    /// Get current search results
+    pub fn search_results(&self) -> &[SearchMatch] {
+        &self.search_results
+    }
+
     /// Get current search index
     pub fn search_current_index(&self) -> Option<usize> {
         self.search_index
     }
 
+    /// Get search options
+    pub fn search_options(&self) -> SearchOptions {
+        self.search_options
+    }
+
+    /// Get search scope
+    pub fn search_scope(&self) -> &SearchScope {
+        &self.search_scope
+    }
+
+    /// Get the error from compiling the current search query, if any
+    pub fn search_error(&self) -> Option<&str> {
+        self.search_error.as_deref()
+    }
+
     /// Get active search field
     pub fn search_active_field(&self) -> SearchField {
         self.search_active_field
@@ -919,6 +2546,7 @@ impl App {
     pub fn open_goto_line_dialog(&mut self) {
         self.show_goto_line_dialog = true;
         self.goto_line_input.clear();
+        self.reset_history_navigation();
     }
 
     /// Close goto line dialog
@@ -953,9 +2581,11 @@ impl App {
         // Move cursor to the line
         self.buffer.cursor_mut().line = target_line;
         self.buffer.cursor_mut().column = 0;
+        self.reveal_cursor();
         self.adjust_scroll();
 
         self.set_status_message(format!("Jumped to line {}", line_number));
+        self.record_history(HistoryKind::GotoLine, self.goto_line_input.clone());
         self.close_goto_line_dialog();
 
         Ok(())
@@ -981,6 +2611,7 @@ impl App {
         self.show_mq_query_dialog = true;
         self.mq_query_input.clear();
         self.mq_query_result = None;
+        self.reset_history_navigation();
     }
 
     /// Close mq query dialog
@@ -999,6 +2630,7 @@ impl App {
 
         let content = self.buffer.content();
         let query = self.mq_query_input.clone();
+        self.record_history(HistoryKind::MqQuery, query.clone());
 
         let mut engine = mq_lang::DefaultEngine::default();
         engine.load_builtin_module();
@@ -1078,9 +2710,12 @@ impl App {
         };
 
         // Save the file
+        self.update_toc_on_save();
+        self.format_on_save();
         self.buffer.save_as(&path)?;
         self.set_status_message(format!("Saved as: {}", path.display()));
         self.close_save_as_dialog();
+        self.update_link_index();
 
         // Update file type based on new extension
         let file_type = FileType::from_path(&path);
@@ -1101,26 +2736,210 @@ impl App {
 
     /// Open search dialog
     pub fn open_search(&mut self) {
+        if self.search_mode == SearchMode::Query {
+            self.search_query.clear();
+        }
         self.show_search_dialog = true;
         self.search_mode = SearchMode::Find;
         self.search_active_field = SearchField::Search;
+        self.update_search_results();
+        self.reset_history_navigation();
+    }
+
+    /// Open the find-by-mq-query dialog
+    pub fn open_query_search(&mut self) {
+        if self.search_mode != SearchMode::Query {
+            self.search_query.clear();
+        }
+        self.show_search_dialog = true;
+        self.search_mode = SearchMode::Query;
+        self.search_active_field = SearchField::Search;
+        self.update_search_results();
+        self.reset_history_navigation();
     }
 
     /// Open replace dialog
     pub fn open_replace(&mut self) {
+        if self.search_mode == SearchMode::Query {
+            self.search_query.clear();
+        }
         self.show_search_dialog = true;
         self.search_mode = SearchMode::Replace;
         self.search_active_field = SearchField::Search;
+        self.update_search_results();
+        self.reset_history_navigation();
+    }
+
+    /// Close search dialog
+    pub fn close_search(&mut self) {
+        self.show_search_dialog = false;
+        let kind = if self.search_mode == SearchMode::Query {
+            HistoryKind::MqQuery
+        } else {
+            HistoryKind::Search
+        };
+        self.record_history(kind, self.search_query.clone());
+        if self.search_mode == SearchMode::Replace {
+            self.record_history(HistoryKind::Replace, self.replace_query.clone());
+        }
+    }
+
+    /// Get the reverse history search prompt, if a search is active
+    pub fn history_search_prompt(&self) -> Option<String> {
+        self.history_search.as_ref().map(|search| search.prompt())
+    }
+
+    /// Record a dialog input in its history and persist it
+    fn record_history(&mut self, kind: HistoryKind, entry: String) {
+        if self.input_history.push(kind, &entry) {
+            // History is a convenience; failing to persist it must not interrupt editing
+            let _ = self.input_history.save();
+        }
+    }
+
+    /// Stop any history navigation or reverse search
+    fn reset_history_navigation(&mut self) {
+        self.history_navigator.reset();
+        self.history_search = None;
+    }
+
+    /// History list of the input being edited in the open dialog
+    fn active_history_kind(&self) -> Option<HistoryKind> {
+        if self.show_search_dialog {
+            Some(match (self.search_mode, self.search_active_field) {
+                (SearchMode::Query, _) => HistoryKind::MqQuery,
+                (_, SearchField::Replace) => HistoryKind::Replace,
+                (_, SearchField::Search) => HistoryKind::Search,
+            })
+        } else if self.show_goto_line_dialog {
+            Some(HistoryKind::GotoLine)
+        } else if self.show_mq_query_dialog {
+            Some(HistoryKind::MqQuery)
+        } else {
+            None
+        }
+    }
+
+    /// Get the input being edited in the open dialog
+    fn history_input(&self) -> &str {
+        if self.show_search_dialog {
+            match self.search_active_field {
+                SearchField::Search => &self.search_query,
+                SearchField::Replace => &self.replace_query,
+            }
+        } else if self.show_goto_line_dialog {
+            &self.goto_line_input
+        } else {
+            &self.mq_query_input
+        }
+    }
+
+    /// Replace the input being edited in the open dialog with a history entry
+    fn set_history_input(&mut self, value: String) {
+        if self.show_search_dialog {
+            match self.search_active_field {
+                SearchField::Search => {
+                    self.search_query = value;
+                    self.update_search_results();
+                }
+                SearchField::Replace => self.replace_query = value,
+            }
+        } else if self.show_goto_line_dialog {
+            self.goto_line_input = value;
+        } else if self.show_mq_query_dialog {
+            self.mq_query_input = value;
+            self.mq_query_result = None;
+        }
+    }
+
+    /// Handle history keys in dialog inputs: Up/Down recall, Ctrl+R reverse search
+    /// Returns true if the key was consumed
+    fn handle_history_key(&mut self, key: KeyEvent) -> bool {
+        let Some(kind) = self.active_history_kind() else {
+            return false;
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        if let Some(mut search) = self.history_search.take() {
+            let found = match key.code {
+                KeyCode::Char('r') if ctrl => search.next(&self.input_history, kind),
+                KeyCode::Char(c) if !ctrl => search.push(c, &self.input_history, kind),
+                KeyCode::Backspace => search.pop(&self.input_history, kind),
+                KeyCode::Esc => {
+                    // Cancel: restore the input from before the search
+                    self.set_history_input(search.original().to_string());
+                    return true;
+                }
+                // Accept the match
+                KeyCode::Enter => return true,
+                // Accept the match and let the dialog handle the key
+                _ => return false,
+            };
+            if let Some(entry) = found {
+                self.set_history_input(entry);
+            }
+            self.history_search = Some(search);
+            return true;
+        }
+
+        let input = self.history_input().to_string();
+        match key.code {
+            KeyCode::Up => {
+                if let Some(entry) = self
+                    .history_navigator
+                    .older(&self.input_history, kind, &input)
+                {
+                    self.set_history_input(entry);
+                }
+                true
+            }
+            KeyCode::Down => {
+                if let Some(entry) = self.history_navigator.newer(&self.input_history, kind) {
+                    self.set_history_input(entry);
+                }
+                true
+            }
+            // Ctrl+R replaces the current match in the replace dialog
+            KeyCode::Char('r')
+                if ctrl
+                    && !(self.show_search_dialog && self.search_mode == SearchMode::Replace) =>
+            {
+                self.history_navigator.reset();
+                self.history_search = Some(ReverseSearch::new(&input));
+                true
+            }
+            _ => {
+                self.history_navigator.reset();
+                false
+            }
+        }
     }
 
-    /// Close search dialog
-    pub fn close_search(&mut self) {
-        self.show_search_dialog = false;
+    /// Compile the current search query with the current options
+    fn search_pattern(&self) -> Option<Result<SearchPattern, String>> {
+        if self.search_query.is_empty() || self.search_mode == SearchMode::Query {
+            return None;
+        }
+        Some(SearchPattern::new(&self.search_query, &self.search_options))
     }
 
     /// Update search results
     fn update_search_results(&mut self) {
-        self.search_results = self.buffer.find_all(&self.search_query);
+        self.search_error = None;
+        let results = if self.search_mode == SearchMode::Query && !self.search_query.is_empty() {
+            Some(self.buffer.search_mq_query(&self.search_query))
+        } else {
+            self.search_pattern()
+                .map(|pattern| pattern.map(|p| self.buffer.search(&p, &self.search_scope)))
+        };
+        self.search_results = match results {
+            Some(Ok(results)) => results,
+            Some(Err(e)) => {
+                self.search_error = Some(e);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
         if self.search_results.is_empty() {
             self.search_index = None;
         } else if self.search_index.is_none()
@@ -1163,21 +2982,77 @@ impl App {
     /// Jump to current match
     fn jump_to_current_match(&mut self) {
         if let Some(idx) = self.search_index
-            && let Some(&(line, column)) = self.search_results.get(idx)
+            && let Some(m) = self.search_results.get(idx)
         {
-            self.buffer.cursor_mut().line = line;
-            self.buffer.cursor_mut().column = column;
+            self.buffer.cursor_mut().line = m.line;
+            self.buffer.cursor_mut().column = m.column;
+            self.buffer.cursor_mut().update_desired_column();
+            self.reveal_cursor();
             self.adjust_scroll();
         }
     }
 
+    /// Select the current match and close the search dialog
+    pub fn select_current_match(&mut self) {
+        let Some(m) = self
+            .search_index
+            .and_then(|idx| self.search_results.get(idx).copied())
+        else {
+            return;
+        };
+        self.close_search();
+        self.select_range((m.line, m.column), (m.end_line, m.end_column));
+    }
+
+    /// Select from `start` up to `end`, leaving the cursor at the end
+    fn select_range(&mut self, start: (usize, usize), end: (usize, usize)) {
+        self.selection_anchor = Some(Cursor::with_position(start.0, start.1));
+        *self.buffer.cursor_mut() = Cursor::with_position(end.0, end.1);
+        self.reveal_cursor();
+        self.adjust_scroll();
+    }
+
+    /// Toggle regex search
+    pub fn toggle_search_regex(&mut self) {
+        self.search_options.regex = !self.search_options.regex;
+        self.update_search_results();
+    }
+
+    /// Cycle case sensitivity (sensitive -> insensitive -> smart)
+    pub fn cycle_search_case_mode(&mut self) {
+        self.search_options.case_mode = self.search_options.case_mode.next();
+        self.update_search_results();
+    }
+
+    /// Toggle whole-word matching
+    pub fn toggle_search_whole_word(&mut self) {
+        self.search_options.whole_word = !self.search_options.whole_word;
+        self.update_search_results();
+    }
+
+    /// Cycle the search scope through the node kinds available in the document
+    pub fn cycle_search_scope(&mut self) {
+        let Some(ast) = self.buffer.document_type().markdown_ast() else {
+            return;
+        };
+        let scopes = SearchScope::available(ast);
+        let next = scopes
+            .iter()
+            .position(|scope| *scope == self.search_scope)
+            .map(|idx| (idx + 1) % scopes.len())
+            .unwrap_or(0);
+        self.search_scope = scopes[next].clone();
+        self.update_search_results();
+    }
+
     /// Replace current match
     pub fn replace_current(&mut self) {
+        let Some(Ok(pattern)) = self.search_pattern() else {
+            return;
+        };
         if let Some(idx) = self.search_index
-            && let Some(&(line, column)) = self.search_results.get(idx)
-            && self
-                .buffer
-                .replace_at(line, column, &self.search_query, &self.replace_query)
+            && let Some(m) = self.search_results.get(idx).copied()
+            && self.buffer.replace_match(&pattern, &m, &self.replace_query)
         {
             self.notify_lsp_document_change();
             self.update_search_results();
@@ -1193,10 +3068,14 @@ impl App {
 
     /// Replace all matches
     pub fn replace_all(&mut self) {
-        let count = self
-            .buffer
-            .replace_all(&self.search_query, &self.replace_query);
+        let Some(Ok(pattern)) = self.search_pattern() else {
+            return;
+        };
+        let count =
+            self.buffer
+                .replace_all_matches(&pattern, &self.search_scope, &self.replace_query);
         if count > 0 {
+            self.adjust_scroll();
             self.notify_lsp_document_change();
             self.set_status_message(format!("Replaced {} occurrences", count));
             self.update_search_results();
@@ -1213,8 +3092,185 @@ impl App {
         }
     }
 
+    /// Table under the cursor, if the cursor is on a table line of a Markdown document
+    fn table_at_cursor(&self) -> Option<TableCursor> {
+        if !matches!(self.buffer.document_type(), DocumentType::Markdown { .. }) {
+            return None;
+        }
+        let cursor = self.buffer.cursor();
+        let lines = self.buffer.lines();
+        let in_code_block = lines[..cursor.line]
+            .iter()
+            .filter(|line| line.trim_start().starts_with("```"))
+            .count()
+            % 2
+            == 1;
+        if in_code_block {
+            return None;
+        }
+
+        let (start, end) = Table::find(lines, cursor.line)?;
+        let table = Table::parse(&lines[start..end])?;
+        let cell = table::cell_at(&lines[cursor.line], cursor.column).min(table.column_count() - 1);
+        Some(TableCursor {
+            start,
+            end,
+            table,
+            row: cursor.line - start,
+            cell,
+        })
+    }
+
+    /// Replace the table lines with the formatted table and move to a cell
+    fn apply_table(&mut self, start: usize, end: usize, table: &Table, row: usize, cell: usize) {
+        let lines = table.format();
+        self.buffer.replace_lines(start, end, &lines);
+
+        let row = row.min(lines.len() - 1);
+        let column = table::cell_start(&lines[row], cell);
+        *self.buffer.cursor_mut() = Cursor::with_position(start + row, column);
+        self.adjust_scroll();
+        self.notify_lsp_document_change();
+    }
+
+    /// Move to the next or previous table cell, reformatting the table
+    ///
+    /// Moving past the last cell adds a row.
+    fn table_move_cell(&mut self, forward: bool) {
+        let Some(TableCursor {
+            start,
+            end,
+            mut table,
+            row,
+            cell,
+        }) = self.table_at_cursor()
+        else {
+            return;
+        };
+        let last_cell = table.column_count() - 1;
+
+        let (row, cell) = if forward {
+            if row != 1 && cell < last_cell {
+                (row, cell + 1)
+            } else {
+                let next = row.max(1) + 1;
+                if next < table.line_count() {
+                    (next, 0)
+                } else {
+                    (table.insert_row(next), 0)
+                }
+            }
+        } else if cell > 0 && row != 1 {
+            (row, cell - 1)
+        } else {
+            match row {
+                0 => (0, 0),
+                1 | 2 => (0, last_cell),
+                _ => (row - 1, last_cell),
+            }
+        };
+        self.apply_table(start, end, &table, row, cell);
+    }
+
+    /// Add a row below the cursor row
+    ///
+    /// Enter on an empty last row removes it and leaves the table on a blank line.
+    fn table_enter(&mut self) {
+        let Some(TableCursor {
+            start,
+            end,
+            mut table,
+            row,
+            ..
+        }) = self.table_at_cursor()
+        else {
+            return;
+        };
+
+        let is_last = row + 1 == table.line_count();
+        if row >= 2
+            && is_last
+            && table
+                .row(row)
+                .is_some_and(|cells| cells.iter().all(String::is_empty))
+        {
+            table.delete_row(row);
+            let mut lines = table.format();
+            lines.push(String::new());
+            self.buffer.replace_lines(start, end, &lines);
+            *self.buffer.cursor_mut() = Cursor::with_position(start + lines.len() - 1, 0);
+            self.adjust_scroll();
+            self.notify_lsp_document_change();
+            return;
+        }
+
+        let new_row = table.insert_row(row.max(1) + 1);
+        self.apply_table(start, end, &table, new_row, 0);
+    }
+
+    /// Table command bound to a key
+    fn table_edit_for_key(&self, key: &KeyEvent) -> Option<TableEdit> {
+        let keybindings = &self.config.keybindings;
+        if keybindings.table_insert_column.matches(key) {
+            Some(|table, cell| {
+                table.insert_column(cell);
+                Some(cell)
+            })
+        } else if keybindings.table_delete_column.matches(key) {
+            Some(|table, cell| {
+                table
+                    .delete_column(cell)
+                    .then(|| cell.min(table.column_count() - 1))
+            })
+        } else if keybindings.table_move_column_left.matches(key) {
+            Some(|table, cell| (cell > 0 && table.move_column(cell, cell - 1)).then(|| cell - 1))
+        } else if keybindings.table_move_column_right.matches(key) {
+            Some(|table, cell| table.move_column(cell, cell + 1).then_some(cell + 1))
+        } else if keybindings.table_cycle_alignment.matches(key) {
+            Some(|table, cell| {
+                let alignment = table::next_alignment(table.alignment(cell)?);
+                table.set_alignment(cell, alignment);
+                Some(cell)
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Apply a table command to the table under the cursor
+    ///
+    /// Returns false if the cursor is not in a table.
+    fn edit_table(&mut self, edit: TableEdit) -> bool {
+        let Some(TableCursor {
+            start,
+            end,
+            mut table,
+            row,
+            cell,
+        }) = self.table_at_cursor()
+        else {
+            return false;
+        };
+        if let Some(cell) = edit(&mut table, cell) {
+            self.apply_table(start, end, &table, row, cell);
+        }
+        true
+    }
+
     /// Handle keyboard input
     pub fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
+        let revision = self.buffer.revision();
+        let result = self.dispatch_key(key);
+        if self.buffer.revision() != revision {
+            self.sync_folds();
+            self.sync_outline();
+        } else {
+            self.reveal_cursor();
+        }
+        result
+    }
+
+    fn dispatch_key(&mut self, key: KeyEvent) -> Result<()> {
         // Clear status message on any key press
         self.clear_status_message();
 
@@ -1236,6 +3292,20 @@ impl App {
             }
         }
 
+        // Handle the create-page confirmation of an unresolved wiki link
+        if self.pending_new_page.is_some() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                    return self.create_pending_page();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    self.pending_new_page = None;
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            }
+        }
+
         // Handle search dialog if visible
         if self.show_search_dialog {
             return self.handle_search_key(key);
@@ -1256,6 +3326,21 @@ impl App {
             return self.handle_mq_query_key(key);
         }
 
+        // Handle front matter form if visible
+        if self.front_matter_form.is_some() {
+            return self.handle_front_matter_key(key);
+        }
+
+        // Handle statistics dialog if visible
+        if self.show_stats_dialog {
+            return self.handle_stats_key(key);
+        }
+
+        // Handle quick fix popup if visible
+        if !self.code_actions.is_empty() {
+            return self.handle_code_action_key(key);
+        }
+
         // Handle completion popup if visible
         if self.show_completion {
             match key.code {
@@ -1315,11 +3400,71 @@ impl App {
             return Ok(());
         }
 
+        // Toggle outline sidebar
+        if self.config.keybindings.toggle_outline.matches(&key) {
+            self.toggle_outline();
+            return Ok(());
+        }
+
+        // Toggle backlinks panel
+        if self.config.keybindings.toggle_backlinks.matches(&key) {
+            self.toggle_backlinks();
+            return Ok(());
+        }
+
+        // Edit the front matter in a form
+        if self.config.keybindings.edit_front_matter.matches(&key) {
+            self.open_front_matter_form();
+            return Ok(());
+        }
+
+        // Toggle read-only preview
+        if self.config.keybindings.toggle_preview.matches(&key) {
+            self.toggle_preview();
+            return Ok(());
+        }
+
+        // Toggle focus mode
+        if self.config.keybindings.toggle_focus.matches(&key) {
+            self.toggle_focus_mode();
+            return Ok(());
+        }
+
+        // Word counts by section
+        if self.config.keybindings.show_stats.matches(&key) {
+            self.open_stats_dialog();
+            return Ok(());
+        }
+
+        // Quick fixes at the cursor
+        if self.config.keybindings.quick_fix.matches(&key) {
+            return self.request_code_actions();
+        }
+
+        if self.config.keybindings.format_document.matches(&key) {
+            return self.format_document();
+        }
+
+        // Handle outline navigation when it has focus
+        if self.outline_focused {
+            return self.handle_outline_key(key);
+        }
+
+        // Handle backlinks navigation when it has focus
+        if self.backlinks_focused {
+            return self.handle_backlinks_key(key);
+        }
+
         // Handle file browser navigation when visible
         if self.show_file_browser {
             return self.handle_file_browser_key(key);
         }
 
+        // Pager keys in preview mode, where only quitting works as usual
+        if self.preview_mode && !self.config.keybindings.quit.matches(&key) {
+            return self.handle_preview_key(key);
+        }
+
         // Check configured keybindings first
         if self.config.keybindings.quit.matches(&key)
             || self.config.keybindings.quit_alt.matches(&key)
@@ -1337,6 +3482,25 @@ impl App {
         // Reset quit confirmation if any other key is pressed
         self.quit_confirm_pending = false;
 
+        // Shift with a movement key extends the selection; any other key clears it
+        let extends_selection = key.modifiers.contains(KeyModifiers::SHIFT)
+            && matches!(
+                key.code,
+                KeyCode::Up
+                    | KeyCode::Down
+                    | KeyCode::Left
+                    | KeyCode::Right
+                    | KeyCode::Home
+                    | KeyCode::End
+                    | KeyCode::PageUp
+                    | KeyCode::PageDown
+            );
+        if extends_selection {
+            self.selection_anchor.get_or_insert(*self.buffer.cursor());
+        } else {
+            self.selection_anchor = None;
+        }
+
         if self.config.keybindings.save.matches(&key) {
             // Check if file has a path
             if self.buffer.file_path().is_none() {
@@ -1344,10 +3508,14 @@ impl App {
                 self.open_save_as_dialog();
             } else {
                 // Has file path, save directly
+                self.update_toc_on_save();
+                self.format_on_save();
                 if let Err(e) = self.buffer.save() {
                     self.set_status_message(format!("Error saving file: {}", e));
                 } else {
                     self.set_status_message("File saved successfully.".to_string());
+                    self.save_folds();
+                    self.update_link_index();
                 }
             }
             return Ok(());
@@ -1395,6 +3563,12 @@ impl App {
             return Ok(());
         }
 
+        // Find by mq query
+        if self.config.keybindings.find_by_query.matches(&key) {
+            self.open_query_search();
+            return Ok(());
+        }
+
         // Go to line
         if self.config.keybindings.goto_line.matches(&key) {
             self.open_goto_line_dialog();
@@ -1428,22 +3602,50 @@ impl App {
             return self.request_completion(None);
         }
 
+        // Folding
+        if self.config.keybindings.toggle_fold.matches(&key) {
+            self.toggle_fold();
+            return Ok(());
+        }
+        if self.config.keybindings.unfold_all.matches(&key) {
+            self.unfold_all();
+            return Ok(());
+        }
+
+        // Table of contents
+        if self.config.keybindings.insert_toc.matches(&key) {
+            self.insert_toc();
+            return Ok(());
+        }
+
+        // Follow link
+        if self.config.keybindings.follow_link.matches(&key) {
+            return self.follow_link();
+        }
+
+        // Table commands, only while the cursor is in a Markdown table
+        if let Some(edit) = self.table_edit_for_key(&key)
+            && self.edit_table(edit)
+        {
+            return Ok(());
+        }
+
         // Navigation and editing keys (not configurable)
         match key.code {
             // Navigation
             KeyCode::Up => {
-                self.buffer.move_cursor(CursorMovement::Up);
+                self.move_cursor_over_folds(CursorMovement::Up);
                 self.adjust_scroll();
             }
             KeyCode::Down => {
-                self.buffer.move_cursor(CursorMovement::Down);
+                self.move_cursor_over_folds(CursorMovement::Down);
                 self.adjust_scroll();
             }
             KeyCode::Left => {
-                self.buffer.move_cursor(CursorMovement::Left);
+                self.move_cursor_over_folds(CursorMovement::Left);
             }
             KeyCode::Right => {
-                self.buffer.move_cursor(CursorMovement::Right);
+                self.move_cursor_over_folds(CursorMovement::Right);
             }
             KeyCode::Home => {
                 self.buffer.move_cursor(CursorMovement::StartOfLine);
@@ -1452,11 +3654,11 @@ impl App {
                 self.buffer.move_cursor(CursorMovement::EndOfLine);
             }
             KeyCode::PageUp => {
-                self.buffer.move_cursor(CursorMovement::PageUp);
+                self.move_cursor_over_folds(CursorMovement::PageUp);
                 self.adjust_scroll();
             }
             KeyCode::PageDown => {
-                self.buffer.move_cursor(CursorMovement::PageDown);
+                self.move_cursor_over_folds(CursorMovement::PageDown);
                 self.adjust_scroll();
             }
 
@@ -1474,6 +3676,9 @@ impl App {
                     let _ = self.request_completion(Some(c.to_string()));
                 }
             }
+            KeyCode::Enter if self.table_at_cursor().is_some() => {
+                self.table_enter();
+            }
             KeyCode::Enter => {
                 self.buffer.insert_newline();
                 self.adjust_scroll();
@@ -1483,6 +3688,9 @@ impl App {
                 self.buffer.delete_char();
                 self.notify_lsp_document_change();
             }
+            KeyCode::Tab | KeyCode::BackTab if self.table_at_cursor().is_some() => {
+                self.table_move_cell(key.code == KeyCode::Tab);
+            }
             KeyCode::Tab => {
                 self.buffer.insert_char('\t');
                 self.notify_lsp_document_change();
@@ -1496,13 +3704,29 @@ impl App {
 
     /// Handle paste event (used for IME input and clipboard paste)
     pub fn handle_paste(&mut self, text: String) -> Result<()> {
-        // Don't handle paste when file browser is visible
-        if self.show_file_browser {
+        // Don't handle paste when file browser is visible or the outline has focus
+        if self.show_file_browser || self.outline_focused || self.backlinks_focused {
+            return Ok(());
+        }
+
+        // The preview is read-only
+        if self.preview_mode {
+            return Ok(());
+        }
+
+        // Pasted text goes into the selected field of the front matter form
+        if let Some(form) = self.front_matter_form.as_mut() {
+            text.chars()
+                .filter(|c| !c.is_control())
+                .for_each(|c| form.insert_char(c));
             return Ok(());
         }
 
         // Insert the pasted text at cursor position
+        self.selection_anchor = None;
         self.buffer.insert_str(&text);
+        self.sync_folds();
+        self.sync_outline();
         self.adjust_scroll();
         self.notify_lsp_document_change();
 
@@ -1511,6 +3735,11 @@ impl App {
 
     /// Adjust scroll offset to keep cursor visible
     fn adjust_scroll(&mut self) {
+        if self.focus_mode && self.config.focus.typewriter && !self.preview_mode {
+            self.center_cursor_line();
+            return;
+        }
+
         let cursor_line = self.buffer.cursor().line;
         let viewport_height = 20; // TODO: Get actual terminal height
 
@@ -1523,6 +3752,31 @@ impl App {
         if cursor_line < self.scroll_offset {
             self.scroll_offset = cursor_line;
         }
+
+        // The first visible line can't be a folded one
+        self.scroll_offset = self.folds.prev_visible(self.scroll_offset);
+
+        // Rows reserved for images push the cursor further down
+        while self.scroll_offset < cursor_line && self.cursor_screen_row() >= viewport_height {
+            self.scroll_offset = self.folds.next_visible(self.scroll_offset + 1);
+        }
+    }
+
+    /// Scroll so the cursor line is in the middle of the editor (typewriter scrolling)
+    ///
+    /// Near the top of the document the cursor stays above the middle.
+    fn center_cursor_line(&mut self) {
+        let cursor_line = self.buffer.cursor().line;
+        let middle = self.preview_page() / 2;
+        self.scroll_offset = self.folds.prev_visible(cursor_line);
+        while self.scroll_offset > 0 {
+            let top = self.scroll_offset;
+            self.scroll_offset = self.folds.prev_visible(top - 1);
+            if self.cursor_screen_row() > middle {
+                self.scroll_offset = top;
+                break;
+            }
+        }
     }
 
     /// Handle keyboard input when file browser is visible
@@ -1589,8 +3843,53 @@ impl App {
         Ok(())
     }
 
+    /// Handle keyboard input when the outline sidebar has focus
+    fn handle_outline_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Up => self.outline.move_up(),
+            KeyCode::Down => self.outline.move_down(),
+            KeyCode::Enter => {
+                // Jump to the selected section and return focus to the editor
+                if let Some(line) = self.outline.selected_entry().map(|entry| entry.line) {
+                    self.push_navigation_location();
+                    *self.buffer.cursor_mut() = Cursor::with_position(
+                        line.min(self.buffer.line_count().saturating_sub(1)),
+                        0,
+                    );
+                    self.reveal_cursor();
+                    self.adjust_scroll();
+                    self.push_navigation_location();
+                    self.outline_focused = false;
+                }
+            }
+            KeyCode::Backspace => self.outline.pop_filter(),
+            KeyCode::Esc => {
+                // Clear the filter first, then leave the outline
+                if self.outline.filter().is_empty() {
+                    self.outline_focused = false;
+                } else {
+                    self.outline.clear_filter();
+                }
+            }
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.outline.push_filter(c);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     /// Handle keyboard input when search dialog is visible
     fn handle_search_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.handle_history_key(key) {
+            return Ok(());
+        }
+
         match key.code {
             KeyCode::Esc => {
                 self.close_search();
@@ -1614,19 +3913,48 @@ impl App {
                     self.replace_query.pop();
                 }
             },
-            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            KeyCode::Char('r')
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && self.search_mode == SearchMode::Replace =>
+            {
                 // Ctrl+R: Replace current
-                if self.search_mode == SearchMode::Replace {
-                    self.replace_current();
-                }
+                self.replace_current();
             }
-            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            KeyCode::Char('a')
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && self.search_mode == SearchMode::Replace =>
+            {
                 // Ctrl+A: Replace all
-                if self.search_mode == SearchMode::Replace {
-                    self.replace_all();
-                }
+                self.replace_all();
+            }
+            KeyCode::Char('l')
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && self.search_mode != SearchMode::Replace =>
+            {
+                // Ctrl+L: Select current match
+                self.select_current_match();
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::ALT) => {
+                // Alt+R: Toggle regex
+                self.toggle_search_regex();
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::ALT) => {
+                // Alt+C: Cycle case sensitivity
+                self.cycle_search_case_mode();
             }
-            KeyCode::Char(c) => {
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::ALT) => {
+                // Alt+W: Toggle whole word
+                self.toggle_search_whole_word();
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::ALT) => {
+                // Alt+S: Cycle search scope (Markdown only)
+                self.cycle_search_scope();
+            }
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
                 match self.search_active_field {
                     SearchField::Search => {
                         self.search_query.push(c);
@@ -1672,6 +4000,10 @@ impl App {
 
     /// Handle keyboard input when mq query dialog is visible
     fn handle_mq_query_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.handle_history_key(key) {
+            return Ok(());
+        }
+
         match key.code {
             KeyCode::Esc => {
                 self.close_mq_query_dialog();
@@ -1694,6 +4026,10 @@ impl App {
 
     /// Handle keyboard input when goto line dialog is visible
     fn handle_goto_line_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.handle_history_key(key) {
+            return Ok(());
+        }
+
         match key.code {
             KeyCode::Esc => {
                 self.close_goto_line_dialog();
@@ -1729,3 +4065,295 @@ fn file_type_to_language_id(file_type: &FileType) -> Option<String> {
         FileType::PlainText => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::ImageProtocol;
+    use image::{Rgba, RgbaImage};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn preview_app(content: &str, height: u16) -> App {
+        let mut app = App::from_string(content).unwrap();
+        app.set_editor_width(80);
+        app.set_editor_height(height);
+        app.toggle_preview();
+        app
+    }
+
+    /// Screen rows from the top line to the end of the document
+    fn rows_to_end(app: &App) -> usize {
+        screen_rows(
+            &app.buffer,
+            &app.markdown_renderer,
+            &app.image_manager,
+            &app.folds,
+            app.scroll_offset()..app.buffer.line_count(),
+            app.line_layout(true),
+        )
+    }
+
+    #[test]
+    fn test_focus_mode_wraps_cursor_line() {
+        let mut app = App::from_string("aaa bbb ccc ddd\nnext").unwrap();
+        app.set_editor_width(10);
+        app.set_editor_height(10);
+        app.toggle_focus_mode();
+
+        app.buffer.cursor_mut().column = 9;
+        assert_eq!(app.cursor_screen_row(), 1);
+        assert_eq!(app.cursor_screen_column(), 1);
+
+        *app.buffer.cursor_mut() = Cursor::with_position(1, 2);
+        assert_eq!(app.cursor_screen_row(), 2);
+        assert_eq!(app.cursor_screen_column(), 2);
+
+        app.toggle_focus_mode();
+        assert_eq!(app.cursor_screen_row(), 1);
+    }
+
+    #[test]
+    fn test_preview_pages_over_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image.png");
+        RgbaImage::from_pixel(40, 20, Rgba([255, 0, 0, 255]))
+            .save(&path)
+            .unwrap();
+        let lines: Vec<String> = (0..30).map(|n| format!("line {}", n)).collect();
+        let content = format!(
+            "# Title\n\n![image]({})\n\n{}",
+            path.display(),
+            lines.join("\n")
+        );
+        let mut app = preview_app(&content, 10);
+        app.image_manager_mut()
+            .set_display(ImageProtocol::Halfblocks, 8);
+
+        // The image takes 9 rows, so the next page starts at it
+        app.handle_key(key(KeyCode::Char(' '))).unwrap();
+        assert_eq!(app.scroll_offset(), 2);
+        app.handle_key(key(KeyCode::Char(' '))).unwrap();
+        assert_eq!(app.scroll_offset(), 3);
+
+        app.handle_key(key(KeyCode::Char('b'))).unwrap();
+        assert_eq!(app.scroll_offset(), 2);
+        app.handle_key(key(KeyCode::Char('j'))).unwrap();
+        assert_eq!(app.scroll_offset(), 3);
+        app.handle_key(key(KeyCode::Char('k'))).unwrap();
+        assert_eq!(app.scroll_offset(), 2);
+        assert_eq!(app.buffer.cursor().line, 2);
+    }
+
+    #[test]
+    fn test_preview_end_shows_table() {
+        let lines: Vec<String> = (0..20).map(|n| format!("line {}", n)).collect();
+        let content = format!(
+            "{}\n\n| Name | Value |\n| --- | --- |\n| a | 1 |\n| b | 2 |\n| c | 3 |",
+            lines.join("\n")
+        );
+        let mut app = preview_app(&content, 8);
+
+        // The bordered table takes more rows than its 5 lines
+        app.handle_key(key(KeyCode::Char('G'))).unwrap();
+        let top = app.scroll_offset();
+        assert!(top > app.buffer.line_count() - 8);
+        assert!(rows_to_end(&app) <= 8);
+        app.scroll_offset = top - 1;
+        assert!(rows_to_end(&app) > 8);
+
+        // Paging down stops at the same line
+        app.preview_scroll_to(0);
+        for _ in 0..10 {
+            app.handle_key(key(KeyCode::PageDown)).unwrap();
+        }
+        assert_eq!(app.scroll_offset(), top);
+    }
+
+    #[test]
+    fn test_preview_heading_jumps() {
+        let content = "# One\n\ntext\n\n## Two\n\ntext\n\n## Three\n\ntext";
+        let mut app = preview_app(content, 3);
+
+        app.handle_key(key(KeyCode::Char(']'))).unwrap();
+        assert_eq!(app.scroll_offset(), 4);
+        app.handle_key(key(KeyCode::Char(']'))).unwrap();
+        assert_eq!(app.scroll_offset(), 8);
+        app.handle_key(key(KeyCode::Char('['))).unwrap();
+        assert_eq!(app.scroll_offset(), 4);
+        app.handle_key(key(KeyCode::Char('['))).unwrap();
+        assert_eq!(app.scroll_offset(), 0);
+    }
+
+    #[test]
+    fn test_preview_is_read_only() {
+        let mut app = preview_app("# Title\n\ntext", 10);
+        for code in [
+            KeyCode::Char('x'),
+            KeyCode::Backspace,
+            KeyCode::Delete,
+            KeyCode::Tab,
+        ] {
+            app.handle_key(key(code)).unwrap();
+        }
+        assert_eq!(app.buffer.content(), "# Title\n\ntext");
+        assert!(app.is_preview());
+
+        // `q` leaves the preview when it was opened from the editor
+        app.handle_key(key(KeyCode::Char('q'))).unwrap();
+        assert!(!app.is_preview());
+        assert!(!app.should_quit());
+    }
+
+    #[test]
+    fn test_preview_ignores_quick_fixes() {
+        let mut app = preview_app("# Title\n\ntxet", 10);
+        app.handle_key(KeyEvent::new(KeyCode::Char('.'), KeyModifiers::ALT))
+            .unwrap();
+        assert_eq!(app.status_message(), Some("Preview is read-only"));
+
+        let edit = TextEdit {
+            range: lsp_types::Range::new(
+                lsp_types::Position::new(2, 0),
+                lsp_types::Position::new(2, 4),
+            ),
+            new_text: "text".to_string(),
+        };
+        let action = lsp_types::CodeAction {
+            title: "Replace with 'text'".to_string(),
+            edit: Some(lsp_types::WorkspaceEdit {
+                changes: Some(
+                    [("file:///a.md".parse().unwrap(), vec![edit])]
+                        .into_iter()
+                        .collect(),
+                ),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        app.apply_code_action(CodeActionOrCommand::CodeAction(action))
+            .unwrap();
+        assert_eq!(app.buffer.content(), "# Title\n\ntxet");
+    }
+
+    #[test]
+    fn test_follow_link_to_anchor_records_one_jump() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A\n\nSee [other](b.md#details).").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B\n\ntext\n\n## Details\n").unwrap();
+        let mut app = App::from_file(dir.path().join("a.md")).unwrap();
+        *app.buffer.cursor_mut() = Cursor::with_position(2, 6);
+
+        app.follow_link().unwrap();
+        assert!(app.buffer.file_path().unwrap().ends_with("b.md"));
+        assert_eq!(app.buffer.cursor().line, 4);
+
+        // Back goes straight to the link, not to the top of the target
+        app.navigate_back().unwrap();
+        assert!(app.buffer.file_path().unwrap().ends_with("a.md"));
+        assert_eq!(app.buffer.cursor().line, 2);
+        assert_eq!(app.buffer.cursor().column, 6);
+    }
+
+    #[test]
+    fn test_view_only_quits_on_q() {
+        let mut app = App::from_string("# Title").unwrap();
+        app.set_view_only();
+        app.toggle_preview();
+        assert!(app.is_preview());
+
+        app.handle_key(key(KeyCode::Char('q'))).unwrap();
+        assert!(app.should_quit());
+    }
+
+    #[test]
+    fn test_select_current_query_match() {
+        let mut app =
+            App::from_string("# Title\n\n[a](x.md) and [b](y.md)\n\n[c](z.md)\n").unwrap();
+        app.open_query_search();
+        for c in ".link".chars() {
+            app.handle_key(key(KeyCode::Char(c))).unwrap();
+        }
+        assert_eq!(app.search_results().len(), 3);
+
+        app.handle_key(key(KeyCode::Enter)).unwrap();
+        let ctrl_l = KeyEvent::new(KeyCode::Char('l'), KeyModifiers::CONTROL);
+        app.handle_key(ctrl_l).unwrap();
+        assert!(!app.show_search_dialog());
+        let (start, end) = app.selection().unwrap();
+        assert_eq!((start.line, start.column), (2, 14));
+        assert_eq!((end.line, end.column), (2, 23));
+    }
+
+    #[test]
+    fn test_outline_follows_edits() {
+        let mut app = App::from_string("# One\n").unwrap();
+        app.toggle_outline();
+        app.outline_focused = false;
+        assert_eq!(app.outline().entries().len(), 1);
+
+        app.handle_key(key(KeyCode::End)).unwrap();
+        for c in "\n## Two".chars() {
+            let code = if c == '\n' {
+                KeyCode::Enter
+            } else {
+                KeyCode::Char(c)
+            };
+            app.handle_key(key(code)).unwrap();
+        }
+        assert_eq!(app.outline().entries().len(), 2);
+    }
+
+    #[test]
+    fn test_stats_follow_edits_and_selection() {
+        let mut app = App::from_string("one two\n").unwrap();
+        assert_eq!(app.document_stats().unwrap().words, 2);
+        assert_eq!(app.selection_stats(), None);
+
+        app.handle_key(key(KeyCode::End)).unwrap();
+        for c in " three".chars() {
+            app.handle_key(key(KeyCode::Char(c))).unwrap();
+        }
+        assert_eq!(app.document_stats().unwrap().words, 3);
+
+        let shift_home = KeyEvent::new(KeyCode::Home, KeyModifiers::SHIFT);
+        app.handle_key(shift_home).unwrap();
+        assert_eq!(app.selection_stats().unwrap().words, 3);
+        let shift_right = KeyEvent::new(KeyCode::Right, KeyModifiers::SHIFT);
+        for _ in 0..4 {
+            app.handle_key(shift_right).unwrap();
+        }
+        assert_eq!(app.selection_stats().unwrap().words, 2);
+        assert_eq!(app.section_stats()[0].stats.words, 3);
+    }
+
+    #[test]
+    fn test_create_wiki_page_inside_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.md");
+        std::fs::write(&path, "[[../outside]]\n[[Notes.md]]\n").unwrap();
+        let mut app = App::from_file(&path).unwrap();
+
+        *app.buffer.cursor_mut() = Cursor::with_position(0, 3);
+        app.request_go_to_definition().unwrap();
+        assert!(app.new_page_prompt().is_none());
+        assert!(
+            app.status_message()
+                .unwrap()
+                .contains("outside the workspace")
+        );
+
+        *app.buffer.cursor_mut() = Cursor::with_position(1, 3);
+        app.request_go_to_definition().unwrap();
+        assert!(app.new_page_prompt().unwrap().contains("Create Notes.md?"));
+        app.handle_key(key(KeyCode::Char('y'))).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("Notes.md")).unwrap(),
+            "# Notes\n"
+        );
+        assert!(!dir.path().join("Notes.md.md").exists());
+        assert!(!dir.path().parent().unwrap().join("outside.md").exists());
+    }
+}
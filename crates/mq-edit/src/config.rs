@@ -1,9 +1,18 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use markdown_lsp;
+use markdown_lsp::format::{
+    BulletMarker, CodeFence, EmphasisMarker, FormatOptions, HeadingStyle, ListNumbering,
+};
+use markdown_lsp::front_matter::FrontMatterSchema;
+use markdown_lsp::spell::SpellConfig;
+use markdown_lsp::toc::TocOptions;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 
+use crate::document::{CaseMode, SearchOptions};
+use crate::renderer::{ImageProtocol, TableOverflow};
+
 /// LSP configuration for language servers
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LspConfig {
@@ -129,6 +138,77 @@ pub struct EditorConfig {
     /// When false, falls back to syntect (default: false)
     #[serde(default = "default_false")]
     pub use_semantic_tokens: bool,
+
+    /// Keep the cursor line rendered, revealing raw syntax only for the
+    /// inline element under the cursor (default: false)
+    #[serde(default = "default_false")]
+    pub partial_wysiwyg: bool,
+
+    /// How tables wider than the editor are shown: "wrap" wraps cell text,
+    /// "scroll" keeps rows on one line and scrolls to the cursor (default: "wrap")
+    #[serde(default)]
+    pub table_overflow: TableOverflow,
+
+    /// Remember folded regions per file in `folds.toml` next to the config file
+    #[serde(default = "default_true")]
+    pub persist_folds: bool,
+
+    /// Format Markdown files when saving them (default: false)
+    #[serde(default = "default_false")]
+    pub format_on_save: bool,
+
+    /// Bullet list marker the formatter writes: "dash", "asterisk" or "plus"
+    #[serde(default)]
+    pub bullet_marker: BulletMarker,
+
+    /// Emphasis delimiter the formatter writes: "asterisk" or "underscore"
+    #[serde(default)]
+    pub emphasis_marker: EmphasisMarker,
+
+    /// Strong emphasis delimiter the formatter writes: "asterisk" or "underscore"
+    #[serde(default)]
+    pub strong_marker: EmphasisMarker,
+
+    /// Ordered list numbers: "ordered" counts up, "one" repeats the first number
+    #[serde(default)]
+    pub ordered_list_numbering: ListNumbering,
+
+    /// Heading syntax: "atx" (`# Title`) or "setext" (underlined, levels 1 and 2)
+    #[serde(default)]
+    pub heading_style: HeadingStyle,
+
+    /// Code block fence: "backtick" or "tilde"
+    #[serde(default)]
+    pub code_fence: CodeFence,
+
+    /// Pad table cells so the pipes line up when formatting
+    #[serde(default = "default_true")]
+    pub align_tables: bool,
+
+    /// Separate top-level blocks by exactly one blank line when formatting
+    #[serde(default = "default_true")]
+    pub normalize_blank_lines: bool,
+
+    /// End formatted documents with a single newline
+    #[serde(default = "default_true")]
+    pub final_newline: bool,
+}
+
+impl EditorConfig {
+    /// Convert to the style used by the Markdown formatter
+    pub fn format_options(&self) -> FormatOptions {
+        FormatOptions {
+            bullet: self.bullet_marker,
+            emphasis: self.emphasis_marker,
+            strong: self.strong_marker,
+            numbering: self.ordered_list_numbering,
+            heading: self.heading_style,
+            code_fence: self.code_fence,
+            align_tables: self.align_tables,
+            blank_lines: self.normalize_blank_lines,
+            final_newline: self.final_newline,
+        }
+    }
 }
 
 impl Default for EditorConfig {
@@ -138,6 +218,19 @@ impl Default for EditorConfig {
             show_current_line_highlight: true,
             theme: default_theme(),
             use_semantic_tokens: false,
+            partial_wysiwyg: false,
+            table_overflow: TableOverflow::default(),
+            persist_folds: true,
+            format_on_save: false,
+            bullet_marker: BulletMarker::default(),
+            emphasis_marker: EmphasisMarker::default(),
+            strong_marker: EmphasisMarker::default(),
+            ordered_list_numbering: ListNumbering::default(),
+            heading_style: HeadingStyle::default(),
+            code_fence: CodeFence::default(),
+            align_tables: true,
+            normalize_blank_lines: true,
+            final_newline: true,
         }
     }
 }
@@ -146,11 +239,222 @@ fn default_theme() -> String {
     "tarn".to_string()
 }
 
+/// Default options for the search dialog
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SearchConfig {
+    /// Interpret search queries as regular expressions
+    #[serde(default)]
+    pub regex: bool,
+
+    /// Case sensitivity: "sensitive", "insensitive" or "smart"
+    #[serde(default)]
+    pub case_mode: CaseMode,
+
+    /// Only match whole words
+    #[serde(default)]
+    pub whole_word: bool,
+}
+
+impl SearchConfig {
+    /// Convert to the search options used by the editor
+    pub fn options(&self) -> SearchOptions {
+        SearchOptions {
+            regex: self.regex,
+            case_mode: self.case_mode,
+            whole_word: self.whole_word,
+        }
+    }
+}
+
+/// Dialog input history configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Save search, goto-line and mq query history to `history.toml` in the config directory
+    #[serde(default = "default_true")]
+    pub persist: bool,
+
+    /// Maximum number of entries kept per dialog
+    #[serde(default = "default_history_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_history_max_entries() -> usize {
+    100
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            persist: true,
+            max_entries: default_history_max_entries(),
+        }
+    }
+}
+
+/// Inline image display configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageConfig {
+    /// How images are drawn: "auto", "kitty", "iterm2", "sixel", "halfblocks" or "none"
+    #[serde(default)]
+    pub protocol: ImageProtocol,
+
+    /// Number of rows an image takes below its line (0 disables images)
+    #[serde(default = "default_image_rows")]
+    pub rows: u16,
+
+    /// Memory budget in MiB for decoded images and thumbnails
+    #[serde(default = "default_image_cache_size_mb")]
+    pub cache_size_mb: usize,
+}
+
+fn default_image_rows() -> u16 {
+    10
+}
+
+fn default_image_cache_size_mb() -> usize {
+    64
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        Self {
+            protocol: ImageProtocol::Auto,
+            rows: default_image_rows(),
+            cache_size_mb: default_image_cache_size_mb(),
+        }
+    }
+}
+
+/// Table of contents configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TocConfig {
+    /// Shallowest heading level listed in the table of contents
+    #[serde(default = "default_toc_min_depth")]
+    pub min_depth: u8,
+
+    /// Deepest heading level listed in the table of contents
+    #[serde(default = "default_toc_max_depth")]
+    pub max_depth: u8,
+
+    /// Refresh the table of contents between `<!-- toc -->` and `<!-- tocstop -->` on save
+    #[serde(default = "default_true")]
+    pub update_on_save: bool,
+}
+
+fn default_toc_min_depth() -> u8 {
+    TocOptions::default().min_depth
+}
+
+fn default_toc_max_depth() -> u8 {
+    TocOptions::default().max_depth
+}
+
+impl Default for TocConfig {
+    fn default() -> Self {
+        Self {
+            min_depth: default_toc_min_depth(),
+            max_depth: default_toc_max_depth(),
+            update_on_save: true,
+        }
+    }
+}
+
+impl TocConfig {
+    /// Convert to the options used to generate the table of contents
+    pub fn options(&self) -> TocOptions {
+        TocOptions {
+            min_depth: self.min_depth,
+            max_depth: self.max_depth,
+            ..TocOptions::default()
+        }
+    }
+}
+
+/// Link following and backlinks configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinksConfig {
+    /// Command that opens URLs and files the editor does not edit, e.g. `firefox`
+    /// (defaults to `open` on macOS and `xdg-open` elsewhere)
+    #[serde(default)]
+    pub opener: Option<String>,
+
+    /// Count `[[Page]]` wiki links as backlinks along with Markdown links
+    #[serde(default = "default_true")]
+    pub wiki_links: bool,
+}
+
+impl Default for LinksConfig {
+    fn default() -> Self {
+        Self {
+            opener: None,
+            wiki_links: true,
+        }
+    }
+}
+
+impl LinksConfig {
+    /// Opener program and its arguments, to which the target is appended
+    pub fn opener_command(&self) -> Vec<String> {
+        match &self.opener {
+            Some(opener) => opener.split_whitespace().map(str::to_string).collect(),
+            None if cfg!(target_os = "macos") => vec!["open".to_string()],
+            None if cfg!(windows) => ["cmd", "/C", "start", ""].map(str::to_string).to_vec(),
+            None => vec!["xdg-open".to_string()],
+        }
+    }
+}
+
+/// Focus (distraction-free writing) mode configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusConfig {
+    /// Width of the centered text column
+    #[serde(default = "default_focus_width")]
+    pub width: u16,
+
+    /// Dim every paragraph except the one under the cursor
+    #[serde(default = "default_true")]
+    pub dim: bool,
+
+    /// Keep the cursor line vertically centered (typewriter scrolling)
+    #[serde(default = "default_false")]
+    pub typewriter: bool,
+}
+
+fn default_focus_width() -> u16 {
+    80
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        Self {
+            width: default_focus_width(),
+            dim: true,
+            typewriter: false,
+        }
+    }
+}
+
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(default)]
     pub editor: EditorConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub images: ImageConfig,
+    #[serde(default)]
+    pub toc: TocConfig,
+    #[serde(default)]
+    pub links: LinksConfig,
+    #[serde(default)]
+    pub front_matter: FrontMatterSchema,
+    #[serde(default)]
+    pub focus: FocusConfig,
+    #[serde(default)]
+    pub spell: SpellConfig,
     pub keybindings: Keybindings,
     #[serde(default)]
     pub lsp: LspConfig,
@@ -196,6 +500,31 @@ impl Config {
         }
     }
 
+    /// Spell checking settings with the editor's own directories filled in
+    ///
+    /// Dictionaries are also looked for in `~/.config/mq/edit/dictionaries`,
+    /// and added words go to `~/.config/mq/edit/personal.dic` unless another
+    /// file is configured.
+    pub fn spell_config(&self) -> SpellConfig {
+        let mut spell = self.spell.clone();
+        if let Some(config_dir) = dirs::config_dir() {
+            let dir = config_dir.join("mq").join("edit");
+            spell.dictionaries.push(dir.join("dictionaries"));
+            spell
+                .personal_dictionary
+                .get_or_insert_with(|| dir.join("personal.dic"));
+        }
+        spell
+    }
+
+    /// Table of contents options, with entries written in the formatter's bullet
+    pub fn toc_options(&self) -> TocOptions {
+        TocOptions {
+            bullet: self.editor.bullet_marker,
+            ..self.toc.options()
+        }
+    }
+
     /// Convert LSP server configs to markdown_lsp format
     pub fn lsp_server_configs(&self) -> HashMap<String, markdown_lsp::LspServerConfig> {
         self.lsp
@@ -271,6 +600,204 @@ pub struct Keybindings {
 
     /// Execute mq query (default: Ctrl+E)
     pub execute_mq_query: KeyBinding,
+
+    /// Find nodes by mq query (default: F5)
+    #[serde(default = "default_find_by_query")]
+    pub find_by_query: KeyBinding,
+
+    /// Insert a table column before the cursor cell (default: Alt+I)
+    #[serde(default = "default_table_insert_column")]
+    pub table_insert_column: KeyBinding,
+
+    /// Delete the table column under the cursor (default: Alt+D)
+    #[serde(default = "default_table_delete_column")]
+    pub table_delete_column: KeyBinding,
+
+    /// Move the table column under the cursor left (default: Alt+Left)
+    #[serde(default = "default_table_move_column_left")]
+    pub table_move_column_left: KeyBinding,
+
+    /// Move the table column under the cursor right (default: Alt+Right)
+    #[serde(default = "default_table_move_column_right")]
+    pub table_move_column_right: KeyBinding,
+
+    /// Cycle the alignment of the table column under the cursor (default: Alt+A)
+    #[serde(default = "default_table_cycle_alignment")]
+    pub table_cycle_alignment: KeyBinding,
+
+    /// Fold or unfold the region under the cursor (default: Alt+F)
+    #[serde(default = "default_toggle_fold")]
+    pub toggle_fold: KeyBinding,
+
+    /// Unfold all folded regions (default: Alt+U)
+    #[serde(default = "default_unfold_all")]
+    pub unfold_all: KeyBinding,
+
+    /// Toggle the document outline sidebar (default: Alt+O)
+    #[serde(default = "default_toggle_outline")]
+    pub toggle_outline: KeyBinding,
+
+    /// Insert a table of contents at the cursor, or refresh the existing one (default: Alt+T)
+    #[serde(default = "default_insert_toc")]
+    pub insert_toc: KeyBinding,
+
+    /// Follow the link or image under the cursor (default: Alt+Enter)
+    #[serde(default = "default_follow_link")]
+    pub follow_link: KeyBinding,
+
+    /// Toggle the backlinks panel (default: Alt+L)
+    #[serde(default = "default_toggle_backlinks")]
+    pub toggle_backlinks: KeyBinding,
+
+    /// Edit the document's front matter in a form (default: Alt+M)
+    #[serde(default = "default_edit_front_matter")]
+    pub edit_front_matter: KeyBinding,
+
+    /// Toggle the read-only preview (default: Alt+P)
+    #[serde(default = "default_toggle_preview")]
+    pub toggle_preview: KeyBinding,
+
+    /// Toggle focus mode for distraction-free writing (default: Alt+Z)
+    #[serde(default = "default_toggle_focus")]
+    pub toggle_focus: KeyBinding,
+
+    /// Show word counts by section (default: Alt+W)
+    #[serde(default = "default_show_stats")]
+    pub show_stats: KeyBinding,
+
+    /// Show quick fixes for the word or diagnostic at the cursor (default: Alt+.)
+    #[serde(default = "default_quick_fix")]
+    pub quick_fix: KeyBinding,
+
+    /// Format the document (default: Alt+Shift+F)
+    #[serde(default = "default_format_document")]
+    pub format_document: KeyBinding,
+}
+
+fn default_find_by_query() -> KeyBinding {
+    KeyBinding {
+        code: "f5".to_string(),
+        modifiers: vec![],
+    }
+}
+
+fn default_table_insert_column() -> KeyBinding {
+    KeyBinding {
+        code: "i".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_table_delete_column() -> KeyBinding {
+    KeyBinding {
+        code: "d".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_table_move_column_left() -> KeyBinding {
+    KeyBinding {
+        code: "left".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_table_move_column_right() -> KeyBinding {
+    KeyBinding {
+        code: "right".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_table_cycle_alignment() -> KeyBinding {
+    KeyBinding {
+        code: "a".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_toggle_fold() -> KeyBinding {
+    KeyBinding {
+        code: "f".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_unfold_all() -> KeyBinding {
+    KeyBinding {
+        code: "u".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_toggle_outline() -> KeyBinding {
+    KeyBinding {
+        code: "o".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_insert_toc() -> KeyBinding {
+    KeyBinding {
+        code: "t".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_follow_link() -> KeyBinding {
+    KeyBinding {
+        code: "enter".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_toggle_backlinks() -> KeyBinding {
+    KeyBinding {
+        code: "l".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_edit_front_matter() -> KeyBinding {
+    KeyBinding {
+        code: "m".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_toggle_preview() -> KeyBinding {
+    KeyBinding {
+        code: "p".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_toggle_focus() -> KeyBinding {
+    KeyBinding {
+        code: "z".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_show_stats() -> KeyBinding {
+    KeyBinding {
+        code: "w".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_quick_fix() -> KeyBinding {
+    KeyBinding {
+        code: ".".to_string(),
+        modifiers: vec!["alt".to_string()],
+    }
+}
+
+fn default_format_document() -> KeyBinding {
+    KeyBinding {
+        code: "f".to_string(),
+        modifiers: vec!["alt".to_string(), "shift".to_string()],
+    }
 }
 
 impl Default for Keybindings {
@@ -360,6 +887,24 @@ impl Default for Keybindings {
                 code: "e".to_string(),
                 modifiers: vec!["ctrl".to_string()],
             },
+            find_by_query: default_find_by_query(),
+            table_insert_column: default_table_insert_column(),
+            table_delete_column: default_table_delete_column(),
+            table_move_column_left: default_table_move_column_left(),
+            table_move_column_right: default_table_move_column_right(),
+            table_cycle_alignment: default_table_cycle_alignment(),
+            toggle_fold: default_toggle_fold(),
+            unfold_all: default_unfold_all(),
+            toggle_outline: default_toggle_outline(),
+            insert_toc: default_insert_toc(),
+            follow_link: default_follow_link(),
+            toggle_backlinks: default_toggle_backlinks(),
+            edit_front_matter: default_edit_front_matter(),
+            toggle_preview: default_toggle_preview(),
+            toggle_focus: default_toggle_focus(),
+            show_stats: default_show_stats(),
+            quick_fix: default_quick_fix(),
+            format_document: default_format_document(),
         }
     }
 }
@@ -381,6 +926,10 @@ impl KeyBinding {
             "backspace" => matches!(key.code, KeyCode::Backspace),
             "tab" => matches!(key.code, KeyCode::Tab),
             "space" => matches!(key.code, KeyCode::Char(' ')),
+            "left" => matches!(key.code, KeyCode::Left),
+            "right" => matches!(key.code, KeyCode::Right),
+            "up" => matches!(key.code, KeyCode::Up),
+            "down" => matches!(key.code, KeyCode::Down),
             "f1" => matches!(key.code, KeyCode::F(1)),
             "f2" => matches!(key.code, KeyCode::F(2)),
             "f3" => matches!(key.code, KeyCode::F(3)),
@@ -458,6 +1007,10 @@ mod tests {
             KeyModifiers::CONTROL | KeyModifiers::SHIFT,
         );
         assert!(!kb.matches(&key2));
+
+        let kb3 = default_table_move_column_left();
+        assert!(kb3.matches(&KeyEvent::new(KeyCode::Left, KeyModifiers::ALT)));
+        assert!(!kb3.matches(&KeyEvent::new(KeyCode::Left, KeyModifiers::NONE)));
     }
 
     #[test]
@@ -551,6 +1104,106 @@ mod tests {
         assert!(config.lsp.servers.contains_key("rust"));
     }
 
+    #[test]
+    fn test_search_config_case_mode() {
+        let toml = r#"
+            regex = true
+            case_mode = "smart"
+        "#;
+
+        let config: SearchConfig = toml::from_str(toml).unwrap();
+        let options = config.options();
+        assert!(options.regex);
+        assert_eq!(options.case_mode, CaseMode::Smart);
+        assert!(!options.whole_word);
+    }
+
+    #[test]
+    fn test_links_config_opener() {
+        let config: LinksConfig = toml::from_str(r#"opener = "firefox --new-tab""#).unwrap();
+        assert_eq!(config.opener_command(), vec!["firefox", "--new-tab"]);
+        assert!(config.wiki_links);
+        assert!(!LinksConfig::default().opener_command().is_empty());
+    }
+
+    #[test]
+    fn test_front_matter_schema_config() {
+        let mut config = Config::default();
+        config.front_matter.fields[0].required = true;
+        config.front_matter.fields[0].description = Some("Page title".to_string());
+
+        // The schema survives a save and load as `[[front_matter.fields]]` tables
+        let saved = toml::to_string_pretty(&config).unwrap();
+        assert!(saved.contains("[[front_matter.fields]]"));
+        let loaded: Config = toml::from_str(&saved).unwrap();
+        assert_eq!(loaded.front_matter, config.front_matter);
+        assert_eq!(loaded.keybindings.edit_front_matter.code, "m");
+    }
+
+    #[test]
+    fn test_spell_config() {
+        let config = Config {
+            spell: toml::from_str("language = \"en_GB\"\ndictionaries = [\"/opt/dicts\"]").unwrap(),
+            ..Config::default()
+        };
+        assert!(config.spell.enabled);
+        assert_eq!(config.spell.language, "en_GB");
+        assert_eq!(config.keybindings.quick_fix.code, ".");
+
+        // The configured directory is searched before the editor's own
+        let spell = config.spell_config();
+        assert_eq!(
+            spell.dictionaries[0],
+            std::path::PathBuf::from("/opt/dicts")
+        );
+        if dirs::config_dir().is_some() {
+            assert_eq!(spell.dictionaries.len(), 2);
+            assert!(spell.personal_dictionary.is_some());
+        }
+    }
+
+    #[test]
+    fn test_format_options() {
+        let editor: EditorConfig = toml::from_str(
+            "bullet_marker = \"asterisk\"\nheading_style = \"setext\"\nalign_tables = false",
+        )
+        .unwrap();
+        assert!(!editor.format_on_save);
+
+        let options = editor.format_options();
+        assert_eq!(options.bullet, BulletMarker::Asterisk);
+        assert_eq!(options.heading, HeadingStyle::Setext);
+        assert_eq!(options.emphasis, EmphasisMarker::Asterisk);
+        assert!(!options.align_tables);
+        assert!(options.blank_lines);
+        assert!(options.final_newline);
+    }
+
+    #[test]
+    fn test_focus_config() {
+        let config: FocusConfig = toml::from_str("typewriter = true").unwrap();
+        assert_eq!(config.width, 80);
+        assert!(config.dim);
+        assert!(config.typewriter);
+
+        let config: FocusConfig = toml::from_str("width = 64\ndim = false").unwrap();
+        assert_eq!(config.width, 64);
+        assert!(!config.dim);
+        assert!(!config.typewriter);
+    }
+
+    #[test]
+    fn test_image_config() {
+        let config: ImageConfig = toml::from_str(r#"protocol = "halfblocks""#).unwrap();
+        assert_eq!(config.protocol, ImageProtocol::Halfblocks);
+        assert_eq!(config.rows, 10);
+        assert_eq!(config.cache_size_mb, 64);
+
+        let config: ImageConfig = toml::from_str("rows = 4").unwrap();
+        assert_eq!(config.protocol, ImageProtocol::Auto);
+        assert_eq!(config.rows, 4);
+    }
+
     #[test]
     fn test_lsp_server_config_defaults() {
         // Test that serde defaults work correctly
@@ -2,11 +2,27 @@ pub mod buffer;
 pub mod cursor;
 pub mod document_type;
 pub mod file_type;
+pub mod folding;
+pub mod front_matter;
 pub mod history;
 pub mod line_analyzer;
+pub mod link;
+pub mod outline;
+pub mod search;
+pub mod stats;
+pub mod table;
 
 pub use buffer::DocumentBuffer;
 pub use cursor::{Cursor, CursorMovement, LineMap};
 pub use document_type::DocumentType;
 pub use file_type::FileType;
+pub use folding::{FoldKind, FoldRange, FoldedLine, Folds};
+pub use front_matter::{FormField, FrontMatterForm};
 pub use line_analyzer::{LineAnalyzer, LineType, TableAlignment};
+pub use link::LinkTarget;
+pub use outline::{Outline, OutlineEntry};
+pub use search::{
+    CaseMode, SearchMatch, SearchOptions, SearchPattern, SearchScope, find_by_mq_query,
+};
+pub use stats::{SectionStats, TextStats};
+pub use table::Table;
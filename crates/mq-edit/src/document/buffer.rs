@@ -5,7 +5,10 @@ use mq_markdown::{Markdown, Node};
 use unicode_width::UnicodeWidthChar;
 
 use super::history::{EditAction, EditHistory};
-use super::{Cursor, CursorMovement, DocumentType, FileType, LineMap};
+use super::{
+    Cursor, CursorMovement, DocumentType, FileType, LineMap, SearchMatch, SearchPattern,
+    SearchScope,
+};
 
 /// Document buffer that manages content editing for any file type
 #[derive(Debug, Clone)]
@@ -22,6 +25,8 @@ pub struct DocumentBuffer {
     lines: Vec<String>,
     /// Whether the buffer has been modified
     modified: bool,
+    /// Number of edits applied so far, including undo and redo
+    revision: u64,
     /// Edit history for undo/redo
     history: EditHistory,
     /// Flag to suppress history recording during undo/redo operations
@@ -43,6 +48,7 @@ impl DocumentBuffer {
             cursor: Cursor::new(),
             lines: vec![String::new()],
             modified: false,
+            revision: 0,
             history: EditHistory::new(),
             recording: true,
         }
@@ -73,6 +79,7 @@ impl DocumentBuffer {
             cursor: Cursor::new(),
             lines,
             modified: false,
+            revision: 0,
             history: EditHistory::new(),
             recording: true,
         })
@@ -90,6 +97,7 @@ impl DocumentBuffer {
             cursor: Cursor::new(),
             lines,
             modified: false,
+            revision: 0,
             history: EditHistory::new(),
             recording: true,
         })
@@ -124,6 +132,11 @@ impl DocumentBuffer {
         self.modified
     }
 
+    /// Number of edits applied so far, which changes whenever the content does
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
     /// Get the file type
     pub fn file_type(&self) -> &FileType {
         &self.file_type
@@ -463,6 +476,7 @@ impl DocumentBuffer {
 
     /// Rebuild document-specific structures from current lines content
     fn rebuild_document(&mut self) {
+        self.revision += 1;
         let content = self.lines.join("\n");
         // Rebuild document type (for Markdown, this reparses the AST)
         let _ = self.document_type.rebuild(&content);
@@ -598,6 +612,16 @@ impl DocumentBuffer {
                     }
                 }
             }
+            EditAction::ReplaceRange {
+                line,
+                column,
+                old_text,
+                new_text,
+            } => {
+                // Reverse of range replace: splice old_text back in place of new_text
+                let offset = self.content_byte_offset(*line, *column);
+                self.splice_content(offset, new_text.len(), old_text);
+            }
         }
     }
 
@@ -706,9 +730,35 @@ impl DocumentBuffer {
                     }
                 }
             }
+            EditAction::ReplaceRange {
+                line,
+                column,
+                old_text,
+                new_text,
+            } => {
+                let offset = self.content_byte_offset(*line, *column);
+                self.splice_content(offset, old_text.len(), new_text);
+            }
         }
     }
 
+    /// Convert a (line, character column) position to a byte offset in the joined content
+    fn content_byte_offset(&self, line: usize, column: usize) -> usize {
+        let preceding: usize = self.lines.iter().take(line).map(|l| l.len() + 1).sum();
+        preceding + self.char_to_byte_idx(line, column)
+    }
+
+    /// Replace `remove_len` bytes at `offset` in the joined content with `insert`
+    fn splice_content(&mut self, offset: usize, remove_len: usize, insert: &str) {
+        let mut content = self.lines.join("\n");
+        let end = (offset + remove_len).min(content.len());
+        if offset > end || !content.is_char_boundary(offset) || !content.is_char_boundary(end) {
+            return;
+        }
+        content.replace_range(offset..end, insert);
+        self.lines = content.split('\n').map(|s| s.to_string()).collect();
+    }
+
     /// Save buffer to file
     pub fn save(&mut self) -> Result<()> {
         if let Some(path) = &self.file_path {
@@ -737,6 +787,30 @@ impl DocumentBuffer {
         self.lines.join("\n")
     }
 
+    /// Text from `start` up to `end`, with positions clamped to the buffer
+    pub fn text_between(&self, start: Cursor, end: Cursor) -> String {
+        let last = self.lines.len().saturating_sub(1);
+        let (start_line, end_line) = (start.line.min(last), end.line.min(last));
+        (start_line..=end_line)
+            .filter_map(|idx| {
+                let line = self.lines.get(idx)?;
+                let from = if idx == start_line { start.column } else { 0 };
+                let to = if idx == end_line {
+                    end.column
+                } else {
+                    usize::MAX
+                };
+                Some(
+                    line.chars()
+                        .skip(from)
+                        .take(to.saturating_sub(from))
+                        .collect(),
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
     /// Get the start column of the word at cursor position
     /// This is useful for code completion to determine how much text to replace
     pub fn word_start_column(&self, line: usize, column: usize) -> usize {
@@ -777,124 +851,6 @@ impl DocumentBuffer {
         }
     }
 
-    /// Find all occurrences of a query string in the buffer
-    /// Returns a list of (line, column) positions (0-indexed)
-    pub fn find_all(&self, query: &str) -> Vec<(usize, usize)> {
-        if query.is_empty() {
-            return Vec::new();
-        }
-
-        let mut results = Vec::new();
-        for (line_idx, line) in self.lines.iter().enumerate() {
-            let mut search_start = 0;
-            while let Some(byte_pos) = line[search_start..].find(query) {
-                let abs_byte_pos = search_start + byte_pos;
-                // Convert byte position to character position
-                let char_pos = line[..abs_byte_pos].chars().count();
-                results.push((line_idx, char_pos));
-                // Move past this match
-                search_start = abs_byte_pos + query.len();
-            }
-        }
-        results
-    }
-
-    /// Find the next occurrence of a query string starting from a position
-    /// Returns the (line, column) position or None if not found
-    pub fn find_next(
-        &self,
-        query: &str,
-        from_line: usize,
-        from_column: usize,
-    ) -> Option<(usize, usize)> {
-        if query.is_empty() {
-            return None;
-        }
-
-        // Search from current line
-        for (line_idx, line) in self.lines.iter().enumerate().skip(from_line) {
-            let start_col = if line_idx == from_line {
-                // Start after current column position
-                self.char_to_byte_idx(line_idx, from_column + 1)
-            } else {
-                0
-            };
-
-            if start_col < line.len()
-                && let Some(byte_pos) = line[start_col..].find(query)
-            {
-                let abs_byte_pos = start_col + byte_pos;
-                let char_pos = line[..abs_byte_pos].chars().count();
-                return Some((line_idx, char_pos));
-            }
-        }
-
-        // Wrap around to beginning
-        for (line_idx, line) in self.lines.iter().enumerate().take(from_line + 1) {
-            let end_col = if line_idx == from_line {
-                self.char_to_byte_idx(line_idx, from_column)
-            } else {
-                line.len()
-            };
-
-            if let Some(byte_pos) = line[..end_col].find(query) {
-                let char_pos = line[..byte_pos].chars().count();
-                return Some((line_idx, char_pos));
-            }
-        }
-
-        None
-    }
-
-    /// Find the previous occurrence of a query string starting from a position
-    /// Returns the (line, column) position or None if not found
-    pub fn find_prev(
-        &self,
-        query: &str,
-        from_line: usize,
-        from_column: usize,
-    ) -> Option<(usize, usize)> {
-        if query.is_empty() {
-            return None;
-        }
-
-        // Search backwards from current line
-        for line_idx in (0..=from_line).rev() {
-            let line = &self.lines[line_idx];
-            let end_col = if line_idx == from_line {
-                self.char_to_byte_idx(line_idx, from_column)
-            } else {
-                line.len()
-            };
-
-            // Find the last occurrence before end_col
-            if let Some(byte_pos) = line[..end_col].rfind(query) {
-                let char_pos = line[..byte_pos].chars().count();
-                return Some((line_idx, char_pos));
-            }
-        }
-
-        // Wrap around to end
-        for line_idx in (from_line..self.lines.len()).rev() {
-            let line = &self.lines[line_idx];
-            let start_col = if line_idx == from_line {
-                self.char_to_byte_idx(line_idx, from_column + 1)
-            } else {
-                0
-            };
-
-            if start_col < line.len()
-                && let Some(byte_pos) = line[start_col..].rfind(query)
-            {
-                let abs_byte_pos = start_col + byte_pos;
-                let char_pos = line[..abs_byte_pos].chars().count();
-                return Some((line_idx, char_pos));
-            }
-        }
-
-        None
-    }
-
     /// Replace text at a specific position
     /// Returns true if replacement was successful
     pub fn replace_at(
@@ -945,28 +901,140 @@ impl DocumentBuffer {
         true
     }
 
-    /// Replace all occurrences of old_text with new_text
-    /// Returns the number of replacements made
-    pub fn replace_all(&mut self, old_text: &str, new_text: &str) -> usize {
-        if old_text.is_empty() {
-            return 0;
+    /// Replace the lines `start..end` with `new_lines` as a single undoable edit
+    /// Returns false if the range is empty or out of bounds
+    pub fn replace_lines(&mut self, start: usize, end: usize, new_lines: &[String]) -> bool {
+        if start >= end || end > self.lines.len() || new_lines.is_empty() {
+            return false;
         }
 
+        let old_text = self.lines[start..end].join("\n");
+        let new_text = new_lines.join("\n");
+        if old_text == new_text {
+            return true;
+        }
+
+        if self.recording {
+            let cursor_before = self.cursor;
+            self.history.push(
+                EditAction::ReplaceRange {
+                    line: start,
+                    column: 0,
+                    old_text,
+                    new_text,
+                },
+                cursor_before,
+            );
+        }
+
+        self.lines.splice(start..end, new_lines.iter().cloned());
+        let max_line = self.lines.len().saturating_sub(1);
+        self.cursor.line = self.cursor.line.min(max_line);
+        self.clamp_cursor_column();
+        self.modified = true;
+        self.rebuild_document();
+        true
+    }
+
+    /// Find all matches of a compiled search pattern within a scope
+    ///
+    /// Scopes other than `SearchScope::All` only apply to Markdown documents.
+    pub fn search(&self, pattern: &SearchPattern, scope: &SearchScope) -> Vec<SearchMatch> {
+        let matches = pattern.find_all(&self.lines);
+        match self.document_type.markdown_ast() {
+            Some(ast) => scope.filter(matches, ast, &self.lines),
+            None => matches,
+        }
+    }
+
+    /// Find the source ranges of the Markdown nodes selected by an mq query
+    pub fn search_mq_query(&self, query: &str) -> Result<Vec<SearchMatch>, String> {
+        super::find_by_mq_query(query, &self.lines)
+    }
+
+    /// Replace a single search match, expanding capture groups in regex mode
+    /// Returns true if replacement was successful
+    pub fn replace_match(
+        &mut self,
+        pattern: &SearchPattern,
+        search_match: &SearchMatch,
+        replacement: &str,
+    ) -> bool {
+        if search_match.line >= self.lines.len() {
+            return false;
+        }
+
+        let content = self.content();
+        let offset = self.content_byte_offset(search_match.line, search_match.column);
+        let Some((old_text, new_text)) = pattern.replacement_at(&content, offset, replacement)
+        else {
+            return false;
+        };
+
+        if self.recording {
+            let cursor_before = self.cursor;
+            self.history.push(
+                EditAction::ReplaceRange {
+                    line: search_match.line,
+                    column: search_match.column,
+                    old_text: old_text.clone(),
+                    new_text: new_text.clone(),
+                },
+                cursor_before,
+            );
+        }
+
+        self.splice_content(offset, old_text.len(), &new_text);
+        self.modified = true;
+        self.rebuild_document();
+        true
+    }
+
+    /// Replace all matches of a compiled search pattern within a scope as a single undoable edit
+    /// Returns the number of replacements made
+    pub fn replace_all_matches(
+        &mut self,
+        pattern: &SearchPattern,
+        scope: &SearchScope,
+        replacement: &str,
+    ) -> usize {
+        let content = self.content();
+        let mut new_content = content.clone();
         let mut count = 0;
-        for line_idx in 0..self.lines.len() {
-            let line = &self.lines[line_idx];
-            if line.contains(old_text) {
-                let new_line = line.replace(old_text, new_text);
-                count += line.matches(old_text).count();
-                self.lines[line_idx] = new_line;
+
+        // Replace from the end so earlier offsets stay valid
+        for search_match in self.search(pattern, scope).iter().rev() {
+            let offset = self.content_byte_offset(search_match.line, search_match.column);
+            if let Some((old_text, new_text)) =
+                pattern.replacement_at(&content, offset, replacement)
+            {
+                new_content.replace_range(offset..offset + old_text.len(), &new_text);
+                count += 1;
             }
         }
+        if count == 0 {
+            return 0;
+        }
 
-        if count > 0 {
-            self.modified = true;
-            self.rebuild_document();
+        if self.recording {
+            let cursor_before = self.cursor;
+            self.history.push(
+                EditAction::ReplaceRange {
+                    line: 0,
+                    column: 0,
+                    old_text: content,
+                    new_text: new_content.clone(),
+                },
+                cursor_before,
+            );
         }
 
+        self.lines = new_content.split('\n').map(|s| s.to_string()).collect();
+        let max_line = self.lines.len().saturating_sub(1);
+        self.cursor.line = self.cursor.line.min(max_line);
+        self.clamp_cursor_column();
+        self.modified = true;
+        self.rebuild_document();
         count
     }
 }
@@ -1024,6 +1092,18 @@ mod tests {
         assert!(buffer.is_modified());
     }
 
+    #[test]
+    fn test_revision() {
+        let mut buffer = DocumentBuffer::from_string("Hello").unwrap();
+        buffer.move_cursor(CursorMovement::EndOfLine);
+        assert_eq!(buffer.revision(), 0);
+
+        buffer.insert_char('!');
+        assert_eq!(buffer.revision(), 1);
+        buffer.undo();
+        assert_eq!(buffer.revision(), 2);
+    }
+
     #[test]
     fn test_delete_char() {
         let mut buffer = DocumentBuffer::from_string("Hello!").unwrap();
@@ -1203,6 +1283,75 @@ mod tests {
         assert_eq!(buffer.display_width_to_column(0, 5), 10); // After 'は'
     }
 
+    #[test]
+    fn test_replace_match_with_captures_and_undo() {
+        use crate::document::{CaseMode, SearchOptions};
+
+        let mut buffer = DocumentBuffer::from_string("name: foo\nname: bar").unwrap();
+        let options = SearchOptions {
+            regex: true,
+            case_mode: CaseMode::Sensitive,
+            whole_word: false,
+        };
+        let pattern = SearchPattern::new(r"name: (\w+)", &options).unwrap();
+        let matches = buffer.search(&pattern, &SearchScope::All);
+        assert_eq!(matches.len(), 2);
+
+        assert!(buffer.replace_match(&pattern, &matches[1], "title: $1"));
+        assert_eq!(buffer.line(1), Some("title: bar"));
+
+        buffer.undo();
+        assert_eq!(buffer.line(1), Some("name: bar"));
+    }
+
+    #[test]
+    fn test_replace_all_matches_multi_line() {
+        use crate::document::{CaseMode, SearchOptions};
+
+        let mut buffer = DocumentBuffer::from_string("a\nb\na\nb").unwrap();
+        let options = SearchOptions {
+            regex: true,
+            case_mode: CaseMode::Sensitive,
+            whole_word: false,
+        };
+        let pattern = SearchPattern::new(r"a\nb", &options).unwrap();
+        assert_eq!(
+            buffer.replace_all_matches(&pattern, &SearchScope::All, "ab"),
+            2
+        );
+        assert_eq!(buffer.content(), "ab\nab");
+
+        buffer.undo();
+        assert_eq!(buffer.content(), "a\nb\na\nb");
+    }
+
+    #[test]
+    fn test_replace_all_matches_obeys_scope() {
+        use crate::document::SearchOptions;
+
+        let mut buffer = DocumentBuffer::from_string("# foo\n\nfoo\n\n```sh\nfoo\n```").unwrap();
+        let pattern = SearchPattern::new("foo", &SearchOptions::default()).unwrap();
+        assert_eq!(
+            buffer.replace_all_matches(&pattern, &SearchScope::Prose, "bar"),
+            2
+        );
+        assert_eq!(buffer.content(), "# bar\n\nbar\n\n```sh\nfoo\n```");
+    }
+
+    #[test]
+    fn test_replace_lines_and_undo() {
+        let mut buffer = DocumentBuffer::from_string("a\nb\nc\nd").unwrap();
+        let new_lines = vec!["x".to_string(), "y".to_string(), "z".to_string()];
+        assert!(buffer.replace_lines(1, 3, &new_lines));
+        assert_eq!(buffer.content(), "a\nx\ny\nz\nd");
+        assert!(!buffer.replace_lines(2, 2, &new_lines));
+
+        buffer.undo();
+        assert_eq!(buffer.content(), "a\nb\nc\nd");
+        buffer.redo();
+        assert_eq!(buffer.content(), "a\nx\ny\nz\nd");
+    }
+
     #[test]
     fn test_display_width_mixed() {
         let buffer = DocumentBuffer::from_string("Hello世界").unwrap();
@@ -1211,4 +1360,15 @@ mod tests {
         assert_eq!(buffer.display_width_to_column(0, 6), 7); // After "Hello世"
         assert_eq!(buffer.display_width_to_column(0, 7), 9); // After "Hello世界"
     }
+
+    #[test]
+    fn test_text_between() {
+        let buffer = DocumentBuffer::from_string("one two\nthree\nfour five").unwrap();
+        let text = buffer.text_between(Cursor::with_position(0, 4), Cursor::with_position(2, 4));
+        assert_eq!(text, "two\nthree\nfour");
+        let text = buffer.text_between(Cursor::with_position(1, 1), Cursor::with_position(1, 3));
+        assert_eq!(text, "hr");
+        let text = buffer.text_between(Cursor::with_position(2, 5), Cursor::with_position(9, 0));
+        assert_eq!(text, "");
+    }
 }
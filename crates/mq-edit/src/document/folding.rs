@@ -0,0 +1,487 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::{DocumentType, LineAnalyzer, LineType};
+
+/// Columns a tab counts for when measuring indentation
+const TAB_WIDTH: usize = 4;
+
+/// Kind of region a fold range covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldKind {
+    /// Heading and its section, up to the next heading of the same or higher level
+    Heading(usize),
+    /// List item and its nested items
+    List,
+    CodeBlock,
+    Blockquote,
+    FrontMatter,
+    /// Line followed by more deeply indented lines (code files)
+    Indent,
+}
+
+/// Lines that can be folded under a header line
+///
+/// The header `start` stays visible; lines `start + 1..end` are hidden when folded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldRange {
+    pub start: usize,
+    pub end: usize,
+    pub kind: FoldKind,
+}
+
+impl FoldRange {
+    /// Lines hidden when the range is folded
+    pub fn hidden(&self) -> Range<usize> {
+        self.start + 1..self.end
+    }
+}
+
+/// Fold ranges of a document, ordered by start line
+pub fn fold_ranges<S: AsRef<str>>(lines: &[S], document_type: &DocumentType) -> Vec<FoldRange> {
+    let mut ranges = match document_type {
+        DocumentType::Markdown { .. } => markdown_ranges(lines),
+        DocumentType::Code { .. } => indent_ranges(lines),
+        DocumentType::PlainText => Vec::new(),
+    };
+    ranges.sort_by_key(|range| (range.start, std::cmp::Reverse(range.end)));
+    ranges
+}
+
+/// Fold ranges of a Markdown document
+///
+/// Lines inside front matter and code blocks only fold as part of their block.
+fn markdown_ranges<S: AsRef<str>>(lines: &[S]) -> Vec<FoldRange> {
+    let mut ranges = Vec::new();
+    let types = markdown_line_types(lines, &mut ranges);
+    let is_blank = |idx: usize| lines[idx].as_ref().trim().is_empty();
+    // End of a range, leaving out trailing blank lines
+    let trim_end = |start: usize, mut end: usize| {
+        while end > start + 1 && is_blank(end - 1) {
+            end -= 1;
+        }
+        end
+    };
+
+    for (idx, line_type) in types.iter().enumerate() {
+        match line_type {
+            Some(LineType::Heading(level)) => {
+                let end = (idx + 1..lines.len())
+                    .find(|&next| {
+                        matches!(types[next], Some(LineType::Heading(other)) if other <= *level)
+                    })
+                    .unwrap_or(lines.len());
+                ranges.push(FoldRange {
+                    start: idx,
+                    end: trim_end(idx, end),
+                    kind: FoldKind::Heading(*level),
+                });
+            }
+            Some(LineType::ListItem | LineType::OrderedListItem | LineType::TaskListItem(_)) => {
+                let indent = indent_width(lines[idx].as_ref());
+                let end = (idx + 1..lines.len())
+                    .find(|&next| {
+                        !is_blank(next)
+                            && (types[next].is_none()
+                                || indent_width(lines[next].as_ref()) <= indent)
+                    })
+                    .unwrap_or(lines.len());
+                ranges.push(FoldRange {
+                    start: idx,
+                    end: trim_end(idx, end),
+                    kind: FoldKind::List,
+                });
+            }
+            Some(LineType::Blockquote)
+                if idx == 0 || !matches!(types[idx - 1], Some(LineType::Blockquote)) =>
+            {
+                let end = (idx + 1..lines.len())
+                    .find(|&next| !matches!(types[next], Some(LineType::Blockquote)))
+                    .unwrap_or(lines.len());
+                ranges.push(FoldRange {
+                    start: idx,
+                    end,
+                    kind: FoldKind::Blockquote,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    ranges.retain(|range| range.end > range.start + 1);
+    ranges
+}
+
+/// Line types of a Markdown document, with front matter and code blocks added to `ranges`
+///
+/// Lines inside those blocks have no type.
+fn markdown_line_types<S: AsRef<str>>(
+    lines: &[S],
+    ranges: &mut Vec<FoldRange>,
+) -> Vec<Option<LineType>> {
+    let mut types = vec![None; lines.len()];
+    let mut idx = 0;
+
+    // Front matter is only recognized at the top of the document
+    if let Some(delimiter) = lines
+        .first()
+        .map(|line| line.as_ref())
+        .filter(|line| *line == "---" || *line == "+++")
+        && let Some(close) = lines[1..]
+            .iter()
+            .position(|line| line.as_ref() == delimiter)
+    {
+        ranges.push(FoldRange {
+            start: 0,
+            end: close + 2,
+            kind: FoldKind::FrontMatter,
+        });
+        idx = close + 2;
+    }
+
+    let mut open_fence: Option<usize> = None;
+    while idx < lines.len() {
+        let line_type = LineAnalyzer::analyze_line(lines[idx].as_ref());
+        match (open_fence, line_type) {
+            (Some(fence), LineType::CodeFence(_)) => {
+                ranges.push(FoldRange {
+                    start: fence,
+                    end: idx + 1,
+                    kind: FoldKind::CodeBlock,
+                });
+                open_fence = None;
+            }
+            (Some(_), _) => {}
+            (None, LineType::CodeFence(_)) => open_fence = Some(idx),
+            // Quote lines without text after the marker
+            (None, LineType::Text) if lines[idx].as_ref().trim() == ">" => {
+                types[idx] = Some(LineType::Blockquote);
+            }
+            (None, line_type) => types[idx] = Some(line_type),
+        }
+        idx += 1;
+    }
+    if let Some(fence) = open_fence {
+        ranges.push(FoldRange {
+            start: fence,
+            end: lines.len(),
+            kind: FoldKind::CodeBlock,
+        });
+    }
+    types
+}
+
+/// Indentation-based fold ranges for code files
+fn indent_ranges<S: AsRef<str>>(lines: &[S]) -> Vec<FoldRange> {
+    let indents: Vec<Option<usize>> = lines
+        .iter()
+        .map(|line| {
+            let line = line.as_ref();
+            (!line.trim().is_empty()).then(|| indent_width(line))
+        })
+        .collect();
+
+    let mut ranges = Vec::new();
+    for (idx, indent) in indents.iter().enumerate() {
+        let Some(indent) = *indent else {
+            continue;
+        };
+        // The range ends after the last deeper line before a line at this indent or less
+        let mut end = idx + 1;
+        for (next, next_indent) in indents.iter().enumerate().skip(idx + 1) {
+            match next_indent {
+                Some(next_indent) if *next_indent > indent => end = next + 1,
+                Some(_) => break,
+                None => {}
+            }
+        }
+        if end > idx + 1 {
+            ranges.push(FoldRange {
+                start: idx,
+                end,
+                kind: FoldKind::Indent,
+            });
+        }
+    }
+    ranges
+}
+
+/// Display columns of a line's leading whitespace
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+/// Header line of a folded range
+///
+/// The line text is kept so the fold can be found again after lines above it
+/// were inserted or removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoldedLine {
+    pub line: usize,
+    pub text: String,
+}
+
+/// Foldable ranges of a document and the ones currently folded
+#[derive(Debug, Clone, Default)]
+pub struct Folds {
+    ranges: Vec<FoldRange>,
+    folded: Vec<FoldedLine>,
+}
+
+impl Folds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restore previously folded lines; they take effect on the next `sync`
+    pub fn with_folded(folded: Vec<FoldedLine>) -> Self {
+        Self {
+            ranges: Vec::new(),
+            folded,
+        }
+    }
+
+    /// Folded header lines
+    pub fn folded(&self) -> &[FoldedLine] {
+        &self.folded
+    }
+
+    /// Recompute fold ranges after the document changed
+    ///
+    /// A folded line whose text moved is matched to the nearest header line with
+    /// the same text. If no header has that text, a header still at the same line
+    /// (e.g. edited in place) keeps the fold; otherwise the fold is dropped.
+    pub fn sync(&mut self, lines: &[String], document_type: &DocumentType) {
+        self.ranges = fold_ranges(lines, document_type);
+
+        let starts: Vec<usize> = self.ranges.iter().map(|range| range.start).collect();
+        let mut folded: Vec<FoldedLine> = std::mem::take(&mut self.folded)
+            .into_iter()
+            .filter_map(|mut fold| {
+                let line = starts
+                    .iter()
+                    .copied()
+                    .filter(|&start| lines[start] == fold.text)
+                    .min_by_key(|&start| start.abs_diff(fold.line))
+                    .or_else(|| starts.binary_search(&fold.line).ok().map(|_| fold.line))?;
+                fold.line = line;
+                fold.text = lines[line].clone();
+                Some(fold)
+            })
+            .collect();
+        folded.sort_by_key(|fold| fold.line);
+        folded.dedup_by_key(|fold| fold.line);
+        self.folded = folded;
+    }
+
+    /// Outermost range starting at `line`
+    pub fn range_at(&self, line: usize) -> Option<&FoldRange> {
+        let idx = self.ranges.partition_point(|range| range.start < line);
+        self.ranges.get(idx).filter(|range| range.start == line)
+    }
+
+    /// Whether the range starting at `line` can be folded
+    pub fn is_foldable(&self, line: usize) -> bool {
+        self.range_at(line).is_some()
+    }
+
+    /// Whether the range starting at `line` is folded
+    pub fn is_folded(&self, line: usize) -> bool {
+        self.folded.iter().any(|fold| fold.line == line)
+    }
+
+    /// Folded ranges that hide `line`, outermost first
+    fn hiding(&self, line: usize) -> impl Iterator<Item = &FoldRange> {
+        self.folded
+            .iter()
+            .filter_map(|fold| self.range_at(fold.line))
+            .filter(move |range| range.hidden().contains(&line))
+    }
+
+    /// Whether `line` is inside a folded range
+    pub fn is_hidden(&self, line: usize) -> bool {
+        self.hiding(line).next().is_some()
+    }
+
+    /// Fold or unfold the range under `line`
+    ///
+    /// A folded range starting at `line` is unfolded; otherwise the innermost range
+    /// starting at or containing `line` is folded. Returns the header line of the
+    /// toggled range.
+    pub fn toggle(&mut self, line: usize, lines: &[String]) -> Option<usize> {
+        if self.is_folded(line) {
+            self.folded.retain(|fold| fold.line != line);
+            return Some(line);
+        }
+        let range = self
+            .ranges
+            .iter()
+            .filter(|range| range.start == line || range.hidden().contains(&line))
+            .filter(|range| !self.is_folded(range.start))
+            .max_by_key(|range| range.start)?;
+        let start = range.start;
+        self.folded.push(FoldedLine {
+            line: start,
+            text: lines[start].clone(),
+        });
+        self.folded.sort_by_key(|fold| fold.line);
+        Some(start)
+    }
+
+    /// Unfold every range that hides `line`; returns true if any was unfolded
+    pub fn reveal(&mut self, line: usize) -> bool {
+        let starts: Vec<usize> = self.hiding(line).map(|range| range.start).collect();
+        self.folded.retain(|fold| !starts.contains(&fold.line));
+        !starts.is_empty()
+    }
+
+    /// Unfold everything; returns true if anything was folded
+    pub fn unfold_all(&mut self) -> bool {
+        let changed = !self.folded.is_empty();
+        self.folded.clear();
+        changed
+    }
+
+    /// Number of lines hidden by the fold at `line`
+    pub fn hidden_count(&self, line: usize) -> usize {
+        match self.range_at(line) {
+            Some(range) if self.is_folded(line) => range.hidden().len(),
+            _ => 0,
+        }
+    }
+
+    /// First visible line at or after `line`
+    pub fn next_visible(&self, line: usize) -> usize {
+        self.hiding(line)
+            .map(|range| range.end)
+            .max()
+            .unwrap_or(line)
+    }
+
+    /// Last visible line at or before `line` (the header of the fold hiding it)
+    pub fn prev_visible(&self, line: usize) -> usize {
+        self.hiding(line)
+            .map(|range| range.start)
+            .min()
+            .unwrap_or(line)
+    }
+
+    /// Visible lines in `start..end`
+    pub fn visible_lines(&self, start: usize, end: usize) -> Vec<usize> {
+        let mut lines = Vec::new();
+        let mut line = self.next_visible(start);
+        while line < end {
+            lines.push(line);
+            line = self.next_visible(line + 1);
+        }
+        lines
+    }
+
+    /// End of the range covering `count` visible lines from `start`
+    pub fn visible_end(&self, start: usize, count: usize, line_count: usize) -> usize {
+        let mut line = start;
+        for _ in 0..count {
+            if line >= line_count {
+                break;
+            }
+            line = self.next_visible(line + 1);
+        }
+        line.min(line_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    fn markdown() -> DocumentType {
+        DocumentType::new_markdown("").unwrap()
+    }
+
+    fn spans(ranges: &[FoldRange]) -> Vec<(usize, usize, FoldKind)> {
+        ranges.iter().map(|r| (r.start, r.end, r.kind)).collect()
+    }
+
+    #[test]
+    fn test_markdown_ranges() {
+        let doc = lines(
+            "---\ntitle: x\n---\n# A\n\n- one\n  - nested\n- two\n\n## B\n```rust\n# not a heading\n```\n> quote\n> more\n# C\ntext",
+        );
+        let ranges = fold_ranges(&doc, &markdown());
+        assert_eq!(
+            spans(&ranges),
+            vec![
+                (0, 3, FoldKind::FrontMatter),
+                (3, 15, FoldKind::Heading(1)),
+                (5, 7, FoldKind::List),
+                (9, 15, FoldKind::Heading(2)),
+                (10, 13, FoldKind::CodeBlock),
+                (13, 15, FoldKind::Blockquote),
+                (15, 17, FoldKind::Heading(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_indent_ranges() {
+        let doc = lines("fn main() {\n    if x {\n        y();\n    }\n\n    z();\n}\n");
+        let ranges = fold_ranges(&doc, &DocumentType::new_code("rust".to_string()));
+        assert_eq!(
+            spans(&ranges),
+            vec![(0, 6, FoldKind::Indent), (1, 3, FoldKind::Indent)]
+        );
+    }
+
+    #[test]
+    fn test_toggle_and_visibility() {
+        let doc = lines("# A\na1\n## B\nb1\nb2\n# C\nc1");
+        let mut folds = Folds::new();
+        folds.sync(&doc, &markdown());
+
+        // Folding from inside a section folds the innermost range
+        assert_eq!(folds.toggle(3, &doc), Some(2));
+        assert!(folds.is_hidden(4) && !folds.is_hidden(2));
+        assert_eq!(folds.hidden_count(2), 2);
+        assert_eq!(folds.toggle(0, &doc), Some(0));
+        assert_eq!(folds.visible_lines(0, doc.len()), vec![0, 5, 6]);
+        assert_eq!(folds.next_visible(1), 5);
+        assert_eq!(folds.prev_visible(4), 0);
+        assert_eq!(folds.visible_end(0, 2, doc.len()), 6);
+
+        // Revealing a line unfolds every range around it
+        assert!(folds.reveal(3));
+        assert!(!folds.is_folded(0) && !folds.is_folded(2));
+        assert!(!folds.reveal(3));
+    }
+
+    #[test]
+    fn test_sync_follows_moved_header() {
+        let mut doc = lines("intro\n# A\na1\n# B\nb1");
+        let mut folds = Folds::new();
+        folds.sync(&doc, &markdown());
+        folds.toggle(3, &doc);
+
+        doc.insert(0, "new line".to_string());
+        folds.sync(&doc, &markdown());
+        assert_eq!(folds.folded()[0].line, 4);
+        assert!(folds.is_hidden(5));
+
+        // Editing the header in place keeps the fold
+        doc[4] = "# B2".to_string();
+        folds.sync(&doc, &markdown());
+        assert!(folds.is_folded(4));
+
+        // The fold is dropped when its header no longer exists
+        doc[4] = "plain text".to_string();
+        folds.sync(&doc, &markdown());
+        assert!(folds.folded().is_empty());
+    }
+}
@@ -0,0 +1,300 @@
+use markdown_lsp::front_matter::{
+    self, FieldType, FieldValue, FrontMatter, FrontMatterFormat, FrontMatterSchema,
+};
+use miette::Result;
+
+/// Field of the front matter form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormField {
+    pub name: String,
+    pub field_type: FieldType,
+    /// Text being edited; list items are separated by commas
+    pub value: String,
+    /// Text the field started with, so untouched fields keep their formatting
+    original: String,
+}
+
+impl FormField {
+    pub fn is_modified(&self) -> bool {
+        self.value != self.original
+    }
+}
+
+/// State of the form for editing the schema's front matter fields
+#[derive(Debug, Clone)]
+pub struct FrontMatterForm {
+    fields: Vec<FormField>,
+    selected: usize,
+}
+
+impl FrontMatterForm {
+    /// Fill the form from a document's front matter
+    ///
+    /// Fails when the block does not parse, as rewriting it could lose content.
+    pub fn new<S: AsRef<str>>(lines: &[S], schema: &FrontMatterSchema) -> Result<Self> {
+        let front_matter = FrontMatter::parse(lines);
+        if let Some((line, message)) = front_matter.as_ref().and_then(|fm| fm.error.as_ref()) {
+            return Err(miette::miette!(
+                "Invalid front matter at line {}: {}",
+                line + 1,
+                message
+            ));
+        }
+
+        let fields = schema
+            .fields
+            .iter()
+            .map(|field| {
+                let value = front_matter
+                    .as_ref()
+                    .and_then(|fm| fm.get(&field.name))
+                    .map(|field| match &field.value {
+                        FieldValue::List(_) => field.value.texts().join(", "),
+                        value => value.texts().join(""),
+                    })
+                    .unwrap_or_default();
+                FormField {
+                    name: field.name.clone(),
+                    field_type: field.field_type,
+                    original: value.clone(),
+                    value,
+                }
+            })
+            .collect();
+        Ok(Self {
+            fields,
+            selected: 0,
+        })
+    }
+
+    pub fn fields(&self) -> &[FormField] {
+        &self.fields
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn next_field(&mut self) {
+        if !self.fields.is_empty() {
+            self.selected = (self.selected + 1) % self.fields.len();
+        }
+    }
+
+    pub fn previous_field(&mut self) {
+        if !self.fields.is_empty() {
+            self.selected = (self.selected + self.fields.len() - 1) % self.fields.len();
+        }
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        if let Some(field) = self.fields.get_mut(self.selected) {
+            field.value.push(c);
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        if let Some(field) = self.fields.get_mut(self.selected) {
+            field.value.pop();
+        }
+    }
+
+    /// Rewrite the front matter block with the edited fields
+    ///
+    /// Returns the number of leading lines to replace (0 when the document has
+    /// no front matter yet) and the new block, or None when nothing changed.
+    /// Untouched keys keep their lines, emptied keys are removed and new keys
+    /// are added at the end of the block.
+    pub fn apply<S: AsRef<str>>(&self, lines: &[S]) -> Option<(usize, Vec<String>)> {
+        if !self.fields.iter().any(FormField::is_modified) {
+            return None;
+        }
+
+        let (format, end, mut block) = match FrontMatter::parse(lines) {
+            Some(fm) => (
+                fm.format,
+                fm.end_line + 1,
+                lines[..=fm.end_line]
+                    .iter()
+                    .map(|line| line.as_ref().to_string())
+                    .collect(),
+            ),
+            None => (
+                FrontMatterFormat::Yaml,
+                0,
+                vec!["---".to_string(), "---".to_string()],
+            ),
+        };
+        for field in self.fields.iter().filter(|field| field.is_modified()) {
+            set_field(&mut block, format, field);
+        }
+
+        // Leave a document without front matter alone if every field was cleared
+        if end == 0 && block.len() == 2 {
+            return None;
+        }
+        Some((end, block))
+    }
+}
+
+/// Replace, add or remove the lines of one key in a front matter block
+fn set_field(block: &mut Vec<String>, format: FrontMatterFormat, field: &FormField) {
+    let close = block.len() - 1;
+    let is_table = |line: &str| format == FrontMatterFormat::Toml && line.starts_with('[');
+    // TOML keys below a [table] header belong to the table
+    let keys_end = (1..close).find(|&i| is_table(&block[i])).unwrap_or(close);
+
+    let start = (1..keys_end)
+        .find(|&i| front_matter::line_key(&block[i], format).as_deref() == Some(&field.name));
+    let (start, end) = match start {
+        Some(start) => {
+            // Block lists and multi-line arrays continue until the next key
+            let end = (start + 1..keys_end)
+                .find(|&i| {
+                    let line = &block[i];
+                    line.trim().is_empty()
+                        || line.starts_with('#')
+                        || front_matter::line_key(line, format).is_some()
+                })
+                .unwrap_or(keys_end);
+            (start, end)
+        }
+        None => (keys_end, keys_end),
+    };
+
+    let block_list = format == FrontMatterFormat::Yaml && end - start > 1;
+    let replacement = if field.value.trim().is_empty() {
+        vec![]
+    } else {
+        field_lines(format, field, block_list)
+    };
+    block.splice(start..end, replacement);
+}
+
+/// Lines for a key and its value
+fn field_lines(format: FrontMatterFormat, field: &FormField, block_list: bool) -> Vec<String> {
+    let value = field.value.trim();
+    let key = match format {
+        FrontMatterFormat::Yaml => format!("{}:", field.name),
+        FrontMatterFormat::Toml => format!("{} =", field.name),
+    };
+    let quote = |text: &str| front_matter::quote_string(text, format);
+
+    let value = match field.field_type {
+        FieldType::List => {
+            let items: Vec<String> = value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(quote)
+                .collect();
+            if block_list {
+                let mut lines = vec![key];
+                lines.extend(items.iter().map(|item| format!("  - {}", item)));
+                return lines;
+            }
+            format!("[{}]", items.join(", "))
+        }
+        FieldType::Number if value.parse::<f64>().is_ok() => value.to_string(),
+        FieldType::Boolean if matches!(value, "true" | "false") => value.to_string(),
+        FieldType::Date if front_matter::is_date(value) => value.to_string(),
+        _ => quote(value),
+    };
+    vec![format!("{} {}", key, value)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(form: &mut FrontMatterForm, name: &str, value: &str) {
+        let field = form.fields.iter_mut().find(|f| f.name == name).unwrap();
+        field.value = value.to_string();
+    }
+
+    #[test]
+    fn test_apply_yaml_form() {
+        let lines = [
+            "---",
+            "title: Old",
+            "author: me",
+            "tags:",
+            "  - rust",
+            "date: 2024-05-01",
+            "---",
+            "# Body",
+        ];
+        let mut form = FrontMatterForm::new(&lines, &FrontMatterSchema::default()).unwrap();
+        let values: Vec<&str> = form.fields().iter().map(|f| f.value.as_str()).collect();
+        assert_eq!(values, vec!["Old", "2024-05-01", "rust"]);
+        assert_eq!(form.apply(&lines), None);
+
+        set(&mut form, "title", "New: notes");
+        set(&mut form, "tags", "rust, editor");
+        set(&mut form, "date", "");
+        assert_eq!(
+            form.apply(&lines),
+            Some((
+                7,
+                vec![
+                    "---".to_string(),
+                    "title: \"New: notes\"".to_string(),
+                    "author: me".to_string(),
+                    "tags:".to_string(),
+                    "  - rust".to_string(),
+                    "  - editor".to_string(),
+                    "---".to_string(),
+                ]
+            ))
+        );
+
+        form.next_field();
+        form.previous_field();
+        form.previous_field();
+        assert_eq!(form.selected(), 2);
+    }
+
+    #[test]
+    fn test_apply_new_and_toml_front_matter() {
+        let lines = ["# Body"];
+        let mut form = FrontMatterForm::new(&lines, &FrontMatterSchema::default()).unwrap();
+        set(&mut form, "title", "Notes");
+        set(&mut form, "tags", "a, b");
+        assert_eq!(
+            form.apply(&lines),
+            Some((
+                0,
+                vec![
+                    "---".to_string(),
+                    "title: Notes".to_string(),
+                    "tags: [a, b]".to_string(),
+                    "---".to_string(),
+                ]
+            ))
+        );
+
+        let lines = ["+++", "title = \"Notes\"", "[extra]", "x = 1", "+++"];
+        let mut form = FrontMatterForm::new(&lines, &FrontMatterSchema::default()).unwrap();
+        set(&mut form, "date", "2024-05-01");
+        set(&mut form, "tags", "rust");
+        let (end, block) = form.apply(&lines).unwrap();
+        assert_eq!(end, 5);
+        assert_eq!(
+            block,
+            vec![
+                "+++",
+                "title = \"Notes\"",
+                "date = 2024-05-01",
+                "tags = [\"rust\"]",
+                "[extra]",
+                "x = 1",
+                "+++",
+            ]
+        );
+
+        assert!(
+            FrontMatterForm::new(&["---", "tags: [a", "---"], &FrontMatterSchema::default())
+                .is_err()
+        );
+    }
+}
@@ -39,6 +39,13 @@ pub enum EditAction {
         old_text: String,
         new_text: String,
     },
+    /// Text spanning one or more lines was replaced (e.g. a multi-line regex match)
+    ReplaceRange {
+        line: usize,
+        column: usize,
+        old_text: String,
+        new_text: String,
+    },
 }
 
 /// Entry in the history stack, pairing an action with the cursor state before it
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use markdown_lsp::MarkdownLsp;
+use markdown_lsp::link_index::{has_scheme, percent_decode};
+use mq_markdown::{Markdown, Node};
+
+use super::outline::markdown_outline;
+
+/// Destination of a Markdown link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// URL with a scheme (`https:`, `mailto:`, ...), handed to the system opener
+    External(String),
+    /// Local file, with the heading anchor after `#` if any
+    File {
+        path: PathBuf,
+        anchor: Option<String>,
+    },
+    /// Heading anchor in the current document (`#anchor`)
+    Anchor(String),
+}
+
+impl LinkTarget {
+    /// Classify a link URL, resolving relative paths against `base_dir`
+    pub fn parse(url: &str, base_dir: &Path) -> Option<Self> {
+        let url = url.trim();
+        if url.is_empty() {
+            return None;
+        }
+        if has_scheme(url) {
+            return Some(Self::External(url.to_string()));
+        }
+        if let Some(anchor) = url.strip_prefix('#') {
+            return Some(Self::Anchor(percent_decode(anchor)));
+        }
+
+        let (path, anchor) = match url.split_once('#') {
+            Some((path, anchor)) => (path, Some(percent_decode(anchor))),
+            None => (url, None),
+        };
+        // Query strings only make sense to web servers
+        let path = path.split_once('?').map_or(path, |(path, _)| path);
+        Some(Self::File {
+            path: base_dir.join(percent_decode(path)),
+            anchor: anchor.filter(|anchor| !anchor.is_empty()),
+        })
+    }
+}
+
+/// URL of the link or image at a buffer position (byte column), resolving reference links
+pub fn link_url_at(markdown: &Markdown, line: usize, column: usize) -> Option<String> {
+    let node = markdown
+        .nodes
+        .iter()
+        .find_map(|node| link_node_at(node, line, column))?;
+
+    let ident = match &node {
+        Node::Link(link) => return Some(link.url.as_str().to_string()),
+        Node::Image(image) => return Some(image.url.clone()),
+        Node::LinkRef(link) => &link.ident,
+        Node::ImageRef(image) => &image.ident,
+        _ => return None,
+    };
+    markdown.nodes.iter().find_map(|node| match node {
+        Node::Definition(def) if def.ident.eq_ignore_ascii_case(ident) => {
+            Some(def.url.as_str().to_string())
+        }
+        _ => None,
+    })
+}
+
+/// Innermost link or image node containing a position
+fn link_node_at(node: &Node, line: usize, column: usize) -> Option<Node> {
+    let position = node.position()?;
+    let (start_line, end_line) = (position.start.line - 1, position.end.line - 1);
+    if line < start_line
+        || line > end_line
+        || (line == start_line && column < position.start.column - 1)
+        || (line == end_line && column >= position.end.column - 1)
+    {
+        return None;
+    }
+
+    match node {
+        Node::Link(_) | Node::Image(_) | Node::LinkRef(_) | Node::ImageRef(_) => Some(node.clone()),
+        _ => node
+            .children()
+            .iter()
+            .find_map(|child| link_node_at(child, line, column)),
+    }
+}
+
+/// Line of the heading whose slug matches an anchor
+pub fn heading_line(markdown: &Markdown, anchor: &str) -> Option<usize> {
+    markdown_outline(markdown)
+        .into_iter()
+        .find(|entry| MarkdownLsp::make_heading_slug(&entry.title).eq_ignore_ascii_case(anchor))
+        .map(|entry| entry.line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_link_target() {
+        let base = Path::new("/notes");
+        assert_eq!(
+            LinkTarget::parse("https://mqlang.org", base),
+            Some(LinkTarget::External("https://mqlang.org".to_string()))
+        );
+        assert_eq!(
+            LinkTarget::parse("mailto:me@example.com", base),
+            Some(LinkTarget::External("mailto:me@example.com".to_string()))
+        );
+        assert_eq!(
+            LinkTarget::parse("#Getting%20Started", base),
+            Some(LinkTarget::Anchor("Getting Started".to_string()))
+        );
+        assert_eq!(
+            LinkTarget::parse("../guide/Setup%20Guide.md#install", base),
+            Some(LinkTarget::File {
+                path: PathBuf::from("/notes/../guide/Setup Guide.md"),
+                anchor: Some("install".to_string()),
+            })
+        );
+        assert_eq!(
+            LinkTarget::parse("img/logo.png?raw=true", base),
+            Some(LinkTarget::File {
+                path: PathBuf::from("/notes/img/logo.png"),
+                anchor: None,
+            })
+        );
+        assert_eq!(LinkTarget::parse(" ", base), None);
+    }
+
+    #[test]
+    fn test_link_url_at() {
+        let markdown = Markdown::from_markdown_str(
+            "# Title\n\n- é [guide](guide.md#setup) ![logo](logo.png)\n\nSee [the docs][docs].\n\n[docs]: https://mqlang.org\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            link_url_at(&markdown, 2, 6).as_deref(),
+            Some("guide.md#setup")
+        );
+        assert_eq!(link_url_at(&markdown, 2, 32).as_deref(), Some("logo.png"));
+        assert_eq!(link_url_at(&markdown, 2, 2), None);
+        assert_eq!(
+            link_url_at(&markdown, 4, 8).as_deref(),
+            Some("https://mqlang.org")
+        );
+        assert_eq!(heading_line(&markdown, "title"), Some(0));
+    }
+}
@@ -0,0 +1,283 @@
+use lsp_types::{DocumentSymbol, DocumentSymbolResponse};
+use mq_markdown::{Markdown, Node};
+
+/// Heading or symbol shown in the outline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineEntry {
+    /// Nesting level, starting at 1 (heading depth for Markdown)
+    pub level: usize,
+    pub title: String,
+    /// Buffer line the entry starts on
+    pub line: usize,
+}
+
+/// Outline entries of a Markdown document, from its headings
+pub fn markdown_outline(markdown: &Markdown) -> Vec<OutlineEntry> {
+    markdown
+        .nodes
+        .iter()
+        .filter_map(|node| {
+            let Node::Heading(heading) = node else {
+                return None;
+            };
+            let position = node.position()?;
+            Some(OutlineEntry {
+                level: heading.depth as usize,
+                title: heading.values.iter().map(|n| n.value()).collect(),
+                line: position.start.line.saturating_sub(1),
+            })
+        })
+        .collect()
+}
+
+/// Outline entries from a language server's document symbols
+pub fn symbol_outline(response: DocumentSymbolResponse) -> Vec<OutlineEntry> {
+    let mut entries = Vec::new();
+    match response {
+        DocumentSymbolResponse::Flat(symbols) => {
+            entries.extend(symbols.into_iter().map(|symbol| OutlineEntry {
+                level: 1,
+                title: symbol.name,
+                line: symbol.location.range.start.line as usize,
+            }));
+        }
+        DocumentSymbolResponse::Nested(symbols) => flatten_symbols(symbols, 1, &mut entries),
+    }
+    entries.sort_by_key(|entry| entry.line);
+    entries
+}
+
+fn flatten_symbols(symbols: Vec<DocumentSymbol>, level: usize, entries: &mut Vec<OutlineEntry>) {
+    for symbol in symbols {
+        entries.push(OutlineEntry {
+            level,
+            title: symbol.name,
+            line: symbol.selection_range.start.line as usize,
+        });
+        if let Some(children) = symbol.children {
+            flatten_symbols(children, level + 1, entries);
+        }
+    }
+}
+
+/// Outline of the current document with a filter and a selected entry
+#[derive(Debug, Clone, Default)]
+pub struct Outline {
+    entries: Vec<OutlineEntry>,
+    filter: String,
+    /// Indices of the entries matching the filter
+    visible: Vec<usize>,
+    /// Index into `visible`
+    selected: usize,
+}
+
+impl Outline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the entries, keeping the filter and the selected entry if it still exists
+    /// Returns true if the entries changed
+    pub fn set_entries(&mut self, entries: Vec<OutlineEntry>) -> bool {
+        if entries == self.entries {
+            return false;
+        }
+        let selected = self.selected_entry().cloned();
+        self.entries = entries;
+        self.update_visible();
+        if let Some(selected) = selected {
+            self.select_nearest(&selected);
+        }
+        true
+    }
+
+    /// All entries, ordered by line
+    pub fn entries(&self) -> &[OutlineEntry] {
+        &self.entries
+    }
+
+    /// Entries matching the filter with their index in `entries`
+    pub fn visible(&self) -> impl Iterator<Item = (usize, &OutlineEntry)> {
+        self.visible.iter().map(|&idx| (idx, &self.entries[idx]))
+    }
+
+    pub fn visible_count(&self) -> usize {
+        self.visible.len()
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn push_filter(&mut self, c: char) {
+        self.filter.push(c);
+        self.update_visible();
+    }
+
+    pub fn pop_filter(&mut self) {
+        self.filter.pop();
+        self.update_visible();
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.update_visible();
+    }
+
+    /// Position of the selected entry among the visible ones
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_entry(&self) -> Option<&OutlineEntry> {
+        self.visible
+            .get(self.selected)
+            .map(|&idx| &self.entries[idx])
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.selected + 1 < self.visible.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Index in `entries` of the section containing a line
+    pub fn current(&self, line: usize) -> Option<usize> {
+        self.entries
+            .partition_point(|entry| entry.line <= line)
+            .checked_sub(1)
+    }
+
+    /// Select the section containing a line, if it matches the filter
+    pub fn select_line(&mut self, line: usize) {
+        if let Some(current) = self.current(line)
+            && let Some(pos) = self.visible.iter().position(|&idx| idx == current)
+        {
+            self.selected = pos;
+        }
+    }
+
+    fn update_visible(&mut self) {
+        let filter = self.filter.to_lowercase();
+        self.visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.title.to_lowercase().contains(&filter))
+            .map(|(idx, _)| idx)
+            .collect();
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    /// Select the entry with the same title closest to `entry`, else the one on its line
+    fn select_nearest(&mut self, entry: &OutlineEntry) {
+        let same_title = self
+            .visible
+            .iter()
+            .enumerate()
+            .filter(|&(_, &idx)| self.entries[idx].title == entry.title)
+            .min_by_key(|&(_, &idx)| self.entries[idx].line.abs_diff(entry.line))
+            .map(|(pos, _)| pos);
+        match same_title {
+            Some(pos) => self.selected = pos,
+            None => self.select_line(entry.line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{Position, Range, SymbolKind};
+
+    fn entry(level: usize, title: &str, line: usize) -> OutlineEntry {
+        OutlineEntry {
+            level,
+            title: title.to_string(),
+            line,
+        }
+    }
+
+    #[test]
+    fn test_markdown_outline() {
+        let markdown =
+            Markdown::from_markdown_str("# Intro\n\ntext\n\n## Usage\n\n### `mq` CLI\n").unwrap();
+        assert_eq!(
+            markdown_outline(&markdown),
+            vec![
+                entry(1, "Intro", 0),
+                entry(2, "Usage", 4),
+                entry(3, "mq CLI", 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbol_outline_nested() {
+        let range = |line| Range {
+            start: Position { line, character: 0 },
+            end: Position { line, character: 1 },
+        };
+        #[allow(deprecated)]
+        let symbol = |name: &str, line, children| DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            deprecated: None,
+            range: range(line),
+            selection_range: range(line),
+            children,
+        };
+        let response = DocumentSymbolResponse::Nested(vec![
+            symbol("Editor", 2, Some(vec![symbol("new", 4, None)])),
+            symbol("main", 0, None),
+        ]);
+        assert_eq!(
+            symbol_outline(response),
+            vec![
+                entry(1, "main", 0),
+                entry(1, "Editor", 2),
+                entry(2, "new", 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_and_current() {
+        let mut outline = Outline::new();
+        outline.set_entries(vec![
+            entry(1, "Intro", 0),
+            entry(2, "Install", 4),
+            entry(2, "Usage", 9),
+        ]);
+        assert_eq!(outline.current(0), Some(0));
+        assert_eq!(outline.current(6), Some(1));
+        assert_eq!(outline.current(20), Some(2));
+
+        outline.push_filter('u');
+        outline.push_filter('s');
+        assert_eq!(outline.visible_count(), 1);
+        assert_eq!(outline.selected_entry().unwrap().title, "Usage");
+        outline.clear_filter();
+        outline.select_line(5);
+        assert_eq!(outline.selected_entry().unwrap().title, "Install");
+    }
+
+    #[test]
+    fn test_set_entries_keeps_selection() {
+        let mut outline = Outline::new();
+        outline.set_entries(vec![entry(1, "A", 0), entry(1, "B", 3)]);
+        outline.move_down();
+
+        // A heading inserted above shifts B down
+        let shifted = vec![entry(1, "A", 0), entry(1, "New", 2), entry(1, "B", 5)];
+        assert!(outline.set_entries(shifted));
+        assert_eq!(outline.selected_entry().unwrap().title, "B");
+        assert!(!outline.set_entries(outline.entries().to_vec()));
+    }
+}
@@ -0,0 +1,685 @@
+use mq_markdown::{Markdown, Node, Point};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Case sensitivity mode for search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseMode {
+    /// Match case exactly
+    #[default]
+    Sensitive,
+    /// Ignore case
+    Insensitive,
+    /// Ignore case unless the query contains an uppercase character
+    Smart,
+}
+
+impl CaseMode {
+    /// Cycle to the next case mode (Sensitive -> Insensitive -> Smart)
+    pub fn next(self) -> Self {
+        match self {
+            CaseMode::Sensitive => CaseMode::Insensitive,
+            CaseMode::Insensitive => CaseMode::Smart,
+            CaseMode::Smart => CaseMode::Sensitive,
+        }
+    }
+
+    /// Short label shown in the search dialog
+    pub fn label(self) -> &'static str {
+        match self {
+            CaseMode::Sensitive => "Case",
+            CaseMode::Insensitive => "Ignore case",
+            CaseMode::Smart => "Smart case",
+        }
+    }
+}
+
+/// Options controlling how a search query is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchOptions {
+    /// Interpret the query as a regular expression
+    pub regex: bool,
+    /// Case sensitivity mode
+    pub case_mode: CaseMode,
+    /// Only match whole words
+    pub whole_word: bool,
+}
+
+/// A single search match (0-indexed, columns are character positions)
+///
+/// The end position is exclusive. Regex matches may span multiple lines,
+/// in which case `end_line` is greater than `line`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl SearchMatch {
+    /// Check whether this match covers the given character position
+    pub fn contains(&self, line: usize, column: usize) -> bool {
+        (line, column) >= (self.line, self.column)
+            && (line, column) < (self.end_line, self.end_column)
+    }
+}
+
+/// A compiled search query
+#[derive(Debug, Clone)]
+pub struct SearchPattern {
+    regex: Regex,
+    /// Whether `$n` references in replacements are expanded
+    expand_captures: bool,
+}
+
+/// Whether a query contains an uppercase letter for smart case
+///
+/// In regex mode escapes such as `\S`, `\W` and `\p{Lu}` are not letters the
+/// user typed, so they are skipped.
+fn has_uppercase(query: &str, regex: bool) -> bool {
+    if !regex {
+        return query.chars().any(char::is_uppercase);
+    }
+
+    let mut chars = query.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            // `\pL` names a class by one letter, `\p{Lu}` by a braced name
+            if let Some('p' | 'P') = chars.next()
+                && chars.next() == Some('{')
+            {
+                chars.by_ref().take_while(|&c| c != '}').for_each(drop);
+            }
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+impl SearchPattern {
+    /// Compile a query with the given options
+    ///
+    /// Returns a human-readable error message if the regex is invalid.
+    pub fn new(query: &str, options: &SearchOptions) -> Result<Self, String> {
+        let mut pattern = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+
+        if options.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        let case_insensitive = match options.case_mode {
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+            CaseMode::Smart => !has_uppercase(query, options.regex),
+        };
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(case_insensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|e| match e {
+                regex::Error::Syntax(msg) => msg
+                    .lines()
+                    .last()
+                    .unwrap_or("invalid regex")
+                    .trim_start_matches("error: ")
+                    .to_string(),
+                other => other.to_string(),
+            })?;
+
+        Ok(Self {
+            regex,
+            expand_captures: options.regex,
+        })
+    }
+
+    /// Find all non-empty matches in the given lines
+    ///
+    /// The lines are searched as a single `\n`-joined text so that regexes
+    /// can match across line boundaries.
+    pub fn find_all(&self, lines: &[String]) -> Vec<SearchMatch> {
+        let text = lines.join("\n");
+        let index = LineIndex::new(&text);
+
+        self.regex
+            .find_iter(&text)
+            .filter(|m| !m.is_empty())
+            .map(|m| {
+                let (line, column) = index.position(&text, m.start());
+                let (end_line, end_column) = index.position(&text, m.end());
+                SearchMatch {
+                    line,
+                    column,
+                    end_line,
+                    end_column,
+                }
+            })
+            .collect()
+    }
+
+    /// Compute the matched text and its replacement for a match starting at `start`
+    ///
+    /// Returns `None` if the pattern no longer matches at that position.
+    pub fn replacement_at(
+        &self,
+        text: &str,
+        start: usize,
+        replacement: &str,
+    ) -> Option<(String, String)> {
+        let captures = self.regex.captures_at(text, start)?;
+        let matched = captures.get(0)?;
+        if matched.start() != start || matched.is_empty() {
+            return None;
+        }
+
+        let new_text = if self.expand_captures {
+            let mut expanded = String::new();
+            captures.expand(replacement, &mut expanded);
+            expanded
+        } else {
+            replacement.to_string()
+        };
+
+        Some((matched.as_str().to_string(), new_text))
+    }
+}
+
+/// Restricts search matches to parts of a Markdown document
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SearchScope {
+    /// Match anywhere
+    #[default]
+    All,
+    /// Only inside headings
+    Headings,
+    /// Only inside link, image and definition URLs
+    LinkUrls,
+    /// Only outside code blocks, inline code and front matter
+    Prose,
+    /// Only inside code fences of the given language
+    Code(String),
+}
+
+/// A source range with 0-indexed lines and character columns (end exclusive)
+type Region = ((usize, usize), (usize, usize));
+
+impl SearchScope {
+    /// Label shown in the search dialog
+    pub fn label(&self) -> String {
+        match self {
+            SearchScope::All => "All".to_string(),
+            SearchScope::Headings => "Headings".to_string(),
+            SearchScope::LinkUrls => "Link URLs".to_string(),
+            SearchScope::Prose => "Prose".to_string(),
+            SearchScope::Code(lang) => format!("Code: {}", lang),
+        }
+    }
+
+    /// Scopes available for a document: the fixed kinds plus one per code fence language
+    pub fn available(ast: &Markdown) -> Vec<SearchScope> {
+        let mut scopes = vec![
+            SearchScope::All,
+            SearchScope::Headings,
+            SearchScope::LinkUrls,
+            SearchScope::Prose,
+        ];
+        for node in &ast.nodes {
+            Self::collect_code_scopes(node, &mut scopes);
+        }
+        scopes
+    }
+
+    /// Add a scope for the language of each code fence in a node and its children
+    fn collect_code_scopes(node: &Node, scopes: &mut Vec<SearchScope>) {
+        if let Node::Code(code) = node {
+            if let Some(lang) = &code.lang {
+                let scope = SearchScope::Code(lang.clone());
+                if !scopes.contains(&scope) {
+                    scopes.push(scope);
+                }
+            }
+            return;
+        }
+        for child in node.children() {
+            Self::collect_code_scopes(&child, scopes);
+        }
+    }
+
+    /// Keep only the matches that lie within this scope
+    pub fn filter(
+        &self,
+        matches: Vec<SearchMatch>,
+        ast: &Markdown,
+        lines: &[String],
+    ) -> Vec<SearchMatch> {
+        let start = |m: &SearchMatch| (m.line, m.column);
+        let end = |m: &SearchMatch| (m.end_line, m.end_column);
+
+        match self {
+            SearchScope::All => matches,
+            SearchScope::Prose => {
+                let excluded = self.regions(ast, lines);
+                matches
+                    .into_iter()
+                    .filter(|m| {
+                        !excluded
+                            .iter()
+                            .any(|(rs, re)| start(m) < *re && *rs < end(m))
+                    })
+                    .collect()
+            }
+            _ => {
+                let included = self.regions(ast, lines);
+                matches
+                    .into_iter()
+                    .filter(|m| {
+                        included
+                            .iter()
+                            .any(|(rs, re)| *rs <= start(m) && end(m) <= *re)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Collect the regions this scope includes (or, for `Prose`, excludes)
+    fn regions(&self, ast: &Markdown, lines: &[String]) -> Vec<Region> {
+        let mut regions = Vec::new();
+        for node in &ast.nodes {
+            self.collect_regions(node, lines, &mut regions);
+        }
+        regions
+    }
+
+    fn collect_regions(&self, node: &Node, lines: &[String], regions: &mut Vec<Region>) {
+        let Some(pos) = node.position() else {
+            return;
+        };
+        let region = (
+            point_to_position(&pos.start, lines),
+            point_to_position(&pos.end, lines),
+        );
+
+        match (self, node) {
+            (SearchScope::Headings, Node::Heading(_)) => {
+                regions.push(region);
+                return;
+            }
+            (SearchScope::LinkUrls, Node::Link(link)) => {
+                regions.extend(url_region(link.url.as_str(), region, lines));
+            }
+            (SearchScope::LinkUrls, Node::Image(image)) => {
+                regions.extend(url_region(&image.url, region, lines));
+            }
+            (SearchScope::LinkUrls, Node::Definition(definition)) => {
+                regions.extend(url_region(definition.url.as_str(), region, lines));
+            }
+            (
+                SearchScope::Prose,
+                Node::Code(_) | Node::CodeInline(_) | Node::Yaml(_) | Node::Toml(_),
+            ) => {
+                regions.push(region);
+                return;
+            }
+            (SearchScope::Code(lang), Node::Code(code))
+                if code
+                    .lang
+                    .as_deref()
+                    .is_some_and(|l| l.eq_ignore_ascii_case(lang)) =>
+            {
+                // Exclude the opening and closing fence lines
+                let first = region.0.0 + 1;
+                let last = region.1.0;
+                let end = if last > region.0.0
+                    && lines.get(last).is_some_and(|l| {
+                        let t = l.trim_start();
+                        t.starts_with("```") || t.starts_with("~~~")
+                    }) {
+                    last
+                } else {
+                    last + 1
+                };
+                if first < end {
+                    regions.push(((first, 0), (end, 0)));
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        for child in node.children() {
+            self.collect_regions(&child, lines, regions);
+        }
+    }
+}
+
+/// Find the source ranges of the Markdown nodes selected by an mq query
+///
+/// Query results that are not Markdown nodes (e.g. strings produced by
+/// `to_text()`) or that have no source position are ignored.
+pub fn find_by_mq_query(query: &str, lines: &[String]) -> Result<Vec<SearchMatch>, String> {
+    let first_line = |e: String| e.lines().next().unwrap_or_default().to_string();
+
+    let content = lines.join("\n");
+    let input = mq_lang::parse_markdown_input(&content).map_err(|e| first_line(e.to_string()))?;
+
+    let mut engine = mq_lang::DefaultEngine::default();
+    engine.load_builtin_module();
+    let results = engine
+        .eval(query, input.into_iter())
+        .map_err(|e| first_line(e.to_string()))?;
+
+    let mut matches = Vec::new();
+    for value in results {
+        collect_query_matches(&value, lines, &mut matches);
+    }
+    matches.sort_by_key(|m| (m.line, m.column, m.end_line, m.end_column));
+    matches.dedup();
+    Ok(matches)
+}
+
+fn collect_query_matches(
+    value: &mq_lang::RuntimeValue,
+    lines: &[String],
+    matches: &mut Vec<SearchMatch>,
+) {
+    match value {
+        mq_lang::RuntimeValue::Markdown(node, _) => {
+            if let Some(pos) = node.position() {
+                let (line, column) = point_to_position(&pos.start, lines);
+                let (end_line, end_column) = point_to_position(&pos.end, lines);
+                if (line, column) < (end_line, end_column) {
+                    matches.push(SearchMatch {
+                        line,
+                        column,
+                        end_line,
+                        end_column,
+                    });
+                }
+            }
+        }
+        mq_lang::RuntimeValue::Array(values) => {
+            for value in values {
+                collect_query_matches(value, lines, matches);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Convert a 1-indexed mdast point (byte column) to a 0-indexed line and character column
+fn point_to_position(point: &Point, lines: &[String]) -> (usize, usize) {
+    let line = point.line.saturating_sub(1);
+    let byte = point.column.saturating_sub(1);
+    let column = lines
+        .get(line)
+        .map(|text| match text.get(..byte) {
+            Some(prefix) => prefix.chars().count(),
+            None => text.chars().count(),
+        })
+        .unwrap_or(0);
+    (line, column)
+}
+
+/// Locate a URL inside the source range of the node that contains it
+fn url_region(url: &str, (start, end): Region, lines: &[String]) -> Option<Region> {
+    if url.is_empty() || start.0 != end.0 {
+        // Multi-line links are rare; fall back to the whole node
+        return (!url.is_empty()).then_some((start, end));
+    }
+    let chars: Vec<char> = lines.get(start.0)?.chars().collect();
+    let source: String = chars[start.1.min(chars.len())..end.1.min(chars.len())]
+        .iter()
+        .collect();
+    let byte = source.rfind(url)?;
+    let column = start.1 + source[..byte].chars().count();
+    Some(((start.0, column), (start.0, column + url.chars().count())))
+}
+
+/// Byte offset to (line, character column) conversion for `\n`-joined text
+struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { line_starts }
+    }
+
+    fn position(&self, text: &str, byte: usize) -> (usize, usize) {
+        let line = self
+            .line_starts
+            .partition_point(|&start| start <= byte)
+            .saturating_sub(1);
+        let column = text[self.line_starts[line]..byte].chars().count();
+        (line, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.split('\n').map(|s| s.to_string()).collect()
+    }
+
+    fn options(regex: bool, case_mode: CaseMode, whole_word: bool) -> SearchOptions {
+        SearchOptions {
+            regex,
+            case_mode,
+            whole_word,
+        }
+    }
+
+    #[test]
+    fn test_literal_search_escapes_metacharacters() {
+        let pattern = SearchPattern::new("a.b", &SearchOptions::default()).unwrap();
+        let matches = pattern.find_all(&lines("a.b axb a.b"));
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].column, 8);
+    }
+
+    #[test]
+    fn test_case_modes() {
+        let text = lines("Hello hello HELLO");
+
+        let sensitive = SearchPattern::new("hello", &SearchOptions::default()).unwrap();
+        assert_eq!(sensitive.find_all(&text).len(), 1);
+
+        let insensitive =
+            SearchPattern::new("hello", &options(false, CaseMode::Insensitive, false)).unwrap();
+        assert_eq!(insensitive.find_all(&text).len(), 3);
+
+        let smart_lower =
+            SearchPattern::new("hello", &options(false, CaseMode::Smart, false)).unwrap();
+        assert_eq!(smart_lower.find_all(&text).len(), 3);
+
+        let smart_upper =
+            SearchPattern::new("Hello", &options(false, CaseMode::Smart, false)).unwrap();
+        assert_eq!(smart_upper.find_all(&text).len(), 1);
+
+        let smart_escape =
+            SearchPattern::new(r"\bhello\S*", &options(true, CaseMode::Smart, false)).unwrap();
+        assert_eq!(smart_escape.find_all(&text).len(), 3);
+
+        let smart_class =
+            SearchPattern::new(r"\p{L}ello", &options(true, CaseMode::Smart, false)).unwrap();
+        assert_eq!(smart_class.find_all(&text).len(), 3);
+
+        let smart_regex_upper =
+            SearchPattern::new(r"\bHello", &options(true, CaseMode::Smart, false)).unwrap();
+        assert_eq!(smart_regex_upper.find_all(&text).len(), 1);
+    }
+
+    #[test]
+    fn test_whole_word() {
+        let pattern =
+            SearchPattern::new("cat", &options(false, CaseMode::Sensitive, true)).unwrap();
+        let matches = pattern.find_all(&lines("cat concat cats cat"));
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].column, 16);
+    }
+
+    #[test]
+    fn test_regex_multi_line_match() {
+        let pattern =
+            SearchPattern::new(r"foo\nbar", &options(true, CaseMode::Sensitive, false)).unwrap();
+        let matches = pattern.find_all(&lines("x foo\nbar y"));
+        assert_eq!(
+            matches,
+            vec![SearchMatch {
+                line: 0,
+                column: 2,
+                end_line: 1,
+                end_column: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_regex_line_anchors() {
+        let pattern =
+            SearchPattern::new(r"^#+ ", &options(true, CaseMode::Sensitive, false)).unwrap();
+        let matches = pattern.find_all(&lines("# One\ntext # not\n## Two"));
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].line, 2);
+    }
+
+    #[test]
+    fn test_character_columns_with_multibyte_text() {
+        let pattern = SearchPattern::new("世界", &SearchOptions::default()).unwrap();
+        let matches = pattern.find_all(&lines("こんにちは世界"));
+        assert_eq!(matches[0].column, 5);
+        assert_eq!(matches[0].end_column, 7);
+    }
+
+    #[test]
+    fn test_invalid_regex_reports_error() {
+        let result = SearchPattern::new("(unclosed", &options(true, CaseMode::Sensitive, false));
+        assert!(result.is_err());
+        assert!(!result.unwrap_err().is_empty());
+    }
+
+    #[test]
+    fn test_replacement_expands_captures() {
+        let pattern =
+            SearchPattern::new(r"(\w+)@(\w+)", &options(true, CaseMode::Sensitive, false)).unwrap();
+        let (old, new) = pattern
+            .replacement_at("mail: user@host", 6, "$2 at $1")
+            .unwrap();
+        assert_eq!(old, "user@host");
+        assert_eq!(new, "host at user");
+    }
+
+    #[test]
+    fn test_literal_replacement_does_not_expand() {
+        let pattern = SearchPattern::new("a", &SearchOptions::default()).unwrap();
+        let (old, new) = pattern.replacement_at("b a", 2, "$1").unwrap();
+        assert_eq!(old, "a");
+        assert_eq!(new, "$1");
+    }
+
+    fn scoped(scope: SearchScope, query: &str, text: &str) -> Vec<(usize, usize)> {
+        let lines = lines(text);
+        let ast = Markdown::from_markdown_str(text).unwrap();
+        let pattern = SearchPattern::new(query, &SearchOptions::default()).unwrap();
+        scope
+            .filter(pattern.find_all(&lines), &ast, &lines)
+            .iter()
+            .map(|m| (m.line, m.column))
+            .collect()
+    }
+
+    const SCOPED_DOC: &str = "---\ntitle: foo\n---\n# Ünïcode foo\n\nfoo [foo](http://foo.dev) `foo`\n\n```rust\nlet foo = 1;\n```\n\n```python\nfoo = 1\n```";
+
+    #[test]
+    fn test_scope_headings() {
+        assert_eq!(
+            scoped(SearchScope::Headings, "foo", SCOPED_DOC),
+            vec![(3, 10)]
+        );
+    }
+
+    #[test]
+    fn test_scope_link_urls() {
+        assert_eq!(
+            scoped(SearchScope::LinkUrls, "foo", SCOPED_DOC),
+            vec![(5, 17)]
+        );
+    }
+
+    #[test]
+    fn test_scope_prose_excludes_code_and_front_matter() {
+        assert_eq!(
+            scoped(SearchScope::Prose, "foo", SCOPED_DOC),
+            vec![(3, 10), (5, 0), (5, 5), (5, 17)]
+        );
+    }
+
+    #[test]
+    fn test_scope_code_language() {
+        assert_eq!(
+            scoped(SearchScope::Code("rust".to_string()), "foo", SCOPED_DOC),
+            vec![(8, 4)]
+        );
+        assert_eq!(
+            scoped(SearchScope::Code("Python".to_string()), "foo", SCOPED_DOC),
+            vec![(12, 0)]
+        );
+    }
+
+    #[test]
+    fn test_available_scopes_include_code_languages() {
+        let ast = Markdown::from_markdown_str(SCOPED_DOC).unwrap();
+        let scopes = SearchScope::available(&ast);
+        assert_eq!(scopes.len(), 6);
+        assert_eq!(scopes[4], SearchScope::Code("rust".to_string()));
+
+        // Fences nested in lists and block quotes count too
+        let doc = "- item\n\n  ```go\n  foo\n  ```\n\n> ```ruby\n> foo\n> ```\n";
+        let ast = Markdown::from_markdown_str(doc).unwrap();
+        assert_eq!(
+            SearchScope::available(&ast)[4..],
+            [
+                SearchScope::Code("go".to_string()),
+                SearchScope::Code("ruby".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_by_mq_query_maps_nodes_to_positions() {
+        let text = lines("# Top\n\n## Sub\n\nSee [link](http://x) here\n\n## Other");
+
+        let headings = find_by_mq_query("select(.h.depth == 2)", &text).unwrap();
+        assert_eq!(headings.len(), 2);
+        assert_eq!((headings[0].line, headings[0].column), (2, 0));
+        assert_eq!((headings[1].end_line, headings[1].end_column), (6, 8));
+
+        let links = find_by_mq_query(".link", &text).unwrap();
+        assert_eq!(
+            links,
+            vec![SearchMatch {
+                line: 4,
+                column: 4,
+                end_line: 4,
+                end_column: 20,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_by_mq_query_reports_errors() {
+        assert!(find_by_mq_query("select(", &lines("# a")).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use std::ops::AddAssign;
+
+use markdown_lsp::spell::is_cjk;
+use mq_markdown::{Markdown, Node};
+
+use crate::document::outline;
+
+/// Reading speed for text written with spaces between words
+const WORDS_PER_MINUTE: usize = 200;
+/// Reading speed for Chinese, Japanese and Korean text, counted per character
+const CJK_CHARACTERS_PER_MINUTE: usize = 500;
+
+/// Word, character and sentence counts of some prose
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStats {
+    /// Words, counting each CJK character as one word
+    pub words: usize,
+    /// Characters other than whitespace
+    pub characters: usize,
+    pub sentences: usize,
+    /// CJK characters, which are read at a different speed
+    pub cjk_characters: usize,
+}
+
+impl TextStats {
+    /// Count a block of prose, such as a paragraph or heading
+    ///
+    /// Sentences end at `.`, `!` or `?` followed by whitespace, at CJK
+    /// full stops, and at the end of the block. Words that are URLs are not
+    /// counted.
+    pub fn of_block(text: &str) -> Self {
+        let mut stats = Self::default();
+        let mut open_sentence = false;
+        let mut chars = text.chars().peekable();
+        let mut word = String::new();
+
+        while let Some(c) = chars.next() {
+            if !c.is_whitespace() {
+                stats.characters += 1;
+            }
+            if is_cjk(c) {
+                stats.end_word(&mut word, &mut open_sentence);
+                stats.words += 1;
+                stats.cjk_characters += 1;
+                open_sentence = true;
+            } else if matches!(c, '。' | '！' | '？') {
+                stats.end_word(&mut word, &mut open_sentence);
+                if open_sentence {
+                    stats.sentences += 1;
+                    open_sentence = false;
+                }
+            } else if c.is_whitespace() {
+                stats.end_word(&mut word, &mut open_sentence);
+            } else {
+                word.push(c);
+                let at_boundary = chars.peek().is_none_or(|next| next.is_whitespace());
+                if matches!(c, '.' | '!' | '?') && at_boundary {
+                    stats.end_word(&mut word, &mut open_sentence);
+                    if open_sentence {
+                        stats.sentences += 1;
+                        open_sentence = false;
+                    }
+                }
+            }
+        }
+        stats.end_word(&mut word, &mut open_sentence);
+        if open_sentence {
+            stats.sentences += 1;
+        }
+        stats
+    }
+
+    /// Count the word collected in `word`, if it is one, and clear it
+    fn end_word(&mut self, word: &mut String, open_sentence: &mut bool) {
+        if word.chars().any(char::is_alphanumeric) && !is_url(word) {
+            self.words += 1;
+            *open_sentence = true;
+        }
+        word.clear();
+    }
+
+    /// Estimated reading time in whole minutes, at least 1 for any words
+    pub fn reading_minutes(&self) -> usize {
+        if self.words == 0 {
+            return 0;
+        }
+        let other_words = self.words - self.cjk_characters;
+        let seconds = other_words * 60 / WORDS_PER_MINUTE
+            + self.cjk_characters * 60 / CJK_CHARACTERS_PER_MINUTE;
+        seconds.div_ceil(60).max(1)
+    }
+}
+
+impl AddAssign for TextStats {
+    fn add_assign(&mut self, other: Self) {
+        self.words += other.words;
+        self.characters += other.characters;
+        self.sentences += other.sentences;
+        self.cjk_characters += other.cjk_characters;
+    }
+}
+
+/// Counts of the prose under one heading, down to the next heading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionStats {
+    /// Heading depth, or 0 for the text before the first heading
+    pub level: usize,
+    pub title: String,
+    /// Line of the heading
+    pub line: usize,
+    pub stats: TextStats,
+}
+
+/// Counts of a whole Markdown document
+///
+/// Only prose is counted: code blocks, HTML, math, front matter, image
+/// descriptions and link URLs are left out.
+pub fn markdown_stats(markdown: &Markdown) -> TextStats {
+    let mut stats = TextStats::default();
+    for (_, text) in prose_blocks(markdown) {
+        stats += TextStats::of_block(&text);
+    }
+    stats
+}
+
+/// Counts of each section of a Markdown document
+///
+/// The text before the first heading, if any, is reported as a section of
+/// level 0.
+pub fn section_stats(markdown: &Markdown) -> Vec<SectionStats> {
+    let mut sections: Vec<SectionStats> = outline::markdown_outline(markdown)
+        .into_iter()
+        .map(|entry| SectionStats {
+            level: entry.level,
+            title: entry.title,
+            line: entry.line,
+            stats: TextStats::default(),
+        })
+        .collect();
+    let mut preamble = SectionStats {
+        level: 0,
+        title: String::new(),
+        line: 0,
+        stats: TextStats::default(),
+    };
+
+    for (line, text) in prose_blocks(markdown) {
+        let section = match sections.iter().rposition(|section| section.line <= line) {
+            Some(idx) => &mut sections[idx],
+            None => &mut preamble,
+        };
+        section.stats += TextStats::of_block(&text);
+    }
+
+    if preamble.stats.words > 0 {
+        sections.insert(0, preamble);
+    }
+    sections
+}
+
+/// Counts of plain text, with paragraphs separated by blank lines
+pub fn plain_text_stats<S: AsRef<str>>(lines: &[S]) -> TextStats {
+    let mut stats = TextStats::default();
+    for paragraph in lines.split(|line| line.as_ref().trim().is_empty()) {
+        let text: Vec<&str> = paragraph.iter().map(AsRef::as_ref).collect();
+        stats += TextStats::of_block(&text.join("\n"));
+    }
+    stats
+}
+
+/// Prose of a Markdown document as blocks with their first line
+///
+/// The parser lists the inline nodes of a paragraph one after another, so
+/// inline nodes continuing on the line where the previous one ended are
+/// joined into one block.
+fn prose_blocks(markdown: &Markdown) -> Vec<(usize, String)> {
+    let mut blocks: Vec<(usize, String)> = Vec::new();
+    let mut paragraph_end: Option<usize> = None;
+    for node in &markdown.nodes {
+        let Some(position) = node.position() else {
+            continue;
+        };
+        let start = position.start.line.saturating_sub(1);
+        let end = position.end.line.saturating_sub(1);
+        let mut text = String::new();
+        prose_text(node, &mut text);
+
+        if is_inline(node) {
+            match blocks.last_mut() {
+                Some((_, block)) if paragraph_end == Some(start) => block.push_str(&text),
+                _ => blocks.push((start, text)),
+            }
+            paragraph_end = Some(end);
+        } else {
+            blocks.push((start, text));
+            paragraph_end = None;
+        }
+    }
+    blocks
+}
+
+/// Whether a node is part of a paragraph rather than a block of its own
+fn is_inline(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Text(_)
+            | Node::Emphasis(_)
+            | Node::Strong(_)
+            | Node::Delete(_)
+            | Node::Link(_)
+            | Node::LinkRef(_)
+            | Node::CodeInline(_)
+            | Node::MathInline(_)
+            | Node::Break(_)
+            | Node::FootnoteRef(_)
+            | Node::Image(_)
+            | Node::ImageRef(_)
+    )
+}
+
+/// Append the prose of a node and its children to `text`
+fn prose_text(node: &Node, text: &mut String) {
+    match node {
+        Node::Text(t) => text.push_str(&t.value),
+        Node::CodeInline(code) => text.push_str(&code.value),
+        Node::Break(_) => text.push('\n'),
+        // Autolinks show their URL as the link text
+        Node::Link(link)
+            if link.values.iter().map(Node::value).collect::<String>() == link.url.as_str() => {}
+        Node::Code(_)
+        | Node::Yaml(_)
+        | Node::Toml(_)
+        | Node::Html(_)
+        | Node::Math(_)
+        | Node::MathInline(_)
+        | Node::Image(_)
+        | Node::ImageRef(_)
+        | Node::Definition(_)
+        | Node::TableAlign(_)
+        | Node::HorizontalRule(_)
+        | Node::MdxFlowExpression(_)
+        | Node::MdxJsxFlowElement(_)
+        | Node::MdxJsxTextElement(_)
+        | Node::MdxTextExpression(_)
+        | Node::MdxJsEsm(_) => {}
+        _ => {
+            for child in node.children() {
+                prose_text(&child, text);
+            }
+        }
+    }
+}
+
+fn is_url(word: &str) -> bool {
+    word.contains("://") || word.starts_with("www.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_stats() {
+        let stats =
+            TextStats::of_block("Hello world. It costs 3.50 dollars!  See https://example.com");
+        assert_eq!(stats.words, 7);
+        assert_eq!(stats.sentences, 3);
+
+        let stats = TextStats::of_block("日本語の文章です。二つ目");
+        assert_eq!(stats.words, 11);
+        assert_eq!(stats.cjk_characters, 11);
+        assert_eq!(stats.characters, 12);
+        assert_eq!(stats.sentences, 2);
+
+        let stats = TextStats::of_block("Mixed 日本 text - ok");
+        assert_eq!(stats.words, 5);
+        assert_eq!(stats.sentences, 1);
+
+        assert_eq!(TextStats::default().reading_minutes(), 0);
+        let long = TextStats {
+            words: 450,
+            ..Default::default()
+        };
+        assert_eq!(long.reading_minutes(), 3);
+    }
+
+    #[test]
+    fn test_markdown_stats() {
+        let markdown: Markdown = "---\ntitle: Front matter words\n---\nIntro text.\n\n# Head *one*\n\nSome [link](http://a.com) text and `code`.\nSecond line.\n\n```rust\nfn main() {}\n```\n\n## Two\n\n- item one\n- see <http://b.com>\n"
+            .parse()
+            .unwrap();
+
+        let stats = markdown_stats(&markdown);
+        assert_eq!(stats.words, 15);
+        assert_eq!(stats.sentences, 7);
+
+        let sections = section_stats(&markdown);
+        let summary: Vec<(usize, &str, usize, usize)> = sections
+            .iter()
+            .map(|s| (s.level, s.title.as_str(), s.line, s.stats.words))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(0, "", 0, 2), (1, "Head one", 5, 9), (2, "Two", 14, 4)]
+        );
+    }
+
+    #[test]
+    fn test_plain_text_stats() {
+        let stats = plain_text_stats(&["One two", "three.", "", "Four"]);
+        assert_eq!(stats.words, 4);
+        assert_eq!(stats.sentences, 2);
+    }
+}
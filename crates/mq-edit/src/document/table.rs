@@ -0,0 +1,401 @@
+use unicode_width::UnicodeWidthStr;
+
+use super::{LineAnalyzer, TableAlignment};
+
+/// Minimum column width, so separator cells keep three dashes
+const MIN_COLUMN_WIDTH: usize = 3;
+
+/// A Markdown pipe table parsed from source lines
+///
+/// Source rows are numbered as in the document: 0 is the header, 1 the
+/// separator and data rows start at 2.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    /// Leading whitespace of the header line, kept on every line
+    indent: String,
+    header: Vec<String>,
+    alignments: Vec<TableAlignment>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Find the lines `start..end` of the table containing `line`
+    ///
+    /// A table is a run of pipe lines whose second line is a separator row.
+    pub fn find<S: AsRef<str>>(lines: &[S], line: usize) -> Option<(usize, usize)> {
+        let is_row = |idx: usize| {
+            lines
+                .get(idx)
+                .is_some_and(|l| LineAnalyzer::is_table_row(l.as_ref()))
+        };
+        if !is_row(line) {
+            return None;
+        }
+
+        let mut start = line;
+        while start > 0 && is_row(start - 1) {
+            start -= 1;
+        }
+        let mut end = line + 1;
+        while is_row(end) {
+            end += 1;
+        }
+
+        (end - start >= 2 && LineAnalyzer::is_table_separator(lines[start + 1].as_ref()))
+            .then_some((start, end))
+    }
+
+    /// Parse a table from its header, separator and data lines
+    pub fn parse<S: AsRef<str>>(lines: &[S]) -> Option<Self> {
+        let [header, separator, rows @ ..] = lines else {
+            return None;
+        };
+        let (header, separator) = (header.as_ref(), separator.as_ref());
+        if !LineAnalyzer::is_table_separator(separator) {
+            return None;
+        }
+
+        let indent = header[..header.len() - header.trim_start().len()].to_string();
+        let mut table = Self {
+            indent,
+            header: split_cells(header),
+            alignments: LineAnalyzer::parse_table_alignment(separator),
+            rows: rows.iter().map(|row| split_cells(row.as_ref())).collect(),
+        };
+
+        let columns = table
+            .rows
+            .iter()
+            .map(Vec::len)
+            .chain([table.header.len(), table.alignments.len()])
+            .max()
+            .unwrap_or(1);
+        table.header.resize(columns, String::new());
+        table.alignments.resize(columns, TableAlignment::None);
+        for row in &mut table.rows {
+            row.resize(columns, String::new());
+        }
+        Some(table)
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.header.len()
+    }
+
+    /// Number of source lines, including header and separator
+    pub fn line_count(&self) -> usize {
+        self.rows.len() + 2
+    }
+
+    /// Cells of a source row (None for the separator)
+    pub fn row(&self, row: usize) -> Option<&[String]> {
+        match row {
+            0 => Some(&self.header),
+            1 => None,
+            _ => self.rows.get(row - 2).map(Vec::as_slice),
+        }
+    }
+
+    pub fn alignment(&self, column: usize) -> Option<TableAlignment> {
+        self.alignments.get(column).copied()
+    }
+
+    /// Insert an empty column before `column`
+    pub fn insert_column(&mut self, column: usize) {
+        let column = column.min(self.column_count());
+        self.header.insert(column, String::new());
+        self.alignments.insert(column, TableAlignment::None);
+        for row in &mut self.rows {
+            row.insert(column, String::new());
+        }
+    }
+
+    /// Delete a column; the last remaining column can't be deleted
+    pub fn delete_column(&mut self, column: usize) -> bool {
+        if self.column_count() <= 1 || column >= self.column_count() {
+            return false;
+        }
+        self.header.remove(column);
+        self.alignments.remove(column);
+        for row in &mut self.rows {
+            row.remove(column);
+        }
+        true
+    }
+
+    /// Swap a column with its neighbour `to`
+    pub fn move_column(&mut self, from: usize, to: usize) -> bool {
+        if from >= self.column_count() || to >= self.column_count() {
+            return false;
+        }
+        self.header.swap(from, to);
+        self.alignments.swap(from, to);
+        for row in &mut self.rows {
+            row.swap(from, to);
+        }
+        true
+    }
+
+    pub fn set_alignment(&mut self, column: usize, alignment: TableAlignment) {
+        if let Some(current) = self.alignments.get_mut(column) {
+            *current = alignment;
+        }
+    }
+
+    /// Insert an empty data row at source row `row` (clamped to the data rows)
+    ///
+    /// Returns the source row of the new row.
+    pub fn insert_row(&mut self, row: usize) -> usize {
+        let index = row.saturating_sub(2).min(self.rows.len());
+        self.rows
+            .insert(index, vec![String::new(); self.column_count()]);
+        index + 2
+    }
+
+    /// Remove a data row
+    pub fn delete_row(&mut self, row: usize) -> bool {
+        if row < 2 || row - 2 >= self.rows.len() {
+            return false;
+        }
+        self.rows.remove(row - 2);
+        true
+    }
+
+    /// Format the table with aligned pipes
+    ///
+    /// Column widths are measured in terminal cells so CJK text lines up.
+    pub fn format(&self) -> Vec<String> {
+        let widths: Vec<usize> = (0..self.column_count())
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .chain([&self.header])
+                    .map(|row| row[column].width())
+                    .max()
+                    .unwrap_or(0)
+                    .max(MIN_COLUMN_WIDTH)
+            })
+            .collect();
+
+        let format_row = |cells: &[String]| {
+            let cells: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .zip(&self.alignments)
+                .map(|((cell, width), alignment)| pad(cell, *width, *alignment))
+                .collect();
+            format!("{}| {} |", self.indent, cells.join(" | "))
+        };
+
+        let separator: Vec<String> = widths
+            .iter()
+            .zip(&self.alignments)
+            .map(|(width, alignment)| match alignment {
+                TableAlignment::Left => format!(":{}", "-".repeat(width - 1)),
+                TableAlignment::Center => format!(":{}:", "-".repeat(width - 2)),
+                TableAlignment::Right => format!("{}:", "-".repeat(width - 1)),
+                TableAlignment::None => "-".repeat(*width),
+            })
+            .collect();
+
+        let mut lines = vec![
+            format_row(&self.header),
+            format!("{}| {} |", self.indent, separator.join(" | ")),
+        ];
+        lines.extend(self.rows.iter().map(|row| format_row(row)));
+        lines
+    }
+}
+
+/// Next alignment in the cycle none → left → center → right
+pub fn next_alignment(alignment: TableAlignment) -> TableAlignment {
+    match alignment {
+        TableAlignment::None => TableAlignment::Left,
+        TableAlignment::Left => TableAlignment::Center,
+        TableAlignment::Center => TableAlignment::Right,
+        TableAlignment::Right => TableAlignment::None,
+    }
+}
+
+/// Pad cell text to `width` terminal cells according to the alignment
+fn pad(text: &str, width: usize, alignment: TableAlignment) -> String {
+    let space = width.saturating_sub(text.width());
+    let (left, right) = match alignment {
+        TableAlignment::Right => (space, 0),
+        TableAlignment::Center => (space / 2, space - space / 2),
+        TableAlignment::Left | TableAlignment::None => (0, space),
+    };
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+}
+
+/// Char columns of the cell-delimiting pipes in a table line
+///
+/// Escaped pipes (`\|`) don't delimit cells.
+fn pipe_columns(line: &str) -> Vec<usize> {
+    let mut pipes = Vec::new();
+    let mut escaped = false;
+    for (column, c) in line.chars().enumerate() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '|' => pipes.push(column),
+            _ => {}
+        }
+    }
+    pipes
+}
+
+/// Split a table line into trimmed cell contents
+pub fn split_cells(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let chars: Vec<char> = trimmed.chars().collect();
+    let mut pipes = pipe_columns(trimmed);
+
+    // Outer pipes are optional
+    let mut cell_start = 0;
+    if pipes.first() == Some(&0) {
+        pipes.remove(0);
+        cell_start = 1;
+    }
+    let end = match pipes.last() {
+        Some(&last) if last + 1 == chars.len() => {
+            pipes.pop();
+            last
+        }
+        _ => chars.len(),
+    };
+
+    pipes
+        .into_iter()
+        .chain([end])
+        .map(|pipe| {
+            let cell: String = chars[cell_start..pipe].iter().collect();
+            cell_start = pipe + 1;
+            cell.trim().to_string()
+        })
+        .collect()
+}
+
+/// Index of the cell containing char column `column` of a table line
+pub fn cell_at(line: &str, column: usize) -> usize {
+    let pipes = pipe_columns(line);
+    let leading = line.trim_start().starts_with('|') as usize;
+    pipes
+        .iter()
+        .filter(|pipe| **pipe < column)
+        .count()
+        .saturating_sub(leading)
+}
+
+/// Char column where the content of cell `cell` starts in a formatted table line
+pub fn cell_start(line: &str, cell: usize) -> usize {
+    let pipes = pipe_columns(line);
+    let Some(&pipe) = pipes.get(cell) else {
+        return line.chars().count();
+    };
+    let next = pipes.get(cell + 1).copied().unwrap_or(line.chars().count());
+    line.chars()
+        .enumerate()
+        .skip(pipe + 1)
+        .take_while(|(column, _)| *column < next)
+        .find(|(_, c)| !c.is_whitespace())
+        .map_or((pipe + 2).min(next), |(column, _)| column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(lines: &[&str]) -> Table {
+        Table::parse(lines).unwrap()
+    }
+
+    #[test]
+    fn test_find() {
+        let lines = ["text", "| a | b |", "|---|---|", "| 1 | 2 |", "", "| x |"];
+        assert_eq!(Table::find(&lines, 3), Some((1, 4)));
+        assert_eq!(Table::find(&lines, 1), Some((1, 4)));
+        assert_eq!(Table::find(&lines, 0), None);
+        // No separator row
+        assert_eq!(Table::find(&lines, 5), None);
+    }
+
+    #[test]
+    fn test_format_aligns_pipes() {
+        let t = table(&["|a|b|c|", "|:-|:-:|-:|", "|long cell|x|yy|", "|1|"]);
+        assert_eq!(
+            t.format(),
+            vec![
+                "| a         |  b  |   c |",
+                "| :-------- | :-: | --: |",
+                "| long cell |  x  |  yy |",
+                "| 1         |     |     |",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_uses_display_width() {
+        let t = table(&["| 名前 | x |", "|---|---|", "| 日本語 | y |"]);
+        assert_eq!(
+            t.format(),
+            vec!["| 名前   | x   |", "| ------ | --- |", "| 日本語 | y   |",]
+        );
+    }
+
+    #[test]
+    fn test_split_cells() {
+        assert_eq!(split_cells("| a | b |"), vec!["a", "b"]);
+        assert_eq!(split_cells("a | b"), vec!["a", "b"]);
+        assert_eq!(split_cells("  | a \\| b | c |"), vec!["a \\| b", "c"]);
+        assert_eq!(split_cells("| a | |"), vec!["a", ""]);
+        assert_eq!(split_cells("|"), vec![""]);
+    }
+
+    #[test]
+    fn test_column_operations() {
+        let mut t = table(&["| a | b |", "| :-- | --- |", "| 1 | 2 |"]);
+        t.insert_column(1);
+        assert_eq!(t.row(0).unwrap(), ["a", "", "b"]);
+        assert_eq!(t.row(2).unwrap(), ["1", "", "2"]);
+
+        assert!(t.move_column(0, 1));
+        assert_eq!(t.row(0).unwrap(), ["", "a", "b"]);
+        assert_eq!(t.alignment(1), Some(TableAlignment::Left));
+
+        assert!(t.delete_column(0));
+        assert!(t.delete_column(0));
+        assert!(!t.delete_column(0));
+        assert_eq!(t.row(2).unwrap(), ["2"]);
+
+        t.set_alignment(0, next_alignment(TableAlignment::Center));
+        assert_eq!(t.format()[1], "| --: |");
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut t = table(&["| a |", "|---|", "| 1 |"]);
+        assert_eq!(t.insert_row(3), 3);
+        assert_eq!(t.insert_row(0), 2);
+        assert_eq!(t.line_count(), 5);
+        assert!(t.row(1).is_none());
+        assert!(t.delete_row(2));
+        assert!(!t.delete_row(1));
+        assert_eq!(t.format()[2], "| 1   |");
+    }
+
+    #[test]
+    fn test_cell_positions() {
+        let line = "| ab  | c \\| d | 日本 |";
+        assert_eq!(cell_at(line, 0), 0);
+        assert_eq!(cell_at(line, 3), 0);
+        assert_eq!(cell_at(line, 8), 1);
+        assert_eq!(cell_at(line, 18), 2);
+
+        assert_eq!(cell_start(line, 0), 2);
+        assert_eq!(cell_start(line, 1), 8);
+        assert_eq!(cell_start(line, 2), 17);
+        assert_eq!(cell_start("| a |   |", 1), 6);
+        assert_eq!(cell_start("| a |  b |", 1), 7);
+    }
+}
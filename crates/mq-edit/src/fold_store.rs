@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::document::FoldedLine;
+
+/// Folded lines of each file, persisted to a TOML file
+///
+/// Files are keyed by their canonical path. A file with no folded lines is
+/// removed from the store.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FoldStore {
+    #[serde(default)]
+    files: BTreeMap<String, Vec<FoldedLine>>,
+    /// File the store is saved to (None keeps it in memory only)
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl FoldStore {
+    /// Create an in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the store from a file, starting empty if it is missing or invalid
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let mut store = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str::<Self>(&content).ok())
+            .unwrap_or_default();
+        store.path = Some(path.to_path_buf());
+        store
+    }
+
+    /// Create a store that persists folds if `persist` is set
+    ///
+    /// Unit tests always get an in-memory store so they never touch the
+    /// user's fold file.
+    pub fn from_config(persist: bool) -> Self {
+        if persist && !cfg!(test) {
+            Self::load(Self::default_path())
+        } else {
+            Self::new()
+        }
+    }
+
+    /// Get the default store path (next to the config file)
+    pub fn default_path() -> PathBuf {
+        if let Some(config_dir) = dirs::config_dir() {
+            config_dir.join("mq").join("edit").join("folds.toml")
+        } else {
+            PathBuf::from(".mq-edit-folds.toml")
+        }
+    }
+
+    /// Save the store to its file (no-op for an in-memory store)
+    pub fn save(&self) -> miette::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| miette::miette!("Failed to create folds directory: {}", e))?;
+        }
+
+        let content = toml::to_string(self)
+            .map_err(|e| miette::miette!("Failed to serialize folds: {}", e))?;
+        std::fs::write(path, content)
+            .map_err(|e| miette::miette!("Failed to write folds file: {}", e))
+    }
+
+    /// Folded lines saved for a file
+    pub fn get(&self, file: &Path) -> Vec<FoldedLine> {
+        self.files
+            .get(&Self::key(file))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Replace the folded lines of a file
+    /// Returns true if the store changed
+    pub fn set(&mut self, file: &Path, folded: &[FoldedLine]) -> bool {
+        let key = Self::key(file);
+        if folded.is_empty() {
+            return self.files.remove(&key).is_some();
+        }
+        if self.files.get(&key).is_some_and(|saved| saved == folded) {
+            return false;
+        }
+        self.files.insert(key, folded.to_vec());
+        true
+    }
+
+    fn key(file: &Path) -> String {
+        file.canonicalize()
+            .unwrap_or_else(|_| file.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("folds.toml");
+        let file = dir.path().join("doc.md");
+        std::fs::write(&file, "# A\ntext\n").unwrap();
+        let folded = vec![FoldedLine {
+            line: 0,
+            text: "# A".to_string(),
+        }];
+
+        let mut store = FoldStore::load(&path);
+        assert!(store.set(&file, &folded));
+        assert!(!store.set(&file, &folded));
+        store.save().unwrap();
+
+        let mut loaded = FoldStore::load(&path);
+        assert_eq!(loaded.get(&file), folded);
+        assert!(loaded.set(&file, &[]));
+        assert!(loaded.get(&file).is_empty());
+    }
+}
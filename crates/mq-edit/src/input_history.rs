@@ -0,0 +1,383 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::HistoryConfig;
+
+/// Dialog inputs that keep a history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryKind {
+    Search,
+    Replace,
+    GotoLine,
+    MqQuery,
+}
+
+/// Per-dialog input history, persisted to a TOML file
+///
+/// Entries are stored oldest first. Pushing an entry that already exists
+/// moves it to the end, and the oldest entries are dropped once a list
+/// exceeds the size limit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputHistory {
+    #[serde(default)]
+    search: Vec<String>,
+    #[serde(default)]
+    replace: Vec<String>,
+    #[serde(default)]
+    goto_line: Vec<String>,
+    #[serde(default)]
+    mq_query: Vec<String>,
+    /// File the history is saved to (None keeps it in memory only)
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    /// Maximum number of entries per list
+    #[serde(skip)]
+    max_entries: usize,
+}
+
+impl InputHistory {
+    /// Create an in-memory history
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            ..Default::default()
+        }
+    }
+
+    /// Load history from a file, starting empty if it is missing or invalid
+    pub fn load(path: impl AsRef<Path>, max_entries: usize) -> Self {
+        let path = path.as_ref();
+        let mut history = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str::<Self>(&content).ok())
+            .unwrap_or_default();
+        history.path = Some(path.to_path_buf());
+        history.max_entries = max_entries;
+        for kind in [
+            HistoryKind::Search,
+            HistoryKind::Replace,
+            HistoryKind::GotoLine,
+            HistoryKind::MqQuery,
+        ] {
+            history.truncate(kind);
+        }
+        history
+    }
+
+    /// Create history according to configuration
+    ///
+    /// Unit tests always get an in-memory history so they never touch the
+    /// user's history file.
+    pub fn from_config(config: &HistoryConfig) -> Self {
+        if config.persist && !cfg!(test) {
+            Self::load(Self::default_path(), config.max_entries)
+        } else {
+            Self::new(config.max_entries)
+        }
+    }
+
+    /// Get the default history file path (next to the config file)
+    pub fn default_path() -> PathBuf {
+        if let Some(config_dir) = dirs::config_dir() {
+            config_dir.join("mq").join("edit").join("history.toml")
+        } else {
+            PathBuf::from(".mq-edit-history.toml")
+        }
+    }
+
+    /// Save history to its file (no-op for in-memory history)
+    pub fn save(&self) -> miette::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| miette::miette!("Failed to create history directory: {}", e))?;
+        }
+
+        let content = toml::to_string(self)
+            .map_err(|e| miette::miette!("Failed to serialize history: {}", e))?;
+        std::fs::write(path, content)
+            .map_err(|e| miette::miette!("Failed to write history file: {}", e))
+    }
+
+    /// Get the entries of a history list, oldest first
+    pub fn entries(&self, kind: HistoryKind) -> &[String] {
+        match kind {
+            HistoryKind::Search => &self.search,
+            HistoryKind::Replace => &self.replace,
+            HistoryKind::GotoLine => &self.goto_line,
+            HistoryKind::MqQuery => &self.mq_query,
+        }
+    }
+
+    fn entries_mut(&mut self, kind: HistoryKind) -> &mut Vec<String> {
+        match kind {
+            HistoryKind::Search => &mut self.search,
+            HistoryKind::Replace => &mut self.replace,
+            HistoryKind::GotoLine => &mut self.goto_line,
+            HistoryKind::MqQuery => &mut self.mq_query,
+        }
+    }
+
+    /// Add an entry as the most recent one, removing earlier duplicates
+    /// Returns true if the history changed
+    pub fn push(&mut self, kind: HistoryKind, entry: &str) -> bool {
+        if entry.trim().is_empty() {
+            return false;
+        }
+        let entries = self.entries_mut(kind);
+        if entries.last().is_some_and(|last| last == entry) {
+            return false;
+        }
+
+        entries.retain(|e| e != entry);
+        entries.push(entry.to_string());
+        self.truncate(kind);
+        true
+    }
+
+    fn truncate(&mut self, kind: HistoryKind) {
+        let max_entries = self.max_entries;
+        let entries = self.entries_mut(kind);
+        if entries.len() > max_entries {
+            entries.drain(..entries.len() - max_entries);
+        }
+    }
+
+    /// Find the most recent entry containing `needle`, older than `before` if given
+    fn find_containing(
+        &self,
+        kind: HistoryKind,
+        needle: &str,
+        before: Option<usize>,
+    ) -> Option<usize> {
+        let entries = self.entries(kind);
+        let end = before.unwrap_or(entries.len()).min(entries.len());
+        entries[..end].iter().rposition(|e| e.contains(needle))
+    }
+}
+
+/// Up/Down navigation through a history list
+///
+/// The input typed before the first Up is used as a prefix filter and is
+/// restored when navigating back past the newest entry.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryNavigator {
+    /// Index of the entry currently shown
+    index: Option<usize>,
+    /// Input at the time navigation started
+    draft: String,
+}
+
+impl HistoryNavigator {
+    /// Stop navigating (e.g. after the input was edited)
+    pub fn reset(&mut self) {
+        self.index = None;
+        self.draft.clear();
+    }
+
+    /// Move to the next older entry matching the prefix
+    pub fn older(
+        &mut self,
+        history: &InputHistory,
+        kind: HistoryKind,
+        input: &str,
+    ) -> Option<String> {
+        if self.index.is_none() {
+            self.draft = input.to_string();
+        }
+        let entries = history.entries(kind);
+        let end = self.index.unwrap_or(entries.len());
+        let index = entries[..end]
+            .iter()
+            .rposition(|e| e.starts_with(&self.draft) && e != input)?;
+        self.index = Some(index);
+        Some(entries[index].clone())
+    }
+
+    /// Move to the next newer entry matching the prefix, or back to the draft
+    pub fn newer(&mut self, history: &InputHistory, kind: HistoryKind) -> Option<String> {
+        let current = self.index?;
+        let entries = history.entries(kind);
+        match entries
+            .iter()
+            .enumerate()
+            .skip(current + 1)
+            .find(|(_, e)| e.starts_with(&self.draft))
+        {
+            Some((index, entry)) => {
+                self.index = Some(index);
+                Some(entry.clone())
+            }
+            None => {
+                self.index = None;
+                Some(std::mem::take(&mut self.draft))
+            }
+        }
+    }
+}
+
+/// Reverse incremental search through a history list (Ctrl+R)
+#[derive(Debug, Clone)]
+pub struct ReverseSearch {
+    /// Text being searched for
+    query: String,
+    /// Input before the search started, restored on cancel
+    original: String,
+    /// Index of the matched entry
+    matched: Option<usize>,
+    /// Whether the last search found nothing
+    failing: bool,
+}
+
+impl ReverseSearch {
+    pub fn new(original: &str) -> Self {
+        Self {
+            query: String::new(),
+            original: original.to_string(),
+            matched: None,
+            failing: false,
+        }
+    }
+
+    /// Input before the search started
+    pub fn original(&self) -> &str {
+        &self.original
+    }
+
+    /// Prompt shown in the dialog while searching
+    pub fn prompt(&self) -> String {
+        if self.failing {
+            format!("failing reverse-i-search: {}_", self.query)
+        } else {
+            format!("reverse-i-search: {}_", self.query)
+        }
+    }
+
+    /// Append to the query and search again from the most recent entry
+    pub fn push(&mut self, c: char, history: &InputHistory, kind: HistoryKind) -> Option<String> {
+        self.query.push(c);
+        self.search(history, kind, None)
+    }
+
+    /// Remove the last query character and search again from the most recent entry
+    pub fn pop(&mut self, history: &InputHistory, kind: HistoryKind) -> Option<String> {
+        self.query.pop();
+        self.search(history, kind, None)
+    }
+
+    /// Find the next older entry matching the query
+    pub fn next(&mut self, history: &InputHistory, kind: HistoryKind) -> Option<String> {
+        self.search(history, kind, self.matched)
+    }
+
+    fn search(
+        &mut self,
+        history: &InputHistory,
+        kind: HistoryKind,
+        before: Option<usize>,
+    ) -> Option<String> {
+        match history.find_containing(kind, &self.query, before) {
+            Some(index) => {
+                self.matched = Some(index);
+                self.failing = false;
+                Some(history.entries(kind)[index].clone())
+            }
+            None => {
+                self.failing = true;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(entries: &[&str]) -> InputHistory {
+        let mut history = InputHistory::new(10);
+        for entry in entries {
+            history.push(HistoryKind::Search, entry);
+        }
+        history
+    }
+
+    #[test]
+    fn test_push_dedup_and_limit() {
+        let mut history = InputHistory::new(3);
+        for entry in ["a", "b", "a", "c", "d", ""] {
+            history.push(HistoryKind::Search, entry);
+        }
+        assert_eq!(history.entries(HistoryKind::Search), ["a", "c", "d"]);
+        assert!(history.entries(HistoryKind::MqQuery).is_empty());
+    }
+
+    #[test]
+    fn test_navigator_prefix_filter() {
+        let history = history(&["foo", "bar", "fob", "baz"]);
+        let mut nav = HistoryNavigator::default();
+
+        assert_eq!(
+            nav.older(&history, HistoryKind::Search, "fo"),
+            Some("fob".to_string())
+        );
+        assert_eq!(
+            nav.older(&history, HistoryKind::Search, "fob"),
+            Some("foo".to_string())
+        );
+        assert_eq!(nav.older(&history, HistoryKind::Search, "foo"), None);
+        assert_eq!(
+            nav.newer(&history, HistoryKind::Search),
+            Some("fob".to_string())
+        );
+        assert_eq!(
+            nav.newer(&history, HistoryKind::Search),
+            Some("fo".to_string())
+        );
+        assert_eq!(nav.newer(&history, HistoryKind::Search), None);
+    }
+
+    #[test]
+    fn test_reverse_search() {
+        let history = history(&["select(.h)", ".link", ".h | upcase()"]);
+        let mut search = ReverseSearch::new("draft");
+
+        assert_eq!(
+            search.push('h', &history, HistoryKind::Search),
+            Some(".h | upcase()".to_string())
+        );
+        assert_eq!(
+            search.next(&history, HistoryKind::Search),
+            Some("select(.h)".to_string())
+        );
+        assert_eq!(search.next(&history, HistoryKind::Search), None);
+        assert!(search.prompt().starts_with("failing"));
+        assert_eq!(search.original(), "draft");
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.toml");
+
+        let mut history = InputHistory::load(&path, 2);
+        history.push(HistoryKind::GotoLine, "10");
+        history.push(HistoryKind::MqQuery, ".link");
+        history.save().unwrap();
+
+        let loaded = InputHistory::load(&path, 2);
+        assert_eq!(loaded.entries(HistoryKind::GotoLine), ["10"]);
+        assert_eq!(loaded.entries(HistoryKind::MqQuery), [".link"]);
+    }
+
+    #[test]
+    fn test_from_config_stays_in_memory_in_tests() {
+        let config = HistoryConfig::default();
+        assert!(config.persist);
+        assert!(InputHistory::from_config(&config).path.is_none());
+    }
+}
@@ -3,6 +3,8 @@ pub mod config;
 pub mod document;
 pub mod editor;
 pub mod file;
+pub mod fold_store;
+pub mod input_history;
 pub mod navigation;
 pub mod renderer;
 pub mod theme;
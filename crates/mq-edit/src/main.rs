@@ -1,25 +1,31 @@
 use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
 use std::time::Duration;
 
 use clap::{Parser, Subcommand};
 use crossterm::{
+    cursor::{MoveTo, RestorePosition, SavePosition},
     event::{self, Event},
-    execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    execute, queue,
+    terminal::{
+        EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode, window_size,
+    },
 };
+use markdown_lsp::format;
 use miette::Result;
 use ratatui::{
     Terminal,
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
 };
 
 use mq_edit::{
     App, Config,
-    renderer::CodeRenderer,
+    renderer::{CodeRenderer, ImagePlacement, ImageProtocol, graphics},
     ui::{
-        CompletionPopup, EditorWidget, FileBrowserWidget, GotoLineDialog, MqQueryDialog,
-        QuitDialog, SaveAsDialog, SearchDialog, SearchMode, StatusBar,
+        BacklinksWidget, CodeActionPopup, CompletionPopup, EditorWidget, FileBrowserWidget,
+        FrontMatterDialog, GotoLineDialog, MqQueryDialog, OutlineWidget, QuitDialog, SaveAsDialog,
+        SearchDialog, SearchMode, StatsDialog, StatusBar,
     },
 };
 
@@ -31,6 +37,10 @@ struct Cli {
     /// File to open
     file: Option<String>,
 
+    /// Open read-only in the rendered preview, as a Markdown pager
+    #[arg(long)]
+    view: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -41,6 +51,15 @@ enum Commands {
     InitConfig,
     /// List available syntax highlighting themes
     ListThemes,
+    /// Format Markdown files in the style set in the config file
+    Fmt {
+        /// Files to format in place (reads stdin and writes stdout when omitted)
+        files: Vec<PathBuf>,
+
+        /// Only list the files that need formatting, failing if there are any
+        #[arg(long)]
+        check: bool,
+    },
 }
 
 fn main() -> Result<()> {
@@ -55,6 +74,9 @@ fn main() -> Result<()> {
             Commands::ListThemes => {
                 return list_themes();
             }
+            Commands::Fmt { files, check } => {
+                return fmt(&files, check);
+            }
         }
     }
 
@@ -75,6 +97,9 @@ fn main() -> Result<()> {
     };
 
     app.set_pipe_mode(pipe_mode);
+    if cli.view {
+        app.set_view_only();
+    }
 
     // Setup terminal
     // When stdout is piped, use stderr for TUI rendering
@@ -86,9 +111,24 @@ fn main() -> Result<()> {
     let mut terminal =
         Terminal::new(backend).map_err(|e| miette::miette!("Failed to create terminal: {}", e))?;
 
+    // Image sizes depend on the cell size in pixels, if the terminal reports it
+    if let Ok(size) = window_size()
+        && size.columns > 0
+        && size.rows > 0
+    {
+        app.image_manager_mut()
+            .set_cell_size(size.width / size.columns, size.height / size.rows);
+    }
+
     // Run app
     let res = run_app(&mut terminal, &mut app);
 
+    if app.image_manager().protocol() == ImageProtocol::Kitty {
+        let _ = terminal
+            .backend_mut()
+            .write_all(graphics::kitty_delete_all());
+    }
+
     // Restore terminal
     disable_raw_mode().map_err(|e| miette::miette!("Failed to disable raw mode: {}", e))?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)
@@ -101,8 +141,9 @@ fn main() -> Result<()> {
         eprintln!("Error: {}", e);
     }
 
-    // In pipe mode, write the buffer content to stdout on exit
-    if pipe_mode {
+    // In pipe mode, write the buffer content to stdout on exit; a read-only
+    // view only passes it on when stdout is piped
+    if pipe_mode && (!cli.view || stdout_is_pipe) {
         let content = app.buffer().content();
         io::stdout()
             .write_all(content.as_bytes())
@@ -115,16 +156,26 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+fn run_app<B: ratatui::backend::Backend + Write>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+) -> Result<()> {
+    // Images currently drawn with terminal graphics protocols
+    let mut drawn_images: Vec<ImagePlacement> = Vec::new();
+
     loop {
         // Draw UI
         terminal
             .draw(|f| {
+                // Focus mode hides the status bar unless it has a prompt to show
+                let new_page_prompt = app.new_page_prompt();
+                let show_status_bar =
+                    !app.is_focus_mode() || app.show_quit_dialog() || new_page_prompt.is_some();
                 let main_chunks = Layout::default()
                     .direction(Direction::Vertical)
                     .constraints([
-                        Constraint::Min(1),    // Main area (editor + optional file browser)
-                        Constraint::Length(1), // Status bar
+                        Constraint::Min(1), // Main area (editor + optional file browser)
+                        Constraint::Length(show_status_bar as u16), // Status bar
                     ])
                     .split(f.area());
 
@@ -142,6 +193,52 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
                     (main_chunks[0], None)
                 };
 
+                // Split the outline and backlinks sidebar off the right of the
+                // editor if visible, stacking them when both are
+                let (editor_area, sidebar_area) =
+                    if app.is_outline_visible() || app.is_backlinks_visible() {
+                        let chunks = Layout::default()
+                            .direction(Direction::Horizontal)
+                            .constraints([
+                                Constraint::Percentage(80), // Editor
+                                Constraint::Percentage(20), // Sidebar
+                            ])
+                            .split(editor_area);
+                        (chunks[0], Some(chunks[1]))
+                    } else {
+                        (editor_area, None)
+                    };
+                let (outline_area, backlinks_area) = match sidebar_area {
+                    Some(area) if app.is_outline_visible() && app.is_backlinks_visible() => {
+                        let chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([
+                                Constraint::Percentage(50), // Outline
+                                Constraint::Percentage(50), // Backlinks
+                            ])
+                            .split(area);
+                        (Some(chunks[0]), Some(chunks[1]))
+                    }
+                    Some(area) if app.is_outline_visible() => (Some(area), None),
+                    area => (None, area),
+                };
+
+                // Render outline if visible
+                if let Some(area) = outline_area {
+                    let outline = OutlineWidget::new(app.outline())
+                        .with_current(app.outline_current())
+                        .with_focus(app.is_outline_focused());
+                    f.render_widget(outline, area);
+                }
+
+                // Render backlinks if visible
+                if let Some(area) = backlinks_area {
+                    let backlinks = BacklinksWidget::new(app.backlinks(), app.workspace_root())
+                        .with_selected(app.backlinks_selected())
+                        .with_focus(app.is_backlinks_focused());
+                    f.render_widget(backlinks, area);
+                }
+
                 // Render file browser if visible
                 if let Some(area) = file_browser_area
                     && let Some(tree) = app.file_tree()
@@ -150,14 +247,44 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
                     f.render_widget(file_browser, area);
                 }
 
+                // Center the text column in focus mode; lines wrap to its width
+                let editor_area = if app.is_focus_mode() {
+                    let width = app.focus_width().min(editor_area.width);
+                    Rect {
+                        x: editor_area.x + (editor_area.width - width) / 2,
+                        width,
+                        ..editor_area
+                    }
+                } else {
+                    editor_area
+                };
+
                 // Render editor
-                let editor = EditorWidget::new(app.buffer())
+                app.set_editor_width(editor_area.width);
+                app.set_editor_height(editor_area.height);
+                let mut editor = EditorWidget::new(app.buffer())
                     .with_scroll(app.scroll_offset())
                     .with_code_renderer(app.code_renderer())
+                    .with_markdown_renderer(app.markdown_renderer())
                     .with_image_manager(app.image_manager())
                     .with_diagnostics(app.diagnostics_manager())
                     .with_line_numbers(app.show_line_numbers())
-                    .with_current_line_highlight(app.show_current_line_highlight());
+                    .with_current_line_highlight(app.show_current_line_highlight())
+                    .with_partial_wysiwyg(app.partial_wysiwyg())
+                    .with_table_options(app.table_options())
+                    .with_folds(app.folds())
+                    .with_preview(app.is_preview())
+                    .with_wrap(app.is_focus_mode());
+                if let Some(paragraph) = app.focus_paragraph() {
+                    editor = editor.with_focus(paragraph);
+                }
+                if let Some((start, end)) = app.selection() {
+                    editor = editor.with_selection(start, end);
+                }
+                if app.show_search_dialog() {
+                    editor = editor
+                        .with_search_matches(app.search_results(), app.search_current_index());
+                }
                 f.render_widget(editor, editor_area);
 
                 // Render status bar
@@ -167,20 +294,31 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
                     status_bar =
                         status_bar.with_warning("Unsaved changes! Press Y to quit, N to cancel");
                 }
-                f.render_widget(status_bar, main_chunks[1]);
+                if let Some(prompt) = &new_page_prompt {
+                    status_bar = status_bar.with_warning(prompt);
+                }
+                if app.is_preview() {
+                    status_bar = status_bar.with_mode("PREVIEW");
+                }
+                if let Some(stats) = app.document_stats() {
+                    status_bar = status_bar.with_stats(stats);
+                }
+                if let Some(stats) = app.selection_stats() {
+                    status_bar = status_bar.with_selection_stats(stats);
+                }
+                if show_status_bar {
+                    f.render_widget(status_bar, main_chunks[1]);
+                }
 
                 // Render completion popup if visible
                 if app.show_completion() {
                     let items = app.filtered_completion_items();
                     if !items.is_empty() {
-                        let cursor = app.buffer().cursor();
                         let gutter_width = app.line_number_gutter_width();
                         // Use display width instead of column count for correct positioning
-                        let display_width = app
-                            .buffer()
-                            .display_width_to_column(cursor.line, cursor.column);
+                        let display_width = app.cursor_screen_column();
                         let cursor_x = display_width as u16 + gutter_width;
-                        let cursor_y = (cursor.line - app.scroll_offset()) as u16;
+                        let cursor_y = app.cursor_screen_row() as u16;
 
                         let popup_rect = CompletionPopup::calculate_rect(
                             editor_area.x + cursor_x,
@@ -193,6 +331,21 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
                     }
                 }
 
+                // Render quick fix popup if visible
+                if !app.code_actions().is_empty() {
+                    let gutter_width = app.line_number_gutter_width();
+                    let cursor_x = app.cursor_screen_column() as u16 + gutter_width;
+                    let cursor_y = app.cursor_screen_row() as u16;
+                    let popup_rect = CompletionPopup::calculate_rect(
+                        editor_area.x + cursor_x,
+                        editor_area.y + cursor_y,
+                        editor_area,
+                    );
+                    let popup =
+                        CodeActionPopup::new(app.code_actions(), app.code_action_selected());
+                    f.render_widget(popup, popup_rect);
+                }
+
                 // Render quit confirmation dialog if visible
                 if app.show_quit_dialog() {
                     let dialog = QuitDialog::new();
@@ -206,11 +359,19 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
                         app.search_match_count(),
                         app.search_current_index(),
                     )
-                    .with_active_field(app.search_active_field());
+                    .with_mode(app.search_mode())
+                    .with_active_field(app.search_active_field())
+                    .with_options(app.search_options())
+                    .with_error(app.search_error());
+                    if app.buffer().document_type().markdown_ast().is_some() {
+                        search_dialog = search_dialog.with_scope(app.search_scope());
+                    }
 
                     if app.search_mode() == SearchMode::Replace {
                         search_dialog = search_dialog.with_replace(app.replace_query());
                     }
+                    let history_prompt = app.history_search_prompt();
+                    search_dialog = search_dialog.with_history_search(history_prompt.as_deref());
 
                     f.render_widget(search_dialog, f.area());
                 }
@@ -223,31 +384,53 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
 
                 // Render goto line dialog if visible
                 if app.show_goto_line_dialog() {
+                    let history_prompt = app.history_search_prompt();
                     let goto_line_dialog = GotoLineDialog::new(
                         app.goto_line_input(),
                         app.buffer().cursor().line,
                         app.buffer().line_count(),
-                    );
+                    )
+                    .with_history_search(history_prompt.as_deref());
                     f.render_widget(goto_line_dialog, f.area());
                 }
 
                 // Render mq query dialog if visible
                 if app.show_mq_query_dialog() {
+                    let history_prompt = app.history_search_prompt();
                     let mq_query_dialog =
-                        MqQueryDialog::new(app.mq_query_input(), app.mq_query_result());
+                        MqQueryDialog::new(app.mq_query_input(), app.mq_query_result())
+                            .with_history_search(history_prompt.as_deref());
                     f.render_widget(mq_query_dialog, f.area());
                 }
 
-                // Set cursor position (only when file browser is not visible and no dialog)
-                if !app.is_file_browser_visible() {
-                    let cursor = app.buffer().cursor();
+                // Render front matter form if visible
+                if let Some(form) = app.front_matter_form() {
+                    f.render_widget(FrontMatterDialog::new(form), f.area());
+                }
+
+                // Render statistics dialog if visible
+                if app.show_stats_dialog()
+                    && let Some(total) = app.document_stats()
+                {
+                    let sections = app.section_stats();
+                    let dialog = StatsDialog::new(total, &sections, app.stats_selected());
+                    f.render_widget(dialog, f.area());
+                }
+
+                // Set cursor position (only when file browser is not visible, the
+                // sidebar has no focus and no dialog)
+                if !app.is_file_browser_visible()
+                    && !app.is_outline_focused()
+                    && !app.is_backlinks_focused()
+                    && app.front_matter_form().is_none()
+                    && !app.show_stats_dialog()
+                    && !app.is_preview()
+                {
                     let gutter_width = app.line_number_gutter_width();
                     // Use display width instead of column count for correct positioning
-                    let display_width = app
-                        .buffer()
-                        .display_width_to_column(cursor.line, cursor.column);
+                    let display_width = app.cursor_screen_column();
                     let cursor_x = display_width as u16 + gutter_width;
-                    let cursor_y = (cursor.line - app.scroll_offset()) as u16;
+                    let cursor_y = app.cursor_screen_row() as u16;
 
                     if cursor_y < editor_area.height {
                         f.set_cursor_position((editor_area.x + cursor_x, editor_area.y + cursor_y));
@@ -256,6 +439,27 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
             })
             .map_err(|e| miette::miette!("Failed to draw terminal: {}", e))?;
 
+        // Graphics protocol images live outside the cell buffer, so they are
+        // redrawn only when their placement changes
+        let placements = app.image_manager().take_placements();
+        if placements != drawn_images {
+            if app.image_manager().protocol() == ImageProtocol::Kitty {
+                terminal
+                    .backend_mut()
+                    .write_all(graphics::kitty_delete_all())
+                    .map_err(|e| miette::miette!("Failed to clear images: {}", e))?;
+            } else if !drawn_images.is_empty() {
+                // Sixel and iTerm2 images overwrite cell pixels; repaint the screen
+                terminal
+                    .clear()
+                    .map_err(|e| miette::miette!("Failed to clear terminal: {}", e))?;
+                drawn_images.clear();
+                continue;
+            }
+            draw_images(terminal.backend_mut(), app, &placements)?;
+            drawn_images = placements;
+        }
+
         // Handle input
         if event::poll(Duration::from_millis(100))
             .map_err(|e| miette::miette!("Failed to poll events: {}", e))?
@@ -282,6 +486,27 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
 
     Ok(())
 }
+/// Write graphics protocol escape sequences for placed images
+fn draw_images<W: Write>(out: &mut W, app: &App, placements: &[ImagePlacement]) -> Result<()> {
+    if placements.is_empty() {
+        return Ok(());
+    }
+
+    queue!(out, SavePosition).map_err(|e| miette::miette!("Failed to draw images: {}", e))?;
+    for placement in placements {
+        // Unreadable images keep their caption only
+        let Ok(sequence) = app.image_manager().escape_sequence(placement) else {
+            continue;
+        };
+        queue!(out, MoveTo(placement.x, placement.y))
+            .and_then(|_| out.write_all(&sequence))
+            .map_err(|e| miette::miette!("Failed to draw images: {}", e))?;
+    }
+    queue!(out, RestorePosition)
+        .and_then(|_| out.flush())
+        .map_err(|e| miette::miette!("Failed to draw images: {}", e))
+}
+
 /// Initialize default configuration file
 fn init_config() -> Result<()> {
     let config = Config::default();
@@ -318,6 +543,52 @@ fn init_config() -> Result<()> {
     Ok(())
 }
 
+/// Format Markdown files in place, or stdin to stdout
+fn fmt(files: &[PathBuf], check: bool) -> Result<()> {
+    let options = Config::load_or_default().editor.format_options();
+
+    if files.is_empty() {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| miette::miette!("Failed to read from stdin: {}", e))?;
+        let formatted = format::format(&content, &options);
+        if check {
+            return if formatted == content {
+                Ok(())
+            } else {
+                Err(miette::miette!("stdin needs formatting"))
+            };
+        }
+        io::stdout()
+            .write_all(formatted.as_bytes())
+            .map_err(|e| miette::miette!("Failed to write to stdout: {}", e))?;
+        return Ok(());
+    }
+
+    let mut unformatted = 0;
+    for path in files {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| miette::miette!("Failed to read {}: {}", path.display(), e))?;
+        let formatted = format::format(&content, &options);
+        if formatted == content {
+            continue;
+        }
+        if check {
+            println!("{}", path.display());
+            unformatted += 1;
+        } else {
+            std::fs::write(path, formatted)
+                .map_err(|e| miette::miette!("Failed to write {}: {}", path.display(), e))?;
+        }
+    }
+
+    if unformatted > 0 {
+        return Err(miette::miette!("{} file(s) need formatting", unformatted));
+    }
+    Ok(())
+}
+
 /// List available syntax highlighting themes
 fn list_themes() -> Result<()> {
     let themes = CodeRenderer::available_themes();
@@ -1,14 +1,23 @@
 pub mod code;
+pub mod graphics;
+pub mod highlight_cache;
+pub mod image_cache;
 pub mod image_manager;
 pub mod markdown;
 pub mod plain_text;
+pub mod source_map;
+pub mod table;
 
 use crate::document::DocumentBuffer;
-pub use code::{CodeRenderer, SemanticToken, decode_semantic_tokens};
-pub use image_manager::ImageManager;
+pub use code::{CodeRenderer, SemanticToken, decode_semantic_tokens, fence_language};
+pub use graphics::ImageProtocol;
+pub use image_cache::ImageState;
+pub use image_manager::{ImageManager, ImagePlacement};
 pub use markdown::MarkdownRenderer;
 pub use plain_text::PlainTextRenderer;
 use ratatui::text::Span;
+pub use source_map::MappedSpan;
+pub use table::{TableLayout, TableOptions, TableOverflow};
 
 /// Trait for rendering document lines
 ///
@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
 
 use lsp_types::SemanticTokens;
 use ratatui::{
@@ -12,7 +14,8 @@ use syntect::{
 };
 
 use super::Renderer;
-use crate::document::DocumentBuffer;
+use super::highlight_cache::{self, HighlightCache, LineState};
+use crate::document::{DocumentBuffer, DocumentType};
 use crate::theme;
 
 /// Semantic token information from LSP
@@ -64,18 +67,44 @@ pub fn decode_semantic_tokens(
     result
 }
 
+/// Ranges with more missing lines than this are highlighted on the worker thread
+const MAX_INLINE_HIGHLIGHT_LINES: usize = 200;
+
+/// Lines highlighted on the worker thread between cache updates
+const HIGHLIGHT_CHUNK_LINES: usize = 256;
+
+/// Request to highlight lines on the worker thread
+struct HighlightJob {
+    generation: u64,
+    language: String,
+    theme: Arc<Theme>,
+    first: usize,
+    state: Option<LineState>,
+    lines: Vec<String>,
+}
+
 /// Code renderer with syntax highlighting
 ///
 /// Supports two modes:
 /// 1. Syntect (default) - Static syntax highlighting
 /// 2. LSP Semantic Tokens (optional) - Semantic highlighting from language servers
+///
+/// Syntect results are cached per line together with the parser state, so
+/// multi-line constructs keep their highlighting and unchanged lines are not
+/// highlighted again.
 pub struct CodeRenderer {
     /// Default syntect syntax set for static highlighting
-    default_syntax_set: SyntaxSet,
+    default_syntax_set: Arc<SyntaxSet>,
     /// Custom syntax set with mq language
-    mq_syntax_set: SyntaxSet,
+    mq_syntax_set: Arc<SyntaxSet>,
     /// Theme for syntax highlighting
-    theme: Theme,
+    theme: Arc<Theme>,
+    /// Highlighted lines of the current code file, shared with the worker thread
+    highlight_cache: Arc<Mutex<HighlightCache>>,
+    /// Highlighted lines of the fenced code blocks of a Markdown document, by fence line
+    code_blocks: Mutex<HashMap<usize, HighlightCache>>,
+    /// Sends highlight jobs for large ranges to the worker thread
+    highlighter: Sender<HighlightJob>,
     /// Theme set for loading themes
     theme_set: ThemeSet,
     /// LSP semantic tokens cache (line_idx -> tokens)
@@ -90,9 +119,78 @@ const MQ_SUBLIME_SYNTAX: &str = include_str!("../../mq.sublime-syntax");
 /// Embedded tarn theme (tmTheme plist format)
 const TARN_THEME: &[u8] = include_bytes!("../../assets/tarn.tmTheme");
 
+/// Code fence languages (as offered by the Markdown LSP completions) mapped to
+/// syntax tokens known to syntect or the mq syntax
+const FENCE_LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("shell", "bash"),
+    ("sh", "bash"),
+    ("zsh", "bash"),
+    ("fish", "bash"),
+    ("console", "bash"),
+    ("javascript", "js"),
+    ("jsx", "js"),
+    ("typescript", "js"),
+    ("typescriptreact", "js"),
+    ("tsx", "js"),
+    ("ts", "js"),
+    ("golang", "go"),
+    ("c++", "cpp"),
+    ("objective-c", "m"),
+    ("objc", "m"),
+    ("csharp", "cs"),
+    ("c#", "cs"),
+    ("scss", "css"),
+    ("less", "css"),
+    ("sqlite", "sql"),
+    ("postgres", "sql"),
+    ("postgresql", "sql"),
+    ("mysql", "sql"),
+    ("plaintext", "txt"),
+    ("text", "txt"),
+    ("markdown", "md"),
+    ("yml", "yaml"),
+    ("py", "python"),
+    ("rb", "ruby"),
+    ("rs", "rust"),
+    ("hs", "haskell"),
+    ("clj", "clojure"),
+    ("erl", "erlang"),
+];
+
+/// Resolve a code fence info string (e.g. `rust,ignore` or `shell`) to a syntax token
+pub fn fence_language(info: &str) -> Option<String> {
+    let language = info
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+        .next()
+        .filter(|language| !language.is_empty())?
+        .to_lowercase();
+
+    Some(
+        FENCE_LANGUAGE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == language)
+            .map_or(language, |(_, token)| token.to_string()),
+    )
+}
+
+/// Find a syntax by token, preferring the custom mq syntax set
+fn find_syntax<'a>(
+    mq_syntax_set: &'a SyntaxSet,
+    default_syntax_set: &'a SyntaxSet,
+    language: &str,
+) -> Option<(&'a SyntaxReference, &'a SyntaxSet)> {
+    if let Some(syntax) = mq_syntax_set.find_syntax_by_token(language) {
+        return Some((syntax, mq_syntax_set));
+    }
+
+    default_syntax_set
+        .find_syntax_by_token(language)
+        .map(|s| (s, default_syntax_set))
+}
+
 /// Load the embedded tarn theme
 fn load_tarn_theme() -> Theme {
-    plist::from_bytes(TARN_THEME).unwrap_or_default()
+    ThemeSet::load_from_reader(&mut std::io::Cursor::new(TARN_THEME)).unwrap_or_default()
 }
 
 impl CodeRenderer {
@@ -102,8 +200,8 @@ impl CodeRenderer {
 
     /// Create a new code renderer with specified theme
     pub fn with_theme(theme_name: &str) -> Self {
-        let default_syntax_set = SyntaxSet::load_defaults_newlines();
-        let mq_syntax_set = Self::build_mq_syntax_set();
+        let default_syntax_set = Arc::new(SyntaxSet::load_defaults_newlines());
+        let mq_syntax_set = Arc::new(Self::build_mq_syntax_set());
         let theme_set = ThemeSet::load_defaults();
         let theme = if theme_name == "tarn" {
             load_tarn_theme()
@@ -115,10 +213,20 @@ impl CodeRenderer {
                 .unwrap_or_else(load_tarn_theme)
         };
 
+        let highlight_cache = Arc::new(Mutex::new(HighlightCache::new()));
+        let highlighter = Self::spawn_highlighter(
+            Arc::clone(&highlight_cache),
+            Arc::clone(&mq_syntax_set),
+            Arc::clone(&default_syntax_set),
+        );
+
         Self {
             default_syntax_set,
             mq_syntax_set,
-            theme,
+            theme: Arc::new(theme),
+            highlight_cache,
+            code_blocks: Mutex::new(HashMap::new()),
+            highlighter,
             theme_set,
             semantic_tokens: HashMap::new(),
             use_semantic_tokens: false,
@@ -128,10 +236,50 @@ impl CodeRenderer {
     /// Set the theme by name
     pub fn set_theme(&mut self, theme_name: &str) {
         if theme_name == "tarn" {
-            self.theme = load_tarn_theme();
+            self.theme = Arc::new(load_tarn_theme());
         } else if let Some(theme) = self.theme_set.themes.get(theme_name) {
-            self.theme = theme.clone();
+            self.theme = Arc::new(theme.clone());
+        } else {
+            return;
         }
+        self.highlight_cache.lock().unwrap().clear();
+        self.code_blocks.lock().unwrap().clear();
+    }
+
+    /// Start the highlighter thread; it exits when the renderer is dropped
+    fn spawn_highlighter(
+        cache: Arc<Mutex<HighlightCache>>,
+        mq_syntax_set: Arc<SyntaxSet>,
+        default_syntax_set: Arc<SyntaxSet>,
+    ) -> Sender<HighlightJob> {
+        let (tx, rx) = mpsc::channel::<HighlightJob>();
+        std::thread::spawn(move || {
+            for job in rx {
+                let Some((syntax, syntax_set)) =
+                    find_syntax(&mq_syntax_set, &default_syntax_set, &job.language)
+                else {
+                    cache.lock().unwrap().cancel(job.generation);
+                    continue;
+                };
+
+                let mut first = job.first;
+                let mut state = job
+                    .state
+                    .unwrap_or_else(|| LineState::new(syntax, &job.theme));
+                for chunk in job.lines.chunks(HIGHLIGHT_CHUNK_LINES) {
+                    let highlighted = highlight_cache::highlight_lines(
+                        syntax_set, &job.theme, first, state, chunk,
+                    );
+                    state = highlighted.state().clone();
+                    first += chunk.len();
+                    // Stop early once an edit made the remaining lines stale
+                    if !cache.lock().unwrap().insert(job.generation, highlighted) {
+                        break;
+                    }
+                }
+            }
+        });
+        tx
     }
 
     /// Get list of available theme names
@@ -176,13 +324,60 @@ impl CodeRenderer {
 
     /// Get syntax reference and corresponding syntax set for a language
     fn get_syntax(&self, language: &str) -> Option<(&SyntaxReference, &SyntaxSet)> {
-        if let Some(syntax) = self.mq_syntax_set.find_syntax_by_token(language) {
-            return Some((syntax, &self.mq_syntax_set));
-        }
+        find_syntax(&self.mq_syntax_set, &self.default_syntax_set, language)
+    }
 
-        self.default_syntax_set
-            .find_syntax_by_token(language)
-            .map(|s| (s, &self.default_syntax_set))
+    /// Bring the syntect cache up to date for the lines above `end` of a code buffer
+    ///
+    /// Changed lines and everything below them are highlighted again, resuming
+    /// from the nearest cached parser state. Small ranges are highlighted right
+    /// away; larger ones on the worker thread, with lines falling back to
+    /// per-line highlighting until the results arrive.
+    pub fn prepare_highlighting(&self, buffer: &DocumentBuffer, end: usize) {
+        let DocumentType::Code { language } = buffer.document_type() else {
+            return;
+        };
+        let Some((syntax, syntax_set)) = self.get_syntax(language) else {
+            return;
+        };
+        let lines = buffer.lines();
+        let end = end.min(lines.len());
+
+        let mut cache = self.highlight_cache.lock().unwrap();
+        cache.sync(language, lines);
+        let Some(request) = cache.request(end) else {
+            return;
+        };
+
+        if end - request.first <= MAX_INLINE_HIGHLIGHT_LINES {
+            let state = request
+                .state
+                .unwrap_or_else(|| LineState::new(syntax, &self.theme));
+            let highlighted = highlight_cache::highlight_lines(
+                syntax_set,
+                &self.theme,
+                request.first,
+                state,
+                &lines[request.first..end],
+            );
+            cache.insert(request.generation, highlighted);
+        } else {
+            drop(cache);
+            let job = HighlightJob {
+                generation: request.generation,
+                language: language.clone(),
+                theme: Arc::clone(&self.theme),
+                first: request.first,
+                state: request.state,
+                lines: lines[request.first..end].to_vec(),
+            };
+            if self.highlighter.send(job).is_err() {
+                self.highlight_cache
+                    .lock()
+                    .unwrap()
+                    .cancel(request.generation);
+            }
+        }
     }
 
     /// Render line with LSP semantic tokens
@@ -258,6 +453,56 @@ impl CodeRenderer {
         }
     }
 
+    /// Highlight the first `end` body lines of the code block fenced at `fence_line`
+    ///
+    /// The parser state is carried from line to line, so constructs spanning
+    /// lines (block comments, multi-line strings) are highlighted correctly.
+    /// Lines are cached per block like those of a code file, so only changed
+    /// lines and the ones below them are highlighted again.
+    /// Returns None if the language is unknown.
+    pub fn highlight_code_block(
+        &self,
+        fence_line: usize,
+        language: &str,
+        body: &[String],
+        end: usize,
+    ) -> Option<Vec<Vec<Span<'static>>>> {
+        let (syntax, syntax_set) = self.get_syntax(language)?;
+        let end = end.min(body.len());
+
+        let mut blocks = self.code_blocks.lock().unwrap();
+        let cache = blocks.entry(fence_line).or_default();
+        cache.sync(language, body);
+        if let Some(request) = cache.request(end) {
+            let state = request
+                .state
+                .unwrap_or_else(|| LineState::new(syntax, &self.theme));
+            let highlighted = highlight_cache::highlight_lines(
+                syntax_set,
+                &self.theme,
+                request.first,
+                state,
+                &body[request.first..end],
+            );
+            cache.insert(request.generation, highlighted);
+        }
+        Some(
+            body[..end]
+                .iter()
+                .enumerate()
+                .map(|(line, text)| cache.spans(line, text).unwrap_or_default())
+                .collect(),
+        )
+    }
+
+    /// Drop the cached code blocks whose fence line doesn't satisfy `keep`
+    pub fn retain_code_blocks(&self, keep: impl Fn(usize) -> bool) {
+        self.code_blocks
+            .lock()
+            .unwrap()
+            .retain(|fence_line, _| keep(*fence_line));
+    }
+
     /// Render line with syntect (fallback)
     fn render_with_syntect(&self, content: &str, language: &str) -> Vec<Span<'_>> {
         if let Some((syntax, syntax_set)) = self.get_syntax(language) {
@@ -311,6 +556,15 @@ impl Renderer for CodeRenderer {
             return self.render_with_semantic_tokens(content, tokens);
         }
 
+        if let Some(spans) = self
+            .highlight_cache
+            .lock()
+            .unwrap()
+            .spans(line_idx, content)
+        {
+            return spans;
+        }
+
         self.render_with_syntect(content, language)
     }
 
@@ -323,6 +577,142 @@ impl Renderer for CodeRenderer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tarn_theme_loads() {
+        let theme = load_tarn_theme();
+        assert_eq!(theme.name.as_deref(), Some("Tarn"));
+        assert!(!theme.scopes.is_empty());
+    }
+
+    #[test]
+    fn test_fence_language() {
+        assert_eq!(fence_language("rust"), Some("rust".to_string()));
+        assert_eq!(fence_language("rust,ignore"), Some("rust".to_string()));
+        assert_eq!(fence_language("Shell"), Some("bash".to_string()));
+        assert_eq!(
+            fence_language("python {.numberLines}"),
+            Some("python".to_string())
+        );
+        assert_eq!(fence_language(""), None);
+
+        let renderer = CodeRenderer::new();
+        for info in [
+            "shell",
+            "zsh",
+            "javascript",
+            "tsx",
+            "postgres",
+            "objective-c",
+            "mq",
+        ] {
+            let language = fence_language(info).unwrap();
+            assert!(
+                renderer.get_syntax(&language).is_some(),
+                "no syntax for {}",
+                info
+            );
+        }
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_highlight_code_block_carries_state() {
+        let renderer = CodeRenderer::new();
+        let mut body = lines(&["s = \"\"\"", "not code", "\"\"\""]);
+        let block = renderer
+            .highlight_code_block(0, "python", &body, 3)
+            .unwrap();
+        let alone = renderer
+            .highlight_code_block(10, "python", &lines(&["not code"]), 1)
+            .unwrap();
+
+        assert_eq!(block.len(), 3);
+        assert_eq!(text(&block[1]), "not code");
+        // Inside the multi-line string the line is styled as a string
+        assert_ne!(block[1][0].style, alone[0][0].style);
+
+        // Closing the string on the first line re-highlights the lines below
+        body[0] = "s = \"\"".to_string();
+        let edited = renderer
+            .highlight_code_block(0, "python", &body, 3)
+            .unwrap();
+        assert_eq!(edited[1], alone[0]);
+        let fresh = CodeRenderer::new()
+            .highlight_code_block(0, "python", &body, 3)
+            .unwrap();
+        assert_eq!(edited, fresh);
+
+        renderer.retain_code_blocks(|fence_line| fence_line != 10);
+        assert_eq!(renderer.code_blocks.lock().unwrap().len(), 1);
+
+        assert!(
+            renderer
+                .highlight_code_block(20, "no-such-language", &lines(&["x"]), 1)
+                .is_none()
+        );
+    }
+
+    fn code_buffer(dir: &tempfile::TempDir, content: &str) -> DocumentBuffer {
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, content).unwrap();
+        DocumentBuffer::from_file(&path).unwrap()
+    }
+
+    fn text(spans: &[Span<'_>]) -> String {
+        spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_prepare_highlighting_carries_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut buffer = code_buffer(&dir, "/*\nlet x = 1;\n*/\nlet y = 2;");
+        let renderer = CodeRenderer::new();
+        let stateless = renderer.render_line(&buffer, 1, false);
+
+        renderer.prepare_highlighting(&buffer, 4);
+        let in_comment = renderer.render_line(&buffer, 1, false);
+        assert_eq!(text(&in_comment), "let x = 1;");
+        assert_eq!(in_comment.len(), 1);
+        assert_ne!(in_comment, stateless);
+
+        // Removing the comment opener re-highlights the following lines
+        buffer.cursor_mut().line = 0;
+        buffer.cursor_mut().column = 2;
+        buffer.delete_char();
+        buffer.delete_char();
+        renderer.prepare_highlighting(&buffer, 4);
+        assert_eq!(renderer.render_line(&buffer, 1, false), stateless);
+    }
+
+    #[test]
+    fn test_prepare_highlighting_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = format!(
+            "/*\n{}*/\nlet y = 2;",
+            "let x = 1;\n".repeat(MAX_INLINE_HIGHLIGHT_LINES * 2)
+        );
+        let buffer = code_buffer(&dir, &content);
+        let last_comment_line = MAX_INLINE_HIGHLIGHT_LINES * 2;
+        let renderer = CodeRenderer::new();
+
+        renderer.prepare_highlighting(&buffer, buffer.line_count());
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while renderer.highlight_cache.lock().unwrap().len() < buffer.line_count() {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "highlighting timed out"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let spans = renderer.render_line(&buffer, last_comment_line, false);
+        assert_eq!(text(&spans), "let x = 1;");
+        assert_eq!(spans.len(), 1);
+    }
+
     #[test]
     fn test_code_renderer_creation() {
         let renderer = CodeRenderer::new();
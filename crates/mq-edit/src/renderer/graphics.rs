@@ -0,0 +1,372 @@
+use std::io::Cursor;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageFormat, Rgba};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::theme;
+
+/// Size of a Kitty graphics payload chunk (the protocol limit)
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// How images are drawn in the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageProtocol {
+    /// Detect from the environment
+    #[default]
+    Auto,
+    /// Kitty graphics protocol (also WezTerm, Ghostty)
+    Kitty,
+    /// iTerm2 inline images (also WezTerm, VS Code)
+    Iterm2,
+    /// DEC Sixel graphics
+    Sixel,
+    /// Unicode half-block cell art, works in any true-color terminal
+    Halfblocks,
+    /// Show only the image caption
+    None,
+}
+
+impl ImageProtocol {
+    /// Resolve `Auto` from the current environment
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto => Self::detect(|name| std::env::var(name).ok()),
+            protocol => protocol,
+        }
+    }
+
+    /// Detect the best protocol from terminal environment variables
+    pub fn detect(env: impl Fn(&str) -> Option<String>) -> Self {
+        let term = env("TERM").unwrap_or_default();
+        let term_program = env("TERM_PROGRAM").unwrap_or_default();
+
+        if env("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || term_program == "ghostty"
+        {
+            Self::Kitty
+        } else if term_program == "iTerm.app"
+            || term_program == "WezTerm"
+            || term_program == "vscode"
+            || env("LC_TERMINAL").is_some_and(|t| t == "iTerm2")
+        {
+            Self::Iterm2
+        } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+            Self::Sixel
+        } else {
+            Self::Halfblocks
+        }
+    }
+
+    /// Whether images are drawn with escape sequences outside the cell buffer
+    pub fn uses_escape_sequences(self) -> bool {
+        matches!(self, Self::Kitty | Self::Iterm2 | Self::Sixel)
+    }
+}
+
+/// Fit an image into a cell area, keeping its aspect ratio without upscaling
+///
+/// Returns the number of columns and rows the image occupies.
+pub fn fit_cells(
+    image_size: (u32, u32),
+    max_cells: (u16, u16),
+    cell_size: (u16, u16),
+) -> (u16, u16) {
+    let (width, height) = image_size;
+    let (max_cols, max_rows) = max_cells;
+    let (cell_width, cell_height) = (cell_size.0.max(1) as f64, cell_size.1.max(1) as f64);
+    if width == 0 || height == 0 || max_cols == 0 || max_rows == 0 {
+        return (0, 0);
+    }
+
+    let scale = (max_cols as f64 * cell_width / width as f64)
+        .min(max_rows as f64 * cell_height / height as f64)
+        .min(1.0);
+    let cols = (width as f64 * scale / cell_width).ceil() as u16;
+    let rows = (height as f64 * scale / cell_height).ceil() as u16;
+    (cols.clamp(1, max_cols), rows.clamp(1, max_rows))
+}
+
+/// Render an image as half-block cell art (two pixels per cell)
+///
+/// Each cell shows `▀` with the upper pixel as foreground and the lower pixel
+/// as background, so the image fits `cols` x `rows * 2` square pixels.
+pub fn halfblock_lines(image: &DynamicImage, cols: u16, rows: u16) -> Vec<Line<'static>> {
+    if cols == 0 || rows == 0 || image.width() == 0 || image.height() == 0 {
+        return Vec::new();
+    }
+
+    let resized = image.resize(cols as u32, rows as u32 * 2, FilterType::Triangle);
+    let (width, height) = resized.dimensions();
+
+    (0..height)
+        .step_by(2)
+        .map(|y| {
+            let spans = (0..width)
+                .map(|x| {
+                    let upper = pixel_color(resized.get_pixel(x, y));
+                    let lower = if y + 1 < height {
+                        pixel_color(resized.get_pixel(x, y + 1))
+                    } else {
+                        theme::BG
+                    };
+                    Span::styled("▀", Style::default().fg(upper).bg(lower))
+                })
+                .collect::<Vec<_>>();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn pixel_color(pixel: Rgba<u8>) -> Color {
+    let [r, g, b, a] = pixel.0;
+    if a < 128 {
+        theme::BG
+    } else {
+        Color::Rgb(r, g, b)
+    }
+}
+
+/// Encode an image as a Kitty graphics escape sequence scaled to `cols` x `rows` cells
+pub fn kitty_sequence(image: &DynamicImage, cols: u16, rows: u16) -> Result<Vec<u8>, String> {
+    let payload = BASE64.encode(encode_png(image)?);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+
+    let mut out = Vec::with_capacity(payload.len() + chunks.len() * 32);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            // Transmit and display a PNG, quietly, without moving the cursor
+            out.extend_from_slice(
+                format!("\x1b_Ga=T,f=100,q=2,C=1,c={},r={},m={};", cols, rows, more).as_bytes(),
+            );
+        } else {
+            out.extend_from_slice(format!("\x1b_Gm={};", more).as_bytes());
+        }
+        out.extend_from_slice(chunk);
+        out.extend_from_slice(b"\x1b\\");
+    }
+    Ok(out)
+}
+
+/// Escape sequence deleting all Kitty images on screen
+pub fn kitty_delete_all() -> &'static [u8] {
+    b"\x1b_Ga=d,d=A,q=2\x1b\\"
+}
+
+/// Encode an image as an iTerm2 inline image escape sequence sized to `cols` x `rows` cells
+pub fn iterm2_sequence(image: &DynamicImage, cols: u16, rows: u16) -> Result<Vec<u8>, String> {
+    let png = encode_png(image)?;
+    Ok(format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+        png.len(),
+        cols,
+        rows,
+        BASE64.encode(&png)
+    )
+    .into_bytes())
+}
+
+/// Encode an image as a Sixel escape sequence
+///
+/// Colors are quantized to a 6x6x6 color cube, which every Sixel terminal
+/// supports (at least 256 color registers).
+pub fn sixel_sequence(image: &DynamicImage) -> Vec<u8> {
+    let rgba = image.to_rgba8();
+    let (width, height) = rgba.dimensions();
+
+    // Color register per pixel, None for transparent pixels
+    let registers: Vec<Option<u8>> = rgba
+        .pixels()
+        .map(|p| {
+            let [r, g, b, a] = p.0;
+            (a >= 128).then(|| {
+                let level = |c: u8| (c as u16 * 5 + 127) / 255;
+                (level(r) * 36 + level(g) * 6 + level(b)) as u8
+            })
+        })
+        .collect();
+
+    let mut out = String::from("\x1bP0;1;0q");
+    out.push_str(&format!("\"1;1;{};{}", width, height));
+
+    let mut used = [false; 216];
+    for register in registers.iter().flatten() {
+        used[*register as usize] = true;
+    }
+    for (register, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+        let percent = |level: usize| level * 100 / 5;
+        out.push_str(&format!(
+            "#{};2;{};{};{}",
+            register,
+            percent(register / 36),
+            percent(register / 6 % 6),
+            percent(register % 6)
+        ));
+    }
+
+    for band in (0..height).step_by(6) {
+        let band_height = (height - band).min(6);
+        let mut first = true;
+        for (register, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+            let mut row = String::new();
+            let mut any = false;
+            for x in 0..width {
+                let mut bits = 0u8;
+                for dy in 0..band_height {
+                    let index = ((band + dy) * width + x) as usize;
+                    if registers[index] == Some(register as u8) {
+                        bits |= 1 << dy;
+                    }
+                }
+                any |= bits != 0;
+                row.push((b'?' + bits) as char);
+            }
+            if !any {
+                continue;
+            }
+            if !first {
+                // Carriage return: overlay the next color on the same band
+                out.push('$');
+            }
+            first = false;
+            out.push_str(&format!("#{}", register));
+            out.push_str(&run_length_encode(&row));
+        }
+        // Move to the next band
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out.into_bytes()
+}
+
+/// Compress repeated sixel characters with `!<count><char>`
+fn run_length_encode(row: &str) -> String {
+    let mut out = String::new();
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut count = 1;
+        while chars.peek() == Some(&c) {
+            chars.next();
+            count += 1;
+        }
+        if count > 3 {
+            out.push_str(&format!("!{}{}", count, c));
+        } else {
+            out.extend(std::iter::repeat_n(c, count));
+        }
+    }
+    out
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn checker(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+            if (x + y) % 2 == 0 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_detect_protocol() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert_eq!(
+            ImageProtocol::detect(env(&[("TERM", "xterm-kitty")])),
+            ImageProtocol::Kitty
+        );
+        assert_eq!(
+            ImageProtocol::detect(env(&[("TERM_PROGRAM", "iTerm.app")])),
+            ImageProtocol::Iterm2
+        );
+        assert_eq!(
+            ImageProtocol::detect(env(&[("TERM", "foot")])),
+            ImageProtocol::Sixel
+        );
+        assert_eq!(
+            ImageProtocol::detect(env(&[("TERM", "xterm-256color")])),
+            ImageProtocol::Halfblocks
+        );
+    }
+
+    #[test]
+    fn test_fit_cells() {
+        // Wide image is limited by the pane width
+        assert_eq!(fit_cells((1600, 400), (40, 10), (8, 16)), (40, 5));
+        // Tall image is limited by the configured rows
+        assert_eq!(fit_cells((400, 1600), (40, 10), (8, 16)), (5, 10));
+        // Small images are not upscaled
+        assert_eq!(fit_cells((16, 16), (40, 10), (8, 16)), (2, 1));
+        assert_eq!(fit_cells((16, 16), (0, 10), (8, 16)), (0, 0));
+    }
+
+    #[test]
+    fn test_halfblock_lines() {
+        let lines = halfblock_lines(&checker(8, 8), 4, 2);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.spans.len() == 4));
+        assert!(
+            lines[0]
+                .spans
+                .iter()
+                .all(|span| span.content == "▀" && matches!(span.style.bg, Some(Color::Rgb(..))))
+        );
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        let image = checker(4, 4);
+
+        let kitty = String::from_utf8(kitty_sequence(&image, 2, 1).unwrap()).unwrap();
+        assert!(kitty.starts_with("\x1b_Ga=T,f=100,q=2,C=1,c=2,r=1,m=0;"));
+        assert!(kitty.ends_with("\x1b\\"));
+
+        let iterm2 = String::from_utf8(iterm2_sequence(&image, 2, 1).unwrap()).unwrap();
+        assert!(iterm2.starts_with("\x1b]1337;File=inline=1;"));
+        assert!(iterm2.ends_with('\x07'));
+
+        let sixel = String::from_utf8(sixel_sequence(&image)).unwrap();
+        assert!(sixel.starts_with("\x1bP0;1;0q\"1;1;4;4"));
+        assert!(sixel.contains("#180;2;100;0;0"));
+        assert!(sixel.contains("#5;2;0;0;100"));
+        assert!(sixel.ends_with("-\x1b\\"));
+    }
+
+    #[test]
+    fn test_run_length_encode() {
+        assert_eq!(run_length_encode("~~~~~??"), "!5~??");
+        assert_eq!(run_length_encode("abc"), "abc");
+    }
+}
@@ -0,0 +1,352 @@
+use ratatui::{
+    style::{Color, Style},
+    text::Span,
+};
+use syntect::{
+    highlighting::{HighlightIterator, HighlightState, Highlighter, Theme},
+    parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet},
+};
+
+/// Lines between saved parser states
+///
+/// Highlighting resumes from the nearest saved state at or above an edited line.
+pub const CHECKPOINT_INTERVAL: usize = 16;
+
+/// Parser and highlighter state at a line boundary
+#[derive(Debug, Clone)]
+pub struct LineState {
+    parse: ParseState,
+    highlight: HighlightState,
+}
+
+impl LineState {
+    /// State at the start of a document
+    pub fn new(syntax: &SyntaxReference, theme: &Theme) -> Self {
+        Self {
+            parse: ParseState::new(syntax),
+            highlight: HighlightState::new(&Highlighter::new(theme), ScopeStack::new()),
+        }
+    }
+}
+
+/// A highlighted line
+#[derive(Debug, Clone)]
+pub struct CachedLine {
+    /// Source text the spans were computed from
+    text: String,
+    spans: Vec<Span<'static>>,
+    /// State before this line, kept on checkpoint lines only
+    state: Option<LineState>,
+}
+
+/// Consecutive lines highlighted from a saved state
+#[derive(Debug)]
+pub struct HighlightedLines {
+    /// Index of the first line
+    first: usize,
+    lines: Vec<CachedLine>,
+    /// State after the last line
+    state: LineState,
+}
+
+impl HighlightedLines {
+    /// State after the last line
+    pub fn state(&self) -> &LineState {
+        &self.state
+    }
+
+    /// Spans of each line
+    pub fn into_spans(self) -> Vec<Vec<Span<'static>>> {
+        self.lines.into_iter().map(|line| line.spans).collect()
+    }
+}
+
+/// Highlight `lines`, the first of which is document line `first`, starting from `state`
+pub fn highlight_lines<S: AsRef<str>>(
+    syntax_set: &SyntaxSet,
+    theme: &Theme,
+    first: usize,
+    state: LineState,
+    lines: &[S],
+) -> HighlightedLines {
+    let highlighter = Highlighter::new(theme);
+    let LineState {
+        mut parse,
+        mut highlight,
+    } = state;
+
+    let lines = lines
+        .iter()
+        .enumerate()
+        .map(|(offset, line)| {
+            let line = line.as_ref();
+            let state = (first + offset)
+                .is_multiple_of(CHECKPOINT_INTERVAL)
+                .then(|| LineState {
+                    parse: parse.clone(),
+                    highlight: highlight.clone(),
+                });
+
+            // The default syntaxes expect lines with their newline
+            let with_newline = format!("{}\n", line);
+            let spans = match parse.parse_line(&with_newline, syntax_set) {
+                Ok(ops) => {
+                    HighlightIterator::new(&mut highlight, &ops, &with_newline, &highlighter)
+                        .filter_map(|(style, text)| {
+                            let text = text.strip_suffix('\n').unwrap_or(text);
+                            (!text.is_empty()).then(|| {
+                                let fg_color = Color::Rgb(
+                                    style.foreground.r,
+                                    style.foreground.g,
+                                    style.foreground.b,
+                                );
+                                Span::styled(text.to_string(), Style::default().fg(fg_color))
+                            })
+                        })
+                        .collect()
+                }
+                Err(_) => vec![Span::raw(line.to_string())],
+            };
+
+            CachedLine {
+                text: line.to_string(),
+                spans,
+                state,
+            }
+        })
+        .collect();
+
+    HighlightedLines {
+        first,
+        lines,
+        state: LineState { parse, highlight },
+    }
+}
+
+/// Where highlighting has to continue to cover a range of lines
+#[derive(Debug)]
+pub struct HighlightRequest {
+    /// Cache generation the result must be inserted into
+    pub generation: u64,
+    /// First line to highlight
+    pub first: usize,
+    /// State before `first` (None at the start of the document)
+    pub state: Option<LineState>,
+}
+
+/// Highlighted lines of one document, cached from the top down
+///
+/// Lines are compared with the buffer on every sync; the first changed line
+/// drops its cached results and everything below it.
+#[derive(Debug, Default)]
+pub struct HighlightCache {
+    /// Language the lines were highlighted as
+    language: Option<String>,
+    lines: Vec<CachedLine>,
+    /// State after the last cached line
+    tail: Option<LineState>,
+    /// Bumped whenever cached lines are dropped so stale results are discarded
+    generation: u64,
+    /// End of the range being highlighted in the background
+    pending: Option<usize>,
+}
+
+impl HighlightCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of cached lines
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Drop everything
+    pub fn clear(&mut self) {
+        self.language = None;
+        self.lines.clear();
+        self.tail = None;
+        self.generation += 1;
+        self.pending = None;
+    }
+
+    /// Compare the cache with the buffer lines and drop results from the first changed line
+    pub fn sync(&mut self, language: &str, lines: &[String]) {
+        if self.language.as_deref() != Some(language) {
+            self.clear();
+            self.language = Some(language.to_string());
+            return;
+        }
+
+        let changed = self
+            .lines
+            .iter()
+            .zip(lines)
+            .position(|(cached, line)| cached.text != *line)
+            .unwrap_or(lines.len());
+        self.invalidate(changed);
+    }
+
+    /// Drop cached results from `line` downward
+    ///
+    /// Lines back to the nearest checkpoint are dropped too, so highlighting can
+    /// resume from a saved state.
+    pub fn invalidate(&mut self, line: usize) {
+        if line >= self.lines.len() {
+            return;
+        }
+        let keep = line / CHECKPOINT_INTERVAL * CHECKPOINT_INTERVAL;
+        self.tail = self.lines[keep].state.clone();
+        self.lines.truncate(keep);
+        self.generation += 1;
+        self.pending = None;
+    }
+
+    /// Cached spans of a line, if they were computed from `text`
+    pub fn spans(&self, line: usize, text: &str) -> Option<Vec<Span<'static>>> {
+        self.lines
+            .get(line)
+            .filter(|cached| cached.text == text)
+            .map(|cached| cached.spans.clone())
+    }
+
+    /// Start point for highlighting lines up to `end`
+    ///
+    /// Returns None if they are cached or another range is still being
+    /// highlighted; otherwise the range up to `end` is marked as pending.
+    pub fn request(&mut self, end: usize) -> Option<HighlightRequest> {
+        if self.lines.len() >= end || self.pending.is_some() {
+            return None;
+        }
+        self.pending = Some(end);
+        Some(HighlightRequest {
+            generation: self.generation,
+            first: self.lines.len(),
+            state: self.tail.clone(),
+        })
+    }
+
+    /// Append highlighted lines computed for `generation`
+    ///
+    /// Returns false if the cache changed since the request; the lines are discarded.
+    pub fn insert(&mut self, generation: u64, highlighted: HighlightedLines) -> bool {
+        if generation != self.generation || highlighted.first != self.lines.len() {
+            return false;
+        }
+        self.lines.extend(highlighted.lines);
+        self.tail = Some(highlighted.state);
+        if self
+            .pending
+            .is_some_and(|pending| pending <= self.lines.len())
+        {
+            self.pending = None;
+        }
+        true
+    }
+
+    /// Give up a pending request of `generation`
+    pub fn cancel(&mut self, generation: u64) {
+        if generation == self.generation {
+            self.pending = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syntect::highlighting::ThemeSet;
+
+    fn setup() -> (SyntaxSet, Theme) {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme = ThemeSet::load_defaults().themes["base16-ocean.dark"].clone();
+        (syntax_set, theme)
+    }
+
+    fn fill(cache: &mut HighlightCache, syntax_set: &SyntaxSet, theme: &Theme, lines: &[String]) {
+        let syntax = syntax_set.find_syntax_by_token("rust").unwrap();
+        cache.sync("rust", lines);
+        let request = cache.request(lines.len()).unwrap();
+        let state = request
+            .state
+            .unwrap_or_else(|| LineState::new(syntax, theme));
+        let highlighted = highlight_lines(
+            syntax_set,
+            theme,
+            request.first,
+            state,
+            &lines[request.first..],
+        );
+        assert!(cache.insert(request.generation, highlighted));
+    }
+
+    fn document() -> Vec<String> {
+        (0..40).map(|i| format!("let x{} = {};", i, i)).collect()
+    }
+
+    #[test]
+    fn test_invalidate_from_edited_line() {
+        let (syntax_set, theme) = setup();
+        let mut cache = HighlightCache::new();
+        let mut lines = document();
+        fill(&mut cache, &syntax_set, &theme, &lines);
+        assert_eq!(cache.len(), 40);
+
+        lines[20] = "/* opened".to_string();
+        cache.sync("rust", &lines);
+        // Dropped back to the checkpoint at line 16
+        assert_eq!(cache.len(), 16);
+        assert!(cache.spans(15, &lines[15]).is_some());
+        assert!(cache.spans(20, &lines[20]).is_none());
+
+        fill(&mut cache, &syntax_set, &theme, &lines);
+        let mut fresh = HighlightCache::new();
+        fill(&mut fresh, &syntax_set, &theme, &lines);
+        for (i, line) in lines.iter().enumerate() {
+            assert_eq!(cache.spans(i, line), fresh.spans(i, line), "line {}", i);
+        }
+        // The comment opened on line 20 carries over to the following lines
+        let comment = cache.spans(30, &lines[30]).unwrap();
+        assert_eq!(comment.len(), 1);
+    }
+
+    #[test]
+    fn test_stale_results_are_discarded() {
+        let (syntax_set, theme) = setup();
+        let syntax = syntax_set.find_syntax_by_token("rust").unwrap();
+        let mut cache = HighlightCache::new();
+        let lines = document();
+        cache.sync("rust", &lines);
+
+        let request = cache.request(10).unwrap();
+        assert!(cache.request(10).is_none());
+        let highlighted = highlight_lines(
+            &syntax_set,
+            &theme,
+            request.first,
+            LineState::new(syntax, &theme),
+            &lines[..10],
+        );
+
+        cache.sync("python", &lines);
+        assert!(!cache.insert(request.generation, highlighted));
+        assert!(cache.is_empty());
+        assert!(cache.request(10).is_some());
+    }
+
+    #[test]
+    fn test_spans_require_matching_text() {
+        let (syntax_set, theme) = setup();
+        let mut cache = HighlightCache::new();
+        let lines = document();
+        fill(&mut cache, &syntax_set, &theme, &lines);
+
+        assert!(cache.spans(0, &lines[0]).is_some());
+        assert!(cache.spans(0, "changed").is_none());
+        assert!(cache.spans(40, "").is_none());
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use image::DynamicImage;
+
+/// How often a cached file's modification time is checked again
+const MTIME_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A decoded image file (`size: None`) or a thumbnail fitted into a pixel box
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageKey {
+    pub path: PathBuf,
+    pub size: Option<(u32, u32)>,
+}
+
+impl ImageKey {
+    pub fn source(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            size: None,
+        }
+    }
+
+    pub fn thumbnail(path: &Path, width: u32, height: u32) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            size: Some((width, height)),
+        }
+    }
+}
+
+/// Decoding state of an image file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageState {
+    /// Not decoded yet
+    Loading,
+    /// Decoded, with the original dimensions
+    Ready { width: u32, height: u32 },
+    /// Missing or undecodable
+    Failed(String),
+}
+
+struct CachedImage {
+    image: Arc<DynamicImage>,
+    bytes: usize,
+    last_used: u64,
+}
+
+struct FileEntry {
+    mtime: Option<SystemTime>,
+    checked_at: Instant,
+    state: ImageState,
+    /// Keys requested from the decoder and not finished yet
+    pending: HashSet<ImageKey>,
+}
+
+/// LRU cache of decoded images and thumbnails bounded by a memory budget
+///
+/// Entries of a file are dropped together when its modification time changes.
+pub struct ImageCache {
+    images: HashMap<ImageKey, CachedImage>,
+    files: HashMap<PathBuf, FileEntry>,
+    /// Maximum bytes of pixel data kept
+    budget: usize,
+    used: usize,
+    /// Logical clock for LRU ordering
+    clock: u64,
+}
+
+impl ImageCache {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            images: HashMap::new(),
+            files: HashMap::new(),
+            budget,
+            used: 0,
+            clock: 0,
+        }
+    }
+
+    /// Change the memory budget, evicting entries if needed
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.evict(None);
+    }
+
+    /// Bytes of pixel data currently cached
+    pub fn used_bytes(&self) -> usize {
+        self.used
+    }
+
+    /// Whether the file's modification time should be checked again
+    pub fn needs_check(&self, path: &Path, now: Instant) -> bool {
+        self.files
+            .get(path)
+            .is_none_or(|file| now.duration_since(file.checked_at) >= MTIME_CHECK_INTERVAL)
+    }
+
+    /// Record the file's current modification time
+    ///
+    /// Returns true if the file changed and its cached entries were dropped.
+    pub fn validate(&mut self, path: &Path, mtime: Option<SystemTime>, now: Instant) -> bool {
+        if let Some(file) = self.files.get_mut(path) {
+            file.checked_at = now;
+            if file.mtime == mtime {
+                return false;
+            }
+        }
+
+        let changed = self.files.contains_key(path);
+        self.remove_file(path);
+        let state = if mtime.is_some() {
+            ImageState::Loading
+        } else {
+            ImageState::Failed("Image not found".to_string())
+        };
+        self.files.insert(
+            path.to_path_buf(),
+            FileEntry {
+                mtime,
+                checked_at: now,
+                state,
+                pending: HashSet::new(),
+            },
+        );
+        changed
+    }
+
+    /// Modification time the cached entries of a file belong to
+    pub fn mtime(&self, path: &Path) -> Option<SystemTime> {
+        self.files.get(path).and_then(|file| file.mtime)
+    }
+
+    /// Decoding state of a file (None if it was never validated)
+    pub fn state(&self, path: &Path) -> Option<&ImageState> {
+        self.files.get(path).map(|file| &file.state)
+    }
+
+    /// Get a cached image, marking it as recently used
+    pub fn get(&mut self, key: &ImageKey) -> Option<Arc<DynamicImage>> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.images.get_mut(key).map(|cached| {
+            cached.last_used = clock;
+            Arc::clone(&cached.image)
+        })
+    }
+
+    /// Mark a key as requested from the decoder
+    ///
+    /// Returns false if it is already cached, pending or the file failed to load.
+    pub fn request(&mut self, key: &ImageKey) -> bool {
+        if self.images.contains_key(key) {
+            return false;
+        }
+        match self.files.get_mut(&key.path) {
+            Some(file) if !matches!(file.state, ImageState::Failed(_)) => {
+                file.pending.insert(key.clone())
+            }
+            _ => false,
+        }
+    }
+
+    /// Store a decoded image for the file version with `mtime`
+    ///
+    /// Results for an outdated version of the file are discarded.
+    pub fn insert(&mut self, key: ImageKey, image: Arc<DynamicImage>, mtime: Option<SystemTime>) {
+        let Some(file) = self.files.get_mut(&key.path) else {
+            return;
+        };
+        if file.mtime != mtime {
+            return;
+        }
+        file.pending.remove(&key);
+        if key.size.is_none() {
+            file.state = ImageState::Ready {
+                width: image.width(),
+                height: image.height(),
+            };
+        }
+
+        let bytes = image.as_bytes().len();
+        self.clock += 1;
+        if let Some(old) = self.images.insert(
+            key.clone(),
+            CachedImage {
+                image,
+                bytes,
+                last_used: self.clock,
+            },
+        ) {
+            self.used -= old.bytes;
+        }
+        self.used += bytes;
+        self.evict(Some(&key));
+    }
+
+    /// Record that a file version could not be decoded
+    pub fn fail(&mut self, path: &Path, error: String, mtime: Option<SystemTime>) {
+        if let Some(file) = self.files.get_mut(path)
+            && file.mtime == mtime
+        {
+            file.pending.clear();
+            file.state = ImageState::Failed(error);
+        }
+    }
+
+    /// Drop everything
+    pub fn clear(&mut self) {
+        self.images.clear();
+        self.files.clear();
+        self.used = 0;
+    }
+
+    fn remove_file(&mut self, path: &Path) {
+        let mut freed = 0;
+        self.images.retain(|key, cached| {
+            let keep = key.path != path;
+            if !keep {
+                freed += cached.bytes;
+            }
+            keep
+        });
+        self.used -= freed;
+        self.files.remove(path);
+    }
+
+    /// Evict least recently used images until the budget is met, keeping `keep`
+    fn evict(&mut self, keep: Option<&ImageKey>) {
+        while self.used > self.budget {
+            let Some(oldest) = self
+                .images
+                .iter()
+                .filter(|(key, _)| Some(*key) != keep)
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            if let Some(cached) = self.images.remove(&oldest) {
+                self.used -= cached.bytes;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn image(width: u32, height: u32) -> Arc<DynamicImage> {
+        Arc::new(DynamicImage::ImageRgba8(RgbaImage::new(width, height)))
+    }
+
+    fn cache_with(path: &Path, budget: usize) -> (ImageCache, Option<SystemTime>) {
+        let mut cache = ImageCache::new(budget);
+        let mtime = Some(SystemTime::UNIX_EPOCH);
+        cache.validate(path, mtime, Instant::now());
+        (cache, mtime)
+    }
+
+    #[test]
+    fn test_request_and_insert() {
+        let path = Path::new("a.png");
+        let (mut cache, mtime) = cache_with(path, 1 << 20);
+        let key = ImageKey::source(path);
+
+        assert_eq!(cache.state(path), Some(&ImageState::Loading));
+        assert!(cache.request(&key));
+        assert!(!cache.request(&key));
+
+        cache.insert(key.clone(), image(4, 2), mtime);
+        assert_eq!(
+            cache.state(path),
+            Some(&ImageState::Ready {
+                width: 4,
+                height: 2
+            })
+        );
+        assert!(cache.get(&key).is_some());
+        assert_eq!(cache.used_bytes(), 4 * 2 * 4);
+    }
+
+    #[test]
+    fn test_lru_budget() {
+        let path = Path::new("a.png");
+        // Room for two 4x4 RGBA thumbnails
+        let (mut cache, mtime) = cache_with(path, 2 * 64);
+        let (a, b, c) = (
+            ImageKey::thumbnail(path, 1, 1),
+            ImageKey::thumbnail(path, 2, 2),
+            ImageKey::thumbnail(path, 3, 3),
+        );
+
+        cache.insert(a.clone(), image(4, 4), mtime);
+        cache.insert(b.clone(), image(4, 4), mtime);
+        cache.get(&a);
+        cache.insert(c.clone(), image(4, 4), mtime);
+
+        assert!(cache.get(&a).is_some());
+        assert!(cache.get(&b).is_none());
+        assert!(cache.get(&c).is_some());
+        assert_eq!(cache.used_bytes(), 2 * 64);
+    }
+
+    #[test]
+    fn test_mtime_invalidation() {
+        let path = Path::new("a.png");
+        let (mut cache, mtime) = cache_with(path, 1 << 20);
+        let key = ImageKey::source(path);
+        cache.insert(key.clone(), image(2, 2), mtime);
+
+        let now = Instant::now();
+        assert!(!cache.validate(path, mtime, now));
+        assert!(cache.get(&key).is_some());
+
+        let modified = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+        assert!(cache.validate(path, modified, now));
+        assert!(cache.get(&key).is_none());
+        assert_eq!(cache.state(path), Some(&ImageState::Loading));
+        assert_eq!(cache.used_bytes(), 0);
+
+        // Late results for the old version are discarded
+        cache.insert(key.clone(), image(2, 2), mtime);
+        assert!(cache.get(&key).is_none());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let path = Path::new("missing.png");
+        let mut cache = ImageCache::new(1 << 20);
+        cache.validate(path, None, Instant::now());
+
+        assert!(matches!(cache.state(path), Some(ImageState::Failed(_))));
+        assert!(!cache.request(&ImageKey::source(path)));
+    }
+}
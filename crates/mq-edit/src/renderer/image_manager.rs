@@ -1,46 +1,215 @@
 use image::DynamicImage;
+use image::imageops::FilterType;
+use ratatui::text::Line;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+use super::graphics::{self, ImageProtocol};
+use super::image_cache::{ImageCache, ImageKey, ImageState};
+
+/// Assumed terminal cell size in pixels when the terminal doesn't report it
+const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+
+/// Default memory budget for decoded images and thumbnails
+const DEFAULT_CACHE_BUDGET: usize = 64 * 1024 * 1024;
+
+/// An image drawn with terminal escape sequences during the last frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePlacement {
+    /// Image path as written in the document
+    pub path: String,
+    /// Top-left screen cell
+    pub x: u16,
+    pub y: u16,
+    /// Size in cells
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// Request to decode a file version on the worker thread
+struct DecodeJob {
+    key: ImageKey,
+    mtime: Option<SystemTime>,
+}
 
 /// Manages image loading and caching for the editor
+///
+/// Images are decoded and scaled on a worker thread; until a result is ready,
+/// lookups return nothing and the editor shows a placeholder.
 pub struct ImageManager {
-    /// Cache of loaded images (uses RefCell for interior mutability)
-    cache: RefCell<HashMap<PathBuf, DynamicImage>>,
+    /// Decoded images and thumbnails shared with the decoder thread
+    cache: Arc<Mutex<ImageCache>>,
+    /// Sends decode jobs to the worker thread
+    decoder: Sender<DecodeJob>,
+    /// Cache of half-block cell art by (path, cols, rows)
+    halfblock_cache: RefCell<HashMap<(PathBuf, u16, u16), Vec<Line<'static>>>>,
     /// Base path for resolving relative image paths
     base_path: Option<PathBuf>,
+    /// How images are drawn (already resolved, never `Auto`)
+    protocol: ImageProtocol,
+    /// Rows reserved below an image line
+    rows: u16,
+    /// Terminal cell size in pixels
+    cell_size: (u16, u16),
+    /// Images placed with escape sequences in the current frame
+    placements: RefCell<Vec<ImagePlacement>>,
 }
 
 impl ImageManager {
     pub fn new() -> Self {
+        let cache = Arc::new(Mutex::new(ImageCache::new(DEFAULT_CACHE_BUDGET)));
+        let decoder = Self::spawn_decoder(Arc::clone(&cache));
+
         Self {
-            cache: RefCell::new(HashMap::new()),
+            cache,
+            decoder,
+            halfblock_cache: RefCell::new(HashMap::new()),
             base_path: None,
+            protocol: ImageProtocol::None,
+            rows: 0,
+            cell_size: DEFAULT_CELL_SIZE,
+            placements: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Start the decoder thread; it exits when the manager is dropped
+    fn spawn_decoder(cache: Arc<Mutex<ImageCache>>) -> Sender<DecodeJob> {
+        let (tx, rx) = mpsc::channel::<DecodeJob>();
+        std::thread::spawn(move || {
+            for job in rx {
+                Self::decode(&cache, job);
+            }
+        });
+        tx
+    }
+
+    /// Decode a file (reusing a cached source image) and scale it if requested
+    fn decode(cache: &Mutex<ImageCache>, job: DecodeJob) {
+        let path = job.key.path.clone();
+        let source_key = ImageKey::source(&path);
+
+        let cached = cache.lock().unwrap().get(&source_key);
+        let source = match cached {
+            Some(source) => source,
+            None => match image::open(&path) {
+                Ok(img) => {
+                    let img = Arc::new(img);
+                    cache
+                        .lock()
+                        .unwrap()
+                        .insert(source_key, Arc::clone(&img), job.mtime);
+                    img
+                }
+                Err(e) => {
+                    cache.lock().unwrap().fail(
+                        &path,
+                        format!("Failed to load image: {}", e),
+                        job.mtime,
+                    );
+                    return;
+                }
+            },
+        };
+
+        if let Some((width, height)) = job.key.size {
+            let thumbnail = if source.width() <= width && source.height() <= height {
+                source
+            } else {
+                Arc::new(source.resize(width, height, FilterType::Triangle))
+            };
+            cache.lock().unwrap().insert(job.key, thumbnail, job.mtime);
         }
     }
 
+    /// Set how images are drawn and how many rows they take
+    pub fn set_display(&mut self, protocol: ImageProtocol, rows: u16) {
+        self.protocol = protocol.resolve();
+        self.rows = rows;
+    }
+
+    /// Set the memory budget for decoded images and thumbnails
+    pub fn set_cache_budget(&mut self, bytes: usize) {
+        self.cache.lock().unwrap().set_budget(bytes);
+    }
+
+    /// Set the terminal cell size in pixels (ignored if unknown)
+    pub fn set_cell_size(&mut self, width: u16, height: u16) {
+        if width > 0 && height > 0 {
+            self.cell_size = (width, height);
+        }
+    }
+
+    /// Get the protocol used to draw images
+    pub fn protocol(&self) -> ImageProtocol {
+        self.protocol
+    }
+
     /// Set the base path for resolving relative image paths
     pub fn set_base_path(&mut self, path: PathBuf) {
         self.base_path = Some(path);
     }
 
-    /// Load an image from the given path (uses interior mutability)
-    fn load_image(&self, path: &str) -> Result<DynamicImage, String> {
+    /// Resolve a path and drop its cached entries if the file changed
+    fn lookup(&self, path: &str) -> Result<PathBuf, String> {
         let resolved_path = self.resolve_path(path)?;
+        let now = Instant::now();
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.needs_check(&resolved_path, now) {
+            let mtime = std::fs::metadata(&resolved_path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            if cache.validate(&resolved_path, mtime, now) {
+                self.halfblock_cache
+                    .borrow_mut()
+                    .retain(|(cached_path, _, _), _| *cached_path != resolved_path);
+            }
+        }
+        Ok(resolved_path)
+    }
 
-        // Check if image is already cached
-        let mut cache = self.cache.borrow_mut();
-        if !cache.contains_key(&resolved_path) {
-            // Load the image
-            let img =
-                image::open(&resolved_path).map_err(|e| format!("Failed to load image: {}", e))?;
-            cache.insert(resolved_path.clone(), img);
+    /// Ask the worker thread to decode `key` unless it is cached or pending
+    fn request(&self, cache: &mut ImageCache, key: ImageKey) {
+        if cache.request(&key) {
+            let mtime = cache.mtime(&key.path);
+            // The worker only stops when the manager is dropped
+            let _ = self.decoder.send(DecodeJob { key, mtime });
         }
+    }
 
-        cache
-            .get(&resolved_path)
+    /// Get the decoding state of an image, starting to decode it if needed
+    pub fn state(&self, path: &str) -> ImageState {
+        let resolved_path = match self.lookup(path) {
+            Ok(resolved_path) => resolved_path,
+            Err(e) => return ImageState::Failed(e),
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        let state = cache
+            .state(&resolved_path)
             .cloned()
-            .ok_or_else(|| "Image not found in cache".to_string())
+            .unwrap_or(ImageState::Loading);
+        if state == ImageState::Loading {
+            self.request(&mut cache, ImageKey::source(&resolved_path));
+        }
+        state
+    }
+
+    /// Get an image scaled to fit `width` x `height` pixels, if already decoded
+    fn thumbnail(&self, path: &str, width: u32, height: u32) -> Option<Arc<DynamicImage>> {
+        let resolved_path = self.lookup(path).ok()?;
+        let key = ImageKey::thumbnail(&resolved_path, width, height);
+
+        let mut cache = self.cache.lock().unwrap();
+        let thumbnail = cache.get(&key);
+        if thumbnail.is_none() {
+            self.request(&mut cache, key);
+        }
+        thumbnail
     }
 
     /// Resolve a path (absolute or relative to base path)
@@ -66,20 +235,116 @@ impl ImageManager {
         }
     }
 
-    /// Check if an image exists and can be loaded
+    /// Check if an image exists and can be loaded (true while still decoding)
     pub fn can_load_image(&self, path: &str) -> bool {
-        self.load_image(path).is_ok()
+        !matches!(self.state(path), ImageState::Failed(_))
     }
 
     /// Clear the image cache
     pub fn clear_cache(&self) {
-        self.cache.borrow_mut().clear();
+        self.cache.lock().unwrap().clear();
+        self.halfblock_cache.borrow_mut().clear();
     }
 
-    /// Get image dimensions
+    /// Get image dimensions (an error while the image is still decoding)
     pub fn get_dimensions(&self, path: &str) -> Result<(u32, u32), String> {
-        let img = self.load_image(path)?;
-        Ok((img.width(), img.height()))
+        match self.state(path) {
+            ImageState::Ready { width, height } => Ok((width, height)),
+            ImageState::Loading => Err("Image is loading".to_string()),
+            ImageState::Failed(e) => Err(e),
+        }
+    }
+
+    /// Rows reserved below an image line (0 if images aren't displayed)
+    ///
+    /// Rows are reserved while an image is decoding so the layout doesn't jump.
+    pub fn display_rows(&self, path: &str) -> u16 {
+        if self.protocol == ImageProtocol::None || self.rows == 0 || !self.can_load_image(path) {
+            0
+        } else {
+            self.rows
+        }
+    }
+
+    /// Size in cells of an image fitted into `max_cols` x `max_rows`
+    pub fn fit(&self, path: &str, max_cols: u16, max_rows: u16) -> Option<(u16, u16)> {
+        let size = self.get_dimensions(path).ok()?;
+        let (cols, rows) = graphics::fit_cells(size, (max_cols, max_rows), self.cell_pixels());
+        (cols > 0 && rows > 0).then_some((cols, rows))
+    }
+
+    /// Pixel size of a cell for the current protocol
+    fn cell_pixels(&self) -> (u16, u16) {
+        if self.protocol.uses_escape_sequences() {
+            self.cell_size
+        } else {
+            // Half-block pixels are square, two per cell
+            (1, 2)
+        }
+    }
+
+    /// Thumbnail size in pixels for an image drawn into `cols` x `rows` cells
+    fn thumbnail_size(&self, cols: u16, rows: u16) -> (u32, u32) {
+        let (cell_width, cell_height) = self.cell_pixels();
+        (
+            cols as u32 * cell_width as u32,
+            rows as u32 * cell_height as u32,
+        )
+    }
+
+    /// Check if the thumbnail for `cols` x `rows` cells is decoded, requesting it if not
+    pub fn is_ready(&self, path: &str, cols: u16, rows: u16) -> bool {
+        let (width, height) = self.thumbnail_size(cols, rows);
+        self.thumbnail(path, width, height).is_some()
+    }
+
+    /// Render an image as half-block cell art fitted into `cols` x `rows`
+    ///
+    /// Returns None while the image is decoding.
+    pub fn halfblock_lines(&self, path: &str, cols: u16, rows: u16) -> Option<Vec<Line<'static>>> {
+        let resolved_path = self.lookup(path).ok()?;
+        let key = (resolved_path, cols, rows);
+        if let Some(lines) = self.halfblock_cache.borrow().get(&key) {
+            return Some(lines.clone());
+        }
+
+        let (width, height) = self.thumbnail_size(cols, rows);
+        let thumbnail = self.thumbnail(path, width, height)?;
+        let lines = graphics::halfblock_lines(&thumbnail, cols, rows);
+        self.halfblock_cache.borrow_mut().insert(key, lines.clone());
+        Some(lines)
+    }
+
+    /// Record an image drawn with escape sequences in the current frame
+    pub fn place(&self, placement: ImagePlacement) {
+        self.placements.borrow_mut().push(placement);
+    }
+
+    /// Forget the placements of the previous frame
+    pub fn clear_placements(&self) {
+        self.placements.borrow_mut().clear();
+    }
+
+    /// Take the placements recorded in the current frame
+    pub fn take_placements(&self) -> Vec<ImagePlacement> {
+        std::mem::take(&mut *self.placements.borrow_mut())
+    }
+
+    /// Build the escape sequence drawing a placed image
+    pub fn escape_sequence(&self, placement: &ImagePlacement) -> Result<Vec<u8>, String> {
+        let (width, height) = self.thumbnail_size(placement.cols, placement.rows);
+        let img = self
+            .thumbnail(&placement.path, width, height)
+            .ok_or_else(|| "Image is loading".to_string())?;
+
+        match self.protocol {
+            ImageProtocol::Kitty => graphics::kitty_sequence(&img, placement.cols, placement.rows),
+            ImageProtocol::Iterm2 => {
+                graphics::iterm2_sequence(&img, placement.cols, placement.rows)
+            }
+            ImageProtocol::Sixel => Ok(graphics::sixel_sequence(&img)),
+            _ => Ok(Vec::new()),
+        }
     }
 }
 
@@ -88,3 +353,70 @@ impl Default for ImageManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use std::time::Duration;
+
+    /// Poll until the image leaves the loading state
+    fn wait_for(manager: &ImageManager, path: &str) -> ImageState {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let state = manager.state(path);
+            if state != ImageState::Loading || Instant::now() > deadline {
+                return state;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_decodes_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image.png");
+        RgbaImage::from_pixel(40, 20, Rgba([255, 0, 0, 255]))
+            .save(&path)
+            .unwrap();
+        let path = path.to_str().unwrap();
+
+        let mut manager = ImageManager::new();
+        manager.set_display(ImageProtocol::Halfblocks, 4);
+
+        assert_eq!(
+            wait_for(&manager, path),
+            ImageState::Ready {
+                width: 40,
+                height: 20
+            }
+        );
+        assert_eq!(manager.display_rows(path), 4);
+        assert_eq!(manager.fit(path, 10, 4), Some((10, 3)));
+
+        // The thumbnail is scaled on the worker thread as well
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let lines = loop {
+            if let Some(lines) = manager.halfblock_lines(path, 10, 3) {
+                break lines;
+            }
+            assert!(Instant::now() < deadline, "thumbnail not decoded");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_missing_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.png");
+        let path = path.to_str().unwrap();
+
+        let mut manager = ImageManager::new();
+        manager.set_display(ImageProtocol::Halfblocks, 4);
+
+        assert!(matches!(manager.state(path), ImageState::Failed(_)));
+        assert_eq!(manager.display_rows(path), 0);
+        assert!(manager.halfblock_lines(path, 10, 3).is_none());
+    }
+}
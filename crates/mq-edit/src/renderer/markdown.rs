@@ -1,13 +1,224 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+use markdown_lsp::wiki_link::{self, WikiLink};
+use mq_markdown::{Markdown, Node, Position};
 use ratatui::{
     style::{Modifier, Style},
     text::Span,
 };
+use unicode_width::UnicodeWidthStr;
 
 use super::Renderer;
-use crate::document::{DocumentBuffer, LineType, TableAlignment};
+use super::source_map::{self, MappedSpan};
+use crate::document::{DocumentBuffer, LineAnalyzer, LineType, TableAlignment};
 use crate::theme;
 
+/// Most inline renderings kept in each generation of `InlineCache`
+const INLINE_CACHE_CAPACITY: usize = 4096;
+
+type InlineKey = (String, Style);
+
+/// Rendered inline Markdown by source text and base style
+///
+/// Table cells are rendered again every frame and whenever screen rows are
+/// counted, so each distinct text is parsed once. When the cache fills up its
+/// entries become the older generation, which is dropped on the next turnover;
+/// entries still in use are moved back before that.
+#[derive(Debug, Clone, Default)]
+struct InlineCache {
+    recent: HashMap<InlineKey, Vec<MappedSpan>>,
+    older: HashMap<InlineKey, Vec<MappedSpan>>,
+}
+
+impl InlineCache {
+    fn get(&mut self, key: &InlineKey) -> Option<Vec<MappedSpan>> {
+        if let Some(spans) = self.recent.get(key) {
+            return Some(spans.clone());
+        }
+        let spans = self.older.remove(key)?;
+        self.insert(key.clone(), spans.clone());
+        Some(spans)
+    }
+
+    fn insert(&mut self, key: InlineKey, spans: Vec<MappedSpan>) {
+        if self.recent.len() >= INLINE_CACHE_CAPACITY {
+            self.older = std::mem::take(&mut self.recent);
+        }
+        self.recent.insert(key, spans);
+    }
+}
+
+/// Inline spans of each document line, None for lines without inline content
+type DocumentSpans = Vec<Option<Vec<MappedSpan>>>;
+
+/// Fenced code block of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// Line of the opening fence
+    pub fence_line: usize,
+    /// Language from the opening fence
+    pub language: Option<String>,
+    /// Line of the closing fence (None if the block is not closed)
+    pub close_line: Option<usize>,
+}
+
+impl CodeBlock {
+    /// Whether `line` is inside the block or is its closing fence
+    pub fn contains(&self, line: usize) -> bool {
+        self.fence_line < line && self.close_line.is_none_or(|close| close >= line)
+    }
+}
+
+/// Buffer and index of the line being rendered, when it is part of a document
+type LineSource<'a> = Option<(&'a DocumentBuffer, usize)>;
+
+/// Rendered inline spans, split at the line breaks of their source
+///
+/// Spans of AST nodes are mapped to the chars of the `source` lines their
+/// positions point to.
+#[derive(Debug)]
+struct SpanLines<'a> {
+    lines: Vec<Vec<MappedSpan>>,
+    source: &'a [&'a str],
+}
+
+impl<'a> SpanLines<'a> {
+    fn new(source: &'a [&'a str]) -> Self {
+        Self {
+            lines: Vec::new(),
+            source,
+        }
+    }
+
+    /// Append a span rendered from a node at `position`, moving to the next
+    /// line at each newline in it
+    fn push(&mut self, span: Span<'static>, position: Option<Position>) {
+        let parts: Vec<&str> = span.content.split('\n').collect();
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 || self.lines.is_empty() {
+                self.lines.push(Vec::new());
+            }
+            if part.is_empty() {
+                continue;
+            }
+            let source = position
+                .as_ref()
+                .and_then(|position| self.part_source(position, i, parts.len(), part));
+            if let Some(line) = self.lines.last_mut() {
+                line.push(MappedSpan::new(
+                    Span::styled(part.to_string(), span.style),
+                    source,
+                ));
+            }
+        }
+    }
+
+    /// Source chars of line `i` of the `count` lines of a node's rendered text
+    ///
+    /// The node's position gives where its first line starts and its last
+    /// line ends; lines in between run to the end of their source line. When
+    /// the rendered text appears within that source (e.g. inside the
+    /// backticks of inline code), only that part is used.
+    fn part_source(
+        &self,
+        position: &Position,
+        i: usize,
+        count: usize,
+        part: &str,
+    ) -> Option<Range<usize>> {
+        let line = self.source.get(position.start.line.checked_sub(1)? + i)?;
+        let start_column = position.start.column.saturating_sub(1);
+        let end_column = position.end.column.saturating_sub(1);
+        let (start, end) = if count == 1 && position.start.line == position.end.line {
+            (start_column, end_column)
+        } else if i == 0 {
+            (start_column, start_column + part.len())
+        } else {
+            let end = if i + 1 == count {
+                end_column
+            } else {
+                line.trim_end().len()
+            };
+            (end.saturating_sub(part.len()), end)
+        };
+        let text = line.get(start..end)?;
+        let (start, end) = match text.find(part) {
+            Some(offset) => (start + offset, start + offset + part.len()),
+            None => (start, end),
+        };
+        let chars = |byte: usize| line[..byte].chars().count();
+        Some(chars(start)..chars(end))
+    }
+
+    /// Continue on the next line
+    fn break_line(&mut self) {
+        if self.lines.is_empty() {
+            self.lines.push(Vec::new());
+        }
+        self.lines.push(Vec::new());
+    }
+
+    /// All spans as one line
+    fn into_spans(self) -> Vec<MappedSpan> {
+        self.lines.into_iter().flatten().collect()
+    }
+}
+
+/// Cursor line rendered with only the inline element under the cursor revealed
+pub struct PartialLine {
+    pub spans: Vec<MappedSpan>,
+    /// Display width of the rendered text before the cursor
+    pub cursor_width: usize,
+}
+
+/// Accumulates rendered segments of a cursor line along with their source ranges
+struct PartialLineBuilder {
+    spans: Vec<MappedSpan>,
+    width: usize,
+    /// Cursor byte offset in the source line
+    cursor: usize,
+    cursor_width: Option<usize>,
+}
+
+impl PartialLineBuilder {
+    fn new(cursor: usize) -> Self {
+        Self {
+            spans: Vec::new(),
+            width: 0,
+            cursor,
+            cursor_width: None,
+        }
+    }
+
+    fn has_cursor(&self) -> bool {
+        self.cursor_width.is_some()
+    }
+
+    /// Append the rendering of `source`, which starts at byte `start` of the line
+    fn push(&mut self, spans: Vec<MappedSpan>, source: &str, start: usize) {
+        let width: usize = spans.iter().map(|s| s.span.content.width()).sum();
+        if self.cursor_width.is_none() && (start..=start + source.len()).contains(&self.cursor) {
+            // Map the source offset directly, clamped in case markup was dropped
+            let offset = source[..self.cursor - start].width().min(width);
+            self.cursor_width = Some(self.width + offset);
+        }
+        self.width += width;
+        self.spans.extend(spans);
+    }
+
+    fn build(self) -> PartialLine {
+        PartialLine {
+            spans: self.spans,
+            cursor_width: self.cursor_width.unwrap_or(self.width),
+        }
+    }
+}
+
 /// Markdown renderer for rich text display
+#[derive(Clone)]
 pub struct MarkdownRenderer {
     /// Theme colors and styles
     heading1_style: Style,
@@ -22,6 +233,15 @@ pub struct MarkdownRenderer {
     table_border_style: Style,
     table_header_style: Style,
     table_cell_style: Style,
+    /// Inline styles
+    inline_code_style: Style,
+    link_style: Style,
+    /// Rendered inline Markdown of texts parsed on their own, such as table cells
+    inline_cache: RefCell<InlineCache>,
+    /// Inline spans of each line of a document by its buffer revision
+    document_spans: RefCell<Option<(u64, DocumentSpans)>>,
+    /// Fenced code blocks of a document by its buffer revision
+    code_blocks: RefCell<Option<(u64, Rc<[CodeBlock]>)>>,
 }
 
 impl MarkdownRenderer {
@@ -48,6 +268,13 @@ impl MarkdownRenderer {
                 .fg(theme::ACCENT)
                 .add_modifier(Modifier::BOLD),
             table_cell_style: Style::default(),
+            inline_code_style: Style::default().fg(theme::STRING).bg(theme::BG_DARK),
+            link_style: Style::default()
+                .fg(theme::ACCENT)
+                .add_modifier(Modifier::UNDERLINED),
+            inline_cache: RefCell::new(InlineCache::default()),
+            document_spans: RefCell::new(None),
+            code_blocks: RefCell::new(None),
         }
     }
 
@@ -63,70 +290,115 @@ impl MarkdownRenderer {
             vec![Span::styled(content.to_string(), Style::default())]
         } else {
             // Other lines: show rich formatted based on line type
-            self.render_rich(content, line_type)
+            source_map::spans(self.render_rich(content, line_type, None))
         }
     }
 
+    /// Render a line of a document as rich formatted text, mapping the spans
+    /// to the source they show
+    pub fn render_mapped_line(&self, buffer: &DocumentBuffer, line_idx: usize) -> Vec<MappedSpan> {
+        let content = buffer.line(line_idx).unwrap_or("");
+        let line_type = self.determine_line_type(buffer, line_idx, content);
+        self.render_rich(content, &line_type, Some((buffer, line_idx)))
+    }
+
     /// Render line as rich formatted text based on LineType
-    fn render_rich(&self, content: &str, line_type: &LineType) -> Vec<Span<'_>> {
+    ///
+    /// With a `source` line, inline Markdown is taken from the document instead
+    /// of parsing `content` on its own.
+    fn render_rich(
+        &self,
+        content: &str,
+        line_type: &LineType,
+        source: LineSource,
+    ) -> Vec<MappedSpan> {
         match line_type {
-            LineType::Heading(level) => self.render_heading_line(content, *level),
-            LineType::ListItem => self.render_list_item(content, false, false),
-            LineType::OrderedListItem => self.render_list_item(content, true, false),
-            LineType::TaskListItem(checked) => self.render_list_item(content, false, *checked),
-            LineType::Blockquote => self.render_blockquote_line(content),
-            LineType::CodeFence(lang) => self.render_code_fence(content, lang.as_deref()),
-            LineType::InCode => self.render_code_content(content),
-            LineType::HorizontalRule => vec![Span::styled(
+            LineType::Heading(level) => self.render_heading(content, *level, None, source),
+            LineType::ListItem => self.render_list_item(content, false, false, source),
+            LineType::OrderedListItem => self.render_list_item(content, true, false, source),
+            LineType::TaskListItem(checked) => {
+                self.render_list_item(content, false, *checked, source)
+            }
+            LineType::Blockquote => self.render_blockquote_line(content, source),
+            LineType::CodeFence(lang) => {
+                source_map::unmapped(self.render_code_fence(content, lang.as_deref()))
+            }
+            LineType::InCode => vec![MappedSpan::source(content, self.code_block_style, 0)],
+            LineType::HorizontalRule => vec![MappedSpan::unmapped(
                 "─".repeat(80),
                 Style::default().fg(theme::BORDER),
             )],
-            LineType::Image(alt_text, path) => self.render_image(alt_text, path),
+            LineType::Image(alt_text, path) => {
+                self.render_image_with_info(content, alt_text, path, None)
+            }
             LineType::TableHeader(cells) => {
                 // Fallback rendering without context (column widths)
-                let widths: Vec<usize> = cells.iter().map(|c| c.chars().count()).collect();
+                let widths: Vec<usize> = cells
+                    .iter()
+                    .map(|c| self.inline_plain_text(c).chars().count())
+                    .collect();
                 let alignments = vec![TableAlignment::Left; cells.len()];
-                self.render_table_header(cells, &widths, &alignments)
+                source_map::unmapped(self.render_table_header(cells, &widths, &alignments))
             }
             LineType::TableSeparator(alignments) => {
                 // Fallback rendering without context
                 let widths = vec![10; alignments.len()];
-                self.render_table_separator(&widths, alignments)
+                source_map::unmapped(self.render_table_separator(&widths, alignments))
             }
             LineType::TableRow(cells) => {
                 // Fallback rendering without context
-                let widths: Vec<usize> = cells.iter().map(|c| c.chars().count()).collect();
+                let widths: Vec<usize> = cells
+                    .iter()
+                    .map(|c| self.inline_plain_text(c).chars().count())
+                    .collect();
                 let alignments = vec![TableAlignment::Left; cells.len()];
-                self.render_table_row(cells, &widths, &alignments)
+                source_map::unmapped(self.render_table_row(cells, &widths, &alignments))
+            }
+            LineType::FrontMatterDelimiter => {
+                source_map::unmapped(self.render_front_matter_delimiter())
             }
-            LineType::FrontMatterDelimiter => self.render_front_matter_delimiter(),
             LineType::FrontMatterContent => self.render_front_matter_content(content),
-            LineType::Text => self.render_text_line(content),
+            LineType::Text => self.render_text_line(content, source),
         }
     }
 
-    /// Render heading line
-    fn render_heading_line(&self, content: &str, level: usize) -> Vec<Span<'_>> {
-        self.render_heading_line_with_width(content, level, None)
+    fn heading_style(&self, level: usize) -> Style {
+        match level {
+            1 => self.heading1_style,
+            2 => self.heading2_style,
+            3 => self.heading3_style,
+            4 => self.heading4_style,
+            _ => self.heading_other_style,
+        }
     }
 
-    /// Render heading line with optional terminal width for full-width background
+    /// Render a heading line of a document with optional terminal width for
+    /// full-width background
     pub fn render_heading_line_with_width(
+        &self,
+        buffer: &DocumentBuffer,
+        line_idx: usize,
+        level: usize,
+        terminal_width: Option<usize>,
+    ) -> Vec<MappedSpan> {
+        let content = buffer.line(line_idx).unwrap_or("");
+        self.render_heading(content, level, terminal_width, Some((buffer, line_idx)))
+    }
+
+    /// Render heading line with optional terminal width for full-width background
+    fn render_heading(
         &self,
         content: &str,
         level: usize,
         terminal_width: Option<usize>,
-    ) -> Vec<Span<'_>> {
-        let style = match level {
-            1 => self.heading1_style,
-            2 => self.heading2_style,
-            3 => self.heading3_style,
-            4 => self.heading4_style,
-            _ => self.heading_other_style,
-        };
+        source: LineSource,
+    ) -> Vec<MappedSpan> {
+        let style = self.heading_style(level);
 
         // Extract text after heading markers
-        let text = content.trim_start_matches('#').trim();
+        let after_markers = content.trim_start_matches('#');
+        let start = content.len() - after_markers.trim_start().len();
+        let text = start..start + after_markers.trim().len();
 
         // Add visual prefix for headings - make level more obvious
         let prefix = match level {
@@ -148,27 +420,28 @@ impl MarkdownRenderer {
         };
         let style_with_bg = style.bg(bg_color);
 
-        let heading_text = format!("{}{}", prefix, text);
+        let mut spans = vec![MappedSpan::unmapped(prefix, style_with_bg)];
+        spans.extend(self.render_line_inline(source, content, text, style_with_bg));
 
         // If terminal width is provided, pad to fill the line
         if let Some(width) = terminal_width {
-            let text_len = heading_text.chars().count();
+            let text_len: usize = spans.iter().map(|s| s.span.content.chars().count()).sum();
             if text_len < width {
                 let padding = " ".repeat(width - text_len);
-                vec![
-                    Span::styled(heading_text, style_with_bg),
-                    Span::styled(padding, Style::default().bg(bg_color)),
-                ]
-            } else {
-                vec![Span::styled(heading_text, style_with_bg)]
+                spans.push(MappedSpan::unmapped(padding, Style::default().bg(bg_color)));
             }
-        } else {
-            vec![Span::styled(heading_text, style_with_bg)]
         }
+        spans
     }
 
     /// Render list item
-    fn render_list_item(&self, content: &str, ordered: bool, task_checked: bool) -> Vec<Span<'_>> {
+    fn render_list_item(
+        &self,
+        content: &str,
+        ordered: bool,
+        task_checked: bool,
+        source: LineSource,
+    ) -> Vec<MappedSpan> {
         let trimmed = content.trim_start();
 
         // Determine indentation
@@ -207,20 +480,22 @@ impl MarkdownRenderer {
             (bullet.to_string(), text)
         };
 
-        vec![
-            Span::raw(indent),
-            Span::styled(bullet, Style::default().fg(theme::ACCENT)),
-            Span::raw(text.to_string()),
-        ]
+        let mut spans = vec![
+            MappedSpan::source(indent, Style::default(), 0),
+            MappedSpan::unmapped(bullet, Style::default().fg(theme::ACCENT)),
+        ];
+        let text = content.len() - text.len()..content.len();
+        spans.extend(self.render_line_inline(source, content, text, Style::default()));
+        spans
     }
 
     /// Render blockquote line
-    fn render_blockquote_line(&self, content: &str) -> Vec<Span<'_>> {
+    fn render_blockquote_line(&self, content: &str, source: LineSource) -> Vec<MappedSpan> {
         let text = content.trim_start().strip_prefix("> ").unwrap_or(content);
-        vec![
-            Span::styled("▎ ", self.quote_border_style),
-            Span::styled(text.to_string(), self.quote_style),
-        ]
+        let text = content.len() - text.len()..content.len();
+        let mut spans = vec![MappedSpan::unmapped("▎ ", self.quote_border_style)];
+        spans.extend(self.render_line_inline(source, content, text, self.quote_style));
+        spans
     }
 
     /// Render code fence line (start or end of code block)
@@ -239,21 +514,39 @@ impl MarkdownRenderer {
     }
 
     /// Render code fence start (opening ```lang)
-    pub fn render_code_fence_start(&self, lang: Option<&str>) -> Vec<Span<'_>> {
-        if let Some(lang) = lang {
-            vec![Span::styled(
-                format!(
-                    "╭─ {} ─────────────────────────────────────────────────────",
-                    lang
-                ),
-                Style::default().fg(theme::BORDER),
-            )]
+    pub fn render_code_fence_start(&self, content: &str) -> Vec<MappedSpan> {
+        self.render_fence_label(
+            content,
+            " ─────────────────────────────────────────────────────",
+        )
+    }
+
+    /// Render the opening fence of a folded code block, short enough to leave
+    /// room for the fold summary
+    pub fn render_folded_code_fence(&self, content: &str) -> Vec<MappedSpan> {
+        self.render_fence_label(content, " ─")
+    }
+
+    /// Border with the language of an opening fence, followed by `rest`
+    fn render_fence_label(&self, content: &str, rest: &str) -> Vec<MappedSpan> {
+        let style = Style::default().fg(theme::BORDER);
+        let info = content
+            .trim_start()
+            .strip_prefix("```")
+            .map(str::trim_start)
+            .unwrap_or_default();
+        let lang = info.trim_end();
+        let label = if lang.is_empty() {
+            MappedSpan::unmapped("code", style)
         } else {
-            vec![Span::styled(
-                "╭─ code ────────────────────────────────────────────────────",
-                Style::default().fg(theme::BORDER),
-            )]
-        }
+            let start = content[..content.len() - info.len()].chars().count();
+            MappedSpan::source(lang, style, start)
+        };
+        vec![
+            MappedSpan::unmapped("╭─ ", style),
+            label,
+            MappedSpan::unmapped(rest, style),
+        ]
     }
 
     /// Render code fence end (closing ```)
@@ -275,66 +568,525 @@ impl MarkdownRenderer {
     }
 
     /// Render text line with inline formatting
-    fn render_text_line(&self, content: &str) -> Vec<Span<'_>> {
-        // For now, simple rendering
-        // TODO: Parse inline formatting (bold, italic, code, links)
-        vec![Span::raw(content.to_string())]
+    fn render_text_line(&self, content: &str, source: LineSource) -> Vec<MappedSpan> {
+        let text = content.trim_start();
+        let indent = &content[..content.len() - text.len()];
+
+        let mut spans = Vec::new();
+        if !indent.is_empty() {
+            spans.push(MappedSpan::source(indent, Style::default(), 0));
+        }
+        let text = indent.len()..content.len();
+        spans.extend(self.render_line_inline(source, content, text, Style::default()));
+        spans
+    }
+
+    /// Render the inline Markdown of a line, whose text after its block marker
+    /// is the `text` bytes of `content`
+    ///
+    /// Lines of a document take their spans from the document's AST, which
+    /// resolves what a line parsed on its own can't: emphasis and links that
+    /// continue on the next line, reference links defined elsewhere and hard
+    /// breaks, which need a following line. Lines without inline content in
+    /// the AST are parsed on their own.
+    fn render_line_inline(
+        &self,
+        source: LineSource,
+        content: &str,
+        text: Range<usize>,
+        base: Style,
+    ) -> Vec<MappedSpan> {
+        source
+            .and_then(|(buffer, line_idx)| self.document_line_spans(buffer, line_idx, base))
+            .unwrap_or_else(|| {
+                let offset = content[..text.start].chars().count();
+                self.render_inline_mapped(&content[text], base)
+                    .into_iter()
+                    .map(|span| span.shifted(offset))
+                    .collect()
+            })
+    }
+
+    /// Inline spans of a document line styled on top of `base`
+    fn document_line_spans(
+        &self,
+        buffer: &DocumentBuffer,
+        line_idx: usize,
+        base: Style,
+    ) -> Option<Vec<MappedSpan>> {
+        let ast = buffer.document_type().markdown_ast()?;
+        let mut cache = self.document_spans.borrow_mut();
+        if cache
+            .as_ref()
+            .is_none_or(|(revision, _)| *revision != buffer.revision())
+        {
+            let source: Vec<&str> = buffer.lines().iter().map(String::as_str).collect();
+            let mut lines = vec![None; buffer.line_count()];
+            for node in &ast.nodes {
+                self.collect_document_spans(node, &source, &mut lines);
+            }
+            *cache = Some((buffer.revision(), lines));
+        }
+
+        let spans = cache.as_ref()?.1.get(line_idx)?.as_ref()?;
+        Some(
+            spans
+                .iter()
+                .map(|mapped| {
+                    MappedSpan::new(
+                        Span::styled(mapped.span.content.clone(), base.patch(mapped.span.style)),
+                        mapped.source.clone(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Add the rendered inline content of a block to the lines it covers
+    fn collect_document_spans(&self, node: &Node, source: &[&str], lines: &mut DocumentSpans) {
+        if matches!(node, Node::Heading(_) | Node::List(_) | Node::Blockquote(_)) {
+            for child in node.children() {
+                self.collect_document_spans(&child, source, lines);
+            }
+            return;
+        }
+        if !Self::is_inline_node(node) {
+            return;
+        }
+        let Some(position) = node.position() else {
+            return;
+        };
+
+        let mut spans = SpanLines::new(source);
+        self.inline_spans(node, Style::default(), &mut spans);
+        let first_line = position.start.line.saturating_sub(1);
+        for (line_spans, line) in spans
+            .lines
+            .into_iter()
+            .zip(lines.iter_mut().skip(first_line))
+        {
+            line.get_or_insert_with(Vec::new).extend(line_spans);
+        }
+    }
+
+    /// Render inline Markdown (emphasis, strong, code, strikethrough, links)
+    ///
+    /// The text is parsed on its own with `mq_markdown` and each inline node is
+    /// styled on top of `base`. Markup that does not form a node (e.g. an
+    /// unclosed `**`) is kept as literal text.
+    pub fn render_inline(&self, text: &str, base: Style) -> Vec<Span<'static>> {
+        source_map::spans(self.render_inline_mapped(text, base))
+    }
+
+    /// Render inline Markdown like `render_inline`, mapping the spans to the
+    /// chars of `text` they show
+    pub fn render_inline_mapped(&self, text: &str, base: Style) -> Vec<MappedSpan> {
+        if text.is_empty() {
+            return Vec::new();
+        }
+
+        let key = (text.to_string(), base);
+        if let Some(spans) = self.inline_cache.borrow_mut().get(&key) {
+            return spans;
+        }
+        let spans = self.parse_inline(text, base);
+        self.inline_cache.borrow_mut().insert(key, spans.clone());
+        spans
+    }
+
+    /// Parse and style inline Markdown for `render_inline_mapped`
+    fn parse_inline(&self, text: &str, base: Style) -> Vec<MappedSpan> {
+        let literal = || vec![MappedSpan::source(text, base, 0)];
+        let nodes = match Markdown::from_markdown_str(text) {
+            Ok(markdown) => markdown.nodes,
+            Err(_) => return literal(),
+        };
+
+        // Fragments that parse as block syntax (e.g. "1986. A year") are not
+        // inline Markdown in their original context, so keep them literal
+        if nodes.iter().any(Self::is_block_node) {
+            return literal();
+        }
+
+        let source = [text];
+        let mut spans = SpanLines::new(&source);
+        for node in &nodes {
+            self.inline_spans(node, base, &mut spans);
+        }
+        let spans = spans.into_spans();
+        if spans.is_empty() {
+            return literal();
+        }
+        spans
+    }
+
+    fn is_inline_node(node: &Node) -> bool {
+        matches!(
+            node,
+            Node::Text(_)
+                | Node::Emphasis(_)
+                | Node::Strong(_)
+                | Node::Delete(_)
+                | Node::CodeInline(_)
+                | Node::MathInline(_)
+                | Node::Link(_)
+                | Node::LinkRef(_)
+                | Node::WikiLink(_)
+                | Node::Image(_)
+                | Node::ImageRef(_)
+                | Node::FootnoteRef(_)
+                | Node::Break(_)
+                | Node::Html(_)
+        )
+    }
+
+    fn is_block_node(node: &Node) -> bool {
+        matches!(
+            node,
+            Node::Heading(_)
+                | Node::List(_)
+                | Node::Blockquote(_)
+                | Node::Code(_)
+                | Node::HorizontalRule(_)
+                | Node::TableCell(_)
+                | Node::TableRow(_)
+                | Node::Definition(_)
+        )
+    }
+
+    /// Get the visible text of inline Markdown (markup removed)
+    pub fn inline_plain_text(&self, text: &str) -> String {
+        self.render_inline(text, Style::default())
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    /// Render the cursor line, revealing raw syntax only where the cursor is
+    ///
+    /// The block marker (`#`, list bullet, `>`) and the inline element containing
+    /// the cursor are shown as source; the rest of the line stays rendered.
+    /// Returns `None` for lines that are always edited as source (code blocks,
+    /// tables, front matter, ...).
+    pub fn render_partial_line(
+        &self,
+        buffer: &DocumentBuffer,
+        line_idx: usize,
+        cursor_column: usize,
+    ) -> Option<PartialLine> {
+        let content = buffer.line(line_idx)?;
+        if self.is_inside_code_block(buffer, line_idx) {
+            return None;
+        }
+
+        let line_type = self.determine_line_type(buffer, line_idx, content);
+        let base = match line_type {
+            LineType::Heading(level) => self.heading_style(level),
+            LineType::Blockquote => self.quote_style,
+            LineType::ListItem
+            | LineType::OrderedListItem
+            | LineType::TaskListItem(_)
+            | LineType::Text => Style::default(),
+            _ => return None,
+        };
+
+        let prefix_len = Self::block_prefix_len(content, &line_type);
+        let (prefix, body) = content.split_at(prefix_len);
+        let cursor = content
+            .char_indices()
+            .nth(cursor_column)
+            .map_or(content.len(), |(i, _)| i);
+
+        let nodes = Markdown::from_markdown_str(body).ok()?.nodes;
+        if nodes.iter().any(Self::is_block_node) {
+            return None;
+        }
+
+        let chars = |byte: usize| content[..byte].chars().count();
+        let mut line = PartialLineBuilder::new(cursor);
+        line.push(vec![MappedSpan::source(prefix, base, 0)], prefix, 0);
+
+        // Wiki links and the text around them share one position, so their
+        // ranges are taken from the source instead
+        let wiki_ranges: Vec<_> = wiki_link::find_wiki_links(body)
+            .into_iter()
+            .map(|link| link.range)
+            .collect();
+        let mut next_wiki = 0;
+
+        let cursor_in_body = cursor.checked_sub(prefix_len);
+        let mut pos = 0;
+        for (i, node) in nodes.iter().enumerate() {
+            let position = node.position()?;
+            let mut start = position
+                .start
+                .column
+                .saturating_sub(1)
+                .clamp(pos, body.len());
+            let mut end = position
+                .end
+                .column
+                .saturating_sub(1)
+                .clamp(start, body.len());
+            if let Some(range) = wiki_ranges.get(next_wiki) {
+                if matches!(node, Node::WikiLink(_)) {
+                    start = range.start.clamp(pos, body.len());
+                    end = range.end.clamp(start, body.len());
+                    next_wiki += 1;
+                } else if matches!(nodes.get(i + 1), Some(Node::WikiLink(_))) {
+                    end = range.start.clamp(start, body.len());
+                }
+            }
+
+            if start > pos {
+                let gap = &body[pos..start];
+                line.push(
+                    vec![MappedSpan::source(gap, base, chars(prefix_len + pos))],
+                    gap,
+                    prefix_len + pos,
+                );
+            }
+
+            let source = &body[start..end];
+            let revealed = !matches!(node, Node::Text(_))
+                && !line.has_cursor()
+                && cursor_in_body.is_some_and(|c| (start..=end).contains(&c));
+            let spans = if revealed {
+                vec![MappedSpan::source(source, base, chars(prefix_len + start))]
+            } else {
+                let body_source = [body];
+                let mut spans = SpanLines::new(&body_source);
+                self.inline_spans(node, base, &mut spans);
+                spans
+                    .into_spans()
+                    .into_iter()
+                    .map(|span| span.shifted(chars(prefix_len)))
+                    .collect()
+            };
+            line.push(spans, source, prefix_len + start);
+            pos = end;
+        }
+        if pos < body.len() {
+            let rest = &body[pos..];
+            line.push(
+                vec![MappedSpan::source(rest, base, chars(prefix_len + pos))],
+                rest,
+                prefix_len + pos,
+            );
+        }
+
+        Some(line.build())
+    }
+
+    /// Byte length of the block marker that starts a line (indent included)
+    fn block_prefix_len(content: &str, line_type: &LineType) -> usize {
+        let indent = content.len() - content.trim_start().len();
+        let rest = &content[indent..];
+        let skip_spaces = |s: &str| s.len() - s.trim_start().len();
+
+        let marker = match line_type {
+            LineType::Heading(_) => rest.len() - rest.trim_start_matches('#').len(),
+            LineType::ListItem | LineType::TaskListItem(_) => 1,
+            LineType::OrderedListItem => {
+                let digits =
+                    rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                digits + 1
+            }
+            LineType::Blockquote => 1,
+            _ => 0,
+        }
+        .min(rest.len());
+
+        let mut len = indent + marker;
+        if marker > 0 {
+            len += skip_spaces(&content[len..]);
+        }
+        if let LineType::TaskListItem(_) = line_type
+            && content[len..].len() >= 3
+            && content[len..].starts_with('[')
+        {
+            len += 3;
+            len += skip_spaces(&content[len..]);
+        }
+        len
+    }
+
+    /// Fenced code blocks of a document, scanned once per buffer revision
+    pub fn code_blocks(&self, buffer: &DocumentBuffer) -> Rc<[CodeBlock]> {
+        let mut cache = self.code_blocks.borrow_mut();
+        if let Some((revision, blocks)) = cache.as_ref()
+            && *revision == buffer.revision()
+        {
+            return Rc::clone(blocks);
+        }
+
+        let mut blocks: Vec<CodeBlock> = Vec::new();
+        for (line_idx, content) in buffer.lines().iter().enumerate() {
+            let LineType::CodeFence(language) = LineAnalyzer::analyze_line(content) else {
+                continue;
+            };
+            match blocks.last_mut() {
+                Some(block) if block.close_line.is_none() => block.close_line = Some(line_idx),
+                _ => blocks.push(CodeBlock {
+                    fence_line: line_idx,
+                    language,
+                    close_line: None,
+                }),
+            }
+        }
+        let blocks: Rc<[CodeBlock]> = blocks.into();
+        *cache = Some((buffer.revision(), Rc::clone(&blocks)));
+        blocks
+    }
+
+    /// Check if a line is inside a fenced code block
+    fn is_inside_code_block(&self, buffer: &DocumentBuffer, line_idx: usize) -> bool {
+        self.code_blocks(buffer)
+            .iter()
+            .any(|block| block.contains(line_idx))
+    }
+
+    fn inline_spans(&self, node: &Node, style: Style, spans: &mut SpanLines) {
+        let children = |style: Style, spans: &mut SpanLines| {
+            for child in node.children() {
+                self.inline_spans(&child, style, spans);
+            }
+        };
+
+        match node {
+            Node::Strong(_) => children(style.add_modifier(Modifier::BOLD), spans),
+            Node::Emphasis(_) => children(style.add_modifier(Modifier::ITALIC), spans),
+            Node::Delete(_) => children(style.add_modifier(Modifier::CROSSED_OUT), spans),
+            Node::CodeInline(_) => spans.push(
+                Span::styled(node.value(), style.patch(self.inline_code_style)),
+                node.position(),
+            ),
+            Node::MathInline(_) => spans.push(
+                Span::styled(node.value(), style.fg(theme::NUMBER)),
+                node.position(),
+            ),
+            Node::Link(link) if link.values.is_empty() => spans.push(
+                Span::styled(link.url.as_str().to_string(), style.patch(self.link_style)),
+                node.position(),
+            ),
+            Node::Link(_) => children(style.patch(self.link_style), spans),
+            // `children` leaves out the text of reference links
+            Node::LinkRef(link) => {
+                for value in &link.values {
+                    self.inline_spans(value, style.patch(self.link_style), spans);
+                }
+            }
+            Node::WikiLink(link) => {
+                let label = WikiLink::from_target(&link.target, link.text.as_deref())
+                    .map_or_else(|| link.target.clone(), |link| link.label());
+                spans.push(
+                    Span::styled(label, style.patch(self.link_style)),
+                    node.position(),
+                );
+            }
+            Node::Image(image) => spans.push(
+                Span::styled(
+                    format!("🖼 {}", image.alt),
+                    style.fg(theme::FUNC).add_modifier(Modifier::ITALIC),
+                ),
+                node.position(),
+            ),
+            Node::ImageRef(image) => spans.push(
+                Span::styled(
+                    format!("🖼 {}", image.alt),
+                    style.fg(theme::FUNC).add_modifier(Modifier::ITALIC),
+                ),
+                node.position(),
+            ),
+            Node::FootnoteRef(footnote) => spans.push(
+                Span::styled(
+                    format!("[{}]", footnote.label.as_deref().unwrap_or(&footnote.ident)),
+                    style.fg(theme::COMMENT),
+                ),
+                node.position(),
+            ),
+            Node::Break(_) => {
+                spans.push(Span::styled(" ↵", style.fg(theme::FG_DIM)), None);
+                spans.break_line();
+            }
+            Node::Html(_) => spans.push(
+                Span::styled(node.value(), style.fg(theme::FG_DIM)),
+                node.position(),
+            ),
+            Node::Text(_) => spans.push(Span::styled(node.value(), style), node.position()),
+            _ => {
+                let child_nodes = node.children();
+                if child_nodes.is_empty() {
+                    let value = node.value();
+                    if !value.is_empty() {
+                        spans.push(Span::styled(value, style), node.position());
+                    }
+                } else {
+                    children(style, spans);
+                }
+            }
+        }
     }
 
     /// Render image placeholder with detailed information
+    ///
+    /// The alt text and path are mapped to where they appear in `content`.
     pub fn render_image_with_info(
         &self,
+        content: &str,
         alt_text: &str,
         path: &str,
         dimensions: Option<(u32, u32)>,
-    ) -> Vec<Span<'_>> {
+    ) -> Vec<MappedSpan> {
+        // Char where `text` starts right after `marker` in the source line
+        let source_start = |marker: &str, text: &str| {
+            let start = content.find(marker)? + marker.len();
+            content[start..]
+                .starts_with(text)
+                .then(|| content[..start].chars().count())
+        };
+        let mapped = |text: &str, style: Style, start: Option<usize>| match start {
+            Some(start) => MappedSpan::source(text, style, start),
+            None => MappedSpan::unmapped(text, style),
+        };
+
+        let alt_style = Style::default()
+            .fg(theme::FUNC)
+            .add_modifier(Modifier::ITALIC);
         let mut spans = vec![
-            Span::styled("🖼️  ", Style::default().fg(theme::ACCENT)),
-            Span::styled(
-                format!("[{}]", alt_text),
-                Style::default()
-                    .fg(theme::FUNC)
-                    .add_modifier(Modifier::ITALIC),
-            ),
-            Span::styled(" ", Style::default()),
+            MappedSpan::unmapped("🖼️  ", Style::default().fg(theme::ACCENT)),
+            MappedSpan::unmapped("[", alt_style),
+            mapped(alt_text, alt_style, source_start("![", alt_text)),
+            MappedSpan::unmapped("]", alt_style),
+            MappedSpan::unmapped(" ", Style::default()),
         ];
 
         // Add dimensions if available
         if let Some((width, height)) = dimensions {
-            spans.push(Span::styled(
+            spans.push(MappedSpan::unmapped(
                 format!("{}x{} ", width, height),
                 Style::default().fg(theme::NUMBER),
             ));
         }
 
-        spans.push(Span::styled(
-            path.to_string(),
+        spans.push(mapped(
+            path,
             Style::default()
                 .fg(theme::FG_DIM)
                 .add_modifier(Modifier::UNDERLINED),
+            source_start("](", path),
         ));
 
         spans
     }
 
-    /// Render image placeholder (basic version for backwards compatibility)
-    fn render_image(&self, alt_text: &str, path: &str) -> Vec<Span<'_>> {
-        vec![
-            Span::styled("🖼️  ", Style::default().fg(theme::ACCENT)),
-            Span::styled(
-                format!("[{}]", alt_text),
-                Style::default()
-                    .fg(theme::FUNC)
-                    .add_modifier(Modifier::ITALIC),
-            ),
-            Span::styled(" ", Style::default()),
-            Span::styled(
-                path.to_string(),
-                Style::default()
-                    .fg(theme::FG_DIM)
-                    .add_modifier(Modifier::UNDERLINED),
-            ),
-        ]
+    /// Border, header and cell styles used for tables
+    pub fn table_styles(&self) -> (Style, Style, Style) {
+        (
+            self.table_border_style,
+            self.table_header_style,
+            self.table_cell_style,
+        )
     }
 
     /// Render table header row
@@ -418,53 +1170,34 @@ impl MarkdownRenderer {
             let width = column_widths
                 .get(i)
                 .copied()
-                .unwrap_or(cell.chars().count());
+                .unwrap_or_else(|| self.inline_plain_text(cell).chars().count());
             let alignment = alignments.get(i).copied().unwrap_or(TableAlignment::Left);
 
-            // Pad cell content based on alignment
-            let padded = Self::pad_cell(cell, width, alignment);
-
             let style = if is_header {
                 self.table_header_style
             } else {
                 self.table_cell_style
             };
 
-            spans.push(Span::styled(format!(" {} ", padded), style));
+            // Pad cell content based on alignment, measuring the rendered text
+            let cell_spans = self.render_inline(cell, style);
+            let cell_width: usize = cell_spans.iter().map(|s| s.content.chars().count()).sum();
+            let padding = width.saturating_sub(cell_width);
+            let (left_pad, right_pad) = match alignment {
+                TableAlignment::Left | TableAlignment::None => (0, padding),
+                TableAlignment::Right => (padding, 0),
+                TableAlignment::Center => (padding / 2, padding - padding / 2),
+            };
+
+            spans.push(Span::styled(format!(" {}", " ".repeat(left_pad)), style));
+            spans.extend(cell_spans);
+            spans.push(Span::styled(format!("{} ", " ".repeat(right_pad)), style));
             spans.push(Span::styled("│", self.table_border_style));
         }
 
         spans
     }
 
-    /// Pad cell content according to alignment
-    fn pad_cell(content: &str, width: usize, alignment: TableAlignment) -> String {
-        let content_width = content.chars().count();
-        if content_width >= width {
-            return content.to_string();
-        }
-
-        let padding = width - content_width;
-        match alignment {
-            TableAlignment::Left | TableAlignment::None => {
-                format!("{}{}", content, " ".repeat(padding))
-            }
-            TableAlignment::Right => {
-                format!("{}{}", " ".repeat(padding), content)
-            }
-            TableAlignment::Center => {
-                let left_pad = padding / 2;
-                let right_pad = padding - left_pad;
-                format!(
-                    "{}{}{}",
-                    " ".repeat(left_pad),
-                    content,
-                    " ".repeat(right_pad)
-                )
-            }
-        }
-    }
-
     /// Render front matter delimiter (--- or +++)
     fn render_front_matter_delimiter(&self) -> Vec<Span<'_>> {
         vec![Span::styled(
@@ -474,7 +1207,7 @@ impl MarkdownRenderer {
     }
 
     /// Render front matter content (YAML/TOML)
-    fn render_front_matter_content(&self, content: &str) -> Vec<Span<'_>> {
+    fn render_front_matter_content(&self, content: &str) -> Vec<MappedSpan> {
         // Simple YAML syntax highlighting
         let trimmed = content.trim_start();
 
@@ -482,38 +1215,41 @@ impl MarkdownRenderer {
         if let Some(colon_pos) = trimmed.find(':') {
             let key = &trimmed[..colon_pos];
             let value = &trimmed[colon_pos..];
+            let key_start = content.len() - trimmed.len();
+            let value_start = content[..key_start + colon_pos].chars().count();
 
             vec![
-                Span::styled(
-                    key.to_string(),
+                MappedSpan::source(
+                    key,
                     Style::default()
                         .fg(theme::VARIABLE)
                         .add_modifier(Modifier::BOLD),
+                    content[..key_start].chars().count(),
                 ),
-                Span::styled(
-                    value.to_string(),
-                    Style::default().fg(theme::FG),
-                ),
+                MappedSpan::source(value, Style::default().fg(theme::FG), value_start),
             ]
         } else if trimmed.starts_with('-') {
             // YAML list item
-            vec![Span::styled(
-                content.to_string(),
+            vec![MappedSpan::source(
+                content,
                 Style::default().fg(theme::FG_MUTED),
+                0,
             )]
         } else if trimmed.starts_with('#') {
             // YAML comment
-            vec![Span::styled(
-                content.to_string(),
+            vec![MappedSpan::source(
+                content,
                 Style::default()
                     .fg(theme::COMMENT)
                     .add_modifier(Modifier::ITALIC),
+                0,
             )]
         } else {
             // Default front matter style
-            vec![Span::styled(
-                content.to_string(),
+            vec![MappedSpan::source(
+                content,
                 Style::default().fg(theme::FG_MUTED),
+                0,
             )]
         }
     }
@@ -525,8 +1261,6 @@ impl MarkdownRenderer {
         line_idx: usize,
         content: &str,
     ) -> crate::document::LineType {
-        use crate::document::LineAnalyzer;
-
         let line_type = LineAnalyzer::analyze_line(content);
 
         // Check if we're inside a front matter block
@@ -596,8 +1330,7 @@ impl Renderer for MarkdownRenderer {
             vec![Span::styled(content.to_string(), Style::default())]
         } else {
             // Other lines: show rich formatted based on line type
-            let line_type = self.determine_line_type(buffer, line_idx, content);
-            self.render_rich(content, &line_type)
+            source_map::spans(self.render_mapped_line(buffer, line_idx))
         }
     }
 
@@ -605,3 +1338,249 @@ impl Renderer for MarkdownRenderer {
         true // Markdown renderer supports WYSIWYG mode
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(spans: &[Span]) -> String {
+        spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    fn span_with<'a>(spans: &'a [Span], text: &str) -> &'a Span<'a> {
+        spans
+            .iter()
+            .find(|s| s.content == text)
+            .unwrap_or_else(|| panic!("no span {:?} in {:?}", text, spans))
+    }
+
+    #[test]
+    fn test_render_inline_is_cached() {
+        let renderer = MarkdownRenderer::new();
+        let text = "cached **inline** text";
+        let key = (text.to_string(), Style::default());
+        let spans = renderer.render_inline_mapped(text, Style::default());
+        assert_eq!(renderer.inline_cache.borrow_mut().get(&key), Some(spans));
+
+        // Each renderer keeps its own cache
+        let other = MarkdownRenderer::new();
+        assert_eq!(other.inline_cache.borrow_mut().get(&key), None);
+    }
+
+    #[test]
+    fn test_inline_cache_keeps_used_entries() {
+        let mut cache = InlineCache::default();
+        let key = |i: usize| (i.to_string(), Style::default());
+        for i in 0..INLINE_CACHE_CAPACITY {
+            cache.insert(key(i), Vec::new());
+        }
+
+        // Filling up turns the entries over to the older generation
+        cache.insert(key(INLINE_CACHE_CAPACITY), Vec::new());
+        assert!(cache.get(&key(0)).is_some());
+        for i in INLINE_CACHE_CAPACITY + 1..2 * INLINE_CACHE_CAPACITY {
+            cache.insert(key(i), Vec::new());
+        }
+
+        // Only the entry used since the first turnover survives the second
+        cache.insert(key(2 * INLINE_CACHE_CAPACITY), Vec::new());
+        assert!(cache.get(&key(0)).is_some());
+        assert!(cache.get(&key(1)).is_none());
+    }
+
+    #[test]
+    fn test_render_lines_in_document_context() {
+        let content = "Some *emphasis\ncontinues* here\\\nafter [a break][r] and [none]\nlast\\\n\n[r]: https://mqlang.org\n";
+        let buffer = DocumentBuffer::from_string(content).unwrap();
+        let renderer = MarkdownRenderer::new();
+        let line = |idx| renderer.render_line(&buffer, idx, false);
+
+        let first = line(0);
+        assert_eq!(plain(&first), "Some emphasis");
+        assert!(
+            span_with(&first, "emphasis")
+                .style
+                .add_modifier
+                .contains(Modifier::ITALIC)
+        );
+
+        // The emphasis closes on the next line, which ends with a hard break
+        let second = line(1);
+        assert_eq!(plain(&second), "continues here ↵");
+        assert!(
+            span_with(&second, "continues")
+                .style
+                .add_modifier
+                .contains(Modifier::ITALIC)
+        );
+
+        // The reference is defined further down; an undefined one stays literal
+        let third = line(2);
+        assert_eq!(plain(&third), "after a break and [none]");
+        assert_eq!(span_with(&third, "a break").style.fg, Some(theme::ACCENT));
+
+        // No line follows in the paragraph, so the backslash is not a break
+        assert_eq!(plain(&line(3)), "last\\");
+    }
+
+    #[test]
+    fn test_render_inline_emphasis_and_strong() {
+        let renderer = MarkdownRenderer::new();
+        let spans = renderer.render_inline("a **bold** and *italic* ~~gone~~", Style::default());
+
+        assert_eq!(plain(&spans), "a bold and italic gone");
+        assert!(
+            span_with(&spans, "bold")
+                .style
+                .add_modifier
+                .contains(Modifier::BOLD)
+        );
+        assert!(
+            span_with(&spans, "italic")
+                .style
+                .add_modifier
+                .contains(Modifier::ITALIC)
+        );
+        assert!(
+            span_with(&spans, "gone")
+                .style
+                .add_modifier
+                .contains(Modifier::CROSSED_OUT)
+        );
+    }
+
+    #[test]
+    fn test_render_inline_code_and_links() {
+        let renderer = MarkdownRenderer::new();
+        let spans = renderer.render_inline(
+            "run `mq` see [docs](https://mqlang.org) or <https://example.com>",
+            Style::default(),
+        );
+
+        assert_eq!(plain(&spans), "run mq see docs or https://example.com");
+        assert_eq!(span_with(&spans, "mq").style.fg, Some(theme::STRING));
+        assert_eq!(span_with(&spans, "docs").style.fg, Some(theme::ACCENT));
+    }
+
+    #[test]
+    fn test_render_inline_keeps_literal_text() {
+        let renderer = MarkdownRenderer::new();
+
+        assert_eq!(renderer.inline_plain_text("2 * 3 = 6"), "2 * 3 = 6");
+        assert_eq!(renderer.inline_plain_text("1986. A year"), "1986. A year");
+        assert_eq!(renderer.inline_plain_text("line end  "), "line end");
+        assert_eq!(renderer.inline_plain_text("line end\\"), "line end\\");
+    }
+
+    fn partial(content: &str, column: usize) -> (String, usize) {
+        let buffer = DocumentBuffer::from_string(content).unwrap();
+        let line = MarkdownRenderer::new()
+            .render_partial_line(&buffer, 0, column)
+            .unwrap();
+        (plain(&source_map::spans(line.spans)), line.cursor_width)
+    }
+
+    #[test]
+    fn test_partial_line_reveals_element_under_cursor() {
+        let content = "see **bold** and `code` here";
+
+        // Cursor inside the strong element: only it shows markers
+        assert_eq!(
+            partial(content, 6),
+            ("see **bold** and code here".to_string(), 6)
+        );
+        // Cursor inside the code span
+        assert_eq!(
+            partial(content, 19),
+            ("see bold and `code` here".to_string(), 15)
+        );
+        // Cursor in plain text after both elements
+        assert_eq!(
+            partial(content, 25),
+            ("see bold and code here".to_string(), 19)
+        );
+    }
+
+    #[test]
+    fn test_partial_line_keeps_block_marker() {
+        assert_eq!(
+            partial("- a [link](https://x.org) b", 26),
+            ("- a link b".to_string(), 9)
+        );
+        assert_eq!(partial("## *Title*", 5), ("## *Title*".to_string(), 5));
+        assert_eq!(partial("- [ ] **task**", 2), ("- [ ] task".to_string(), 2));
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let content = "see [[Page Name]] and [[Page#Setup|setup]] end";
+        let spans = MarkdownRenderer::new().render_inline(content, Style::default());
+        assert_eq!(plain(&spans), "see Page Name and setup end");
+        assert_eq!(span_with(&spans, "Page Name").style.fg, Some(theme::ACCENT));
+
+        assert_eq!(
+            partial(content, 7),
+            ("see [[Page Name]] and setup end".to_string(), 7)
+        );
+        assert_eq!(
+            partial(content, 44),
+            ("see Page Name and setup end".to_string(), 25)
+        );
+    }
+
+    #[test]
+    fn test_partial_line_skips_code_blocks() {
+        let buffer = DocumentBuffer::from_string("```\n**a**\n```").unwrap();
+        let renderer = MarkdownRenderer::new();
+        assert!(renderer.render_partial_line(&buffer, 1, 0).is_none());
+        assert!(renderer.render_partial_line(&buffer, 0, 0).is_none());
+    }
+
+    #[test]
+    fn test_code_blocks_follow_revision() {
+        let mut buffer = DocumentBuffer::from_string("```rust\nfn a() {}\n```\n```").unwrap();
+        let renderer = MarkdownRenderer::new();
+        let blocks = renderer.code_blocks(&buffer);
+        assert_eq!(
+            *blocks,
+            [
+                CodeBlock {
+                    fence_line: 0,
+                    language: Some("rust".to_string()),
+                    close_line: Some(2),
+                },
+                CodeBlock {
+                    fence_line: 3,
+                    language: None,
+                    close_line: None,
+                },
+            ]
+        );
+        assert!(Rc::ptr_eq(&blocks, &renderer.code_blocks(&buffer)));
+
+        // Editing the buffer scans it again
+        buffer.cursor_mut().column = 3;
+        buffer.insert_str("x");
+        let blocks = renderer.code_blocks(&buffer);
+        assert_eq!(blocks[0].language, Some("xrust".to_string()));
+        assert!(blocks[0].contains(2) && !blocks[0].contains(0) && !blocks[0].contains(3));
+    }
+
+    #[test]
+    fn test_render_inline_in_blocks() {
+        let renderer = MarkdownRenderer::new();
+
+        let item = renderer.render_list_item("- **done** item", false, false, None);
+        assert!(plain(&source_map::spans(item)).ends_with("done item"));
+
+        let quote = renderer.render_blockquote_line("> a `code` quote", None);
+        assert_eq!(plain(&source_map::spans(quote)), "▎ a code quote");
+
+        let row = renderer.render_table_row(
+            &["**a**".to_string(), "b".to_string()],
+            &[3, 3],
+            &[TableAlignment::Left, TableAlignment::Right],
+        );
+        assert_eq!(plain(&row), "│ a   │   b │");
+    }
+}
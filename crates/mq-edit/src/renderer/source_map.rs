@@ -0,0 +1,196 @@
+use std::ops::Range;
+
+use ratatui::{style::Style, text::Span};
+
+/// A rendered char with its style and source
+pub type MappedChar = (char, Style, Option<Range<usize>>);
+
+/// A rendered span along with the chars of its source line it shows
+///
+/// A span whose text has as many chars as its source range maps to it char
+/// by char; any other span (e.g. `code` rendered without its backticks) maps
+/// to it as a whole. Text drawn by the renderer itself, such as bullets,
+/// borders and padding, has no source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappedSpan {
+    pub span: Span<'static>,
+    /// Char range of the source line
+    pub source: Option<Range<usize>>,
+}
+
+impl MappedSpan {
+    pub fn new(span: Span<'static>, source: Option<Range<usize>>) -> Self {
+        Self { span, source }
+    }
+
+    /// Text that doesn't come from the source line
+    pub fn unmapped(content: impl Into<String>, style: Style) -> Self {
+        Self::new(Span::styled(content.into(), style), None)
+    }
+
+    /// Text shown as written at char `start` of the source line
+    pub fn source(content: impl Into<String>, style: Style, start: usize) -> Self {
+        let content = content.into();
+        let end = start + content.chars().count();
+        Self::new(Span::styled(content, style), Some(start..end))
+    }
+
+    /// The same span with its source moved right by `offset` chars
+    pub fn shifted(self, offset: usize) -> Self {
+        Self {
+            source: self
+                .source
+                .map(|source| source.start + offset..source.end + offset),
+            ..self
+        }
+    }
+
+    /// Whether the span maps to its source char by char
+    fn is_exact(&self) -> bool {
+        self.source
+            .as_ref()
+            .is_some_and(|source| source.len() == self.span.content.chars().count())
+    }
+
+    /// Source of the span's char `index`
+    fn char_source(&self, index: usize) -> Option<Range<usize>> {
+        let source = self.source.clone()?;
+        if self.is_exact() {
+            Some(source.start + index..source.start + index + 1)
+        } else {
+            Some(source)
+        }
+    }
+}
+
+/// Spans drawn without any source text
+pub fn unmapped(spans: Vec<Span<'_>>) -> Vec<MappedSpan> {
+    spans
+        .into_iter()
+        .map(|span| MappedSpan::unmapped(span.content.into_owned(), span.style))
+        .collect()
+}
+
+/// Spans without their sources
+pub fn spans(mapped: Vec<MappedSpan>) -> Vec<Span<'static>> {
+    mapped.into_iter().map(|mapped| mapped.span).collect()
+}
+
+/// Char range of the rendered line showing chars `source` of its source line
+///
+/// Text between the spans that show part of `source` (e.g. an emphasis
+/// marker's rendering, if any) is included. Returns None when none of the
+/// source range is shown.
+pub fn rendered_range(spans: &[MappedSpan], source: Range<usize>) -> Option<Range<usize>> {
+    let mut result: Option<Range<usize>> = None;
+    let mut offset = 0;
+    for mapped in spans {
+        let len = mapped.span.content.chars().count();
+        if let Some(span_source) = &mapped.source {
+            let start = source.start.max(span_source.start);
+            let end = source.end.min(span_source.end);
+            if start < end {
+                let range = if mapped.is_exact() {
+                    offset + start - span_source.start..offset + end - span_source.start
+                } else {
+                    offset..offset + len
+                };
+                result = Some(match result {
+                    Some(r) => r.start.min(range.start)..r.end.max(range.end),
+                    None => range,
+                });
+            }
+        }
+        offset += len;
+    }
+    result.filter(|range| !range.is_empty())
+}
+
+/// Split spans into styled chars, each with its own source
+pub fn split_chars(spans: &[MappedSpan]) -> Vec<MappedChar> {
+    spans
+        .iter()
+        .flat_map(|mapped| {
+            mapped
+                .span
+                .content
+                .chars()
+                .enumerate()
+                .map(move |(i, c)| (c, mapped.span.style, mapped.char_source(i)))
+        })
+        .collect()
+}
+
+/// Merge runs of styled chars back into spans
+///
+/// Chars join a span when they share its style and either continue its
+/// source char by char or come from the same source as a whole.
+pub fn merge_chars(chars: &[MappedChar]) -> Vec<MappedSpan> {
+    let mut spans: Vec<MappedSpan> = Vec::new();
+    for (c, style, source) in chars {
+        if let Some(last) = spans.last_mut()
+            && last.span.style == *style
+        {
+            let continues = match (&mut last.source, source) {
+                (None, None) => true,
+                (Some(last_source), Some(source)) if *last_source == *source => true,
+                (Some(last_source), Some(source))
+                    if last_source.end == source.start
+                        && last_source.len() == last.span.content.chars().count() =>
+                {
+                    last_source.end = source.end;
+                    true
+                }
+                _ => false,
+            };
+            if continues {
+                last.span.content.to_mut().push(*c);
+                continue;
+            }
+        }
+        spans.push(MappedSpan::new(
+            Span::styled(c.to_string(), *style),
+            source.clone(),
+        ));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rendered_range() {
+        // "a **bold** c" rendered as "a bold c"
+        let spans = vec![
+            MappedSpan::source("a ", Style::default(), 0),
+            MappedSpan::source("bold", Style::default(), 4),
+            MappedSpan::source(" c", Style::default(), 10),
+        ];
+        assert_eq!(rendered_range(&spans, 5..7), Some(3..5));
+        // Markup and the text on both sides
+        assert_eq!(rendered_range(&spans, 1..11), Some(1..7));
+        // Markup alone is not shown
+        assert_eq!(rendered_range(&spans, 2..4), None);
+
+        // "`code`" rendered as "code" maps as a whole
+        let spans = vec![
+            MappedSpan::unmapped("• ", Style::default()),
+            MappedSpan::new(Span::raw("code"), Some(2..8)),
+        ];
+        assert_eq!(rendered_range(&spans, 3..4), Some(2..6));
+    }
+
+    #[test]
+    fn test_split_and_merge_chars() {
+        let style = Style::default();
+        let spans = vec![
+            MappedSpan::source("ab", style, 3),
+            MappedSpan::new(Span::raw("cd"), Some(7..9)),
+            MappedSpan::new(Span::raw("x"), Some(0..3)),
+            MappedSpan::unmapped("--", style),
+        ];
+        assert_eq!(merge_chars(&split_chars(&spans)), spans);
+    }
+}
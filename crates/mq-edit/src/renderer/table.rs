@@ -0,0 +1,485 @@
+use ratatui::style::Style;
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::MarkdownRenderer;
+use super::source_map::{self, MappedChar, MappedSpan};
+use crate::document::{Table, TableAlignment};
+
+/// Narrowest a column is shrunk to when the table is wrapped
+const MIN_WRAP_WIDTH: usize = 3;
+
+/// How rendered tables that are wider than the pane are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableOverflow {
+    /// Wrap cell text so the table fits the pane
+    #[default]
+    Wrap,
+    /// Keep rows on one line and scroll the table to the cell under the cursor
+    Scroll,
+}
+
+/// Table rendering settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableOptions {
+    pub overflow: TableOverflow,
+    /// Columns available for a rendered table
+    pub width: usize,
+}
+
+/// Styled text of one screen row, mapped to the source line of its table row
+type Row = Vec<MappedSpan>;
+
+/// Screen layout of a table rendered with box-drawing borders
+///
+/// Each source row takes as many screen rows as its tallest wrapped cell; the
+/// top and bottom borders are drawn with the first and last source row.
+pub struct TableLayout {
+    /// Buffer line of the header row
+    start: usize,
+    /// Content width of each column
+    widths: Vec<usize>,
+    alignments: Vec<TableAlignment>,
+    /// Wrapped cell text per source row (None for the separator)
+    cells: Vec<Option<Vec<Vec<Row>>>>,
+    /// Horizontal scroll offset and visible width, in scroll mode
+    scroll: Option<(usize, usize)>,
+    border_style: Style,
+    header_style: Style,
+    cell_style: Style,
+}
+
+impl TableLayout {
+    /// Lay out a table parsed from `lines`, whose header is buffer line `start`
+    ///
+    /// In scroll mode the view is scrolled so that `cursor_cell` is visible.
+    pub fn new<S: AsRef<str>>(
+        renderer: &MarkdownRenderer,
+        table: &Table,
+        lines: &[S],
+        start: usize,
+        options: TableOptions,
+        cursor_cell: Option<usize>,
+    ) -> Self {
+        let (border_style, header_style, cell_style) = renderer.table_styles();
+        let columns = table.column_count();
+        let rendered: Vec<Option<Vec<Row>>> = (0..table.line_count())
+            .map(|row| {
+                let style = if row == 0 { header_style } else { cell_style };
+                let line = lines.get(row).map_or("", AsRef::as_ref);
+                table.row(row).map(|cells| {
+                    cells
+                        .iter()
+                        .zip(cell_offsets(line, cells))
+                        .map(|(cell, offset)| {
+                            renderer
+                                .render_inline_mapped(cell, style)
+                                .into_iter()
+                                .map(|span| span.shifted(offset))
+                                .collect()
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+
+        let natural: Vec<usize> = (0..columns)
+            .map(|column| {
+                rendered
+                    .iter()
+                    .flatten()
+                    .map(|cells| spans_width(&cells[column]))
+                    .max()
+                    .unwrap_or(0)
+                    .max(1)
+            })
+            .collect();
+        // Borders and padding: "│ " before each cell, " " after, and the final "│"
+        let chrome = 3 * columns + 1;
+        let widths = match options.overflow {
+            TableOverflow::Wrap => fit_widths(&natural, options.width.saturating_sub(chrome)),
+            TableOverflow::Scroll => natural,
+        };
+
+        let total = widths.iter().sum::<usize>() + chrome;
+        let scroll =
+            (options.overflow == TableOverflow::Scroll && total > options.width).then(|| {
+                let offset = cursor_cell.map_or(0, |cell| {
+                    let cell = cell.min(columns - 1);
+                    let left: usize = widths[..cell].iter().map(|w| w + 3).sum();
+                    let right = left + widths[cell] + 4;
+                    if right <= options.width {
+                        0
+                    } else {
+                        left.min(right - options.width)
+                    }
+                });
+                (offset, options.width)
+            });
+
+        let cells = rendered
+            .into_iter()
+            .map(|row| {
+                row.map(|cells| {
+                    cells
+                        .iter()
+                        .zip(&widths)
+                        .map(|(spans, width)| wrap_spans(spans, *width))
+                        .collect()
+                })
+            })
+            .collect();
+
+        Self {
+            start,
+            widths,
+            alignments: (0..columns)
+                .map(|column| table.alignment(column).unwrap_or(TableAlignment::None))
+                .collect(),
+            cells,
+            scroll,
+            border_style,
+            header_style,
+            cell_style,
+        }
+    }
+
+    /// Whether buffer line `line` belongs to the table
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start..self.start + self.cells.len()).contains(&line)
+    }
+
+    /// Screen rows taken by a table line rendered with `render`
+    pub fn height(&self, line: usize) -> usize {
+        let row = line - self.start;
+        let content = match &self.cells[row] {
+            Some(cells) => cells.iter().map(Vec::len).max().unwrap_or(1).max(1),
+            None => 1,
+        };
+        content + (row == 0) as usize + (row + 1 == self.cells.len()) as usize
+    }
+
+    /// Render a table line into screen rows
+    pub fn render(&self, line: usize) -> Vec<Row> {
+        let row = line - self.start;
+        let mut rows = Vec::new();
+        if row == 0 {
+            rows.push(self.border('┌', '┬', '┐'));
+        }
+
+        match &self.cells[row] {
+            Some(cells) => {
+                let style = if row == 0 {
+                    self.header_style
+                } else {
+                    self.cell_style
+                };
+                let height = cells.iter().map(Vec::len).max().unwrap_or(1).max(1);
+                rows.extend((0..height).map(|k| self.content_row(cells, k, style)));
+            }
+            None => rows.push(self.separator()),
+        }
+
+        if row + 1 == self.cells.len() {
+            rows.push(self.border('└', '┴', '┘'));
+        }
+
+        match self.scroll {
+            Some((offset, width)) => rows
+                .into_iter()
+                .map(|row| self.scroll_row(row, offset, width))
+                .collect(),
+            None => rows,
+        }
+    }
+
+    /// Screen row `k` of a source row's wrapped cells
+    fn content_row(&self, cells: &[Vec<Row>], k: usize, style: Style) -> Row {
+        let mut spans = vec![MappedSpan::unmapped("│", self.border_style)];
+        for ((lines, width), alignment) in cells.iter().zip(&self.widths).zip(&self.alignments) {
+            let text = lines.get(k).cloned().unwrap_or_default();
+            let padding = width.saturating_sub(spans_width(&text));
+            let (left, right) = match alignment {
+                TableAlignment::Left | TableAlignment::None => (0, padding),
+                TableAlignment::Right => (padding, 0),
+                TableAlignment::Center => (padding / 2, padding - padding / 2),
+            };
+            spans.push(MappedSpan::unmapped(" ".repeat(left + 1), style));
+            spans.extend(text);
+            spans.push(MappedSpan::unmapped(" ".repeat(right + 1), style));
+            spans.push(MappedSpan::unmapped("│", self.border_style));
+        }
+        spans
+    }
+
+    /// Horizontal border with the given corner and junction characters
+    fn border(&self, left: char, junction: char, right: char) -> Row {
+        let segments: Vec<String> = self.widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        let text = format!("{}{}{}", left, segments.join(&junction.to_string()), right);
+        vec![MappedSpan::unmapped(text, self.border_style)]
+    }
+
+    /// Header separator, with colons marking column alignment
+    fn separator(&self) -> Row {
+        let segments: Vec<String> = self
+            .widths
+            .iter()
+            .zip(&self.alignments)
+            .map(|(width, alignment)| {
+                let left = matches!(alignment, TableAlignment::Left | TableAlignment::Center);
+                let right = matches!(alignment, TableAlignment::Right | TableAlignment::Center);
+                format!(
+                    "{}{}{}",
+                    if left { ':' } else { '─' },
+                    "─".repeat(*width),
+                    if right { ':' } else { '─' }
+                )
+            })
+            .collect();
+        vec![MappedSpan::unmapped(
+            format!("├{}┤", segments.join("┼")),
+            self.border_style,
+        )]
+    }
+
+    /// Cut a row to the visible columns, marking hidden content at the edges
+    fn scroll_row(&self, row: Row, offset: usize, width: usize) -> Row {
+        let total = spans_width(&row);
+        let left_marker = offset > 0;
+        let right_marker = total > offset + width;
+        let visible = width.saturating_sub(left_marker as usize + right_marker as usize);
+
+        let mut spans = Vec::new();
+        if left_marker {
+            spans.push(MappedSpan::unmapped("‹", self.border_style));
+        }
+        spans.extend(slice_spans(&row, offset + left_marker as usize, visible));
+        if right_marker {
+            spans.push(MappedSpan::unmapped("›", self.border_style));
+        }
+        spans
+    }
+}
+
+/// Char offset of each trimmed cell text in its source line
+///
+/// Cells follow each other in the line, so each is looked up after the
+/// previous one.
+fn cell_offsets(line: &str, cells: &[String]) -> Vec<usize> {
+    let mut pos = 0;
+    cells
+        .iter()
+        .map(|cell| {
+            let start = line[pos..].find(cell.as_str()).map_or(pos, |i| pos + i);
+            pos = start + cell.len();
+            line[..start].chars().count()
+        })
+        .collect()
+}
+
+/// Display width of styled text
+fn spans_width(spans: &[MappedSpan]) -> usize {
+    spans.iter().map(|mapped| mapped.span.content.width()).sum()
+}
+
+/// Shrink column widths to fit `budget`, keeping narrow columns at their natural width
+///
+/// Columns that fit within an even share keep their width; the remaining space
+/// is split evenly between the wider ones.
+fn fit_widths(natural: &[usize], budget: usize) -> Vec<usize> {
+    if natural.iter().sum::<usize>() <= budget {
+        return natural.to_vec();
+    }
+
+    let mut widths = natural.to_vec();
+    let mut open: Vec<usize> = (0..natural.len()).collect();
+    let mut remaining = budget;
+    while !open.is_empty() {
+        let share = remaining / open.len();
+        let (fits, wide): (Vec<usize>, Vec<usize>) =
+            open.iter().partition(|&&column| natural[column] <= share);
+        if fits.is_empty() {
+            let extra = remaining % wide.len();
+            for (k, &column) in wide.iter().enumerate() {
+                let width = share + (k < extra) as usize;
+                widths[column] = width.max(MIN_WRAP_WIDTH.min(natural[column]));
+            }
+            break;
+        }
+        for column in fits {
+            remaining -= natural[column];
+        }
+        open = wide;
+    }
+    widths
+}
+
+/// Wrap styled text into rows of at most `width` display columns
+///
+/// Lines break between words; words longer than a row are split.
+fn wrap_spans(spans: &[MappedSpan], width: usize) -> Vec<Row> {
+    let width = width.max(1);
+    let chars = source_map::split_chars(spans);
+
+    let mut rows: Vec<Vec<MappedChar>> = Vec::new();
+    let mut current: Vec<MappedChar> = Vec::new();
+    let mut current_width = 0;
+    for word in chars
+        .split(|(c, _, _)| *c == ' ')
+        .filter(|word| !word.is_empty())
+    {
+        let word_width: usize = word.iter().map(|(c, _, _)| c.width().unwrap_or(0)).sum();
+        if !current.is_empty() && current_width + 1 + word_width <= width {
+            let style = current
+                .last()
+                .map_or(Style::default(), |(_, style, _)| *style);
+            current.push((' ', style, None));
+            current.extend_from_slice(word);
+            current_width += 1 + word_width;
+            continue;
+        }
+        if !current.is_empty() {
+            rows.push(std::mem::take(&mut current));
+            current_width = 0;
+        }
+        for mapped in word {
+            let char_width = mapped.0.width().unwrap_or(0);
+            if current_width + char_width > width && !current.is_empty() {
+                rows.push(std::mem::take(&mut current));
+                current_width = 0;
+            }
+            current.push(mapped.clone());
+            current_width += char_width;
+        }
+    }
+    if !current.is_empty() || rows.is_empty() {
+        rows.push(current);
+    }
+
+    rows.into_iter()
+        .map(|row| source_map::merge_chars(&row))
+        .collect()
+}
+
+/// Display columns `offset..offset + width` of styled text
+///
+/// Wide characters cut by either edge are replaced with spaces.
+fn slice_spans(spans: &[MappedSpan], offset: usize, width: usize) -> Row {
+    let end = offset + width;
+    let mut chars = Vec::new();
+    let mut column = 0;
+    for (c, style, source) in source_map::split_chars(spans) {
+        let char_width = c.width().unwrap_or(0);
+        let next = column + char_width;
+        if column >= offset && next <= end {
+            chars.push((c, style, source));
+        } else if next > offset && column < end {
+            // Partly visible wide character
+            let cut = next.min(end) - column.max(offset);
+            chars.extend(std::iter::repeat_n((' ', style, None), cut));
+        }
+        column = next;
+    }
+    source_map::merge_chars(&chars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(row: &[MappedSpan]) -> String {
+        row.iter().map(|s| s.span.content.as_ref()).collect()
+    }
+
+    fn layout(lines: &[&str], overflow: TableOverflow, width: usize) -> TableLayout {
+        let table = Table::parse(lines).unwrap();
+        let options = TableOptions { overflow, width };
+        TableLayout::new(&MarkdownRenderer::new(), &table, lines, 10, options, None)
+    }
+
+    #[test]
+    fn test_box_borders() {
+        let layout = layout(
+            &["| a | b |", "|:-|-:|", "| **x** | yy |"],
+            TableOverflow::Wrap,
+            80,
+        );
+        assert!(layout.contains(10) && layout.contains(12) && !layout.contains(13));
+
+        let header: Vec<String> = layout.render(10).iter().map(|r| plain(r)).collect();
+        assert_eq!(header, vec!["┌───┬────┐", "│ a │  b │"]);
+        assert_eq!(plain(&layout.render(11)[0]), "├:──┼───:┤");
+        let row: Vec<String> = layout.render(12).iter().map(|r| plain(r)).collect();
+        assert_eq!(row, vec!["│ x │ yy │", "└───┴────┘"]);
+        assert_eq!(layout.height(10), 2);
+        assert_eq!(layout.height(11), 1);
+        assert_eq!(layout.height(12), 2);
+    }
+
+    #[test]
+    fn test_wrap_to_width() {
+        let layout = layout(
+            &["| id | text |", "|---|---|", "| 1 | one two three four |"],
+            TableOverflow::Wrap,
+            20,
+        );
+        let rows: Vec<String> = layout.render(12).iter().map(|r| plain(r)).collect();
+        assert_eq!(
+            rows,
+            vec![
+                "│ 1  │ one two     │",
+                "│    │ three four  │",
+                "└────┴─────────────┘",
+            ]
+        );
+        assert!(rows.iter().all(|row| row.width() <= 20));
+        assert_eq!(layout.height(12), 3);
+    }
+
+    #[test]
+    fn test_scroll_to_cursor_cell() {
+        let lines = ["| aaaa | bbbb | cccc |", "|---|---|---|"];
+        let table = Table::parse(&lines).unwrap();
+        let options = TableOptions {
+            overflow: TableOverflow::Scroll,
+            width: 12,
+        };
+        let renderer = MarkdownRenderer::new();
+
+        let first = TableLayout::new(&renderer, &table, &lines, 0, options, Some(0));
+        let rows = first.render(0);
+        assert_eq!(plain(&rows[1]), "│ aaaa │ bb›");
+        assert_eq!(first.height(0), 2);
+
+        let last = TableLayout::new(&renderer, &table, &lines, 0, options, Some(2));
+        let row = plain(&last.render(0)[1]);
+        assert_eq!(row, "‹bb │ cccc │");
+        assert_eq!(row.width(), 12);
+    }
+
+    #[test]
+    fn test_fit_widths() {
+        assert_eq!(fit_widths(&[5, 5], 20), vec![5, 5]);
+        assert_eq!(fit_widths(&[10, 50, 5], 40), vec![10, 25, 5]);
+        assert_eq!(fit_widths(&[30, 30], 21), vec![11, 10]);
+        // Columns never shrink below the minimum
+        assert_eq!(fit_widths(&[30, 30], 2), vec![3, 3]);
+    }
+
+    #[test]
+    fn test_wrap_spans() {
+        let wrapped = wrap_spans(
+            &[MappedSpan::unmapped("日本語のテキスト", Style::default())],
+            6,
+        );
+        let rows: Vec<String> = wrapped.iter().map(|r| plain(r)).collect();
+        assert_eq!(rows, vec!["日本語", "のテキ", "スト"]);
+
+        let wrapped = wrap_spans(&[MappedSpan::unmapped("abcdefgh ij", Style::default())], 4);
+        let rows: Vec<String> = wrapped.iter().map(|r| plain(r)).collect();
+        assert_eq!(rows, vec!["abcd", "efgh", "ij"]);
+
+        assert_eq!(wrap_spans(&[], 4).len(), 1);
+    }
+}
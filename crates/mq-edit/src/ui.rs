@@ -1,15 +1,27 @@
+pub mod backlinks;
+pub mod code_actions;
 pub mod completion;
 pub mod dialog;
 pub mod editor;
 pub mod file_browser;
+pub mod front_matter_dialog;
 pub mod mq_query_dialog;
+pub mod outline;
 pub mod search_dialog;
+pub mod stats_dialog;
 pub mod status_bar;
 
+pub use backlinks::BacklinksWidget;
+pub use code_actions::CodeActionPopup;
 pub use completion::CompletionPopup;
 pub use dialog::{GotoLineDialog, QuitDialog, SaveAsDialog};
-pub use editor::EditorWidget;
+pub use editor::{
+    EditorWidget, LineLayout, cursor_line_text, line_rows, screen_rows, wrap_columns,
+};
 pub use file_browser::{FileBrowserWidget, FileTree};
+pub use front_matter_dialog::FrontMatterDialog;
 pub use mq_query_dialog::MqQueryDialog;
+pub use outline::OutlineWidget;
 pub use search_dialog::{SearchDialog, SearchField, SearchMode};
+pub use stats_dialog::StatsDialog;
 pub use status_bar::StatusBar;
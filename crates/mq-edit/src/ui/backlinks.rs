@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use markdown_lsp::link_index::LinkReference;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Widget},
+};
+
+use crate::theme;
+
+/// Panel listing the links to the current document from other files
+pub struct BacklinksWidget<'a> {
+    backlinks: &'a [LinkReference],
+    /// Workspace root, stripped from the displayed paths
+    root: &'a Path,
+    selected: usize,
+    /// Whether the panel has keyboard focus
+    focused: bool,
+}
+
+impl<'a> BacklinksWidget<'a> {
+    pub fn new(backlinks: &'a [LinkReference], root: &'a Path) -> Self {
+        Self {
+            backlinks,
+            root,
+            selected: 0,
+            focused: false,
+        }
+    }
+
+    pub fn with_selected(mut self, selected: usize) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn with_focus(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+}
+
+impl Widget for BacklinksWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let border_color = if self.focused {
+            theme::ACCENT
+        } else {
+            theme::BORDER
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Backlinks ({})", self.backlinks.len()))
+            .border_style(Style::default().fg(border_color));
+
+        if self.backlinks.is_empty() {
+            let empty = ListItem::new(Line::from(Span::styled(
+                "No links to this file",
+                Style::default().fg(theme::FG_DIM),
+            )));
+            List::new(vec![empty])
+                .block(block)
+                .style(Style::default().fg(theme::FG).bg(theme::BG))
+                .render(area, buf);
+            return;
+        }
+
+        // Each entry takes two rows: the location and the line's text
+        let height = area.height.saturating_sub(2) as usize / 2;
+        let offset = (self.selected + 1).saturating_sub(height.max(1));
+
+        let items: Vec<ListItem> = self
+            .backlinks
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(height.max(1))
+            .map(|(idx, link)| {
+                let path = link.source.strip_prefix(self.root).unwrap_or(&link.source);
+                let location = format!("{}:{}", path.display(), link.range.start.line + 1);
+
+                let (location_style, context_style) = if self.focused && idx == self.selected {
+                    let selected = Style::default().fg(theme::BG).bg(theme::ACCENT);
+                    (selected.add_modifier(Modifier::BOLD), selected)
+                } else {
+                    (
+                        Style::default().fg(theme::FUNC),
+                        Style::default().fg(theme::FG_MUTED),
+                    )
+                };
+
+                ListItem::new(vec![
+                    Line::from(Span::styled(location, location_style)),
+                    Line::from(Span::styled(format!("  {}", link.context), context_style)),
+                ])
+            })
+            .collect();
+
+        List::new(items)
+            .block(block)
+            .style(Style::default().fg(theme::FG).bg(theme::BG))
+            .render(area, buf);
+    }
+}
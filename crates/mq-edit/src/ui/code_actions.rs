@@ -0,0 +1,84 @@
+use lsp_types::CodeActionOrCommand;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, Widget},
+};
+
+use crate::theme;
+
+/// Popup listing the quick fixes at the cursor
+pub struct CodeActionPopup<'a> {
+    actions: &'a [CodeActionOrCommand],
+    selected: usize,
+}
+
+impl<'a> CodeActionPopup<'a> {
+    pub fn new(actions: &'a [CodeActionOrCommand], selected: usize) -> Self {
+        Self { actions, selected }
+    }
+}
+
+/// Title shown for a code action
+pub fn action_title(action: &CodeActionOrCommand) -> &str {
+    match action {
+        CodeActionOrCommand::CodeAction(action) => &action.title,
+        CodeActionOrCommand::Command(command) => &command.title,
+    }
+}
+
+impl Widget for CodeActionPopup<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let visible_height = area.height.saturating_sub(2) as usize;
+        if visible_height == 0 || self.actions.is_empty() {
+            return;
+        }
+
+        Clear.render(area, buf);
+
+        let scroll_offset = (self.selected + 1).saturating_sub(visible_height);
+        let width = area.width.saturating_sub(2) as usize;
+        let items: Vec<ListItem> = self
+            .actions
+            .iter()
+            .enumerate()
+            .skip(scroll_offset)
+            .take(visible_height)
+            .map(|(idx, action)| {
+                let (marker, style) = if idx == self.selected {
+                    (
+                        ">",
+                        Style::default()
+                            .fg(theme::BG)
+                            .bg(theme::ACCENT)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    (" ", Style::default().fg(theme::FG).bg(theme::BG_DARK))
+                };
+                let title: String = action_title(action)
+                    .chars()
+                    .take(width.saturating_sub(2))
+                    .collect();
+                ListItem::new(Line::styled(
+                    format!("{} {:<w$}", marker, title, w = width.saturating_sub(2)),
+                    style,
+                ))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(" Quick Fixes ")
+                    .borders(Borders::ALL)
+                    .style(Style::default().bg(theme::BG_DARK).fg(theme::FG))
+                    .border_style(Style::default().fg(theme::ACCENT)),
+            )
+            .style(Style::default().bg(theme::BG_DARK).fg(theme::FG));
+
+        list.render(area, buf);
+    }
+}
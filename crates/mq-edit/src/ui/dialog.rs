@@ -165,6 +165,7 @@ pub struct GotoLineDialog<'a> {
     line_number: &'a str,
     current_line: usize,
     total_lines: usize,
+    history_search: Option<&'a str>,
 }
 
 impl<'a> GotoLineDialog<'a> {
@@ -173,9 +174,16 @@ impl<'a> GotoLineDialog<'a> {
             line_number,
             current_line,
             total_lines,
+            history_search: None,
         }
     }
 
+    /// Show a reverse history search prompt in place of the hint
+    pub fn with_history_search(mut self, prompt: Option<&'a str>) -> Self {
+        self.history_search = prompt;
+        self
+    }
+
     /// Calculate the dialog area (centered in the given area)
     fn dialog_area(area: Rect) -> Rect {
         let dialog_width = 50.min(area.width.saturating_sub(4));
@@ -239,9 +247,11 @@ impl<'a> Widget for GotoLineDialog<'a> {
         input.render(chunks[3], buf);
 
         // Hint
-        let hint = Paragraph::new("Press Enter to jump, Esc to cancel")
-            .alignment(Alignment::Center)
-            .style(Style::default().fg(theme::FG_DIM));
-        hint.render(chunks[4], buf);
+        let hint = match self.history_search {
+            Some(prompt) => Paragraph::new(prompt).style(Style::default().fg(theme::ACCENT_HI)),
+            None => Paragraph::new("Press Enter to jump, Esc to cancel")
+                .style(Style::default().fg(theme::FG_DIM)),
+        };
+        hint.alignment(Alignment::Center).render(chunks[4], buf);
     }
 }
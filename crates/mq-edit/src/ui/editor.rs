@@ -1,38 +1,189 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::Style,
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Widget},
 };
+use unicode_width::UnicodeWidthChar;
 
-use crate::document::{DocumentBuffer, LineAnalyzer, LineType, TableAlignment};
-use crate::renderer::{CodeRenderer, ImageManager, MarkdownRenderer, Renderer};
+use crate::document::{
+    Cursor, DocumentBuffer, Folds, LineAnalyzer, LineType, SearchMatch, Table, table,
+};
+use crate::renderer::{
+    CodeRenderer, ImageManager, ImagePlacement, MappedSpan, MarkdownRenderer, Renderer,
+    TableLayout, TableOptions, fence_language, markdown::CodeBlock, source_map,
+};
 use crate::theme;
 use markdown_lsp::DiagnosticsManager;
 
-/// Table rendering context
-struct TableContext {
-    /// Column widths (calculated from all rows)
-    column_widths: Vec<usize>,
-    /// Column alignments from separator row
-    alignments: Vec<TableAlignment>,
-    /// Start line of current table
-    start_line: usize,
-    /// Line indices that belong to current table
-    table_lines: Vec<usize>,
+/// Whether line `start` is inside a code block opened above it (or is its closing fence)
+fn starts_in_code_block(blocks: &[CodeBlock], start: usize) -> bool {
+    blocks.iter().any(|block| block.contains(start))
+}
+
+/// Display column at which each row of `text` starts when wrapped to `width`
+///
+/// Rows break after the last space that fits, so the spaces between words
+/// stay at the end of a row; a word wider than a row is broken where the row
+/// is full.
+pub fn wrap_columns(text: &str, width: usize) -> Vec<usize> {
+    let width = width.max(1);
+    let mut starts = vec![0];
+    let mut column = 0;
+    // Column after the last space on the current row
+    let mut after_space = None;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if c == ' ' {
+            column += char_width;
+            after_space = Some(column);
+            continue;
+        }
+        let row_start = *starts.last().unwrap_or(&0);
+        if column + char_width - row_start > width {
+            let start = after_space
+                .filter(|&after_space| after_space > row_start)
+                .unwrap_or(column);
+            starts.push(start);
+            after_space = None;
+        }
+        column += char_width;
+    }
+    starts
+}
+
+/// Split spans into the rows starting at display columns `starts`
+fn split_rows<'b>(spans: Vec<Span<'b>>, starts: &[usize]) -> Vec<Vec<Span<'b>>> {
+    let mut rows: Vec<Vec<Span<'b>>> = vec![Vec::new()];
+    let mut next_starts = starts.iter().skip(1).peekable();
+    let mut column = 0;
+    for span in spans {
+        let mut text = String::new();
+        for c in span.content.chars() {
+            let char_width = c.width().unwrap_or(0);
+            // Combining chars stay with the char before them
+            if char_width > 0 && next_starts.next_if(|&&start| column >= start).is_some() {
+                if !text.is_empty() {
+                    let text = std::mem::take(&mut text);
+                    rows.last_mut()
+                        .unwrap()
+                        .push(Span::styled(text, span.style));
+                }
+                rows.push(Vec::new());
+            }
+            text.push(c);
+            column += char_width;
+        }
+        if !text.is_empty() {
+            rows.last_mut()
+                .unwrap()
+                .push(Span::styled(text, span.style));
+        }
+    }
+    rows
+}
+
+/// Text of rendered spans
+fn mapped_text(spans: &[MappedSpan]) -> String {
+    spans
+        .iter()
+        .map(|mapped| mapped.span.content.as_ref())
+        .collect()
+}
+
+/// Text the cursor line is drawn with: partly rendered with partial WYSIWYG,
+/// raw source otherwise
+pub fn cursor_line_text(
+    buffer: &DocumentBuffer,
+    renderer: &MarkdownRenderer,
+    partial_wysiwyg: bool,
+) -> String {
+    let cursor = buffer.cursor();
+    if partial_wysiwyg
+        && matches!(
+            buffer.document_type(),
+            crate::document::DocumentType::Markdown { .. }
+        )
+        && let Some(line) = renderer.render_partial_line(buffer, cursor.line, cursor.column)
+    {
+        return mapped_text(&line.spans);
+    }
+    buffer.line(cursor.line).unwrap_or("").to_string()
+}
+
+/// Rows taken by a line drawn as the text `text` returns, wrapped to `wrap` columns
+fn wrapped_rows(wrap: Option<usize>, text: impl FnOnce() -> String) -> usize {
+    wrap.map_or(1, |width| wrap_columns(&text(), width).len())
+}
+
+/// Lay out the tables with lines in `start..end`
+///
+/// Tables are parsed in full, so column widths don't change as a table
+/// scrolls partly out of view. The cell under the cursor is highlighted
+/// unless `cursor` is None.
+fn table_layouts(
+    buffer: &DocumentBuffer,
+    renderer: &MarkdownRenderer,
+    options: TableOptions,
+    start: usize,
+    end: usize,
+    cursor: Option<&Cursor>,
+) -> Vec<TableLayout> {
+    let lines = buffer.lines();
+    let mut layouts = Vec::new();
+    let mut line_idx = start;
+    while line_idx < end {
+        let Some((table_start, table_end)) = Table::find(lines, line_idx) else {
+            line_idx += 1;
+            continue;
+        };
+        let table_lines = &lines[table_start..table_end];
+        if let Some(parsed) = Table::parse(table_lines) {
+            let cursor_cell = cursor
+                .filter(|cursor| (table_start..table_end).contains(&cursor.line))
+                .map(|cursor| table::cell_at(&lines[cursor.line], cursor.column));
+            layouts.push(TableLayout::new(
+                renderer,
+                &parsed,
+                table_lines,
+                table_start,
+                options,
+                cursor_cell,
+            ));
+        }
+        line_idx = table_end;
+    }
+    layouts
 }
 
 /// Editor widget for rendering the document
 pub struct EditorWidget<'a> {
     buffer: &'a DocumentBuffer,
     scroll_offset: usize,
-    markdown_renderer: MarkdownRenderer,
+    markdown_renderer: Cow<'a, MarkdownRenderer>,
     code_renderer: Option<&'a CodeRenderer>,
     image_manager: Option<&'a ImageManager>,
     diagnostics: Option<&'a DiagnosticsManager>,
     show_line_numbers: bool,
     show_current_line_highlight: bool,
+    partial_wysiwyg: bool,
+    search_matches: &'a [SearchMatch],
+    current_match: Option<usize>,
+    /// Selected text from its start up to its end
+    selection: Option<(Cursor, Cursor)>,
+    table_options: Option<TableOptions>,
+    folds: Option<&'a Folds>,
+    /// Render the cursor line like any other, for read-only preview
+    preview: bool,
+    /// Lines of the paragraph being written; all others are dimmed
+    focus: Option<Range<usize>>,
+    /// Wrap lines wider than the pane onto the rows below
+    wrap: bool,
 }
 
 impl<'a> EditorWidget<'a> {
@@ -40,12 +191,21 @@ impl<'a> EditorWidget<'a> {
         Self {
             buffer,
             scroll_offset: 0,
-            markdown_renderer: MarkdownRenderer::new(),
+            markdown_renderer: Cow::Owned(MarkdownRenderer::new()),
             code_renderer: None,
             image_manager: None,
             diagnostics: None,
             show_line_numbers: true,
             show_current_line_highlight: true,
+            partial_wysiwyg: false,
+            search_matches: &[],
+            current_match: None,
+            selection: None,
+            table_options: None,
+            folds: None,
+            preview: false,
+            focus: None,
+            wrap: false,
         }
     }
 
@@ -59,11 +219,53 @@ impl<'a> EditorWidget<'a> {
         self
     }
 
+    /// Render the cursor line, revealing only the inline element under the cursor
+    pub fn with_partial_wysiwyg(mut self, enabled: bool) -> Self {
+        self.partial_wysiwyg = enabled;
+        self
+    }
+
+    /// Render every line, including the cursor line, without raw source
+    pub fn with_preview(mut self, enabled: bool) -> Self {
+        self.preview = enabled;
+        self
+    }
+
+    /// Dim every line outside `lines`, for focus mode
+    pub fn with_focus(mut self, lines: Range<usize>) -> Self {
+        self.focus = Some(lines);
+        self
+    }
+
+    /// Continue lines wider than the pane on the rows below, for focus mode
+    pub fn with_wrap(mut self, enabled: bool) -> Self {
+        self.wrap = enabled;
+        self
+    }
+
     pub fn with_scroll(mut self, offset: usize) -> Self {
         self.scroll_offset = offset;
         self
     }
 
+    /// Render tables with these options instead of wrapping them to the pane width
+    pub fn with_table_options(mut self, options: TableOptions) -> Self {
+        self.table_options = Some(options);
+        self
+    }
+
+    /// Hide folded lines and mark foldable ones in the gutter
+    pub fn with_folds(mut self, folds: &'a Folds) -> Self {
+        self.folds = Some(folds);
+        self
+    }
+
+    /// Render Markdown with this renderer, keeping its caches between frames
+    pub fn with_markdown_renderer(mut self, renderer: &'a MarkdownRenderer) -> Self {
+        self.markdown_renderer = Cow::Borrowed(renderer);
+        self
+    }
+
     pub fn with_code_renderer(mut self, renderer: &'a CodeRenderer) -> Self {
         self.code_renderer = Some(renderer);
         self
@@ -79,13 +281,84 @@ impl<'a> EditorWidget<'a> {
         self
     }
 
+    /// Highlight search matches (sorted by position), emphasizing `current`
+    pub fn with_search_matches(
+        mut self,
+        matches: &'a [SearchMatch],
+        current: Option<usize>,
+    ) -> Self {
+        self.search_matches = matches;
+        self.current_match = current;
+        self
+    }
+
+    /// Highlight the selection from `start` up to `end`
+    pub fn with_selection(mut self, start: Cursor, end: Cursor) -> Self {
+        self.selection = Some((start, end));
+        self
+    }
+
     /// Calculate visible line range based on viewport
     fn visible_range(&self, height: usize) -> (usize, usize) {
         let start = self.scroll_offset;
-        let end = (start + height).min(self.buffer.line_count());
+        let end = match self.folds {
+            Some(folds) => folds.visible_end(start, height, self.buffer.line_count()),
+            None => (start + height).min(self.buffer.line_count()),
+        };
         (start, end)
     }
 
+    /// Lines in `start..end` that are not folded away
+    fn visible_lines(&self, start: usize, end: usize) -> Vec<usize> {
+        match self.folds {
+            Some(folds) => folds.visible_lines(start, end),
+            None => (start..end).collect(),
+        }
+    }
+
+    /// Dim the rows of buffer lines outside the focused paragraph
+    ///
+    /// `line_rows` holds the first row of each rendered buffer line. Image
+    /// rows keep their colours, as the half blocks are drawn with them.
+    fn dim_unfocused(
+        &self,
+        lines: &mut [Line<'_>],
+        line_rows: &[(usize, usize)],
+        image_rows: &[Range<usize>],
+    ) {
+        let Some(focus) = &self.focus else {
+            return;
+        };
+        let ends = line_rows
+            .iter()
+            .skip(1)
+            .map(|&(row, _)| row)
+            .chain([lines.len()]);
+        for (&(start, line_idx), end) in line_rows.iter().zip(ends) {
+            if focus.contains(&line_idx) {
+                continue;
+            }
+            for row in (start..end).filter(|row| !image_rows.iter().any(|r| r.contains(row))) {
+                let line = &mut lines[row];
+                line.style = line.style.fg(theme::FG_DIM);
+                for span in &mut line.spans {
+                    span.style = span.style.fg(theme::FG_DIM);
+                }
+            }
+        }
+    }
+
+    /// Summary shown after the header line of a folded range
+    fn fold_summary(&self, line_idx: usize) -> Option<Span<'static>> {
+        let hidden = self.folds?.hidden_count(line_idx);
+        (hidden > 0).then(|| {
+            Span::styled(
+                format!(" ⋯ {} lines", hidden),
+                Style::default().fg(theme::FG_DIM),
+            )
+        })
+    }
+
     /// Add diagnostic marker to a line if there are diagnostics
     fn add_diagnostic_marker<'b>(
         &self,
@@ -115,6 +388,166 @@ impl<'a> EditorWidget<'a> {
         spans
     }
 
+    /// Character ranges of search matches on a source line, with whether each is the current match
+    ///
+    /// The selection is highlighted like a search match that is not current.
+    fn match_ranges(&self, line_idx: usize, content: &str) -> Vec<(usize, usize, bool)> {
+        let line_len = content.chars().count();
+        let first = self
+            .search_matches
+            .partition_point(|m| m.end_line < line_idx);
+        let selection = self.selection.map(|(start, end)| SearchMatch {
+            line: start.line,
+            column: start.column,
+            end_line: end.line,
+            end_column: end.column,
+        });
+        let bounds = |m: &SearchMatch| {
+            let start = if m.line == line_idx { m.column } else { 0 };
+            let end = if m.end_line == line_idx {
+                m.end_column
+            } else {
+                line_len
+            };
+            (start < end).then_some((start, end))
+        };
+
+        let mut ranges: Vec<(usize, usize, bool)> = self.search_matches[first..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, m)| m.line <= line_idx)
+            .filter_map(|(i, m)| {
+                let (start, end) = bounds(m)?;
+                Some((start, end, self.current_match == Some(first + i)))
+            })
+            .collect();
+        if let Some((start, end)) = selection
+            .filter(|m| (m.line..=m.end_line).contains(&line_idx))
+            .and_then(|m| bounds(&m))
+        {
+            ranges.push((start, end, false));
+            ranges.sort_by_key(|&(start, _, _)| start);
+        }
+        ranges
+    }
+
+    /// Apply search match highlighting to spans showing a source line as written
+    fn highlight_search_matches<'b>(
+        &self,
+        spans: Vec<Span<'b>>,
+        line_idx: usize,
+        content: &str,
+    ) -> Vec<Span<'b>> {
+        let ranges = self.match_ranges(line_idx, content);
+        Self::apply_match_ranges(spans, &ranges)
+    }
+
+    /// Apply search match highlighting to the rendered spans of a line
+    ///
+    /// Each match is placed on the spans showing its source chars; matches
+    /// whose text is not visible (e.g. inside hidden markup) are skipped.
+    fn highlight_mapped_matches(
+        &self,
+        spans: Vec<MappedSpan>,
+        line_idx: usize,
+        content: &str,
+    ) -> Vec<Span<'static>> {
+        let ranges: Vec<(usize, usize, bool)> = self
+            .match_ranges(line_idx, content)
+            .into_iter()
+            .filter_map(|(start, end, is_current)| {
+                let range = source_map::rendered_range(&spans, start..end)?;
+                Some((range.start, range.end, is_current))
+            })
+            .collect();
+        Self::apply_match_ranges(source_map::spans(spans), &ranges)
+    }
+
+    /// Highlight the char `ranges` of rendered spans, each flagged when it is
+    /// the current match
+    fn apply_match_ranges<'b>(
+        spans: Vec<Span<'b>>,
+        ranges: &[(usize, usize, bool)],
+    ) -> Vec<Span<'b>> {
+        if ranges.is_empty() {
+            return spans;
+        }
+
+        let match_style = Style::default().fg(theme::FG).bg(theme::BG_SEL);
+        let current_style = Style::default()
+            .fg(theme::BG)
+            .bg(theme::WARNING)
+            .add_modifier(Modifier::BOLD);
+
+        let mut result = Vec::with_capacity(spans.len() + ranges.len() * 2);
+        let mut offset = 0;
+        for span in spans {
+            let chars: Vec<char> = span.content.chars().collect();
+            let span_end = offset + chars.len();
+            let mut pos = offset;
+
+            while pos < span_end {
+                let highlight = ranges
+                    .iter()
+                    .find(|(start, end, _)| *start <= pos && pos < *end);
+                let next = match highlight {
+                    Some((_, end, _)) => (*end).min(span_end),
+                    None => ranges
+                        .iter()
+                        .map(|(start, _, _)| *start)
+                        .filter(|start| *start > pos)
+                        .min()
+                        .unwrap_or(span_end)
+                        .min(span_end),
+                };
+                let text: String = chars[pos - offset..next - offset].iter().collect();
+                let style = match highlight {
+                    Some((_, _, true)) => span.style.patch(current_style),
+                    Some((_, _, false)) => span.style.patch(match_style),
+                    None => span.style,
+                };
+                result.push(Span::styled(text, style));
+                pos = next;
+            }
+            offset = span_end;
+        }
+        result
+    }
+
+    /// Draw search match markers in a one-column scrollbar gutter
+    fn render_match_gutter(&self, area: Rect, buf: &mut Buffer) {
+        let line_count = self.buffer.line_count().max(1);
+        let height = area.height as usize;
+
+        for y in area.top()..area.bottom() {
+            buf[(area.x, y)]
+                .set_symbol(" ")
+                .set_style(Style::default().bg(theme::BG_DARK));
+        }
+
+        // Viewport indicator
+        let thumb_start = self.scroll_offset * height / line_count;
+        let thumb_end = ((self.scroll_offset + height) * height)
+            .div_ceil(line_count)
+            .min(height);
+        for row in thumb_start..thumb_end.max(thumb_start + 1).min(height) {
+            buf[(area.x, area.y + row as u16)].set_style(Style::default().bg(theme::BG_SEL));
+        }
+
+        let mut draw_marker = |line: usize, color| {
+            let row = (line * height / line_count).min(height.saturating_sub(1));
+            buf[(area.x, area.y + row as u16)]
+                .set_symbol("━")
+                .set_fg(color);
+        };
+        for m in self.search_matches {
+            draw_marker(m.line, theme::ACCENT);
+        }
+        if let Some(m) = self.current_match.and_then(|i| self.search_matches.get(i)) {
+            draw_marker(m.line, theme::WARNING);
+        }
+    }
+
     /// Create a line number span
     fn make_line_number_span(
         &self,
@@ -123,7 +556,13 @@ impl<'a> EditorWidget<'a> {
         is_current: bool,
     ) -> Span<'static> {
         let line_num = line_idx + 1; // 1-indexed display
-        let formatted = format!("{:>width$} │ ", line_num, width = width);
+        // Fold marker between the number and the separator
+        let marker = match self.folds {
+            Some(folds) if folds.is_folded(line_idx) => '▸',
+            Some(folds) if folds.is_foldable(line_idx) => '▾',
+            _ => ' ',
+        };
+        let formatted = format!("{:>width$}{}│ ", line_num, marker, width = width);
         let style = if is_current {
             Style::default().fg(theme::ACCENT).bg(theme::BG_PANEL)
         } else {
@@ -144,75 +583,305 @@ impl<'a> EditorWidget<'a> {
         .max(3) // Minimum width of 3
     }
 
-    /// Pre-scan lines to identify tables and calculate column widths
-    fn scan_tables(&self, start: usize, end: usize) -> Vec<TableContext> {
-        let mut tables = Vec::new();
-        let mut current_table: Option<TableContext> = None;
-
-        for line_idx in start..end {
-            let content = self.buffer.line(line_idx).unwrap_or("");
+    /// Highlight the body lines of code blocks visible in `start..end`
+    ///
+    /// Each block is highlighted from its first line so multi-line constructs
+    /// that begin above the viewport are colored correctly; the code renderer
+    /// caches the lines of each block, so unchanged ones aren't highlighted again.
+    fn highlight_code_blocks(
+        &self,
+        blocks: &[CodeBlock],
+        start: usize,
+        end: usize,
+    ) -> HashMap<usize, Vec<Span<'static>>> {
+        let mut highlighted = HashMap::new();
+        let Some(code_renderer) = self.code_renderer else {
+            return highlighted;
+        };
 
-            if LineAnalyzer::is_table_row(content) {
-                if current_table.is_none() {
-                    // Start new potential table
-                    let ctx = TableContext {
-                        column_widths: Vec::new(),
-                        alignments: Vec::new(),
-                        start_line: line_idx,
-                        table_lines: Vec::new(),
-                    };
-                    current_table = Some(ctx);
-                }
+        for block in blocks {
+            let body_start = block.fence_line + 1;
+            let body_end = block.close_line.unwrap_or(end).min(end);
+            if body_end <= start.max(body_start) {
+                continue;
+            }
+            let Some(language) = block.language.as_deref().and_then(fence_language) else {
+                continue;
+            };
+            let lines = self.buffer.lines();
+            let body = &lines[body_start..block.close_line.unwrap_or(lines.len())];
+            if let Some(lines) = code_renderer.highlight_code_block(
+                block.fence_line,
+                &language,
+                body,
+                body_end - body_start,
+            ) {
+                highlighted.extend(
+                    (body_start..body_end)
+                        .zip(lines)
+                        .filter(|(line_idx, _)| *line_idx >= start),
+                );
+            }
+        }
+        code_renderer
+            .retain_code_blocks(|fence_line| blocks.iter().any(|b| b.fence_line == fence_line));
+        highlighted
+    }
 
-                if let Some(ref mut ctx) = current_table {
-                    ctx.table_lines.push(line_idx);
+    /// Push the rows of a buffer line
+    ///
+    /// A line whose `content` text is wider than `wrap` columns continues on
+    /// the rows below. `spans` holds the gutter, if any, then the content;
+    /// whatever follows it (fold summary, diagnostic marker) stays on the last row.
+    fn push_line<'b>(
+        &self,
+        lines: &mut Vec<Line<'b>>,
+        mut spans: Vec<Span<'b>>,
+        content: &str,
+        style: Style,
+        wrap: Option<usize>,
+    ) {
+        let starts = wrap.map_or_else(|| vec![0], |width| wrap_columns(content, width));
+        if starts.len() == 1 {
+            lines.push(Line::from(spans).style(style));
+            return;
+        }
 
-                    if LineAnalyzer::is_table_separator(content) {
-                        ctx.alignments = LineAnalyzer::parse_table_alignment(content);
-                    } else {
-                        // Update column widths
-                        let cells = LineAnalyzer::parse_table_cells(content);
-                        while ctx.column_widths.len() < cells.len() {
-                            ctx.column_widths.push(0);
-                        }
-                        for (i, cell) in cells.iter().enumerate() {
-                            ctx.column_widths[i] = ctx.column_widths[i].max(cell.chars().count());
-                        }
-                    }
-                }
+        let gutter_width = if self.show_line_numbers {
+            self.line_number_width() + 3
+        } else {
+            0
+        };
+        let content_spans = spans.split_off(usize::from(self.show_line_numbers).min(spans.len()));
+        for (row, mut row_spans) in split_rows(content_spans, &starts).into_iter().enumerate() {
+            let mut line_spans = if row == 0 {
+                std::mem::take(&mut spans)
             } else {
-                // End of table
-                if let Some(ctx) = current_table.take() {
-                    // Validate: table must have header + separator (at least 2 lines)
-                    if ctx.table_lines.len() >= 2 && !ctx.alignments.is_empty() {
-                        tables.push(ctx);
-                    }
-                }
-            }
+                vec![Self::continuation_gutter(gutter_width)]
+            };
+            line_spans.append(&mut row_spans);
+            lines.push(Line::from(line_spans).style(style));
         }
+    }
 
-        // Don't forget table at end of range
-        if let Some(ctx) = current_table
-            && ctx.table_lines.len() >= 2
-            && !ctx.alignments.is_empty()
-        {
-            tables.push(ctx);
+    /// Gutter for screen rows that continue the line above
+    fn continuation_gutter(gutter_width: usize) -> Span<'static> {
+        if gutter_width > 0 {
+            Span::styled(
+                format!("{:>width$} │ ", "", width = gutter_width - 3),
+                Style::default().fg(theme::BORDER),
+            )
+        } else {
+            Span::raw("")
         }
+    }
+
+    /// Render the rows reserved below an image line, starting at screen row `top`
+    ///
+    /// Half-block art is drawn into the rows directly; for graphics protocols the
+    /// rows stay blank and the image is recorded as a placement to draw after the frame.
+    fn render_image_rows(
+        &self,
+        path: &str,
+        area: Rect,
+        gutter_width: usize,
+        top: usize,
+    ) -> Vec<Line<'static>> {
+        let Some(image_manager) = self.image_manager else {
+            return Vec::new();
+        };
+        let rows = image_manager.display_rows(path);
+        if rows == 0 {
+            return Vec::new();
+        }
+
+        let gutter = Self::continuation_gutter(gutter_width);
+        let mut lines: Vec<Line<'static>> = (0..rows).map(|_| Line::from(gutter.clone())).collect();
+
+        // Shrink images cut off by the bottom of the viewport
+        let max_cols = area.width.saturating_sub(gutter_width as u16);
+        let max_rows = rows.min(area.height.saturating_sub(top as u16));
+        let loading = Span::styled("  Loading image…", Style::default().fg(theme::FG_DIM));
+        let Some((cols, image_rows)) = image_manager.fit(path, max_cols, max_rows) else {
+            lines[0].spans.push(loading);
+            return lines;
+        };
 
-        tables
+        if image_manager.protocol().uses_escape_sequences() {
+            if image_manager.is_ready(path, cols, image_rows) {
+                image_manager.place(ImagePlacement {
+                    path: path.to_string(),
+                    x: area.x + gutter_width as u16,
+                    y: area.y + top as u16,
+                    cols,
+                    rows: image_rows,
+                });
+            } else {
+                lines[0].spans.push(loading);
+            }
+        } else if let Some(art) = image_manager.halfblock_lines(path, cols, image_rows) {
+            for (line, art) in lines.iter_mut().zip(art) {
+                line.spans.extend(art.spans);
+            }
+        } else {
+            lines[0].spans.push(loading);
+        }
+        lines
     }
+}
+
+/// Editor options that decide how many screen rows each line takes
+#[derive(Debug, Clone, Copy)]
+pub struct LineLayout {
+    pub table_options: TableOptions,
+    /// Render the cursor line like any other, for read-only preview
+    pub preview: bool,
+    /// Render the cursor line, revealing only the inline element under the cursor
+    pub partial_wysiwyg: bool,
+    /// Wrap lines wider than this many columns (None to let them run off the pane)
+    pub wrap: Option<usize>,
+}
 
-    /// Get table context for a specific line
-    fn get_table_context(line_idx: usize, tables: &[TableContext]) -> Option<&TableContext> {
-        tables.iter().find(|t| t.table_lines.contains(&line_idx))
+/// Screen rows taken by each visible buffer line in `lines`, including the
+/// rows reserved below images, the extra rows of rendered tables and the rows
+/// wrapped lines continue on; folded lines are left out. Unless `preview` is
+/// set, the cursor line shows its source in place of any table or image.
+pub fn line_rows(
+    buffer: &DocumentBuffer,
+    renderer: &MarkdownRenderer,
+    image_manager: &ImageManager,
+    folds: &Folds,
+    lines: Range<usize>,
+    layout: LineLayout,
+) -> Vec<(usize, usize)> {
+    let Range { start, end } = lines;
+    let visible = folds.visible_lines(start, end);
+    let wrap = layout.wrap;
+    if matches!(
+        buffer.document_type(),
+        crate::document::DocumentType::Code { .. }
+    ) {
+        return visible
+            .into_iter()
+            .map(|line_idx| {
+                let content = buffer.line(line_idx).unwrap_or("");
+                (line_idx, wrapped_rows(wrap, || content.to_string()))
+            })
+            .collect();
     }
+
+    // Mirrors the code block tracking in `EditorWidget::render`
+    let cursor = (!layout.preview).then(|| buffer.cursor());
+    let cursor_line = cursor.map(|cursor| cursor.line);
+    let code_blocks = renderer.code_blocks(buffer);
+    let mut in_code_block = starts_in_code_block(&code_blocks, start);
+    let mut next_line = start;
+    let tables = table_layouts(buffer, renderer, layout.table_options, start, end, cursor);
+    let mut rows = |line_idx: usize| {
+        if line_idx != next_line {
+            in_code_block = starts_in_code_block(&code_blocks, line_idx);
+        }
+        next_line = line_idx + 1;
+
+        let content = buffer.line(line_idx).unwrap_or("");
+        let is_current = Some(line_idx) == cursor_line;
+        if content.trim().starts_with("```") {
+            in_code_block = !in_code_block;
+            // Rendered fences are never wrapped
+            return if is_current {
+                wrapped_rows(wrap, || content.to_string())
+            } else {
+                1
+            };
+        }
+        if is_current {
+            return wrapped_rows(wrap, || {
+                cursor_line_text(buffer, renderer, layout.partial_wysiwyg)
+            });
+        }
+        if in_code_block {
+            return wrapped_rows(wrap, || content.to_string());
+        }
+        if let Some(layout) = tables.iter().find(|layout| layout.contains(line_idx)) {
+            return layout.height(line_idx);
+        }
+        match LineAnalyzer::analyze_line(content) {
+            // The heading's padding never makes it wider than the pane
+            LineType::Heading(level) => wrapped_rows(wrap, || {
+                mapped_text(&renderer.render_heading_line_with_width(buffer, line_idx, level, None))
+            }),
+            LineType::Image(alt_text, path) => {
+                let text = || {
+                    let dimensions = image_manager.get_dimensions(&path).ok();
+                    mapped_text(
+                        &renderer.render_image_with_info(content, &alt_text, &path, dimensions),
+                    )
+                };
+                wrapped_rows(wrap, text) + image_manager.display_rows(&path) as usize
+            }
+            _ => wrapped_rows(wrap, || {
+                mapped_text(&renderer.render_mapped_line(buffer, line_idx))
+            }),
+        }
+    };
+    visible
+        .into_iter()
+        .map(|line_idx| (line_idx, rows(line_idx)))
+        .collect()
+}
+
+/// Count the screen rows taken by buffer lines `lines` when its start is the
+/// first visible line, as laid out by `line_rows`
+pub fn screen_rows(
+    buffer: &DocumentBuffer,
+    renderer: &MarkdownRenderer,
+    image_manager: &ImageManager,
+    folds: &Folds,
+    lines: Range<usize>,
+    layout: LineLayout,
+) -> usize {
+    line_rows(buffer, renderer, image_manager, folds, lines, layout)
+        .into_iter()
+        .map(|(_, rows)| rows)
+        .sum()
 }
 
 impl Widget for EditorWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        // Reserve the rightmost column for match markers while searching
+        let area = if !self.search_matches.is_empty() && area.width > 1 {
+            let gutter = Rect {
+                x: area.right() - 1,
+                width: 1,
+                ..area
+            };
+            self.render_match_gutter(gutter, buf);
+            Rect {
+                width: area.width - 1,
+                ..area
+            }
+        } else {
+            area
+        };
+
         let (start, end) = self.visible_range(area.height as usize);
-        let cursor_line = self.buffer.cursor().line;
+        // No line counts as the cursor line in preview, so all are rendered
+        let cursor = (!self.preview).then(|| self.buffer.cursor());
+        let cursor_line = cursor.map_or(usize::MAX, |cursor| cursor.line);
         let line_num_width = self.line_number_width();
+        let gutter_width = if self.show_line_numbers {
+            line_num_width + 3 // width + " │ "
+        } else {
+            0
+        };
+        let wrap = self
+            .wrap
+            .then(|| (area.width as usize).saturating_sub(gutter_width));
+        let current_line_style = if self.show_current_line_highlight {
+            Style::default().bg(theme::BG_PANEL)
+        } else {
+            Style::default()
+        };
 
         // Determine which renderer to use based on file type
         let use_code_renderer = matches!(
@@ -221,12 +890,17 @@ impl Widget for EditorWidget<'_> {
         ) && self.code_renderer.is_some();
 
         let mut lines = Vec::new();
+        // First row of each buffer line, for dimming in focus mode
+        let mut line_rows = Vec::new();
+        let mut image_rows = Vec::new();
 
         if use_code_renderer {
             // Use CodeRenderer for code files
             let code_renderer = self.code_renderer.unwrap();
+            code_renderer.prepare_highlighting(self.buffer, end);
 
-            for line_idx in start..end {
+            for line_idx in self.visible_lines(start, end) {
+                line_rows.push((lines.len(), line_idx));
                 let is_current = line_idx == cursor_line;
 
                 let mut spans = Vec::new();
@@ -236,25 +910,55 @@ impl Widget for EditorWidget<'_> {
                     spans.push(self.make_line_number_span(line_idx, line_num_width, is_current));
                 }
 
-                spans.extend(code_renderer.render_line(self.buffer, line_idx, is_current));
+                let content = self.buffer.line(line_idx).unwrap_or("");
+                spans.extend(self.highlight_search_matches(
+                    code_renderer.render_line(self.buffer, line_idx, is_current),
+                    line_idx,
+                    content,
+                ));
+                spans.extend(self.fold_summary(line_idx));
 
                 // Add diagnostic marker
                 spans = self.add_diagnostic_marker(spans, line_idx);
 
-                if is_current && self.show_current_line_highlight {
-                    lines.push(Line::from(spans).style(Style::default().bg(theme::BG_PANEL)));
+                let style = if is_current {
+                    current_line_style
                 } else {
-                    lines.push(Line::from(spans));
-                }
+                    Style::default()
+                };
+                self.push_line(&mut lines, spans, content, style, wrap);
             }
         } else {
             // Use MarkdownRenderer for markdown files
-            let mut in_code_block = false;
+            let code_blocks = self.markdown_renderer.code_blocks(self.buffer);
+            let mut in_code_block = starts_in_code_block(&code_blocks, start);
+            let mut highlighted = self.highlight_code_blocks(&code_blocks, start, end);
+            if let Some(image_manager) = self.image_manager {
+                image_manager.clear_placements();
+            }
 
-            // Pre-scan for tables
-            let tables = self.scan_tables(start, end);
+            let table_options = self.table_options.unwrap_or(TableOptions {
+                overflow: Default::default(),
+                width: (area.width as usize).saturating_sub(gutter_width),
+            });
+            let tables = table_layouts(
+                self.buffer,
+                &self.markdown_renderer,
+                table_options,
+                start,
+                end,
+                cursor,
+            );
+
+            let mut next_line = start;
+            for line_idx in self.visible_lines(start, end) {
+                line_rows.push((lines.len(), line_idx));
+                // Folded lines may have opened or closed a code block
+                if line_idx != next_line {
+                    in_code_block = starts_in_code_block(&code_blocks, line_idx);
+                }
+                next_line = line_idx + 1;
 
-            for line_idx in start..end {
                 let content = self.buffer.line(line_idx).unwrap_or("");
                 let is_current = line_idx == cursor_line;
                 let trimmed = content.trim();
@@ -275,98 +979,117 @@ impl Widget for EditorWidget<'_> {
                 if is_code_fence {
                     if !in_code_block {
                         // Opening fence
-                        let code_block_lang = trimmed
-                            .strip_prefix("```")
-                            .map(|s| s.trim())
-                            .filter(|s| !s.is_empty())
-                            .map(|s| s.to_string());
-
-                        let content_spans = if is_current {
-                            self.markdown_renderer.render_source(content)
+                        let summary = self.fold_summary(line_idx);
+                        if is_current {
+                            base_spans.extend(self.highlight_search_matches(
+                                self.markdown_renderer.render_source(content),
+                                line_idx,
+                                content,
+                            ));
                         } else {
-                            self.markdown_renderer
-                                .render_code_fence_start(code_block_lang.as_deref())
-                        };
-                        base_spans.extend(content_spans);
+                            let content_spans = if summary.is_some() {
+                                self.markdown_renderer.render_folded_code_fence(content)
+                            } else {
+                                self.markdown_renderer.render_code_fence_start(content)
+                            };
+                            base_spans.extend(self.highlight_mapped_matches(
+                                content_spans,
+                                line_idx,
+                                content,
+                            ));
+                        }
+                        base_spans.extend(summary);
 
                         // Add diagnostic marker
                         base_spans = self.add_diagnostic_marker(base_spans, line_idx);
 
-                        lines.push(if is_current && self.show_current_line_highlight {
-                            Line::from(base_spans).style(Style::default().bg(theme::BG_PANEL))
+                        // The rendered fence is never wrapped
+                        if is_current {
+                            self.push_line(
+                                &mut lines,
+                                base_spans,
+                                content,
+                                current_line_style,
+                                wrap,
+                            );
                         } else {
-                            Line::from(base_spans)
-                        });
+                            lines.push(Line::from(base_spans));
+                        }
 
                         in_code_block = true;
                     } else {
                         // Closing fence
-                        let content_spans = if is_current {
-                            self.markdown_renderer.render_source(content)
+                        if is_current {
+                            base_spans.extend(self.highlight_search_matches(
+                                self.markdown_renderer.render_source(content),
+                                line_idx,
+                                content,
+                            ));
                         } else {
-                            self.markdown_renderer.render_code_fence_end()
-                        };
-                        base_spans.extend(content_spans);
+                            base_spans.extend(self.markdown_renderer.render_code_fence_end());
+                        }
 
                         // Add diagnostic marker
                         base_spans = self.add_diagnostic_marker(base_spans, line_idx);
 
-                        lines.push(if is_current && self.show_current_line_highlight {
-                            Line::from(base_spans).style(Style::default().bg(theme::BG_PANEL))
+                        if is_current {
+                            self.push_line(
+                                &mut lines,
+                                base_spans,
+                                content,
+                                current_line_style,
+                                wrap,
+                            );
                         } else {
-                            Line::from(base_spans)
-                        });
+                            lines.push(Line::from(base_spans));
+                        }
 
                         in_code_block = false;
                     }
                 } else if in_code_block && !is_current {
                     // Inside code block (not cursor line)
-                    let content_spans = self.markdown_renderer.render_code_content(content);
-                    base_spans.extend(content_spans);
+                    let content_spans = highlighted
+                        .remove(&line_idx)
+                        .unwrap_or_else(|| self.markdown_renderer.render_code_content(content));
+                    base_spans.extend(self.highlight_search_matches(
+                        content_spans,
+                        line_idx,
+                        content,
+                    ));
                     // Add diagnostic marker
                     base_spans = self.add_diagnostic_marker(base_spans, line_idx);
-                    lines.push(Line::from(base_spans));
-                } else if !in_code_block && Self::get_table_context(line_idx, &tables).is_some() {
-                    // Table line handling
-                    let table_ctx = Self::get_table_context(line_idx, &tables).unwrap();
-
-                    let content_spans = if is_current {
+                    self.push_line(&mut lines, base_spans, content, Style::default(), wrap);
+                } else if !in_code_block
+                    && let Some(layout) = tables.iter().find(|layout| layout.contains(line_idx))
+                {
+                    if is_current {
                         // Current line: show raw source for editing
-                        self.markdown_renderer.render_source(content)
-                    } else if LineAnalyzer::is_table_separator(content) {
-                        // Separator row
-                        self.markdown_renderer
-                            .render_table_separator(&table_ctx.column_widths, &table_ctx.alignments)
-                    } else {
-                        // Header or data row
-                        let cells = LineAnalyzer::parse_table_cells(content);
-                        let is_header = line_idx == table_ctx.start_line;
-
-                        if is_header {
-                            self.markdown_renderer.render_table_header(
-                                &cells,
-                                &table_ctx.column_widths,
-                                &table_ctx.alignments,
-                            )
+                        base_spans.extend(self.highlight_search_matches(
+                            self.markdown_renderer.render_source(content),
+                            line_idx,
+                            content,
+                        ));
+                        base_spans = self.add_diagnostic_marker(base_spans, line_idx);
+                        self.push_line(&mut lines, base_spans, content, current_line_style, wrap);
+                        continue;
+                    }
+
+                    // Table line: drawn with box borders, wrapped over several rows
+                    for (row, content_spans) in layout.render(line_idx).into_iter().enumerate() {
+                        let mut spans = if row == 0 {
+                            std::mem::take(&mut base_spans)
                         } else {
-                            self.markdown_renderer.render_table_row(
-                                &cells,
-                                &table_ctx.column_widths,
-                                &table_ctx.alignments,
-                            )
+                            vec![Self::continuation_gutter(gutter_width)]
+                        };
+                        spans.extend(self.highlight_mapped_matches(
+                            content_spans,
+                            line_idx,
+                            content,
+                        ));
+                        if row == 0 {
+                            spans = self.add_diagnostic_marker(spans, line_idx);
                         }
-                    };
-                    base_spans.extend(content_spans);
-
-                    // Add diagnostic marker
-                    base_spans = self.add_diagnostic_marker(base_spans, line_idx);
-
-                    if is_current && self.show_current_line_highlight {
-                        lines.push(
-                            Line::from(base_spans).style(Style::default().bg(theme::BG_PANEL)),
-                        );
-                    } else {
-                        lines.push(Line::from(base_spans));
+                        lines.push(Line::from(spans));
                     }
                 } else {
                     // Regular line or cursor line
@@ -376,6 +1099,7 @@ impl Widget for EditorWidget<'_> {
                         LineAnalyzer::analyze_line(content)
                     };
 
+                    let summary = self.fold_summary(line_idx);
                     let content_spans = if !is_current {
                         // For non-current lines, check if it's a heading to apply full-width background
                         if let LineType::Heading(level) = line_type {
@@ -385,50 +1109,69 @@ impl Widget for EditorWidget<'_> {
                             } else {
                                 0
                             };
-                            let content_width =
-                                area.width.saturating_sub(line_num_offset as u16) as usize;
+                            let summary_width = summary.as_ref().map_or(0, |s| s.width());
+                            let content_width = (area.width as usize)
+                                .saturating_sub(line_num_offset + summary_width);
                             self.markdown_renderer.render_heading_line_with_width(
-                                content,
+                                self.buffer,
+                                line_idx,
                                 level,
                                 Some(content_width),
                             )
-                        } else if let LineType::Image(ref alt_text, ref path) = line_type {
+                        } else if let LineType::Image(ref alt_text, ref path) = line_type
+                            && let Some(img_mgr) = self.image_manager.as_ref()
+                        {
                             // Try to get image dimensions and terminal support info
-                            if let Some(img_mgr) = self.image_manager.as_ref() {
-                                let dimensions = img_mgr.get_dimensions(path).ok();
-                                self.markdown_renderer
-                                    .render_image_with_info(alt_text, path, dimensions)
-                            } else {
-                                self.markdown_renderer.render_line(
-                                    self.buffer,
-                                    line_idx,
-                                    is_current,
-                                )
-                            }
+                            let dimensions = img_mgr.get_dimensions(path).ok();
+                            self.markdown_renderer
+                                .render_image_with_info(content, alt_text, path, dimensions)
                         } else {
                             self.markdown_renderer
-                                .render_line(self.buffer, line_idx, is_current)
+                                .render_mapped_line(self.buffer, line_idx)
                         }
+                    } else if self.partial_wysiwyg
+                        && let Some(partial) = self.markdown_renderer.render_partial_line(
+                            self.buffer,
+                            line_idx,
+                            self.buffer.cursor().column,
+                        )
+                    {
+                        partial.spans
                     } else {
-                        self.markdown_renderer
-                            .render_line(self.buffer, line_idx, is_current)
+                        // Current line: show source for editing
+                        vec![MappedSpan::source(content, Style::default(), 0)]
                     };
-                    base_spans.extend(content_spans);
+                    let text = mapped_text(&content_spans);
+                    base_spans.extend(self.highlight_mapped_matches(
+                        content_spans,
+                        line_idx,
+                        content,
+                    ));
+                    base_spans.extend(summary);
 
                     // Add diagnostic marker
                     base_spans = self.add_diagnostic_marker(base_spans, line_idx);
 
-                    if is_current && self.show_current_line_highlight {
-                        lines.push(
-                            Line::from(base_spans).style(Style::default().bg(theme::BG_PANEL)),
-                        );
+                    let style = if is_current {
+                        current_line_style
                     } else {
-                        lines.push(Line::from(base_spans));
+                        Style::default()
+                    };
+                    self.push_line(&mut lines, base_spans, &text, style, wrap);
+
+                    // Images are shown below their line, except on the cursor line
+                    if !is_current && let LineType::Image(_, ref path) = line_type {
+                        let image_lines =
+                            self.render_image_rows(path, area, gutter_width, lines.len());
+                        image_rows.push(lines.len()..lines.len() + image_lines.len());
+                        lines.extend(image_lines);
                     }
                 }
             }
         }
 
+        self.dim_unfocused(&mut lines, &line_rows, &image_rows);
+
         let paragraph = Paragraph::new(lines)
             .block(Block::default().borders(Borders::NONE))
             .style(Style::default().fg(theme::FG).bg(theme::BG));
@@ -436,3 +1179,207 @@ impl Widget for EditorWidget<'_> {
         paragraph.render(area, buf);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search_match(line: usize, column: usize, end_line: usize, end_column: usize) -> SearchMatch {
+        SearchMatch {
+            line,
+            column,
+            end_line,
+            end_column,
+        }
+    }
+
+    /// Text of the spans styled as a match, with whether each is the current one
+    fn highlighted(spans: &[Span]) -> Vec<(String, bool)> {
+        spans
+            .iter()
+            .filter_map(|span| match span.style.bg {
+                Some(theme::BG_SEL) => Some((span.content.to_string(), false)),
+                Some(theme::WARNING) => Some((span.content.to_string(), true)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_match_ranges_across_lines() {
+        let buffer = DocumentBuffer::from_string("abc\ndefg\nhij").unwrap();
+        let matches = [search_match(0, 1, 2, 2)];
+        let editor = EditorWidget::new(&buffer).with_search_matches(&matches, None);
+        assert_eq!(editor.match_ranges(0, "abc"), vec![(1, 3, false)]);
+        assert_eq!(editor.match_ranges(1, "defg"), vec![(0, 4, false)]);
+        assert_eq!(editor.match_ranges(2, "hij"), vec![(0, 2, false)]);
+    }
+
+    #[test]
+    fn test_match_ranges_current_match() {
+        let buffer = DocumentBuffer::from_string("one two one\nxx one").unwrap();
+        let matches = [
+            search_match(0, 0, 0, 3),
+            search_match(0, 8, 0, 11),
+            search_match(1, 3, 1, 6),
+        ];
+        let editor = EditorWidget::new(&buffer).with_search_matches(&matches, Some(1));
+        assert_eq!(
+            editor.match_ranges(0, "one two one"),
+            vec![(0, 3, false), (8, 11, true)]
+        );
+        assert_eq!(editor.match_ranges(1, "xx one"), vec![(3, 6, false)]);
+
+        let spans =
+            editor.highlight_search_matches(vec![Span::raw("one two one")], 0, "one two one");
+        assert_eq!(
+            highlighted(&spans),
+            vec![("one".to_string(), false), ("one".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn test_highlight_rendered_markdown() {
+        let content = "see **bold** and [a link](https://example.com)";
+        let buffer = DocumentBuffer::from_string(content).unwrap();
+        let matches = [search_match(0, 4, 0, 12), search_match(0, 17, 0, 46)];
+        let editor = EditorWidget::new(&buffer).with_search_matches(&matches, Some(0));
+        let renderer = MarkdownRenderer::new();
+        let spans = renderer.render_mapped_line(&buffer, 0);
+        let rendered: String = spans.iter().map(|s| s.span.content.as_ref()).collect();
+        assert!(!rendered.contains("**"));
+
+        let spans = editor.highlight_mapped_matches(spans, 0, content);
+        assert_eq!(
+            highlighted(&spans),
+            vec![("bold".to_string(), true), ("a link".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn test_highlight_repeated_text_at_its_source() {
+        // Only the second "x" matches (e.g. a regex or scoped search)
+        let content = "x and *x*, then `x`";
+        let buffer = DocumentBuffer::from_string(content).unwrap();
+        let matches = [search_match(0, 7, 0, 8), search_match(0, 17, 0, 18)];
+        let editor = EditorWidget::new(&buffer).with_search_matches(&matches, Some(0));
+        let renderer = MarkdownRenderer::new();
+
+        let spans =
+            editor.highlight_mapped_matches(renderer.render_mapped_line(&buffer, 0), 0, content);
+        let rendered: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(rendered, "x and x, then x");
+        let columns: Vec<usize> = spans
+            .iter()
+            .scan(0, |offset, span| {
+                let start = *offset;
+                *offset += span.content.chars().count();
+                Some((start, span))
+            })
+            .filter(|(_, span)| matches!(span.style.bg, Some(theme::BG_SEL | theme::WARNING)))
+            .map(|(start, _)| start)
+            .collect();
+        assert_eq!(columns, vec![6, 14]);
+
+        // The cursor line, rendered except for the element under the cursor
+        let partial = renderer.render_partial_line(&buffer, 0, 0).unwrap();
+        let spans = editor.highlight_mapped_matches(partial.spans, 0, content);
+        assert_eq!(
+            highlighted(&spans),
+            vec![("x".to_string(), true), ("x".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn test_highlight_table_cell_at_its_source() {
+        let lines = ["| a | b |", "|---|---|", "| b | **b** |"];
+        let buffer = DocumentBuffer::from_string(&lines.join("\n")).unwrap();
+        let matches = [search_match(2, 8, 2, 9)];
+        let editor = EditorWidget::new(&buffer).with_search_matches(&matches, Some(0));
+        let renderer = MarkdownRenderer::new();
+        let table = Table::parse(&lines).unwrap();
+        let options = TableOptions {
+            overflow: Default::default(),
+            width: 80,
+        };
+        let layout = TableLayout::new(&renderer, &table, &lines, 0, options, None);
+
+        let row = layout.render(2).remove(0);
+        let spans = editor.highlight_mapped_matches(row, 2, lines[2]);
+        let before: String = spans
+            .iter()
+            .take_while(|span| span.style.bg != Some(theme::WARNING))
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(before, "│ b │ ");
+        assert_eq!(highlighted(&spans), vec![("b".to_string(), true)]);
+    }
+
+    #[test]
+    fn test_match_gutter_rows() {
+        let buffer = DocumentBuffer::from_string("a\nb\nc\nd").unwrap();
+        let matches = [search_match(1, 0, 1, 1), search_match(3, 0, 3, 1)];
+        let editor = EditorWidget::new(&buffer).with_search_matches(&matches, Some(0));
+        let area = Rect::new(0, 0, 1, 8);
+        let mut buf = Buffer::empty(area);
+        editor.render_match_gutter(area, &mut buf);
+
+        let markers: Vec<(u16, _)> = (0..8)
+            .filter(|&y| buf[(0, y)].symbol() == "━")
+            .map(|y| (y, buf[(0, y)].fg))
+            .collect();
+        assert_eq!(markers, vec![(2, theme::WARNING), (6, theme::ACCENT)]);
+    }
+
+    #[test]
+    fn test_wrap_columns() {
+        assert_eq!(wrap_columns("aaa bbb ccc ddd", 10), vec![0, 8]);
+        // Spaces stay at the end of the row
+        assert_eq!(wrap_columns("aaa bbb ccc ddd", 7), vec![0, 8]);
+        assert_eq!(wrap_columns("abcdefgh", 3), vec![0, 3, 6]);
+        assert_eq!(wrap_columns("漢字漢字", 5), vec![0, 4]);
+        assert_eq!(wrap_columns("", 10), vec![0]);
+    }
+
+    #[test]
+    fn test_wrapped_lines() {
+        let buffer = DocumentBuffer::from_string("aaa bbb ccc ddd\nnext").unwrap();
+        let area = Rect::new(0, 0, 10, 4);
+        let mut buf = Buffer::empty(area);
+        EditorWidget::new(&buffer)
+            .with_line_numbers(false)
+            .with_preview(true)
+            .with_wrap(true)
+            .render(area, &mut buf);
+
+        let rows: Vec<String> = (0..3)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(rows, vec!["aaa bbb", "ccc ddd", "next"]);
+
+        let layout = LineLayout {
+            table_options: TableOptions {
+                overflow: Default::default(),
+                width: 10,
+            },
+            preview: true,
+            partial_wysiwyg: false,
+            wrap: Some(10),
+        };
+        let rows = line_rows(
+            &buffer,
+            &MarkdownRenderer::new(),
+            &ImageManager::new(),
+            &Folds::new(),
+            0..2,
+            layout,
+        );
+        assert_eq!(rows, vec![(0, 2), (1, 1)]);
+    }
+}
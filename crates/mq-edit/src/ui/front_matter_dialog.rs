@@ -0,0 +1,111 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::document::FrontMatterForm;
+use crate::theme;
+
+/// Form dialog for editing the front matter fields of the schema
+pub struct FrontMatterDialog<'a> {
+    form: &'a FrontMatterForm,
+}
+
+impl<'a> FrontMatterDialog<'a> {
+    pub fn new(form: &'a FrontMatterForm) -> Self {
+        Self { form }
+    }
+
+    /// Calculate the dialog area (centered in the given area)
+    fn dialog_area(&self, area: Rect) -> Rect {
+        let dialog_width = 60.min(area.width.saturating_sub(4));
+        let dialog_height =
+            (self.form.fields().len() as u16 + 5).min(area.height.saturating_sub(2));
+
+        let x = (area.width.saturating_sub(dialog_width)) / 2;
+        let y = (area.height.saturating_sub(dialog_height)) / 2;
+
+        Rect::new(x, y, dialog_width, dialog_height)
+    }
+}
+
+impl Widget for FrontMatterDialog<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let dialog_area = self.dialog_area(area);
+
+        // Clear the dialog area first
+        Clear.render(dialog_area, buf);
+
+        let block = Block::default()
+            .title(" Front Matter ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme::ACCENT))
+            .style(Style::default().bg(theme::BG));
+
+        let inner_area = block.inner(dialog_area);
+        block.render(dialog_area, buf);
+
+        let fields = self.form.fields();
+        let chunks = Layout::vertical([
+            Constraint::Length(1),                   // spacing
+            Constraint::Length(fields.len() as u16), // fields
+            Constraint::Length(1),                   // spacing
+            Constraint::Length(1),                   // hint
+        ])
+        .split(inner_area);
+
+        // Field names are padded to line up the values
+        let name_width = fields
+            .iter()
+            .map(|f| f.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<Line> = fields
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                let selected = idx == self.form.selected();
+                let name_style = if selected {
+                    Style::default()
+                        .fg(theme::ACCENT)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme::FG)
+                };
+                let mut spans = vec![
+                    Span::styled(
+                        format!(" {:<width$} ", field.name, width = name_width),
+                        name_style,
+                    ),
+                    Span::styled(
+                        format!("({}) ", field.field_type.name()),
+                        Style::default().fg(theme::FG_DIM),
+                    ),
+                    Span::styled(
+                        field.value.as_str(),
+                        Style::default()
+                            .fg(theme::WARNING)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                ];
+                if selected {
+                    spans.push(Span::styled(
+                        " ",
+                        Style::default().bg(theme::FG).fg(theme::BG),
+                    ));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        Paragraph::new(lines).render(chunks[1], buf);
+
+        let hint = Paragraph::new("Tab: next field  Enter: apply  Esc: cancel")
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(theme::FG_DIM));
+        hint.render(chunks[3], buf);
+    }
+}
@@ -1,6 +1,6 @@
 use ratatui::{
     buffer::Buffer,
-    layout::{Alignment, Constraint, Layout, Rect},
+    layout::{Alignment, Constraint, Layout, Margin, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph, Widget},
@@ -12,11 +12,22 @@ use crate::theme;
 pub struct MqQueryDialog<'a> {
     query: &'a str,
     result: Option<&'a str>,
+    history_search: Option<&'a str>,
 }
 
 impl<'a> MqQueryDialog<'a> {
     pub fn new(query: &'a str, result: Option<&'a str>) -> Self {
-        Self { query, result }
+        Self {
+            query,
+            result,
+            history_search: None,
+        }
+    }
+
+    /// Show a reverse history search prompt in place of the result
+    pub fn with_history_search(mut self, prompt: Option<&'a str>) -> Self {
+        self.history_search = prompt;
+        self
     }
 
     /// Calculate the dialog area (centered in the given area)
@@ -34,7 +45,8 @@ impl<'a> MqQueryDialog<'a> {
 
 impl Widget for MqQueryDialog<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let dialog_area = Self::dialog_area(area, self.result.is_some());
+        let expanded = self.result.is_some() || self.history_search.is_some();
+        let dialog_area = Self::dialog_area(area, expanded);
 
         Clear.render(dialog_area, buf);
 
@@ -48,7 +60,7 @@ impl Widget for MqQueryDialog<'_> {
         let inner_area = block.inner(dialog_area);
         block.render(dialog_area, buf);
 
-        if self.result.is_some() {
+        if expanded {
             let chunks = Layout::vertical([
                 Constraint::Length(1), // top padding
                 Constraint::Length(1), // query input
@@ -104,7 +116,7 @@ impl MqQueryDialog<'_> {
         input.render(chunks[2], buf);
 
         // Inline hints when no result shown
-        if self.result.is_none() {
+        if self.result.is_none() && self.history_search.is_none() {
             let hints = Line::from(vec![
                 Span::styled("Enter", Style::default().fg(theme::FG_DIM)),
                 Span::raw(" "),
@@ -115,7 +127,11 @@ impl MqQueryDialog<'_> {
     }
 
     fn render_result(&self, area: Rect, buf: &mut Buffer) {
-        if let Some(result) = self.result {
+        if let Some(prompt) = self.history_search {
+            Paragraph::new(prompt)
+                .style(Style::default().fg(theme::ACCENT_HI))
+                .render(area.inner(Margin::new(1, 0)), buf);
+        } else if let Some(result) = self.result {
             let chunks = Layout::horizontal([
                 Constraint::Length(1), // left padding
                 Constraint::Min(1),    // content
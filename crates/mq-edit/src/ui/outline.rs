@@ -0,0 +1,104 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Widget},
+};
+
+use crate::document::Outline;
+use crate::theme;
+
+/// Outline sidebar listing the headings or symbols of the document
+pub struct OutlineWidget<'a> {
+    outline: &'a Outline,
+    /// Entry of the section containing the cursor
+    current: Option<usize>,
+    /// Whether the panel has keyboard focus
+    focused: bool,
+}
+
+impl<'a> OutlineWidget<'a> {
+    pub fn new(outline: &'a Outline) -> Self {
+        Self {
+            outline,
+            current: None,
+            focused: false,
+        }
+    }
+
+    pub fn with_current(mut self, current: Option<usize>) -> Self {
+        self.current = current;
+        self
+    }
+
+    pub fn with_focus(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+}
+
+impl Widget for OutlineWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let title = if self.outline.filter().is_empty() {
+            "Outline".to_string()
+        } else {
+            format!("Outline /{}", self.outline.filter())
+        };
+        let border_color = if self.focused {
+            theme::ACCENT
+        } else {
+            theme::BORDER
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(border_color));
+
+        // Keep the selected entry, or the current one while editing, in view
+        let height = area.height.saturating_sub(2) as usize;
+        let anchor = if self.focused {
+            self.outline.selected()
+        } else {
+            self.current
+                .and_then(|current| self.outline.visible().position(|(idx, _)| idx == current))
+                .unwrap_or(0)
+        };
+        let offset = (anchor + 1).saturating_sub(height);
+
+        let items: Vec<ListItem> = self
+            .outline
+            .visible()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .map(|(pos, (idx, entry))| {
+                let indent = "  ".repeat(entry.level.saturating_sub(1));
+                let is_current = self.current == Some(idx);
+                let marker = if is_current { "▸ " } else { "  " };
+
+                let style = if self.focused && pos == self.outline.selected() {
+                    Style::default()
+                        .fg(theme::BG)
+                        .bg(theme::ACCENT)
+                        .add_modifier(Modifier::BOLD)
+                } else if is_current {
+                    Style::default()
+                        .fg(theme::ACCENT)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme::FG)
+                };
+
+                let content = format!("{}{}{}", marker, indent, entry.title);
+                ListItem::new(Line::from(vec![Span::styled(content, style)]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .style(Style::default().fg(theme::FG).bg(theme::BG));
+
+        list.render(area, buf);
+    }
+}
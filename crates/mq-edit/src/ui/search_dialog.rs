@@ -6,6 +6,7 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph, Widget},
 };
 
+use crate::document::{CaseMode, SearchOptions, SearchScope};
 use crate::theme;
 
 /// Search mode
@@ -13,6 +14,8 @@ use crate::theme;
 pub enum SearchMode {
     Find,
     Replace,
+    /// Find Markdown nodes selected by an mq query
+    Query,
 }
 
 /// Search dialog widget
@@ -23,6 +26,10 @@ pub struct SearchDialog<'a> {
     match_count: usize,
     current_match: Option<usize>,
     active_field: SearchField,
+    options: SearchOptions,
+    scope: Option<&'a SearchScope>,
+    error: Option<&'a str>,
+    history_search: Option<&'a str>,
 }
 
 /// Which field is currently active for input
@@ -41,6 +48,10 @@ impl<'a> SearchDialog<'a> {
             match_count,
             current_match,
             active_field: SearchField::Search,
+            options: SearchOptions::default(),
+            scope: None,
+            error: None,
+            history_search: None,
         }
     }
 
@@ -50,15 +61,43 @@ impl<'a> SearchDialog<'a> {
         self
     }
 
+    pub fn with_mode(mut self, mode: SearchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
     pub fn with_active_field(mut self, field: SearchField) -> Self {
         self.active_field = field;
         self
     }
 
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Show the Markdown search scope (omitted for non-Markdown documents)
+    pub fn with_scope(mut self, scope: &'a SearchScope) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// Show an error (e.g. an invalid regex) in place of the match count
+    pub fn with_error(mut self, error: Option<&'a str>) -> Self {
+        self.error = error;
+        self
+    }
+
+    /// Show a reverse history search prompt in place of the key hints
+    pub fn with_history_search(mut self, prompt: Option<&'a str>) -> Self {
+        self.history_search = prompt;
+        self
+    }
+
     /// Calculate the dialog area (at top center of the given area)
     fn dialog_area(area: Rect, mode: SearchMode) -> Rect {
         let dialog_width = 60.min(area.width.saturating_sub(4));
-        let dialog_height = if mode == SearchMode::Replace { 10 } else { 8 };
+        let dialog_height = if mode == SearchMode::Replace { 11 } else { 9 };
         let dialog_height = dialog_height.min(area.height.saturating_sub(2));
 
         let x = (area.width.saturating_sub(dialog_width)) / 2;
@@ -112,6 +151,56 @@ impl<'a> SearchDialog<'a> {
         let input_text = Paragraph::new(display_value).style(input_style);
         input_text.render(chunks[3], buf);
     }
+
+    /// Render the option toggles with their current state
+    fn render_options(&self, area: Rect, buf: &mut Buffer) {
+        let toggle = |key: &'static str, label: &'static str, enabled: bool| {
+            let style = if enabled {
+                Style::default()
+                    .fg(theme::BG)
+                    .bg(theme::ACCENT)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme::FG_DIM).bg(theme::BG_DARK)
+            };
+            vec![
+                Span::styled(format!(" {} ", label), style),
+                Span::styled(format!(" {} ", key), Style::default().fg(theme::FG_DIM)),
+            ]
+        };
+
+        let case_label = match self.options.case_mode {
+            CaseMode::Sensitive => "Aa",
+            CaseMode::Insensitive => "aa",
+            CaseMode::Smart => "aA",
+        };
+
+        let mut spans = toggle("M-r", ".*", self.options.regex);
+        spans.extend(toggle(
+            "M-c",
+            case_label,
+            self.options.case_mode != CaseMode::Sensitive,
+        ));
+        spans.extend(toggle("M-w", "ab", self.options.whole_word));
+        spans.push(Span::styled(
+            self.options.case_mode.label(),
+            Style::default().fg(theme::FG_MUTED),
+        ));
+        if let Some(scope) = self.scope {
+            let style = if *scope == SearchScope::All {
+                Style::default().fg(theme::FG_MUTED)
+            } else {
+                Style::default().fg(theme::ACCENT_HI)
+            };
+            spans.push(Span::styled("  in ", Style::default().fg(theme::FG_DIM)));
+            spans.push(Span::styled(scope.label(), style));
+            spans.push(Span::styled(" M-s", Style::default().fg(theme::FG_DIM)));
+        }
+
+        Paragraph::new(Line::from(spans))
+            .alignment(Alignment::Center)
+            .render(area, buf);
+    }
 }
 
 impl Widget for SearchDialog<'_> {
@@ -125,6 +214,7 @@ impl Widget for SearchDialog<'_> {
         let title = match self.mode {
             SearchMode::Find => " Find ",
             SearchMode::Replace => " Find & Replace ",
+            SearchMode::Query => " Find by mq Query ",
         };
         let block = Block::default()
             .title(title)
@@ -145,6 +235,7 @@ impl Widget for SearchDialog<'_> {
                 Constraint::Length(1), // replace field
                 Constraint::Length(1), // gap
                 Constraint::Length(1), // match info
+                Constraint::Length(1), // options
                 Constraint::Length(1), // hints
                 Constraint::Min(0),    // remaining space
             ])
@@ -155,6 +246,7 @@ impl Widget for SearchDialog<'_> {
                 Constraint::Length(1), // search field
                 Constraint::Length(1), // gap
                 Constraint::Length(1), // match info
+                Constraint::Length(1), // options
                 Constraint::Length(1), // gap
                 Constraint::Length(1), // hints
                 Constraint::Min(0),    // remaining space
@@ -164,7 +256,11 @@ impl Widget for SearchDialog<'_> {
 
         // Render search field (index 1, after top padding)
         self.render_text_field(
-            "Find",
+            if self.mode == SearchMode::Query {
+                "Query"
+            } else {
+                "Find"
+            },
             self.search_query,
             self.active_field == SearchField::Search,
             chunks[1],
@@ -172,7 +268,7 @@ impl Widget for SearchDialog<'_> {
         );
 
         // Render replace field if in replace mode
-        let (match_chunk, hints_chunk) = if self.mode == SearchMode::Replace {
+        let (match_chunk, options_chunk, hints_chunk) = if self.mode == SearchMode::Replace {
             self.render_text_field(
                 "Replace",
                 self.replace_query,
@@ -180,13 +276,37 @@ impl Widget for SearchDialog<'_> {
                 chunks[3],
                 buf,
             );
-            (chunks[5], chunks[6])
+            (chunks[5], chunks[6], chunks[7])
         } else {
-            (chunks[3], chunks[5])
+            (chunks[3], chunks[4], chunks[6])
         };
 
+        if self.mode == SearchMode::Query {
+            Paragraph::new("e.g. .link  .code(\"rust\")  select(.h.depth == 2)")
+                .style(Style::default().fg(theme::FG_DIM))
+                .alignment(Alignment::Center)
+                .render(options_chunk, buf);
+        } else {
+            self.render_options(options_chunk, buf);
+        }
+
+        // Render regex/query errors in place of the match count
+        if let Some(error) = self.error {
+            let prefix = if self.mode == SearchMode::Query {
+                "Query error"
+            } else {
+                "Invalid regex"
+            };
+            Paragraph::new(format!("{}: {}", prefix, error))
+                .style(Style::default().fg(theme::ERROR))
+                .alignment(Alignment::Center)
+                .render(match_chunk, buf);
+        }
+
         // Render match count on its own line
-        let match_info = if self.match_count > 0 {
+        let match_info = if self.error.is_some() {
+            String::new()
+        } else if self.match_count > 0 {
             if let Some(current) = self.current_match {
                 format!("{}/{}", current + 1, self.match_count)
             } else {
@@ -205,6 +325,14 @@ impl Widget for SearchDialog<'_> {
             match_para.render(match_chunk, buf);
         }
 
+        if let Some(prompt) = self.history_search {
+            Paragraph::new(prompt)
+                .style(Style::default().fg(theme::ACCENT_HI))
+                .alignment(Alignment::Center)
+                .render(hints_chunk, buf);
+            return;
+        }
+
         // Render hints
         let hints = if self.mode == SearchMode::Replace {
             Line::from(vec![
@@ -258,6 +386,13 @@ impl Widget for SearchDialog<'_> {
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(" Prev  "),
+                Span::styled(
+                    "^L",
+                    Style::default()
+                        .fg(theme::SUCCESS)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" Select  "),
                 Span::styled(
                     "Esc",
                     Style::default().fg(theme::ERROR).add_modifier(Modifier::BOLD),
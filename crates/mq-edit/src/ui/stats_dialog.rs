@@ -0,0 +1,138 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::document::{SectionStats, TextStats};
+use crate::theme;
+
+/// Dialog with the document's counts and a breakdown by section
+pub struct StatsDialog<'a> {
+    total: TextStats,
+    sections: &'a [SectionStats],
+    selected: usize,
+}
+
+impl<'a> StatsDialog<'a> {
+    pub fn new(total: TextStats, sections: &'a [SectionStats], selected: usize) -> Self {
+        Self {
+            total,
+            sections,
+            selected,
+        }
+    }
+
+    /// Calculate the dialog area (centered in the given area)
+    fn dialog_area(&self, area: Rect) -> Rect {
+        let dialog_width = 64.min(area.width.saturating_sub(4));
+        let dialog_height =
+            (self.sections.len().max(1) as u16 + 8).min(area.height.saturating_sub(2));
+
+        let x = (area.width.saturating_sub(dialog_width)) / 2;
+        let y = (area.height.saturating_sub(dialog_height)) / 2;
+
+        Rect::new(x, y, dialog_width, dialog_height)
+    }
+}
+
+impl Widget for StatsDialog<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let dialog_area = self.dialog_area(area);
+
+        // Clear the dialog area first
+        Clear.render(dialog_area, buf);
+
+        let block = Block::default()
+            .title(" Statistics ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme::ACCENT))
+            .style(Style::default().bg(theme::BG));
+
+        let inner_area = block.inner(dialog_area);
+        block.render(dialog_area, buf);
+
+        let chunks = Layout::vertical([
+            Constraint::Length(1), // spacing
+            Constraint::Length(2), // totals
+            Constraint::Length(1), // spacing
+            Constraint::Min(1),    // sections
+            Constraint::Length(1), // hint
+        ])
+        .split(inner_area);
+
+        let label = Style::default().fg(theme::FG_DIM);
+        let value = Style::default()
+            .fg(theme::WARNING)
+            .add_modifier(Modifier::BOLD);
+        let totals = vec![
+            Line::from(vec![
+                Span::styled(" Words ", label),
+                Span::styled(format!("{:<8}", self.total.words), value),
+                Span::styled(" Characters ", label),
+                Span::styled(format!("{:<8}", self.total.characters), value),
+            ]),
+            Line::from(vec![
+                Span::styled(" Sentences ", label),
+                Span::styled(format!("{:<4}", self.total.sentences), value),
+                Span::styled(" Reading time ", label),
+                Span::styled(format!("{} min", self.total.reading_minutes()), value),
+            ]),
+        ];
+        Paragraph::new(totals).render(chunks[1], buf);
+
+        // Keep the selected section in view, with its counts right-aligned
+        let height = chunks[3].height as usize;
+        let offset = (self.selected + 1).saturating_sub(height);
+        let width = chunks[3].width as usize;
+        let lines: Vec<Line> = if self.sections.is_empty() {
+            vec![Line::styled(" No headings", label)]
+        } else {
+            self.sections
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(height)
+                .map(|(idx, section)| {
+                    let title = if section.level == 0 {
+                        "(before the first heading)".to_string()
+                    } else {
+                        format!(
+                            "{}{}",
+                            "  ".repeat(section.level.saturating_sub(1)),
+                            section.title
+                        )
+                    };
+                    let counts = format!(
+                        "{} words · {} min ",
+                        section.stats.words,
+                        section.stats.reading_minutes()
+                    );
+                    let title_width = width.saturating_sub(counts.width() + 2);
+                    let title: String = title.chars().take(title_width).collect();
+                    let padding = " ".repeat(title_width.saturating_sub(title.width()));
+
+                    let style = if idx == self.selected {
+                        Style::default()
+                            .fg(theme::BG)
+                            .bg(theme::ACCENT)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(theme::FG)
+                    };
+                    Line::styled(format!(" {}{} {}", title, padding, counts), style)
+                })
+                .collect()
+        };
+        Paragraph::new(lines).render(chunks[3], buf);
+
+        let hint = Paragraph::new("Up/Down: select  Enter: go to section  Esc: close")
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(theme::FG_DIM));
+        hint.render(chunks[4], buf);
+    }
+}
@@ -7,7 +7,7 @@ use ratatui::{
 };
 use unicode_width::UnicodeWidthStr;
 
-use crate::document::DocumentBuffer;
+use crate::document::{DocumentBuffer, TextStats};
 use crate::theme;
 use markdown_lsp::DiagnosticsManager;
 
@@ -16,6 +16,12 @@ pub struct StatusBar<'a> {
     buffer: &'a DocumentBuffer,
     diagnostics: Option<&'a DiagnosticsManager>,
     warning_message: Option<&'a str>,
+    /// Mode label shown before the file name, such as "PREVIEW"
+    mode: Option<&'a str>,
+    /// Counts of the document's prose
+    stats: Option<TextStats>,
+    /// Counts of the selected text
+    selection_stats: Option<TextStats>,
 }
 
 impl<'a> StatusBar<'a> {
@@ -24,6 +30,9 @@ impl<'a> StatusBar<'a> {
             buffer,
             diagnostics: None,
             warning_message: None,
+            mode: None,
+            stats: None,
+            selection_stats: None,
         }
     }
 
@@ -36,6 +45,23 @@ impl<'a> StatusBar<'a> {
         self.warning_message = Some(message);
         self
     }
+
+    pub fn with_mode(mut self, mode: &'a str) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Show the word count and reading time of the document
+    pub fn with_stats(mut self, stats: TextStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Show the word and character counts of the selection instead of the reading time
+    pub fn with_selection_stats(mut self, stats: TextStats) -> Self {
+        self.selection_stats = Some(stats);
+        self
+    }
 }
 
 impl Widget for StatusBar<'_> {
@@ -72,6 +98,14 @@ impl Widget for StatusBar<'_> {
 
         let line_count = format!(" {} lines ", self.buffer.line_count());
 
+        let stats_section = self.stats.map(|stats| match self.selection_stats {
+            Some(selection) => format!(
+                " {} of {} words · {} chars ",
+                selection.words, stats.words, selection.characters
+            ),
+            None => format!(" {} words · {} min ", stats.words, stats.reading_minutes()),
+        });
+
         // Get diagnostic counts if available
         let (error_count, warning_count) = if let Some(diagnostics) = self.diagnostics {
             (diagnostics.error_count(), diagnostics.warning_count())
@@ -82,14 +116,25 @@ impl Widget for StatusBar<'_> {
         let separator = "│";
 
         let file_section = format!(" {}{} ", file_name, modified);
+        let mode_section = self.mode.map(|mode| format!(" {} ", mode));
 
-        let mut spans = vec![Span::styled(
+        let mut spans = Vec::new();
+        if let Some(mode_section) = &mode_section {
+            spans.push(Span::styled(
+                mode_section.clone(),
+                Style::default()
+                    .fg(theme::BG)
+                    .bg(theme::SUCCESS)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        spans.push(Span::styled(
             file_section.clone(),
             Style::default()
                 .fg(theme::BG)
                 .bg(theme::ACCENT)
                 .add_modifier(Modifier::BOLD),
-        )];
+        ));
 
         // Build diagnostic spans
         let error_text = if error_count > 0 {
@@ -109,9 +154,13 @@ impl Widget for StatusBar<'_> {
         let has_diagnostics = error_count > 0 || warning_count > 0;
         let diagnostics_sep_width = if has_diagnostics { separator_width } else { 0 };
 
-        let used_width = file_section.width()
+        let used_width = mode_section.as_ref().map_or(0, |s| s.width())
+            + file_section.width()
             + position.width()
             + line_count.width()
+            + stats_section
+                .as_ref()
+                .map_or(0, |s| s.width() + separator_width)
             + diagnostics_width
             + diagnostics_sep_width
             + separator_width;
@@ -148,6 +197,17 @@ impl Widget for StatusBar<'_> {
             ));
         }
 
+        if let Some(stats_section) = stats_section {
+            spans.push(Span::styled(
+                stats_section,
+                Style::default().fg(theme::FG_MUTED).bg(theme::BG_PANEL),
+            ));
+            spans.push(Span::styled(
+                separator,
+                Style::default().fg(theme::FG_MUTED).bg(theme::BG_PANEL),
+            ));
+        }
+
         spans.push(Span::styled(
             line_count,
             Style::default().fg(theme::FG).bg(theme::BG_PANEL),